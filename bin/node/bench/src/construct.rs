@@ -217,7 +217,7 @@ impl sc_transaction_pool_api::InPoolTransaction for PoolTransaction {
 }
 
 #[derive(Clone, Debug)]
-pub struct Transactions(Vec<Arc<PoolTransaction>>);
+pub struct Transactions(pub Vec<Arc<PoolTransaction>>);
 pub struct TransactionsIterator(std::vec::IntoIter<Arc<PoolTransaction>>);
 
 impl Iterator for TransactionsIterator {
@@ -0,0 +1,647 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020-2022 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! DC workload benchmarks.
+//!
+//! These benchmarks measure import and construction of blocks full of the
+//! free calls which storage nodes send to `pallet-dc-node`.
+//!
+//! Before measuring, the database is populated with `nodes` onchain storage
+//! nodes and `users` wallet accounts, each of them served by one of the nodes.
+//! The measured block then carries `files` calls of one kind, spread over the
+//! storage nodes. The result is the execution time per call, and the growth
+//! of the DC storage caused by the block is logged.
+
+use std::{borrow::Cow, collections::BTreeMap, sync::Arc};
+
+use kitchensink_runtime::{
+	constants::{currency::DOLLARS, time::SLOT_DURATION},
+	AccountId, Balance, CheckedExtrinsic, GenesisConfig, RuntimeCall,
+};
+use node_primitives::{Block, BlockNumber, Index};
+use node_testing::{
+	bench::{BenchDb, BenchKeyring, DatabaseType, KeyTypes, Profile},
+	client::Client,
+	keyring::signed_extra,
+};
+use sc_client_api::StorageProvider;
+use sp_api::ProvideRuntimeApi;
+use sp_block_builder::BlockBuilder;
+use sp_consensus::{Environment, Proposer};
+use sp_consensus_babe::digests::{CompatibleDigestItem, PreDigest, SecondaryPlainPreDigest};
+use sp_core::{hashing::twox_128, storage::StorageKey, ExecutionContext};
+use sp_inherents::{InherentData, InherentDataProvider};
+use sp_runtime::{
+	codec::{Decode, Encode},
+	generic::{BlockId, Digest, DigestItem},
+	OpaqueExtrinsic,
+};
+
+use crate::{
+	construct::{PoolTransaction, Transactions},
+	core::{self, Mode, Path},
+};
+
+/// Calls a storage node may send in one block, as configured in the genesis of `node-testing`.
+const PEER_CALL_QUOTA: usize = 100;
+
+/// Calls in a block which populates the database, to stay clear of the block limits.
+const SETUP_BLOCK_CALLS: usize = 500;
+
+/// Size of the files added by the workload.
+const FILE_SIZE: u64 = 1024 * 1024;
+
+/// Space reported by the storage nodes of the workload.
+const NODE_SPACE: u64 = 1024 * 1024 * 1024 * 1024;
+
+/// Shape of the DC chain and of the measured block.
+#[derive(Clone, Copy, Debug)]
+pub struct DcWorkload {
+	/// Number of onchain storage nodes.
+	pub nodes: usize,
+	/// Number of wallet accounts served by the storage nodes.
+	pub users: usize,
+	/// Number of calls in the measured block.
+	pub files: usize,
+}
+
+impl DcWorkload {
+	fn path(&self) -> String {
+		format!("{}n_{}u_{}f", self.nodes, self.users, self.files)
+	}
+}
+
+/// Kind of the calls in the measured block.
+#[derive(Clone, Copy, Debug)]
+pub enum DcCall {
+	AddFileInfo,
+	UserLogin,
+	SubmitWorkReport,
+}
+
+impl DcCall {
+	fn path(&self) -> &'static str {
+		match self {
+			DcCall::AddFileInfo => "add_file_info",
+			DcCall::UserLogin => "user_login",
+			DcCall::SubmitWorkReport => "submit_work_report",
+		}
+	}
+}
+
+/// Accounts of the workload and the nonces they have used so far.
+struct DcChain {
+	workload: DcWorkload,
+	nodes: Vec<AccountId>,
+	users: Vec<AccountId>,
+	nonces: BTreeMap<AccountId, Index>,
+}
+
+impl DcChain {
+	/// Populate the database with the storage nodes and users of the workload.
+	fn setup(bench_db: &mut BenchDb, workload: DcWorkload) -> Self {
+		assert!(workload.nodes > 0 && workload.users > 0, "DC workload needs nodes and users");
+		assert!(
+			workload.files <= workload.nodes * PEER_CALL_QUOTA,
+			"DC workload exceeds the call quota of {} storage nodes",
+			workload.nodes,
+		);
+
+		let keyring = bench_db.keyring();
+		let mut chain = DcChain {
+			workload,
+			nodes: (0..workload.nodes).map(|i| keyring.at(i)).collect(),
+			users: (workload.nodes..workload.nodes + workload.users)
+				.map(|i| keyring.at(i))
+				.collect(),
+			nonces: BTreeMap::new(),
+		};
+
+		// Bond every storage node as its own stash, so that it goes onchain when it joins
+		// again after setting the stash.
+		let mut calls = Vec::new();
+		for (index, node) in chain.nodes.iter().enumerate() {
+			let join = RuntimeCall::DcNode(pallet_dc_node::Call::join_storage_node {
+				peer_id: peer_id(index),
+				total_space: NODE_SPACE,
+				free_space: NODE_SPACE,
+				ip_address: b"127.0.0.1".to_vec(),
+				sgx_version_number: 0,
+				program_version: 0,
+				enclave_id: Vec::new(),
+				block_height: 1,
+				tee_report: Vec::new(),
+			});
+			calls.push((
+				node.clone(),
+				RuntimeCall::Staking(pallet_staking::Call::bond {
+					controller: sp_runtime::MultiAddress::Id(node.clone()),
+					value: 10 * DOLLARS,
+					payee: pallet_staking::RewardDestination::Staked,
+				}),
+			));
+			calls.push((node.clone(), join.clone()));
+			calls.push((
+				node.clone(),
+				RuntimeCall::DcNode(pallet_dc_node::Call::set_stash_peer {
+					stash: node.clone(),
+					peer_id: peer_id(index),
+				}),
+			));
+			calls.push((node.clone(), join));
+		}
+		for chunk in calls.chunks(SETUP_BLOCK_CALLS) {
+			chain.import_setup_block(bench_db, chunk.to_vec());
+		}
+
+		let users = chain.users.clone();
+		for chunk in users.chunks(SETUP_BLOCK_CALLS) {
+			let calls = chunk
+				.iter()
+				.map(|user| {
+					(
+						user.clone(),
+						RuntimeCall::DcNode(pallet_dc_node::Call::purchase_storage {
+							for_account: user.clone(),
+							package_id: 1,
+						}),
+					)
+				})
+				.collect();
+			chain.import_setup_block(bench_db, calls);
+		}
+
+		// Let every node add its users, without exceeding its call quota in a block.
+		let users = users.iter().enumerate().collect::<Vec<_>>();
+		for chunk in users.chunks(SETUP_BLOCK_CALLS.min(workload.nodes * PEER_CALL_QUOTA)) {
+			let block_height = best_number(bench_db) + 1;
+			let calls = chunk
+				.iter()
+				.map(|&(index, user)| {
+					(
+						chain.nodes[index % workload.nodes].clone(),
+						RuntimeCall::DcNode(pallet_dc_node::Call::add_user_peer {
+							for_account: user.clone(),
+							block_height,
+						}),
+					)
+				})
+				.collect();
+			chain.import_setup_block(bench_db, calls);
+		}
+
+		chain.check(bench_db);
+		chain
+	}
+
+	/// Check that the storage nodes are onchain and that every user is served by its node.
+	fn check(&self, bench_db: &mut BenchDb) {
+		let client = bench_db.client();
+		let hash = client.chain_info().best_hash;
+		let read = |key: Vec<u8>| {
+			client
+				.storage(hash, &StorageKey(key))
+				.expect("Failed to read DC storage")
+				.map(|data| data.0)
+		};
+
+		let onchain_peers = read(dc_primitives::storage_value_key(b"OnchainPeerNumber"))
+			.map(|data| u32::decode(&mut &data[..]).expect("Failed to decode peer number"))
+			.unwrap_or_default();
+		assert_eq!(onchain_peers, self.workload.nodes as u32, "DC storage nodes are not onchain");
+
+		for (index, user) in self.users.iter().enumerate() {
+			let storage = read(dc_primitives::wallet_account_storage_key(user))
+				.map(|data| {
+					pallet_dc_node::UserStorage::<AccountId, BlockNumber, Balance>::decode(
+						&mut &data[..],
+					)
+					.expect("Failed to decode user storage")
+				})
+				.expect("DC user has no storage");
+			assert!(
+				storage.peers.contains(&peer_id(index % self.workload.nodes)),
+				"DC user is not served by its storage node",
+			);
+		}
+	}
+
+	/// Sign the calls, then build and import a block of them into the database.
+	fn import_setup_block(&mut self, bench_db: &mut BenchDb, calls: Vec<(AccountId, RuntimeCall)>) {
+		let count = calls.len();
+		let block = bench_db.generate_block_with(|client, keyring| {
+			let calls = calls
+				.into_iter()
+				.map(|(who, call)| self.sign(client, keyring, who, call))
+				.collect();
+			(digest(client), inherents(client), calls)
+		});
+		// One inherent for the timestamp.
+		assert_eq!(block.extrinsics.len(), count + 1, "DC setup block is exhausted");
+		bench_db.import_block(block);
+	}
+
+	/// Sign the calls of the measured block, which is built on top of the best block.
+	fn workload_calls(
+		&mut self,
+		call: DcCall,
+		client: &Client,
+		keyring: &BenchKeyring,
+	) -> Vec<OpaqueExtrinsic> {
+		let block_height = client.chain_info().best_number + 1;
+		(0..self.workload.files)
+			.map(|index| {
+				let node_index = index % self.workload.nodes;
+				let user_index = index % self.workload.users;
+				let (node, function) = match call {
+					DcCall::AddFileInfo => {
+						let owner = self.users[user_index].clone();
+						let file_id = format!("dc-bench-file-{}", index).into_bytes();
+						let file_type = 1u32;
+						let mut message = file_id.clone();
+						message.extend(FILE_SIZE.to_le_bytes());
+						message.extend(block_height.to_le_bytes());
+						message.extend(file_type.to_le_bytes());
+						message.extend(peer_id(node_index));
+						let function = pallet_dc_node::Call::add_file_info {
+							signature: keyring.sign_message(&owner, &message),
+							owner,
+							file_id,
+							file_size: FILE_SIZE,
+							file_type,
+							block_height,
+						};
+						(self.nodes[node_index].clone(), function)
+					},
+					// Login through the node which added the user.
+					DcCall::UserLogin => {
+						let login_account = self.users[user_index].clone();
+						let login_node = user_index % self.workload.nodes;
+						let app_ids: Vec<Vec<u8>> = Vec::new();
+						let mut message = b"user_login".to_vec();
+						message.extend(app_ids.encode());
+						message.extend(block_height.to_le_bytes());
+						message.extend(peer_id(login_node));
+						let function = pallet_dc_node::Call::user_login {
+							signature: keyring.sign_message(&login_account, &message),
+							login_account,
+							app_ids,
+							block_height,
+							app_signatures: Vec::new(),
+						};
+						(self.nodes[login_node].clone(), function)
+					},
+					DcCall::SubmitWorkReport => (
+						self.nodes[node_index].clone(),
+						pallet_dc_node::Call::submit_work_report {
+							total_space: NODE_SPACE,
+							free_space: NODE_SPACE - FILE_SIZE * index as u64,
+							ip_address: b"127.0.0.1".to_vec(),
+							miss_files: Vec::new(),
+							miss_accounts: Vec::new(),
+							program_version: 0,
+							enclave_id: Vec::new(),
+							block_height,
+							_tee_report: Vec::new(),
+						},
+					),
+				};
+				self.sign(client, keyring, node, RuntimeCall::DcNode(function))
+			})
+			.collect()
+	}
+
+	fn sign(
+		&mut self,
+		client: &Client,
+		keyring: &BenchKeyring,
+		who: AccountId,
+		function: RuntimeCall,
+	) -> OpaqueExtrinsic {
+		let runtime_version = client
+			.runtime_version_at(&BlockId::number(0))
+			.expect("There should be runtime version at 0");
+		let nonce = self.nonces.entry(who.clone()).or_default();
+		let signed = keyring.sign(
+			CheckedExtrinsic { signed: Some((who, signed_extra(*nonce, 0))), function },
+			runtime_version.spec_version,
+			runtime_version.transaction_version,
+			client.chain_info().genesis_hash.into(),
+		);
+		*nonce += 1;
+
+		OpaqueExtrinsic::from_bytes(&signed.encode()).expect("Failed to decode opaque")
+	}
+}
+
+fn peer_id(index: usize) -> Vec<u8> {
+	format!("dc-bench-peer-{}", index).into_bytes()
+}
+
+/// Let the endowed accounts run storage nodes which go onchain as soon as they are staked, and
+/// repeat their work reports and logins in the following blocks.
+fn dc_genesis(genesis: &mut GenesisConfig) {
+	genesis.dc_node.min_staking_amount = DOLLARS;
+	genesis.dc_node.tee_report_verify_number = 0;
+	genesis.dc_node.interval_blocks_work_report = 0;
+	genesis.dc_node.interval_blocks_login = 0;
+	genesis.dc_node.storage_packages = vec![(1, NODE_SPACE, DOLLARS, 1_000_000, 0)];
+}
+
+/// Digest of the next block, which claims the BABE slot following the best block.
+fn digest(client: &Client) -> Digest {
+	let slot = client.chain_info().best_number as u64 + 1;
+	let pre_digest = PreDigest::SecondaryPlain(SecondaryPlainPreDigest {
+		authority_index: 0,
+		slot: slot.into(),
+	});
+	Digest { logs: vec![DigestItem::babe_pre_digest(pre_digest)] }
+}
+
+/// Inherents of the next block, whose timestamp is the beginning of the slot claimed by
+/// [`digest`].
+fn inherents(client: &Client) -> Vec<OpaqueExtrinsic> {
+	let mut inherent_data = InherentData::new();
+	let best_number = client.chain_info().best_number;
+	let timestamp = (best_number as u64 + 1) * SLOT_DURATION;
+
+	inherent_data
+		.put_data(sp_timestamp::INHERENT_IDENTIFIER, &timestamp)
+		.expect("Put timestamp failed");
+
+	client
+		.runtime_api()
+		.inherent_extrinsics_with_context(
+			&BlockId::number(best_number),
+			ExecutionContext::BlockConstruction,
+			inherent_data,
+		)
+		.expect("Get inherents failed")
+}
+
+fn best_number(bench_db: &mut BenchDb) -> BlockNumber {
+	bench_db.client().chain_info().best_number
+}
+
+/// Total size of the keys and values of `pallet-dc-node` at the block.
+fn dc_storage_size(client: &Client, hash: node_primitives::Hash) -> usize {
+	client
+		.storage_pairs(hash, &StorageKey(twox_128(dc_primitives::PALLET_PREFIX).to_vec()))
+		.expect("Failed to read DC storage")
+		.iter()
+		.map(|(key, data)| key.0.len() + data.0.len())
+		.sum()
+}
+
+pub struct DcImportBenchmarkDescription {
+	pub profile: Profile,
+	pub workload: DcWorkload,
+	pub call: DcCall,
+	pub database_type: DatabaseType,
+}
+
+pub struct DcImportBenchmark {
+	profile: Profile,
+	database: BenchDb,
+	block: Block,
+	calls: usize,
+}
+
+impl core::BenchmarkDescription for DcImportBenchmarkDescription {
+	fn path(&self) -> Path {
+		let mut path = Path::new(&["node", "dc", "import"]);
+
+		match self.profile {
+			Profile::Wasm => path.push("wasm"),
+			Profile::Native => path.push("native"),
+		}
+
+		path.push(self.call.path());
+
+		match self.database_type {
+			DatabaseType::RocksDb => path.push("rocksdb"),
+			DatabaseType::ParityDb => path.push("paritydb"),
+		}
+
+		path.push(&self.workload.path());
+
+		path
+	}
+
+	fn setup(self: Box<Self>) -> Box<dyn core::Benchmark> {
+		let mut bench_db = BenchDb::with_genesis(
+			self.database_type,
+			self.workload.nodes + self.workload.users,
+			KeyTypes::Ed25519,
+			dc_genesis,
+		);
+		let mut chain = DcChain::setup(&mut bench_db, self.workload);
+		let block = bench_db.generate_block_with(|client, keyring| {
+			(digest(client), inherents(client), chain.workload_calls(self.call, client, keyring))
+		});
+		assert_eq!(block.extrinsics.len(), self.workload.files + 1, "DC block is exhausted");
+
+		Box::new(DcImportBenchmark {
+			profile: self.profile,
+			database: bench_db,
+			block,
+			calls: self.workload.files,
+		})
+	}
+
+	fn name(&self) -> Cow<'static, str> {
+		format!(
+			"DC block import ({:?}, {} nodes/{} users/{} calls, {:?}, {:?} backend)",
+			self.call,
+			self.workload.nodes,
+			self.workload.users,
+			self.workload.files,
+			self.profile,
+			self.database_type,
+		)
+		.into()
+	}
+}
+
+impl core::Benchmark for DcImportBenchmark {
+	fn run(&mut self, mode: Mode) -> std::time::Duration {
+		let mut context = self.database.create_context(self.profile);
+		let parent_hash = context.client.chain_info().best_hash;
+
+		if mode == Mode::Profile {
+			std::thread::park_timeout(std::time::Duration::from_secs(3));
+		}
+
+		let start = std::time::Instant::now();
+		context.import_block(self.block.clone());
+		let elapsed = start.elapsed();
+
+		if mode == Mode::Profile {
+			std::thread::park_timeout(std::time::Duration::from_secs(1));
+		}
+
+		let before = dc_storage_size(&context.client, parent_hash);
+		let after = dc_storage_size(&context.client, self.block.header.hash());
+		log::info!(
+			target: "bench-logistics",
+			"imported DC block with {} calls, took: {:#?}, storage grew by {} bytes ({} per call)",
+			self.calls,
+			elapsed,
+			after.saturating_sub(before),
+			after.saturating_sub(before) / self.calls.max(1),
+		);
+
+		elapsed / self.calls.max(1) as u32
+	}
+}
+
+pub struct DcConstructionBenchmarkDescription {
+	pub profile: Profile,
+	pub workload: DcWorkload,
+	pub call: DcCall,
+	pub database_type: DatabaseType,
+}
+
+pub struct DcConstructionBenchmark {
+	profile: Profile,
+	database: BenchDb,
+	transactions: Transactions,
+	calls: usize,
+}
+
+impl core::BenchmarkDescription for DcConstructionBenchmarkDescription {
+	fn path(&self) -> Path {
+		let mut path = Path::new(&["node", "dc", "proposer"]);
+
+		match self.profile {
+			Profile::Wasm => path.push("wasm"),
+			Profile::Native => path.push("native"),
+		}
+
+		path.push(self.call.path());
+
+		match self.database_type {
+			DatabaseType::RocksDb => path.push("rocksdb"),
+			DatabaseType::ParityDb => path.push("paritydb"),
+		}
+
+		path.push(&self.workload.path());
+
+		path
+	}
+
+	fn setup(self: Box<Self>) -> Box<dyn core::Benchmark> {
+		let mut bench_db = BenchDb::with_genesis(
+			self.database_type,
+			self.workload.nodes + self.workload.users,
+			KeyTypes::Ed25519,
+			dc_genesis,
+		);
+		let mut chain = DcChain::setup(&mut bench_db, self.workload);
+
+		let client = bench_db.client();
+		let extrinsics = chain
+			.workload_calls(self.call, &client, bench_db.keyring())
+			.into_iter()
+			.map(|transaction| Arc::new(PoolTransaction::from(transaction)))
+			.collect();
+
+		Box::new(DcConstructionBenchmark {
+			profile: self.profile,
+			database: bench_db,
+			transactions: Transactions(extrinsics),
+			calls: self.workload.files,
+		})
+	}
+
+	fn name(&self) -> Cow<'static, str> {
+		format!(
+			"DC block construction ({:?}, {} nodes/{} users/{} calls, {:?}, {:?} backend)",
+			self.call,
+			self.workload.nodes,
+			self.workload.users,
+			self.workload.files,
+			self.profile,
+			self.database_type,
+		)
+		.into()
+	}
+}
+
+impl core::Benchmark for DcConstructionBenchmark {
+	fn run(&mut self, mode: Mode) -> std::time::Duration {
+		let context = self.database.create_context(self.profile);
+		let best = context.client.chain_info();
+
+		if mode == Mode::Profile {
+			std::thread::park_timeout(std::time::Duration::from_secs(3));
+		}
+
+		let mut proposer_factory = sc_basic_authorship::ProposerFactory::new(
+			context.spawn_handle.clone(),
+			context.client.clone(),
+			self.transactions.clone().into(),
+			None,
+			None,
+		);
+		// The block claims the slot after the best block, like the blocks of the setup.
+		let timestamp_provider = sp_timestamp::InherentDataProvider::new(
+			((best.best_number as u64 + 1) * SLOT_DURATION).into(),
+		);
+
+		let start = std::time::Instant::now();
+
+		let proposer = futures::executor::block_on(
+			proposer_factory.init(
+				&context
+					.client
+					.header(best.best_hash)
+					.expect("Database error querying best block")
+					.expect("Best block should exist"),
+			),
+		)
+		.expect("Proposer initialization failed");
+
+		let inherent_data = futures::executor::block_on(timestamp_provider.create_inherent_data())
+			.expect("Create inherent data failed");
+		let block = futures::executor::block_on(proposer.propose(
+			inherent_data,
+			digest(&context.client),
+			std::time::Duration::from_secs(20),
+			None,
+		))
+		.map(|r| r.block)
+		.expect("Proposing failed");
+
+		let elapsed = start.elapsed();
+
+		if mode == Mode::Profile {
+			std::thread::park_timeout(std::time::Duration::from_secs(1));
+		}
+
+		log::info!(
+			target: "bench-logistics",
+			"constructed DC block with {} of {} calls, took: {:#?}",
+			block.extrinsics.len().saturating_sub(1),
+			self.calls,
+			elapsed,
+		);
+
+		elapsed / self.calls.max(1) as u32
+	}
+}
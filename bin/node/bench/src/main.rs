@@ -20,6 +20,7 @@ mod common;
 mod construct;
 #[macro_use]
 mod core;
+mod dc;
 mod generator;
 mod import;
 mod simple_trie;
@@ -36,6 +37,7 @@ use crate::{
 	common::SizeType,
 	construct::ConstructionBenchmarkDescription,
 	core::{run_benchmark, Mode as BenchmarkMode},
+	dc::{DcCall, DcConstructionBenchmarkDescription, DcImportBenchmarkDescription, DcWorkload},
 	import::ImportBenchmarkDescription,
 	tempdb::DatabaseType,
 	trie::{DatabaseSize, TrieReadBenchmarkDescription, TrieWriteBenchmarkDescription},
@@ -66,6 +68,18 @@ struct Opt {
 	#[arg(long)]
 	transactions: Option<usize>,
 
+	/// Number of storage nodes for DC benchmarks.
+	#[arg(long, default_value = "10")]
+	dc_nodes: usize,
+
+	/// Number of users served by the storage nodes for DC benchmarks.
+	#[arg(long, default_value = "1000")]
+	dc_users: usize,
+
+	/// Number of DC calls in the block of DC benchmarks.
+	#[arg(long, default_value = "500")]
+	dc_files: usize,
+
 	/// Mode
 	///
 	/// "regular" for regular benchmark
@@ -106,6 +120,10 @@ fn main() {
 		}
 	}
 
+	let dc_workload =
+		DcWorkload { nodes: opt.dc_nodes, users: opt.dc_users, files: opt.dc_files };
+	let dc_calls = [DcCall::AddFileInfo, DcCall::UserLogin, DcCall::SubmitWorkReport];
+
 	let benchmarks = matrix!(
 		(profile, size, block_type, database_type) in import_benchmarks.into_iter() =>
 			ImportBenchmarkDescription {
@@ -152,6 +170,24 @@ fn main() {
 			database_type: BenchDataBaseType::RocksDb,
 		},
 		PoolBenchmarkDescription { database_type: BenchDataBaseType::RocksDb },
+		(call, database_type) in dc_calls.iter().flat_map(|call|
+			[
+				BenchDataBaseType::RocksDb, BenchDataBaseType::ParityDb
+			]
+			.into_iter().map(move |database_type| (*call, database_type)))
+			=> DcImportBenchmarkDescription {
+				profile: Profile::Wasm,
+				workload: dc_workload,
+				call,
+				database_type,
+			},
+		call in dc_calls =>
+			DcConstructionBenchmarkDescription {
+				profile: Profile::Wasm,
+				workload: dc_workload,
+				call,
+				database_type: BenchDataBaseType::RocksDb,
+			},
 	);
 
 	if opt.list {
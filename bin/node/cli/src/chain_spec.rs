@@ -407,6 +407,19 @@ pub fn testnet_genesis(
 			interval_blocks_work_report: 28800u32.into(),
 			interval_blocks_login: 28800u32.into(),
 			tee_report_verify_number: 0u32.into(),
+			peer_call_quota: 100,
+			storage_packages: vec![
+				(1, 60*1024*1024*1024, 10000, 1000000, 10),
+				(2, 120*1024*1024*1024, 20000, 1000000, 5),
+			],
+			enclave_ids: vec![(
+				"72c3b468cd159809cb521885deb3ddce8a6f8a0b23b43785990c7f4b5bc7b8cf".as_bytes().to_vec(),
+				"bxbmtgfh5wc7cwzlof5hifsv5rgswkmnvkaamfchcaaniqqclzxmc2z7yaald7qejho6s4thcy3uc6dhtfexp44qsmrn32jpkzfccsca".as_bytes().to_vec(),
+			)],
+			dc_program: None,
+			endpoints: vec![],
+			app_accounts: vec![],
+			wallet_accounts: vec![],
 			dev_config: vec![(storage_controller, storage_stash, storage_call_account, ("12D3KooWNr1ERkUSdUQjGtmtWPB8AtWGVuDVhcoZSH4UkudU2in9".as_bytes().to_vec()))]
 		},
 	}
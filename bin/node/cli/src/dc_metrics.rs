@@ -0,0 +1,138 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2018-2022 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Prometheus metrics of the health of the DC network.
+//!
+//! The gauges are read through the `DcNodeApi` runtime API on every new best block, so that
+//! dashboards can follow the DC network without an external indexer. The runtime API only reads
+//! the counters that the pallet keeps up to date on chain.
+
+use futures::prelude::*;
+use node_primitives::{AccountId, Balance, Block, BlockNumber};
+use pallet_dc_node::{node_status_name, NetworkStats};
+use pallet_dc_node_runtime_api::DcNodeApi;
+use prometheus_endpoint::{register, Gauge, GaugeVec, Opts, PrometheusError, Registry, F64, U64};
+use sc_client_api::BlockchainEvents;
+use sp_api::ProvideRuntimeApi;
+use sp_runtime::generic::BlockId;
+use std::sync::Arc;
+
+/// Gauges of the health of the DC network.
+#[derive(Clone)]
+pub struct DcMetrics {
+	peers: GaugeVec<U64>,
+	total_space: Gauge<U64>,
+	free_space: Gauge<U64>,
+	active_users: Gauge<U64>,
+	files: Gauge<U64>,
+	average_replication: Gauge<F64>,
+	reward_pot: GaugeVec<F64>,
+	reports_in_era: Gauge<U64>,
+}
+
+impl DcMetrics {
+	/// Register the gauges in the registry.
+	pub fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			peers: register(
+				GaugeVec::new(
+					Opts::new("dc_storage_nodes", "Number of storage nodes by status."),
+					&["status"],
+				)?,
+				registry,
+			)?,
+			total_space: register(
+				Gauge::new("dc_total_space_bytes", "Total space of the onchain storage nodes.")?,
+				registry,
+			)?,
+			free_space: register(
+				Gauge::new("dc_free_space_bytes", "Free space of the onchain storage nodes.")?,
+				registry,
+			)?,
+			active_users: register(
+				Gauge::new(
+					"dc_active_users",
+					"Number of wallet accounts whose subscription has not expired.",
+				)?,
+				registry,
+			)?,
+			files: register(Gauge::new("dc_files", "Number of files.")?, registry)?,
+			average_replication: register(
+				Gauge::new(
+					"dc_average_replication",
+					"Average number of storage nodes that a file is stored on.",
+				)?,
+				registry,
+			)?,
+			reward_pot: register(
+				GaugeVec::new(
+					Opts::new("dc_reward_pot", "Balance of the reward pots, in the smallest unit."),
+					&["pot"],
+				)?,
+				registry,
+			)?,
+			reports_in_era: register(
+				Gauge::new(
+					"dc_reports_in_era",
+					"Number of reports against storage nodes in the current era.",
+				)?,
+				registry,
+			)?,
+		})
+	}
+
+	/// Set the gauges to the health of the DC network.
+	pub fn update(&self, stats: &NetworkStats<Balance>) {
+		self.peers.reset();
+		for (status, number) in stats.peers_by_status.iter() {
+			self.peers.with_label_values(&[node_status_name(*status)]).set(*number as u64);
+		}
+		self.total_space.set(stats.total_space);
+		self.free_space.set(stats.free_space);
+		self.active_users.set(stats.active_users as u64);
+		self.files.set(stats.files);
+		let average_replication = if stats.files == 0 {
+			0.0
+		} else {
+			stats.file_replicas as f64 / stats.files as f64
+		};
+		self.average_replication.set(average_replication);
+		self.reward_pot.with_label_values(&["app"]).set(stats.app_reward_total as f64);
+		self.reward_pot.with_label_values(&["storage"]).set(stats.storage_reward_total as f64);
+		self.reports_in_era.set(stats.reports_in_era as u64);
+	}
+}
+
+/// Update the metrics on every new best block, until the client stops importing blocks.
+pub async fn run<C>(client: Arc<C>, metrics: DcMetrics)
+where
+	C: ProvideRuntimeApi<Block> + BlockchainEvents<Block> + Send + Sync + 'static,
+	C::Api: DcNodeApi<Block, AccountId, BlockNumber, Balance>,
+{
+	let mut imported = client.import_notification_stream();
+	while let Some(notification) = imported.next().await {
+		// Blocks imported during the major sync are not the head of the chain
+		if !notification.is_new_best {
+			continue
+		}
+		match client.runtime_api().network_stats(&BlockId::Hash(notification.hash)) {
+			Ok(stats) => metrics.update(&stats),
+			Err(e) => log::debug!(target: "dc-metrics", "Failed to read the DC network stats: {}", e),
+		}
+	}
+}
@@ -31,6 +31,7 @@
 #![warn(missing_docs)]
 
 pub mod chain_spec;
+pub mod dc_metrics;
 
 #[macro_use]
 pub mod service;
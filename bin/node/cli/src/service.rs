@@ -97,6 +97,7 @@ pub fn create_extrinsic(
 		pallet_asset_tx_payment::ChargeAssetTxPayment::<kitchensink_runtime::Runtime>::from(
 			tip, None,
 		),
+		pallet_dc_node::CheckPeerQuota::<kitchensink_runtime::Runtime>::new(),
 	);
 
 	let raw_payload = kitchensink_runtime::SignedPayload::from_raw(
@@ -111,6 +112,7 @@ pub fn create_extrinsic(
 			(),
 			(),
 			(),
+			(),
 		),
 	);
 	let signature = raw_payload.using_encoded(|e| sender.sign(e));
@@ -409,6 +411,15 @@ pub fn new_full_base(
 		}
 	}
 
+	if let Some(registry) = prometheus_registry.as_ref() {
+		let metrics = crate::dc_metrics::DcMetrics::register(registry)?;
+		task_manager.spawn_handle().spawn(
+			"dc-metrics",
+			None,
+			crate::dc_metrics::run(client.clone(), metrics),
+		);
+	}
+
 	let (block_import, grandpa_link, babe_link) = import_setup;
 
 	(with_startup_data)(&block_import, &babe_link);
@@ -769,6 +780,7 @@ mod tests {
 				let check_nonce = frame_system::CheckNonce::from(index);
 				let check_weight = frame_system::CheckWeight::new();
 				let tx_payment = pallet_asset_tx_payment::ChargeAssetTxPayment::from(0, None);
+				let check_peer_quota = pallet_dc_node::CheckPeerQuota::new();
 				let extra = (
 					check_non_zero_sender,
 					check_spec_version,
@@ -778,11 +790,12 @@ mod tests {
 					check_nonce,
 					check_weight,
 					tx_payment,
+					check_peer_quota,
 				);
 				let raw_payload = SignedPayload::from_raw(
 					function,
 					extra,
-					((), spec_version, transaction_version, genesis_hash, genesis_hash, (), (), ()),
+					((), spec_version, transaction_version, genesis_hash, genesis_hash, (), (), (), ()),
 				);
 				let signature = raw_payload.using_encoded(|payload| signer.sign(payload));
 				let (function, extra, _) = raw_payload.deconstruct();
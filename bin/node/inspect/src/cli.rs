@@ -59,4 +59,45 @@ pub enum InspectSubCmd {
 		#[arg(value_name = "BLOCK:INDEX or BYTES")]
 		input: String,
 	},
+	/// Print the state of the DC node pallet from the database.
+	Dc {
+		/// Block to read the state at, the best block by default.
+		///
+		/// Can be either a block hash (no 0x prefix) or a number.
+		#[arg(long, value_name = "HASH or NUMBER")]
+		at: Option<String>,
+
+		/// Print the output as JSON instead of tables.
+		#[arg(long)]
+		json: bool,
+
+		#[allow(missing_docs)]
+		#[clap(subcommand)]
+		query: DcQuery,
+	},
+}
+
+/// The DC state to print.
+#[derive(Debug, clap::Subcommand)]
+pub enum DcQuery {
+	/// Storage nodes grouped by status, with the space totals of every status.
+	Peers,
+	/// Storage nodes of every stash.
+	Stashes,
+	/// Storage, expiry and sub accounts of a wallet account.
+	User {
+		/// The SS58 address of the wallet account.
+		#[arg(value_name = "ACCOUNT")]
+		account: String,
+	},
+	/// Histogram of the files by the number of replicas.
+	Files,
+	/// Reports against storage nodes.
+	Reports {
+		/// Only print the reports of this era.
+		#[arg(long)]
+		era: Option<u32>,
+	},
+	/// The reward pots that are not paid out yet.
+	Rewards,
 }
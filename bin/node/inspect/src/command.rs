@@ -19,12 +19,16 @@
 //! Command ran by the CLI
 
 use crate::{
-	cli::{InspectCmd, InspectSubCmd},
-	Inspector,
+	cli::{DcQuery, InspectCmd, InspectSubCmd},
+	dc::{render, DcInspector},
+	BlockAddress, BlockAddressFor, Inspector,
 };
+use node_primitives::AccountId;
 use sc_cli::{CliConfiguration, ImportParams, Result, SharedParams};
 use sc_executor::NativeElseWasmExecutor;
 use sc_service::{new_full_client, Configuration, NativeExecutionDispatch};
+use sp_blockchain::HeaderBackend;
+use sp_core::crypto::Ss58Codec;
 use sp_runtime::traits::Block;
 use std::str::FromStr;
 
@@ -45,21 +49,51 @@ impl InspectCmd {
 		);
 
 		let client = new_full_client::<B, RA, _>(&config, None, executor)?;
-		let inspect = Inspector::<B>::new(client);
 
 		match &self.command {
 			InspectSubCmd::Block { input } => {
+				let inspect = Inspector::<B>::new(client);
 				let input = input.parse()?;
 				let res = inspect.block(input).map_err(|e| format!("{}", e))?;
 				println!("{}", res);
 				Ok(())
 			},
 			InspectSubCmd::Extrinsic { input } => {
+				let inspect = Inspector::<B>::new(client);
 				let input = input.parse()?;
 				let res = inspect.extrinsic(input).map_err(|e| format!("{}", e))?;
 				println!("{}", res);
 				Ok(())
 			},
+			InspectSubCmd::Dc { at, json, query } => {
+				let at = match at {
+					None => client.info().best_hash,
+					Some(at) => match at.parse::<BlockAddressFor<B>>()? {
+						BlockAddress::Hash(hash) => hash,
+						BlockAddress::Number(number) => client
+							.hash(number)
+							.map_err(|e| format!("{}", e))?
+							.ok_or_else(|| format!("Could not find block {:?}", number))?,
+						BlockAddress::Bytes(_) => Err("Expected a block hash or number".to_string())?,
+					},
+				};
+				let inspect = DcInspector::<B, sc_service::TFullBackend<B>, _>::new(&client, at);
+				let res = match query {
+					DcQuery::Peers => inspect.peers().and_then(|r| render(&r, *json)),
+					DcQuery::Stashes => inspect.stashes().and_then(|r| render(&r, *json)),
+					DcQuery::User { account } => {
+						let account = AccountId::from_ss58check(account)
+							.map_err(|e| format!("Invalid account {}: {:?}", account, e))?;
+						inspect.user(&account).and_then(|r| render(&r, *json))
+					},
+					DcQuery::Files => inspect.files().and_then(|r| render(&r, *json)),
+					DcQuery::Reports { era } => inspect.reports(*era).and_then(|r| render(&r, *json)),
+					DcQuery::Rewards => inspect.rewards().and_then(|r| render(&r, *json)),
+				}
+				.map_err(|e| format!("{}", e))?;
+				println!("{}", res);
+				Ok(())
+			},
 		}
 	}
 }
@@ -0,0 +1,478 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020-2022 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Offline inspection of the state of the DC node pallet.
+//!
+//! The state is read from the storage of a block in the node database, so that it is available
+//! when no RPC is running.
+
+use crate::Error;
+use codec::Decode;
+use dc_primitives::{storage_map_key, storage_value_key, WALLET_ACCOUNT_STORAGE};
+use node_primitives::{AccountId, Balance, BlockNumber};
+use pallet_dc_node::{
+	node_status_name, FileInfo, ReportInfo, ReportType, StorageNode, UserStorage,
+};
+use sc_client_api::{backend::Backend, StorageProvider};
+use serde::Serialize;
+use sp_core::{crypto::Ss58Codec, storage::StorageKey};
+use sp_runtime::traits::Block;
+use sp_staking::EraIndex;
+use std::{collections::BTreeMap, fmt, marker::PhantomData};
+
+/// The length of the `Twox64Concat` hash in front of the encoded key.
+const TWOX_64_LEN: usize = 8;
+
+fn report_type_name(report_type: &ReportType) -> &'static str {
+	match report_type {
+		ReportType::ReportTeeFaking => "report-tee-faking",
+		ReportType::VerifyTeeFaking => "verify-tee-faking",
+		ReportType::ReportPeerOffchain => "report-peer-offchain",
+		ReportType::ReportPeerNoResponse => "report-peer-no-response",
+	}
+}
+
+fn text(bytes: &[u8]) -> String {
+	String::from_utf8_lossy(bytes).into_owned()
+}
+
+/// Decode the key following the prefix and its `Twox64Concat` hash.
+fn decode_key<K: Decode>(prefix: &[u8], key: &[u8]) -> Result<K, Error> {
+	Ok(K::decode(&mut &key[prefix.len() + TWOX_64_LEN..])?)
+}
+
+/// A storage node.
+#[derive(Debug, Serialize)]
+pub struct PeerRow {
+	/// Peer id of the node.
+	pub peer_id: String,
+	/// Stash of the node.
+	pub stash: String,
+	/// Total space size unit byte.
+	pub total_space: u64,
+	/// Free space size unit byte.
+	pub free_space: u64,
+	/// The block number of the last work report.
+	pub report_number: BlockNumber,
+}
+
+/// The storage nodes of a status.
+#[derive(Debug, Serialize)]
+pub struct PeerGroup {
+	/// Name of the status.
+	pub status: &'static str,
+	/// Total space of the nodes unit byte.
+	pub total_space: u64,
+	/// Free space of the nodes unit byte.
+	pub free_space: u64,
+	/// The nodes.
+	pub peers: Vec<PeerRow>,
+}
+
+/// Storage nodes grouped by status.
+#[derive(Debug, Serialize)]
+pub struct PeersReport(pub Vec<PeerGroup>);
+
+impl fmt::Display for PeersReport {
+	fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+		for group in self.0.iter() {
+			writeln!(
+				fmt,
+				"{} ({} peers, total space {}, free space {})",
+				group.status,
+				group.peers.len(),
+				group.total_space,
+				group.free_space
+			)?;
+			for peer in group.peers.iter() {
+				writeln!(
+					fmt,
+					"  {:<56} {:<50} {:>16} {:>16} {:>10}",
+					peer.peer_id, peer.stash, peer.total_space, peer.free_space, peer.report_number
+				)?;
+			}
+		}
+		Ok(())
+	}
+}
+
+/// The storage nodes of stashes.
+#[derive(Debug, Serialize)]
+pub struct StashesReport(pub BTreeMap<String, Vec<String>>);
+
+impl fmt::Display for StashesReport {
+	fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+		for (stash, peers) in self.0.iter() {
+			writeln!(fmt, "{} ({} peers)", stash, peers.len())?;
+			for peer in peers.iter() {
+				writeln!(fmt, "  {}", peer)?;
+			}
+		}
+		Ok(())
+	}
+}
+
+/// Storage information of a wallet account and its sub accounts.
+#[derive(Debug, Serialize)]
+pub struct UserReport {
+	/// The wallet account.
+	pub account: String,
+	/// The parent account, the account itself if it is not a sub account.
+	pub parent_account: String,
+	/// Subscribed space size unit byte.
+	pub subscribe_space: u64,
+	/// Used space size unit byte.
+	pub used_space: u64,
+	/// The block number that the subscription expires at.
+	pub expire_number: BlockNumber,
+	/// The nodes where the login information is stored.
+	pub peers: Vec<String>,
+	/// The sub accounts.
+	pub sub_accounts: Vec<String>,
+}
+
+impl fmt::Display for UserReport {
+	fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+		writeln!(fmt, "Account:         {}", self.account)?;
+		writeln!(fmt, "Parent account:  {}", self.parent_account)?;
+		writeln!(fmt, "Subscribe space: {}", self.subscribe_space)?;
+		writeln!(fmt, "Used space:      {}", self.used_space)?;
+		writeln!(fmt, "Expire number:   {}", self.expire_number)?;
+		writeln!(fmt, "Peers ({}):", self.peers.len())?;
+		for peer in self.peers.iter() {
+			writeln!(fmt, "  {}", peer)?;
+		}
+		writeln!(fmt, "Sub accounts ({}):", self.sub_accounts.len())?;
+		for sub_account in self.sub_accounts.iter() {
+			writeln!(fmt, "  {}", sub_account)?;
+		}
+		Ok(())
+	}
+}
+
+/// The files with a number of replicas.
+#[derive(Debug, Default, Serialize)]
+pub struct ReplicationBucket {
+	/// The number of replicas.
+	pub replicas: usize,
+	/// The number of files.
+	pub files: u64,
+	/// The total size of the files unit byte.
+	pub size: u64,
+}
+
+/// Histogram of the files by the number of replicas.
+#[derive(Debug, Serialize)]
+pub struct FilesReport(pub Vec<ReplicationBucket>);
+
+impl fmt::Display for FilesReport {
+	fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+		writeln!(fmt, "{:>8} {:>12} {:>20}", "replicas", "files", "size")?;
+		for bucket in self.0.iter() {
+			writeln!(fmt, "{:>8} {:>12} {:>20}", bucket.replicas, bucket.files, bucket.size)?;
+		}
+		Ok(())
+	}
+}
+
+/// A report against a storage node.
+#[derive(Debug, Serialize)]
+pub struct ReportRow {
+	/// The era of the report.
+	pub era: EraIndex,
+	/// Type of the report.
+	pub report_type: &'static str,
+	/// The reported node.
+	pub peer_id: String,
+	/// The reporters.
+	pub reporters: Vec<String>,
+}
+
+/// Reports against storage nodes by era.
+#[derive(Debug, Serialize)]
+pub struct ReportsReport(pub Vec<ReportRow>);
+
+impl fmt::Display for ReportsReport {
+	fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+		for report in self.0.iter() {
+			writeln!(
+				fmt,
+				"era {:<6} {:<24} {:<56} {} reporters",
+				report.era,
+				report.report_type,
+				report.peer_id,
+				report.reporters.len()
+			)?;
+		}
+		Ok(())
+	}
+}
+
+/// The rewards collected from storage purchases and not paid out yet.
+#[derive(Debug, Serialize)]
+pub struct RewardsReport {
+	/// The storage nodes' share.
+	pub storage_reward_total: Balance,
+	/// The apps' share.
+	pub app_reward_total: Balance,
+}
+
+impl fmt::Display for RewardsReport {
+	fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+		writeln!(fmt, "Storage reward total: {}", self.storage_reward_total)?;
+		writeln!(fmt, "App reward total:     {}", self.app_reward_total)
+	}
+}
+
+/// Render a report as a table or as JSON.
+pub fn render<R: Serialize + fmt::Display>(report: &R, json: bool) -> Result<String, Error> {
+	if json {
+		Ok(serde_json::to_string_pretty(report)?)
+	} else {
+		Ok(report.to_string())
+	}
+}
+
+/// Reader of the DC node pallet state at a block.
+pub struct DcInspector<'a, TBlock: Block, TBackend, TClient> {
+	client: &'a TClient,
+	at: TBlock::Hash,
+	_backend: PhantomData<TBackend>,
+}
+
+impl<'a, TBlock, TBackend, TClient> DcInspector<'a, TBlock, TBackend, TClient>
+where
+	TBlock: Block,
+	TBackend: Backend<TBlock>,
+	TClient: StorageProvider<TBlock, TBackend>,
+{
+	/// Create new instance of the inspector reading the state at the block.
+	pub fn new(client: &'a TClient, at: TBlock::Hash) -> Self {
+		DcInspector { client, at, _backend: Default::default() }
+	}
+
+	fn value<V: Decode>(&self, key: Vec<u8>) -> Result<Option<V>, Error> {
+		self.client
+			.storage(self.at, &StorageKey(key))?
+			.map(|data| V::decode(&mut &data.0[..]))
+			.transpose()
+			.map_err(Into::into)
+	}
+
+	/// Decode the entries under the prefix of a `Twox64Concat` map, or of a `Twox64Concat`
+	/// double map with the first key given.
+	fn entries<K: Decode, V: Decode>(&self, prefix: Vec<u8>) -> Result<Vec<(K, V)>, Error> {
+		self.client
+			.storage_pairs(self.at, &StorageKey(prefix.clone()))?
+			.into_iter()
+			.map(|(key, data)| Ok((decode_key(&prefix, &key.0)?, V::decode(&mut &data.0[..])?)))
+			.collect()
+	}
+
+	/// Storage nodes grouped by status, with the space totals of every status.
+	pub fn peers(&self) -> Result<PeersReport, Error> {
+		let mut groups = BTreeMap::<u32, PeerGroup>::new();
+		for (peer_id, node) in self
+			.entries::<Vec<u8>, StorageNode<AccountId, BlockNumber>>(storage_value_key(b"Peers"))?
+		{
+			let group = groups.entry(node.status).or_insert_with(|| PeerGroup {
+				status: node_status_name(node.status),
+				total_space: 0,
+				free_space: 0,
+				peers: Vec::new(),
+			});
+			group.total_space = group.total_space.saturating_add(node.total_space);
+			group.free_space = group.free_space.saturating_add(node.free_space);
+			group.peers.push(PeerRow {
+				peer_id: text(&peer_id),
+				stash: node.stash.to_ss58check(),
+				total_space: node.total_space,
+				free_space: node.free_space,
+				report_number: node.report_number,
+			});
+		}
+		Ok(PeersReport(groups.into_values().collect()))
+	}
+
+	/// Storage nodes of every stash.
+	pub fn stashes(&self) -> Result<StashesReport, Error> {
+		let stash_peers =
+			self.entries::<AccountId, Vec<Vec<u8>>>(storage_value_key(b"StashPeers"))?;
+		Ok(StashesReport(
+			stash_peers
+				.into_iter()
+				.map(|(stash, peers)| {
+					(stash.to_ss58check(), peers.iter().map(|peer_id| text(peer_id)).collect())
+				})
+				.collect(),
+		))
+	}
+
+	/// Storage, expiry and sub accounts of a wallet account.
+	pub fn user(&self, account: &AccountId) -> Result<UserReport, Error> {
+		let user = self
+			.value::<UserStorage<AccountId, BlockNumber, Balance>>(storage_map_key(
+				WALLET_ACCOUNT_STORAGE,
+				account,
+			))?
+			.ok_or_else(|| {
+				Error::NotFound(format!("Could not find wallet account {}", account.to_ss58check()))
+			})?;
+		// Sub accounts are only known by their parent account, so every user is read.
+		let sub_accounts = self
+			.entries::<AccountId, UserStorage<AccountId, BlockNumber, Balance>>(storage_value_key(
+				WALLET_ACCOUNT_STORAGE,
+			))?
+			.iter()
+			.filter(|(who, sub)| who != account && sub.parent_account == *account)
+			.map(|(who, _)| who.to_ss58check())
+			.collect();
+
+		Ok(UserReport {
+			account: account.to_ss58check(),
+			parent_account: user.parent_account.to_ss58check(),
+			subscribe_space: user.subscribe_space,
+			used_space: user.used_space,
+			expire_number: user.expire_number,
+			peers: user.peers.iter().map(|peer_id| text(peer_id)).collect(),
+			sub_accounts,
+		})
+	}
+
+	/// Histogram of the files by the number of replicas.
+	pub fn files(&self) -> Result<FilesReport, Error> {
+		let mut buckets = BTreeMap::<usize, ReplicationBucket>::new();
+		for (_, file) in
+			self.entries::<Vec<u8>, FileInfo<AccountId>>(storage_value_key(b"Files"))?
+		{
+			let replicas = file.peers.len();
+			let bucket = buckets
+				.entry(replicas)
+				.or_insert_with(|| ReplicationBucket { replicas, ..Default::default() });
+			bucket.files += 1;
+			bucket.size = bucket.size.saturating_add(file.file_size);
+		}
+		Ok(FilesReport(buckets.into_values().collect()))
+	}
+
+	/// Reports against storage nodes, of an era or of all eras.
+	pub fn reports(&self, era: Option<EraIndex>) -> Result<ReportsReport, Error> {
+		let rows = match era {
+			Some(era) => self
+				.entries::<ReportInfo, Vec<AccountId>>(storage_map_key(b"ReportsInEra", &era))?
+				.into_iter()
+				.map(|(info, reporters)| (era, info, reporters))
+				.collect::<Vec<_>>(),
+			None => self
+				.entries::<(EraIndex, [u8; TWOX_64_LEN], ReportInfo), Vec<AccountId>>(
+					storage_value_key(b"ReportsInEra"),
+				)?
+				.into_iter()
+				.map(|((era, _, info), reporters)| (era, info, reporters))
+				.collect(),
+		};
+		Ok(ReportsReport(
+			rows.into_iter()
+				.map(|(era, info, reporters)| ReportRow {
+					era,
+					report_type: report_type_name(&info.report_type),
+					peer_id: text(&info.peer_id),
+					reporters: reporters.iter().map(|who| who.to_ss58check()).collect(),
+				})
+				.collect(),
+		))
+	}
+
+	/// The reward pots that are not paid out yet.
+	pub fn rewards(&self) -> Result<RewardsReport, Error> {
+		Ok(RewardsReport {
+			storage_reward_total: self
+				.value(storage_value_key(b"StorageRewardTotal"))?
+				.unwrap_or_default(),
+			app_reward_total: self.value(storage_value_key(b"AppRewardTotal"))?.unwrap_or_default(),
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use codec::Encode;
+	use sp_core::bytes::from_hex;
+
+	#[test]
+	fn should_decode_peers_entry() {
+		// `Peers` key of the peer id `b"peer"`
+		let key = from_hex("0x50d202ba26a5dc6d31e538a4e0cd17842db2d969c1d2247f7efebb578ef18d2b11a892bcf456a0b71070656572").unwrap();
+		assert_eq!(storage_map_key(b"Peers", &b"peer".to_vec()), key);
+		let node = StorageNode::<AccountId, BlockNumber> {
+			req_account: AccountId::new([1; 32]),
+			stash: AccountId::new([2; 32]),
+			total_space: 100,
+			free_space: 40,
+			status: 3,
+			report_number: 10,
+			staked_number: 5,
+			reward_number: 5,
+			ip_address: Vec::new(),
+			sgx_version_number: 2,
+		};
+
+		let peer_id = decode_key::<Vec<u8>>(&storage_value_key(b"Peers"), &key).unwrap();
+		let decoded =
+			StorageNode::<AccountId, BlockNumber>::decode(&mut &node.encode()[..]).unwrap();
+		assert_eq!(peer_id, b"peer".to_vec());
+		assert_eq!(decoded, node);
+		assert_eq!(node_status_name(decoded.status), "onchain");
+	}
+
+	#[test]
+	fn should_decode_reports_in_era_entry() {
+		// `ReportsInEra` key of the era 3 and the offchain report of the peer id `b"peer"`
+		let key = from_hex("0x50d202ba26a5dc6d31e538a4e0cd17849c69a22b2ca2c4aeeb1df86c9be3d1a9bfb27f1eaef06bb90300000063d451f2d6b22cf0031070656572").unwrap();
+
+		let (era, _, info) = decode_key::<(EraIndex, [u8; TWOX_64_LEN], ReportInfo)>(
+			&storage_value_key(b"ReportsInEra"),
+			&key,
+		)
+		.unwrap();
+		assert_eq!(era, 3);
+		assert_eq!(report_type_name(&info.report_type), "report-peer-offchain");
+		assert_eq!(info.peer_id, b"peer".to_vec());
+
+		// The entries of an era are read after the era key
+		let prefix = storage_map_key(b"ReportsInEra", &3u32);
+		assert!(key.starts_with(&prefix));
+		let info = decode_key::<ReportInfo>(&prefix, &key).unwrap();
+		assert_eq!(info.peer_id, b"peer".to_vec());
+	}
+
+	#[test]
+	fn should_group_files_by_replicas() {
+		let report = FilesReport(vec![
+			ReplicationBucket { replicas: 1, files: 2, size: 30 },
+			ReplicationBucket { replicas: 3, files: 1, size: 10 },
+		]);
+
+		assert_eq!(
+			report.to_string(),
+			"replicas        files                 size\n       1            2                   30\n       3            1                   10\n"
+		);
+		assert!(render(&report, true).unwrap().contains("\"replicas\": 3"));
+	}
+}
@@ -20,12 +20,14 @@
 //! about blocks and extrinsics.
 //!
 //! The blocks and extrinsics can either be retrieved from the database (on-chain),
-//! or a raw SCALE-encoding can be provided.
+//! or a raw SCALE-encoding can be provided. The state of the DC node pallet can be
+//! printed from the database as well, see [`dc`].
 
 #![warn(missing_docs)]
 
 pub mod cli;
 pub mod command;
+pub mod dc;
 
 use codec::{Decode, Encode};
 use sc_client_api::BlockBackend;
@@ -89,6 +91,9 @@ pub enum Error {
 	/// Given block has not been found.
 	#[error("{0}")]
 	NotFound(String),
+	/// Could not serialize the output to JSON.
+	#[error(transparent)]
+	Json(#[from] serde_json::Error),
 }
 
 /// A helper trait to access block headers and bodies.
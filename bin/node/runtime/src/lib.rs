@@ -33,7 +33,7 @@ use frame_support::{
 	parameter_types,
 	traits::{
 		fungible::ItemOf, AsEnsureOriginWithArg, ConstBool, ConstU128, ConstU16, ConstU32,
-		Currency, EitherOfDiverse, EqualPrivilegeOnly, Everything, Imbalance, InstanceFilter,
+		Currency, EitherOfDiverse, EqualPrivilegeOnly, Imbalance, InstanceFilter,
 		KeyOwnerProofSystem, LockIdentifier, Nothing, OnUnbalanced, U128CurrencyToVote,
 		WithdrawReasons,
 	},
@@ -131,10 +131,10 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	// and set impl_version to 0. If only runtime
 	// implementation changes and behavior does not, then leave spec_version as
 	// is and increment impl_version.
-	spec_version: 100,
+	spec_version: 101,
 	impl_version: 0,
 	apis: RUNTIME_API_VERSIONS,
-	transaction_version: 2,
+	transaction_version: 3,
 	state_version: 1,
 };
 
@@ -207,7 +207,7 @@ parameter_types! {
 const_assert!(NORMAL_DISPATCH_RATIO.deconstruct() >= AVERAGE_ON_INITIALIZE_RATIO.deconstruct());
 
 impl frame_system::Config for Runtime {
-	type BaseCallFilter = Everything;
+	type BaseCallFilter = pallet_dc_node::PausedCalls<Runtime>;
 	type BlockWeights = RuntimeBlockWeights;
 	type BlockLength = RuntimeBlockLength;
 	type DbWeight = RocksDbWeight;
@@ -586,7 +586,6 @@ impl pallet_staking::Config for Runtime {
 	type OnStakerSlash = NominationPools;
 	type WeightInfo = pallet_staking::weights::SubstrateWeight<Runtime>;
 	type BenchmarkingConfig = StakingBenchmarkingConfig;
-	#[cfg(feature = "dc_lib")]
 	type DcProvider = DcNode;
 }
 
@@ -1267,6 +1266,7 @@ where
 			frame_system::CheckNonce::<Runtime>::from(nonce),
 			frame_system::CheckWeight::<Runtime>::new(),
 			pallet_asset_tx_payment::ChargeAssetTxPayment::<Runtime>::from(tip, None),
+			pallet_dc_node::CheckPeerQuota::<Runtime>::new(),
 		);
 		let raw_payload = SignedPayload::new(call, extra)
 			.map_err(|e| {
@@ -1586,7 +1586,8 @@ impl pallet_nfts::Config for Runtime {
 	#[cfg(feature = "runtime-benchmarks")]
 	type Helper = ();
 	type CreateOrigin = AsEnsureOriginWithArg<EnsureSigned<AccountId>>;
-	type Locker = ();
+	type Locker = DcNode;
+	type CallbackHandle = DcNode;
 }
 
 impl pallet_transaction_storage::Config for Runtime {
@@ -1696,6 +1697,12 @@ impl pallet_alliance::Config for Runtime {
 
 parameter_types! {
 	pub DefaultAccountId: AccountId = hex_literal::hex!("2bb33fdff91b4d6adfe15c38cccc71ef62eafbf19a791bf6ee5927dfd2a59891").into();
+	pub const DcLivenessProbeInterval: BlockNumber = 10 * MINUTES;
+	pub const DcMaxLivenessProbes: u32 = 100;
+	pub const DcNodeUnsignedPriority: TransactionPriority = TransactionPriority::max_value() / 2;
+	pub const DcAppDeposit: Balance = 100 * DOLLARS;
+	pub const DcPriceAdjustmentInterval: BlockNumber = 1 * HOURS;
+	pub const DcPeerCallWindow: BlockNumber = 1 * MINUTES;
 }
 
 impl pallet_dc_node::Config for Runtime {
@@ -1705,8 +1712,20 @@ impl pallet_dc_node::Config for Runtime {
 	type AccountStore = frame_system::Pallet<Runtime>;
 	type DefaultAccountId = DefaultAccountId;
 	type StakingProvider = Staking;
+	type ReportOffence = Offences;
 	type WeightInfo = pallet_dc_node::weights::SubstrateWeight<Runtime>;
 	type BlockMultiplier = TransactionPayment;
+	type NftCollectionId = u32;
+	type Nfts = Nfts;
+	type AuthorityId = pallet_dc_node::liveness::sr25519::AuthorityId;
+	type LivenessProbeInterval = DcLivenessProbeInterval;
+	type MaxLivenessProbes = DcMaxLivenessProbes;
+	type UnsignedPriority = DcNodeUnsignedPriority;
+	type PriceAdjustmentInterval = DcPriceAdjustmentInterval;
+	type PeerCallWindow = DcPeerCallWindow;
+	type AppDeposit = DcAppDeposit;
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = Nfts;
 }
 
 construct_runtime!(
@@ -1803,6 +1822,7 @@ pub type SignedExtra = (
 	frame_system::CheckNonce<Runtime>,
 	frame_system::CheckWeight<Runtime>,
 	pallet_asset_tx_payment::ChargeAssetTxPayment<Runtime>,
+	pallet_dc_node::CheckPeerQuota<Runtime>,
 );
 
 /// Unchecked extrinsic type as expected by this runtime.
@@ -1828,6 +1848,11 @@ type Migrations = (
 	pallet_nomination_pools::migration::v2::MigrateToV2<Runtime>,
 	pallet_alliance::migration::Migration<Runtime>,
 	pallet_contracts::Migration<Runtime>,
+	pallet_dc_node::migrations::v1::MigrateToV1<Runtime>,
+	pallet_dc_node::migrations::v2::MigrateToV2<Runtime>,
+	pallet_dc_node::migrations::v3::MigrateToV3<Runtime>,
+	pallet_dc_node::migrations::v4::MigrateToV4<Runtime>,
+	pallet_staking::migrations::v13::MigrateToV13<Runtime>,
 );
 
 /// MMR helper types.
@@ -1996,6 +2021,24 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl pallet_dc_node_runtime_api::DcNodeApi<Block, AccountId, BlockNumber, Balance> for Runtime {
+		fn endpoints(
+			kind: pallet_dc_node::EndpointKind,
+			region: Option<Vec<u8>>,
+			min_api_version: u32,
+		) -> Vec<pallet_dc_node::NodeEndpoint<AccountId, BlockNumber>> {
+			DcNode::endpoints(kind, region, min_api_version)
+		}
+
+		fn storage_package_price(package_id: u32) -> Option<Balance> {
+			DcNode::storage_package_price(package_id)
+		}
+
+		fn network_stats() -> pallet_dc_node::NetworkStats<Balance> {
+			DcNode::network_stats()
+		}
+	}
+
 	impl sp_consensus_babe::BabeApi<Block> for Runtime {
 		fn configuration() -> sp_consensus_babe::BabeConfiguration {
 			let epoch_config = Babe::epoch_config().unwrap_or(BABE_GENESIS_EPOCH_CONFIG);
@@ -35,8 +35,8 @@ use crate::{
 use codec::{Decode, Encode};
 use futures::executor;
 use kitchensink_runtime::{
-	constants::currency::DOLLARS, AccountId, BalancesCall, CheckedExtrinsic, MinimumPeriod,
-	RuntimeCall, Signature, SystemCall, UncheckedExtrinsic,
+	constants::currency::DOLLARS, AccountId, BalancesCall, CheckedExtrinsic, GenesisConfig,
+	MinimumPeriod, RuntimeCall, Signature, SystemCall, UncheckedExtrinsic,
 };
 use node_primitives::Block;
 use sc_block_builder::BlockBuilderProvider;
@@ -53,7 +53,7 @@ use sp_consensus::BlockOrigin;
 use sp_core::{blake2_256, ed25519, sr25519, traits::SpawnNamed, ExecutionContext, Pair, Public};
 use sp_inherents::InherentData;
 use sp_runtime::{
-	generic::BlockId,
+	generic::{BlockId, Digest},
 	traits::{Block as BlockT, IdentifyAccount, Verify, Zero},
 	OpaqueExtrinsic,
 };
@@ -83,6 +83,13 @@ impl BenchPair {
 			Self::Ed25519(pair) => pair.sign(payload).into(),
 		}
 	}
+
+	fn sign_raw(&self, payload: &[u8]) -> Vec<u8> {
+		match self {
+			Self::Sr25519(pair) => pair.sign(payload).0.to_vec(),
+			Self::Ed25519(pair) => pair.sign(payload).0.to_vec(),
+		}
+	}
 }
 
 /// Drop system cache.
@@ -151,12 +158,14 @@ pub struct BenchDb {
 	keyring: BenchKeyring,
 	directory_guard: Guard,
 	database_type: DatabaseType,
+	genesis: fn(&mut GenesisConfig),
 }
 
 impl Clone for BenchDb {
 	fn clone(&self) -> Self {
 		let keyring = self.keyring.clone();
 		let database_type = self.database_type;
+		let genesis = self.genesis;
 		let dir = tempfile::tempdir().expect("temp dir creation failed");
 
 		let seed_dir = self.directory_guard.0.path();
@@ -181,7 +190,7 @@ impl Clone for BenchDb {
 		// from database).
 		drop_system_cache();
 
-		BenchDb { keyring, directory_guard: Guard(dir), database_type }
+		BenchDb { keyring, directory_guard: Guard(dir), database_type, genesis }
 	}
 }
 
@@ -349,6 +358,20 @@ impl BenchDb {
 		database_type: DatabaseType,
 		keyring_length: usize,
 		key_types: KeyTypes,
+	) -> Self {
+		Self::with_genesis(database_type, keyring_length, key_types, |_| {})
+	}
+
+	/// New immutable benchmarking database, whose genesis is changed by `genesis` after the
+	/// accounts of the keyring are endowed.
+	///
+	/// See [`BenchDb::new`] method documentation for more information about the purpose
+	/// of this structure.
+	pub fn with_genesis(
+		database_type: DatabaseType,
+		keyring_length: usize,
+		key_types: KeyTypes,
+		genesis: fn(&mut GenesisConfig),
 	) -> Self {
 		let keyring = BenchKeyring::new(keyring_length, key_types);
 
@@ -359,10 +382,10 @@ impl BenchDb {
 			dir.path().to_string_lossy(),
 		);
 		let (_client, _backend, _task_executor) =
-			Self::bench_client(database_type, dir.path(), Profile::Native, &keyring);
+			Self::bench_client(database_type, dir.path(), Profile::Native, &keyring, genesis);
 		let directory_guard = Guard(dir);
 
-		BenchDb { keyring, directory_guard, database_type }
+		BenchDb { keyring, directory_guard, database_type, genesis }
 	}
 
 	/// New immutable benchmarking database.
@@ -387,6 +410,7 @@ impl BenchDb {
 		dir: &std::path::Path,
 		profile: Profile,
 		keyring: &BenchKeyring,
+		genesis: fn(&mut GenesisConfig),
 	) -> (Client, std::sync::Arc<Backend>, TaskExecutor) {
 		let db_config = sc_client_db::DatabaseSettings {
 			trie_cache_maximum_size: Some(16 * 1024 * 1024),
@@ -406,8 +430,10 @@ impl BenchDb {
 			2,
 		);
 		let client_config = sc_service::ClientConfig::default();
+		let mut genesis_config = keyring.generate_genesis();
+		genesis(&mut genesis_config);
 		let genesis_block_builder = sc_service::GenesisBlockBuilder::new(
-			&keyring.generate_genesis(),
+			&genesis_config,
 			!client_config.no_genesis,
 			backend.clone(),
 			executor.clone(),
@@ -464,23 +490,60 @@ impl BenchDb {
 			self.directory_guard.path(),
 			Profile::Wasm,
 			&self.keyring,
+			self.genesis,
 		);
 
 		client
 	}
 
+	/// Keyring of the accounts endowed in this database.
+	pub fn keyring(&self) -> &BenchKeyring {
+		&self.keyring
+	}
+
 	/// Generate new block using this database.
 	pub fn generate_block(&mut self, content: BlockContent) -> Block {
 		let client = self.client();
+		let inherents = self.generate_inherents(&client);
+		let extrinsics = self.block_content(content, &client).collect::<Vec<_>>();
+		Self::build_block(&client, Default::default(), inherents, extrinsics)
+	}
+
+	/// Generate new block with the digest, the inherents and the extrinsics, which are created
+	/// with the client and the keyring of this database.
+	pub fn generate_block_with(
+		&mut self,
+		content: impl FnOnce(
+			&Client,
+			&BenchKeyring,
+		) -> (Digest, Vec<OpaqueExtrinsic>, Vec<OpaqueExtrinsic>),
+	) -> Block {
+		let client = self.client();
+		let (digest, inherents, extrinsics) = content(&client, &self.keyring);
+		Self::build_block(&client, digest, inherents, extrinsics)
+	}
+
+	/// Import the block into this database, so that the contexts created afterwards start from
+	/// its state.
+	pub fn import_block(&mut self, block: Block) {
+		let mut client = self.client();
+		import_block_into(&mut client, block);
+	}
 
-		let mut block = client.new_block(Default::default()).expect("Block creation failed");
+	fn build_block(
+		client: &Client,
+		digest: Digest,
+		inherents: Vec<OpaqueExtrinsic>,
+		extrinsics: Vec<OpaqueExtrinsic>,
+	) -> Block {
+		let mut block = client.new_block(digest).expect("Block creation failed");
 
-		for extrinsic in self.generate_inherents(&client) {
+		for extrinsic in inherents {
 			block.push(extrinsic).expect("Push inherent failed");
 		}
 
 		let start = std::time::Instant::now();
-		for opaque in self.block_content(content, &client) {
+		for opaque in extrinsics {
 			match block.push(opaque) {
 				Err(sp_blockchain::Error::ApplyExtrinsicFailed(
 					sp_blockchain::ApplyExtrinsicFailed::Validity(e),
@@ -508,9 +571,9 @@ impl BenchDb {
 
 	/// Clone this database and create context for testing/benchmarking.
 	pub fn create_context(&self, profile: Profile) -> BenchContext {
-		let BenchDb { directory_guard, keyring, database_type } = self.clone();
+		let BenchDb { directory_guard, keyring, database_type, genesis } = self.clone();
 		let (client, backend, task_executor) =
-			Self::bench_client(database_type, directory_guard.path(), profile, &keyring);
+			Self::bench_client(database_type, directory_guard.path(), profile, &keyring, genesis);
 
 		BenchContext {
 			client: Arc::new(client),
@@ -567,6 +630,12 @@ impl BenchKeyring {
 		self.accounts.keys().nth(index).expect("Failed to get account").clone()
 	}
 
+	/// Sign the raw message with keypair from this keyring, like the apps that sign data for
+	/// storage nodes.
+	pub fn sign_message(&self, who: &AccountId, message: &[u8]) -> Vec<u8> {
+		self.accounts.get(who).expect("Account id not found in keyring").sign_raw(message)
+	}
+
 	/// Sign transaction with keypair from this keyring.
 	pub fn sign(
 		&self,
@@ -676,31 +745,38 @@ where
 	AccountPublic::from(get_from_seed::<TPublic>(seed)).into_account()
 }
 
+/// Import the block on top of the best block of the client.
+fn import_block_into<C: BlockImport<Block>>(client: &mut C, block: Block)
+where
+	C::Error: std::fmt::Debug,
+{
+	let mut import_params =
+		BlockImportParams::new(BlockOrigin::NetworkBroadcast, block.header.clone());
+	import_params.body = Some(block.extrinsics().to_vec());
+	import_params.fork_choice = Some(ForkChoiceStrategy::LongestChain);
+
+	assert_eq!(
+		futures::executor::block_on(client.import_block(import_params, Default::default()))
+			.expect("Failed to import block"),
+		ImportResult::Imported(ImportedAux {
+			header_only: false,
+			clear_justification_requests: false,
+			needs_justification: false,
+			bad_justification: false,
+			is_new_best: true,
+		})
+	);
+}
+
 impl BenchContext {
 	/// Import some block.
 	pub fn import_block(&mut self, block: Block) {
-		let mut import_params =
-			BlockImportParams::new(BlockOrigin::NetworkBroadcast, block.header.clone());
-		import_params.body = Some(block.extrinsics().to_vec());
-		import_params.fork_choice = Some(ForkChoiceStrategy::LongestChain);
+		let best_number = self.client.chain_info().best_number;
+		assert_eq!(block.header.number, best_number + 1);
 
-		assert_eq!(self.client.chain_info().best_number, 0);
-
-		assert_eq!(
-			futures::executor::block_on(
-				self.client.import_block(import_params, Default::default())
-			)
-			.expect("Failed to import block"),
-			ImportResult::Imported(ImportedAux {
-				header_only: false,
-				clear_justification_requests: false,
-				needs_justification: false,
-				bad_justification: false,
-				is_new_best: true,
-			})
-		);
+		import_block_into(&mut &*self.client, block);
 
-		assert_eq!(self.client.chain_info().best_number, 1);
+		assert_eq!(self.client.chain_info().best_number, best_number + 1);
 	}
 
 	/// Database path for the current context.
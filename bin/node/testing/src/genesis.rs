@@ -115,6 +115,13 @@ pub fn config_endowed(code: Option<&[u8]>, extra_endowed: Vec<AccountId>) -> Gen
 			interval_blocks_work_report: 28800u32.into(),
 			interval_blocks_login: 28800u32.into(),
 			tee_report_verify_number: 300u32.into(),
+			peer_call_quota: 100,
+			storage_packages: Default::default(),
+			enclave_ids: Default::default(),
+			dc_program: None,
+			endpoints: Default::default(),
+			app_accounts: Default::default(),
+			wallet_accounts: Default::default(),
 			dev_config: Default::default(),
 		},
 	}
@@ -78,6 +78,7 @@ pub fn signed_extra(nonce: Index, extra_fee: Balance) -> SignedExtra {
 		frame_system::CheckNonce::from(nonce),
 		frame_system::CheckWeight::new(),
 		pallet_asset_tx_payment::ChargeAssetTxPayment::from(extra_fee, None),
+		pallet_dc_node::CheckPeerQuota::new(),
 	)
 }
 
@@ -0,0 +1,351 @@
+//! Fuzzer for random call sequences of the dc node pallet.
+//!
+//! # Running
+//! Running this fuzzer can be done with `cargo hfuzz run call`. `honggfuzz` CLI
+//! options can be used by setting `HFUZZ_RUN_ARGS`, such as `-n 4` to use 4 threads.
+//!
+//! The pallet storage is checked with `do_try_state` after every call by default, set
+//! `SANITY_CHECK_INTERVAL` to check less often.
+//!
+//! # Debugging a panic
+//! Once a panic is found, it can be debugged with
+//! `cargo hfuzz run-debug call hfuzz_workspace/call/*.fuzz`.
+
+use frame_support::traits::{Currency, UnfilteredDispatchable};
+use honggfuzz::fuzz;
+use pallet_dc_node::{
+	mock::*,
+	Call as DcCall, DcProvider, Peers, StashPeers, WalletAccountStorage, Files,
+};
+use rand::{seq::SliceRandom, Rng};
+use sp_runtime::AccountId32;
+
+type T = Test;
+
+/// Number of calls (and blocks) in one era.
+const ERA: BlockNumber = 200;
+/// Number of storage nodes the fuzzer plays with.
+const NODES: u8 = 8;
+/// Number of user and stash accounts the fuzzer plays with.
+const USERS: u8 = 16;
+const PACKAGE_ID: u32 = 1;
+
+fn node_account(index: u8) -> AccountId {
+	AccountId32::new([index + 1; 32])
+}
+
+fn user_account(index: u8) -> AccountId {
+	AccountId32::new([index + 1 + NODES; 32])
+}
+
+fn node_peer_id(index: u8) -> Vec<u8> {
+	vec![index + 1; 32]
+}
+
+/// Grab a random storage node, returning its request account and peer ID.
+fn random_node<R: Rng>(rng: &mut R) -> (RuntimeOrigin, AccountId, Vec<u8>) {
+	let index = rng.gen_range(0..NODES);
+	let who = node_account(index);
+	(RuntimeOrigin::signed(who.clone()), who, node_peer_id(index))
+}
+
+fn random_user<R: Rng>(rng: &mut R) -> AccountId {
+	user_account(rng.gen_range(0..USERS))
+}
+
+/// Grab the ID of a random existing file, or a fresh one.
+fn random_file<R: Rng>(mut rng: &mut R) -> Vec<u8> {
+	let files = Files::<T>::iter_keys().collect::<Vec<_>>();
+	match files.choose(&mut rng) {
+		Some(file_id) if rng.gen::<bool>() => file_id.clone(),
+		_ => vec![rng.gen_range(0..32u8); 32],
+	}
+}
+
+/// A block height close to the current block, sometimes out of the valid window.
+fn random_block_height<R: Rng>(rng: &mut R) -> u32 {
+	let now = System::block_number() as u32;
+	if rng.gen_range(0..10) == 0 {
+		now.saturating_add(rng.gen_range(1..1000))
+	} else {
+		now.saturating_sub(rng.gen_range(0..10))
+	}
+}
+
+fn random_space<R: Rng>(rng: &mut R) -> u64 {
+	rng.gen_range(0..(200 * 1024 * 1024 * 1024 * 1024u64))
+}
+
+fn random_call<R: Rng>(mut rng: &mut R) -> (DcCall<T>, RuntimeOrigin) {
+	match rng.gen_range(0..20) {
+		0 => {
+			let (origin, _, peer_id) = random_node(&mut rng);
+			let total_space = random_space(&mut rng);
+			let call = DcCall::<T>::join_storage_node {
+				peer_id,
+				total_space,
+				free_space: rng.gen_range(0..=total_space),
+				ip_address: vec![36; 32],
+				sgx_version_number: 1,
+				program_version: 1,
+				enclave_id: vec![37; 64],
+				block_height: random_block_height(&mut rng).into(),
+				tee_report: vec![33; 64],
+			};
+			(call, origin)
+		},
+		1 => {
+			let (origin, _, _) = random_node(&mut rng);
+			let total_space = random_space(&mut rng);
+			let miss_files = (0..rng.gen_range(0..3)).map(|_| random_file(&mut rng)).collect();
+			let miss_accounts = (0..rng.gen_range(0..3)).map(|_| random_user(&mut rng)).collect();
+			let call = DcCall::<T>::submit_work_report {
+				total_space,
+				free_space: rng.gen_range(0..=total_space),
+				ip_address: vec![36; 32],
+				miss_files,
+				miss_accounts,
+				program_version: 1,
+				enclave_id: vec![37; 64],
+				block_height: random_block_height(&mut rng),
+				tee_report: vec![33; 64],
+			};
+			(call, origin)
+		},
+		2 => {
+			let stash = random_user(&mut rng);
+			let (_, _, peer_id) = random_node(&mut rng);
+			(DcCall::<T>::set_stash_peer { stash: stash.clone(), peer_id }, RuntimeOrigin::signed(stash))
+		},
+		3 => {
+			let stash = random_user(&mut rng);
+			let (_, _, peer_id) = random_node(&mut rng);
+			(DcCall::<T>::remove_stash_peer { stash: stash.clone(), peer_id }, RuntimeOrigin::signed(stash))
+		},
+		4 => {
+			let stash = random_user(&mut rng);
+			let (_, _, peer_id) = random_node(&mut rng);
+			(DcCall::<T>::stop_stash_peer { stash: stash.clone(), peer_id }, RuntimeOrigin::signed(stash))
+		},
+		5 => {
+			let who = random_user(&mut rng);
+			let for_account = random_user(&mut rng);
+			(DcCall::<T>::purchase_storage { for_account, package_id: PACKAGE_ID }, RuntimeOrigin::signed(who))
+		},
+		6 => {
+			let (origin, _, _) = random_node(&mut rng);
+			let for_account = random_user(&mut rng);
+			let block_height = random_block_height(&mut rng);
+			(DcCall::<T>::add_user_peer { for_account, block_height }, origin)
+		},
+		7 => {
+			let (origin, _, _) = random_node(&mut rng);
+			let for_account = random_user(&mut rng);
+			let block_height = random_block_height(&mut rng);
+			(DcCall::<T>::remove_self_user_peer { for_account, block_height }, origin)
+		},
+		8 => {
+			let (origin, _, _) = random_node(&mut rng);
+			let (_, _, peer_id) = random_node(&mut rng);
+			let for_account = random_user(&mut rng);
+			(DcCall::<T>::remove_other_user_peer { peer_id, for_account }, origin)
+		},
+		9 => {
+			let (origin, _, _) = random_node(&mut rng);
+			let call = DcCall::<T>::add_file_info {
+				owner: random_user(&mut rng),
+				file_id: random_file(&mut rng),
+				file_size: rng.gen_range(0..(1024 * 1024 * 1024u64)),
+				file_type: rng.gen_range(1..=2),
+				block_height: random_block_height(&mut rng),
+				signature: vec![35; 64],
+			};
+			(call, origin)
+		},
+		10 => {
+			let (origin, _, _) = random_node(&mut rng);
+			let file_id = random_file(&mut rng);
+			let block_height = random_block_height(&mut rng);
+			(DcCall::<T>::add_file_peer { file_id, block_height }, origin)
+		},
+		11 => {
+			let (origin, _, _) = random_node(&mut rng);
+			let call = DcCall::<T>::remove_self_file_peer {
+				file_id: random_file(&mut rng),
+				file_type: 1,
+				block_height: random_block_height(&mut rng),
+			};
+			(call, origin)
+		},
+		12 => {
+			let (origin, _, _) = random_node(&mut rng);
+			let (_, _, peer_id) = random_node(&mut rng);
+			let file_id = random_file(&mut rng);
+			(DcCall::<T>::remove_other_file_peer { peer_id, file_id }, origin)
+		},
+		13 => {
+			let (origin, _, _) = random_node(&mut rng);
+			let call = DcCall::<T>::delete_file_info {
+				owner: random_user(&mut rng),
+				file_id: random_file(&mut rng),
+				file_type: rng.gen_range(1..=2),
+				block_height: random_block_height(&mut rng),
+				signature: vec![35; 64],
+			};
+			(call, origin)
+		},
+		14 => {
+			let (origin, _, _) = random_node(&mut rng);
+			let (_, _, peer_id) = random_node(&mut rng);
+			let block_height = random_block_height(&mut rng);
+			(DcCall::<T>::report_peer_offchain { peer_id, block_height, tee_report: vec![33; 64] }, origin)
+		},
+		15 => {
+			let (origin, _, _) = random_node(&mut rng);
+			let (_, _, peer_id) = random_node(&mut rng);
+			let block_height = random_block_height(&mut rng);
+			(DcCall::<T>::report_peer_no_response { peer_id, block_height, tee_report: vec![33; 64] }, origin)
+		},
+		16 => {
+			let (origin, _, _) = random_node(&mut rng);
+			let (_, _, peer_id) = random_node(&mut rng);
+			let block_height = random_block_height(&mut rng);
+			(DcCall::<T>::report_peer_error { peer_id, block_height, tee_report: vec![33; 64] }, origin)
+		},
+		17 => {
+			let (origin, _, _) = random_node(&mut rng);
+			let call = DcCall::<T>::user_login {
+				login_account: random_user(&mut rng),
+				app_ids: vec![vec![rng.gen_range(0..4u8); 8]],
+				block_height: random_block_height(&mut rng),
+				signature: vec![35; 64],
+				app_signatures: Vec::new(),
+			};
+			(call, origin)
+		},
+		18 => {
+			let (origin, _, _) = random_node(&mut rng);
+			let call = DcCall::<T>::create_sub_account {
+				parent_account: random_user(&mut rng),
+				sub_account: random_user(&mut rng),
+				block_height: random_block_height(&mut rng),
+				signature: vec![35; 64],
+			};
+			(call, origin)
+		},
+		19 => {
+			let (origin, _, _) = random_node(&mut rng);
+			let call = DcCall::<T>::unbind_sub_account {
+				parent_account: random_user(&mut rng),
+				sub_account: random_user(&mut rng),
+				block_height: random_block_height(&mut rng),
+				signature: vec![35; 64],
+			};
+			(call, origin)
+		},
+		_ => unreachable!(),
+	}
+}
+
+/// Move to the next era, the way staking drives the pallet through `DcProvider`.
+fn new_era<R: Rng>(rng: &mut R) {
+	CurrentEra::mutate(|c| *c += 1);
+	StakingActive::set(rng.gen_range(0..10) * DcNode::min_staking_amount());
+
+	let stashes = StashPeers::<T>::iter_keys().collect::<Vec<_>>();
+	for stash in stashes.iter() {
+		<DcNode as DcProvider<_, _>>::update_active(stash, StakingActive::get());
+		let _ = <DcNode as DcProvider<_, _>>::can_be_unbound_amount(stash, StakingActive::get());
+	}
+	let peers_space = <DcNode as DcProvider<_, _>>::rewardable_peers_space();
+	let login_times = <DcNode as DcProvider<_, _>>::rewardable_app_login_times();
+	let storage_payout = <DcNode as DcProvider<_, _>>::era_storage_payout(ERA * 6_000);
+	let app_payout = <DcNode as DcProvider<_, _>>::era_app_payout(ERA * 6_000);
+
+	log::info!(
+		target: "dc-node-fuzzer",
+		"era {}: {} peers, {} stashes, {} users, {} files, rewardable space {:?}, logins {:?}, payouts {} / {}",
+		CurrentEra::get(),
+		Peers::<T>::iter_keys().count(),
+		stashes.len(),
+		WalletAccountStorage::<T>::iter_keys().count(),
+		Files::<T>::iter_keys().count(),
+		peers_space.map(|(total, _)| total),
+		login_times.map(|(total, _)| total),
+		storage_payout,
+		app_payout,
+	);
+}
+
+fn main() {
+	sp_tracing::try_init_simple();
+	let mut ext = new_test_ext();
+	let mut iteration = 0 as BlockNumber;
+	let mut ok = 0;
+	let mut err = 0;
+
+	ext.execute_with(|| {
+		System::set_block_number(1);
+		for index in 0..NODES {
+			let _ = Balances::deposit_creating(&node_account(index), 1_000_000_000);
+		}
+		for index in 0..USERS {
+			let _ = Balances::deposit_creating(&user_account(index), 1_000_000_000);
+		}
+		assert!(DcCall::<T>::set_storage_package {
+			package_id: PACKAGE_ID,
+			subscribe_space: 60 * 1024 * 1024 * 1024,
+			subscribe_price: 1000,
+			call_minus_number: 10,
+			expire_number: 10 * ERA,
+		}
+		.dispatch_bypass_filter(RuntimeOrigin::root())
+		.is_ok());
+	});
+
+	loop {
+		fuzz!(|seed: [u8; 32]| {
+			use ::rand::{rngs::SmallRng, SeedableRng};
+			let mut rng = SmallRng::from_seed(seed);
+
+			ext.execute_with(|| {
+				let (call, origin) = random_call(&mut rng);
+				let outcome = call.clone().dispatch_bypass_filter(origin.clone());
+				iteration += 1;
+				match outcome {
+					Ok(_) => ok += 1,
+					Err(_) => err += 1,
+				};
+
+				log::trace!(
+					target: "dc-node-fuzzer",
+					"iteration {}, call {:?}, origin {:?}, outcome: {:?}, so far {} ok {} err",
+					iteration,
+					call,
+					origin,
+					outcome,
+					ok,
+					err,
+				);
+
+				// execute sanity checks at a fixed interval, possibly on every call.
+				if iteration %
+					(std::env::var("SANITY_CHECK_INTERVAL")
+						.ok()
+						.and_then(|x| x.parse::<u64>().ok()))
+					.unwrap_or(1) == 0
+				{
+					DcNode::do_try_state().unwrap();
+				}
+
+				System::set_block_number(System::block_number() + 1);
+				System::reset_events();
+
+				if iteration % ERA == 0 {
+					new_era(&mut rng);
+					DcNode::do_try_state().unwrap();
+				}
+			})
+		})
+	}
+}
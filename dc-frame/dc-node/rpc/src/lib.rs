@@ -0,0 +1,161 @@
+//! RPC interface for dc node pallet.
+//!
+//! The DC state of a wallet account is served together with a read proof of it, so that
+//! light wallets can check the state against a header they trust instead of trusting the
+//! RPC node. See `dc_primitives::verify_user_state`.
+
+use std::{marker::PhantomData, sync::Arc};
+
+use codec::Codec;
+use jsonrpsee::{
+	core::RpcResult,
+	proc_macros::rpc,
+	types::error::{CallError, ErrorObject},
+};
+pub use pallet_dc_node::UserStorage;
+use sc_client_api::ProofProvider;
+use serde::{Deserialize, Serialize};
+use sp_blockchain::HeaderBackend;
+use sp_core::Bytes;
+use sp_runtime::traits::{Block as BlockT, HashFor, Header as HeaderT};
+
+/// DC state of a wallet account with a read proof of it.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserStateProof<BlockHash, AccountId, BlockNumber, Balance> {
+	/// Hash of the block, whose state root the proof is checked against.
+	pub at: BlockHash,
+	/// Storage information of the wallet account.
+	pub user_storage: Option<UserStorage<AccountId, BlockNumber, Balance>>,
+	/// Wallet account bound to the requested NFT account.
+	pub nft_wallet_account: Option<AccountId>,
+	/// Trie nodes proving both entries.
+	pub proof: Vec<Bytes>,
+}
+
+#[rpc(client, server)]
+pub trait DcApi<BlockHash, AccountId, BlockNumber, Balance> {
+	/// Returns the storage information of the wallet account and, if the NFT account is
+	/// given, the wallet account bound to it, with a read proof against the state of the
+	/// block (the best block by default).
+	#[method(name = "dc_userStateProof")]
+	fn user_state_proof(
+		&self,
+		account: AccountId,
+		nft_account: Option<Bytes>,
+		at: Option<BlockHash>,
+	) -> RpcResult<UserStateProof<BlockHash, AccountId, BlockNumber, Balance>>;
+}
+
+/// Provides RPC methods to query the DC state of wallet accounts.
+pub struct Dc<C, Block> {
+	/// Shared reference to the client.
+	client: Arc<C>,
+	_marker: PhantomData<Block>,
+}
+
+impl<C, Block> Dc<C, Block> {
+	/// Creates a new instance of the DC Rpc helper.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client, _marker: Default::default() }
+	}
+}
+
+/// Error type of this RPC api.
+pub enum Error {
+	/// The block is unknown.
+	UnknownBlock,
+	/// The state could not be read or proven.
+	ProofError,
+}
+
+impl From<Error> for i32 {
+	fn from(e: Error) -> i32 {
+		match e {
+			Error::UnknownBlock => 1,
+			Error::ProofError => 2,
+		}
+	}
+}
+
+impl<C, Block, AccountId, BlockNumber, Balance>
+	DcApiServer<<Block as BlockT>::Hash, AccountId, BlockNumber, Balance> for Dc<C, Block>
+where
+	Block: BlockT,
+	C: ProofProvider<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	AccountId: Codec + Send + Sync + 'static,
+	BlockNumber: Codec + Send + Sync + 'static,
+	Balance: Codec + Send + Sync + 'static,
+{
+	fn user_state_proof(
+		&self,
+		account: AccountId,
+		nft_account: Option<Bytes>,
+		at: Option<Block::Hash>,
+	) -> RpcResult<UserStateProof<Block::Hash, AccountId, BlockNumber, Balance>> {
+		let at = at.unwrap_or_else(|| self.client.info().best_hash);
+		let header = self
+			.client
+			.header(at)
+			.map_err(|e| error_into_rpc_error(Error::ProofError, e))?
+			.ok_or_else(|| error_into_rpc_error(Error::UnknownBlock, at))?;
+
+		let mut keys = vec![dc_primitives::wallet_account_storage_key(&account)];
+		if let Some(nft_account) = &nft_account {
+			keys.push(dc_primitives::nft_to_wallet_account_key(nft_account));
+		}
+		let proof = self
+			.client
+			.read_proof(at, &mut keys.iter().map(|key| key.as_slice()))
+			.map_err(|e| error_into_rpc_error(Error::ProofError, e))?;
+
+		// Decode the entries from the proof, like the wallet will do.
+		let (user_storage, nft_wallet_account) =
+			dc_primitives::verify_user_state::<HashFor<Block>, _, _>(
+				*header.state_root(),
+				proof.clone(),
+				&account,
+				nft_account.as_deref(),
+			)
+			.map_err(|e| error_into_rpc_error(Error::ProofError, e))?;
+
+		Ok(UserStateProof {
+			at,
+			user_storage,
+			nft_wallet_account,
+			proof: proof.into_iter_nodes().map(Into::into).collect(),
+		})
+	}
+}
+
+/// Converts an error of this RPC api into a [`CallError`].
+fn error_into_rpc_error(error: Error, details: impl std::fmt::Debug) -> CallError {
+	let message = match error {
+		Error::UnknownBlock => "Unknown block",
+		Error::ProofError => "Unable to prove the DC state",
+	};
+	CallError::Custom(ErrorObject::owned(error.into(), message, Some(format!("{:?}", details))))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::H256;
+
+	#[test]
+	fn should_serialize_user_state_proof() {
+		let proof = UserStateProof::<H256, u64, u32, u128> {
+			at: H256::repeat_byte(1),
+			user_storage: None,
+			nft_wallet_account: Some(7),
+			proof: vec![Bytes(vec![1, 2])],
+		};
+
+		let json = serde_json::to_string(&proof).unwrap();
+		assert_eq!(
+			json,
+			r#"{"at":"0x0101010101010101010101010101010101010101010101010101010101010101","userStorage":null,"nftWalletAccount":7,"proof":["0x0102"]}"#
+		);
+		assert_eq!(serde_json::from_str::<UserStateProof<H256, u64, u32, u128>>(&json).unwrap(), proof);
+	}
+}
@@ -0,0 +1,29 @@
+//! Runtime API definition for dc node pallet.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+use pallet_dc_node::{EndpointKind, NetworkStats, NodeEndpoint};
+use sp_std::vec::Vec;
+
+sp_api::decl_runtime_apis! {
+	/// Runtime api for accessing information about DC.
+	pub trait DcNodeApi<AccountId, BlockNumber, Balance>
+		where AccountId: Codec, BlockNumber: Codec, Balance: Codec
+	{
+		/// Returns the endpoints of kind in the region that support the min API version,
+		/// sorted by priority, and then by the last heartbeat.
+		fn endpoints(
+			kind: EndpointKind,
+			region: Option<Vec<u8>>,
+			min_api_version: u32,
+		) -> Vec<NodeEndpoint<AccountId, BlockNumber>>;
+
+		/// Returns the price that the storage package is purchased at now, which follows
+		/// the utilization of the network when the pricing curve is set.
+		fn storage_package_price(package_id: u32) -> Option<Balance>;
+
+		/// Returns the health of the DC network from the counters kept on chain.
+		fn network_stats() -> NetworkStats<Balance>;
+	}
+}
@@ -12,7 +12,7 @@ use sp_std::{
     prelude::*,
     collections::{btree_map::BTreeMap, btree_set::BTreeSet},
 };
-use sp_runtime::SaturatedConversion;
+use sp_runtime::{SaturatedConversion, traits::TrailingZeroInput};
 
 benchmarks! {
     where_clause { where
@@ -24,10 +24,13 @@ benchmarks! {
         let total_space = 100*1024*1024*1024*1024;
         let free_space = 100*1024*1024*1024*1024;
         let ip_address = vec![33; 256];
+        let sgx_version_number = 1;
+        let program_version = 1;
+        let enclave_id = vec![37; 64];
         let report_number = 10293u32.into();
         let tee_report = vec![38; 512];
         let caller: T::AccountId = whitelisted_caller();
-    }: _(RawOrigin::Signed(caller), peer_id.clone(), total_space, free_space, ip_address, report_number, tee_report)
+    }: _(RawOrigin::Signed(caller), peer_id.clone(), total_space, free_space, ip_address, sgx_version_number, program_version, enclave_id, report_number, tee_report)
     verify {
         assert!(Peers::<T>::contains_key(peer_id));
     }
@@ -72,7 +75,7 @@ benchmarks! {
 
         frame_system::Pallet::<T>::set_block_number(frame_system::Pallet::<T>::block_number() + Pallet::<T>::interval_blocks_work_report());
         let report_number = frame_system::Pallet::<T>::block_number();
-    }: _(RawOrigin::Signed(caller), total_space, free_space, ip_address, miss_files, miss_accounts, report_number.saturated_into(), tee_report)
+    }: _(RawOrigin::Signed(caller), total_space, free_space, ip_address, miss_files, miss_accounts, 1, vec![37; 64], report_number.saturated_into(), tee_report)
     
     purchase_storage {
         let for_account = user_purchase_storage::<T>("purchase_storage");
@@ -170,11 +173,12 @@ benchmarks! {
     }
 
     apply_nft_account {
+        NftCollection::<T>::put(T::BenchmarkHelper::nft_collection());
         let peer_id = vec![33; 32];
         let caller = add_onchain_node::<T>(peer_id, "apply_nft_account", NODE_STATUS_ONCHAIN);
         let for_account = user_purchase_storage::<T>("for_account");
         let signature = vec![33; 1024];
-        let nft_account = vec![36; 32];
+        let nft_account = b"dc-nft-account".to_vec();
         let enc_nft_account = vec![36; 32];
         let private_key_enc_hash = vec![36; 32];
     }: _(RawOrigin::Signed(caller), nft_account.clone(), for_account.clone(), enc_nft_account, private_key_enc_hash, 1000u32.into(), signature)
@@ -185,12 +189,13 @@ benchmarks! {
     }
     
     transfer_nft_account {
+        NftCollection::<T>::put(T::BenchmarkHelper::nft_collection());
         let peer_id = vec![33; 32];
         let caller = add_onchain_node::<T>(peer_id, "transfer_nft_account", NODE_STATUS_ONCHAIN);
         let from_account = user_purchase_storage::<T>("from_account");
         let to_account = user_purchase_storage::<T>("to_account");
         let signature = vec![33; 1024];
-        let nft_account = vec![36; 32];
+        let nft_account = b"dc-nft-account".to_vec();
         let enc_nft_account = vec![36; 32];
         let private_key_enc_hash = vec![36; 32];
         let _ = Pallet::<T>::apply_nft_account(RawOrigin::Signed(caller.clone()).into(), nft_account.clone(), from_account.clone(),
@@ -206,11 +211,12 @@ benchmarks! {
     }
     
     update_nft_account {
+        NftCollection::<T>::put(T::BenchmarkHelper::nft_collection());
         let peer_id = vec![33; 32];
         let caller = add_onchain_node::<T>(peer_id, "transfer_nft_account", NODE_STATUS_ONCHAIN);
         let for_account = user_purchase_storage::<T>("for_account");
         let signature = vec![33; 1024];
-        let nft_account = vec![36; 32];
+        let nft_account = b"dc-nft-account".to_vec();
         let enc_nft_account = vec![36; 32];
         let private_key_enc_hash = vec![36; 32];
         let _ = Pallet::<T>::apply_nft_account(RawOrigin::Signed(caller.clone()).into(), nft_account.clone(), for_account.clone(),
@@ -313,7 +319,7 @@ benchmarks! {
         let file_id = vec![37; 32]; 
         let log_id = vec![37; 32];
         let file_size = 1000123; 
-        let file_type = 1;
+        let file_type = 2;
         let signature = vec![33; 1024];
 
         let _ = Pallet::<T>::add_file_info(RawOrigin::Signed(
@@ -362,6 +368,117 @@ benchmarks! {
         assert!(info.used_space == user_storage.used_space+u64::try_from(increase_size).unwrap());
     }
 
+    update_thread_db_log {
+        let peer_id = vec![33; 32];
+        let caller = add_onchain_node::<T>(peer_id, "update_thread_db_log", NODE_STATUS_ONCHAIN);
+        let owner = user_purchase_storage::<T>("owner");
+        let file_id = vec![37; 32]; 
+        let log_id = vec![37; 32];
+        let log_size = 67890;
+        let signature = vec![33; 1024];
+
+        let _ = Pallet::<T>::add_file_info(RawOrigin::Signed(
+            caller.clone()).into(), 
+            owner.clone(), 
+            file_id.clone(), 
+            1000123, 
+            2, 
+            1000u32.into(), 
+            signature.clone()
+        );
+        // The log owner is a sub account, the space of its parent account is charged too
+        let sub_account: T::AccountId = account("sub_account", 0, 0);
+        let _ = Pallet::<T>::create_sub_account(RawOrigin::Signed(caller.clone()).into(), owner.clone(), sub_account.clone(), 1000u32.into(), signature.clone());
+        // The previous heads are full, the oldest is compacted by the update
+        for i in 0 ..= THREAD_DB_LOG_MAX_PREV_HEADS as u8 {
+            let _ = Pallet::<T>::update_thread_db_log(RawOrigin::Signed(
+                caller.clone()).into(), 
+                file_id.clone(), 
+                log_id.clone(), 
+                sub_account.clone(), 
+                vec![i; 32], 
+                0, 
+                1000u32.into(), 
+                signature.clone()
+            );
+        }
+        let user_storage = <WalletAccountStorage<T>>::get(&owner).unwrap();
+    }: _(RawOrigin::Signed(caller), file_id.clone(), log_id.clone(), sub_account, vec![255; 32], log_size, 1000u32.into(), signature)
+    verify {
+        let info = WalletAccountStorage::<T>::get(&owner).unwrap();
+        assert!(info.used_space == user_storage.used_space+log_size);
+        assert!(ThreadDbLogs::<T>::get(&file_id, &log_id).unwrap().prev_heads.len() == THREAD_DB_LOG_MAX_PREV_HEADS as usize);
+    }
+
+    add_thread_db_log_peer {
+        let peer_id = vec![33; 32];
+        let caller = add_onchain_node::<T>(peer_id, "add_thread_db_log_peer", NODE_STATUS_ONCHAIN);
+        let new_peer_id = vec![39; 32];
+        let new_caller = add_onchain_node::<T>(new_peer_id.clone(), "new_caller", NODE_STATUS_ONCHAIN);
+        let owner = user_purchase_storage::<T>("owner");
+        let file_id = vec![37; 32]; 
+        let log_id = vec![37; 32];
+        let signature = vec![33; 1024];
+
+        let _ = Pallet::<T>::add_file_info(RawOrigin::Signed(
+            caller.clone()).into(), 
+            owner.clone(), 
+            file_id.clone(), 
+            1000123, 
+            2, 
+            1000u32.into(), 
+            signature.clone()
+        );
+        let _ = Pallet::<T>::add_log_to_thread_db(RawOrigin::Signed(
+            caller.clone()).into(), 
+            file_id.clone(), 
+            log_id.clone(), 
+            1000u32.into(), 
+            signature.clone()
+        );
+    }: _(RawOrigin::Signed(new_caller), file_id.clone(), log_id.clone(), 1000u32.into())
+    verify {
+        assert!(ThreadDbLogs::<T>::get(&file_id, &log_id).unwrap().peers.contains(&new_peer_id));
+    }
+
+    compact_thread_db_log {
+        let peer_id = vec![33; 32];
+        let caller = add_onchain_node::<T>(peer_id, "compact_thread_db_log", NODE_STATUS_ONCHAIN);
+        let owner = user_purchase_storage::<T>("owner");
+        let file_id = vec![37; 32]; 
+        let log_id = vec![37; 32];
+        let signature = vec![33; 1024];
+
+        let _ = Pallet::<T>::add_file_info(RawOrigin::Signed(
+            caller.clone()).into(), 
+            owner.clone(), 
+            file_id.clone(), 
+            1000123, 
+            2, 
+            1000u32.into(), 
+            signature.clone()
+        );
+        // The log owner is a sub account, the space of its parent account is charged too
+        let sub_account: T::AccountId = account("sub_account", 0, 0);
+        let _ = Pallet::<T>::create_sub_account(RawOrigin::Signed(caller.clone()).into(), owner.clone(), sub_account.clone(), 1000u32.into(), signature.clone());
+        for i in 0 ..= THREAD_DB_LOG_MAX_PREV_HEADS as u8 {
+            let _ = Pallet::<T>::update_thread_db_log(RawOrigin::Signed(
+                caller.clone()).into(), 
+                file_id.clone(), 
+                log_id.clone(), 
+                sub_account.clone(), 
+                vec![i; 32], 
+                0, 
+                1000u32.into(), 
+                signature.clone()
+            );
+        }
+        assert!(ThreadDbLogs::<T>::get(&file_id, &log_id).unwrap().prev_heads.len() == THREAD_DB_LOG_MAX_PREV_HEADS as usize);
+    }: _(RawOrigin::Signed(caller), file_id.clone(), log_id.clone(), 0, 1000u32.into(), signature)
+    verify {
+        assert!(ThreadDbLogs::<T>::get(&file_id, &log_id).unwrap().prev_heads.is_empty());
+    }
+
     report_file_miss {
         let peer_id = vec![33; 32];
         let peer_id2 = vec![35; 32];
@@ -567,16 +684,23 @@ benchmarks! {
     }
 
     user_login {
+        let n in 1 .. 1000 as u32;
         let peer_id = vec![33; 32];
-        let caller = add_onchain_node::<T>(peer_id, "user_login", NODE_STATUS_ONCHAIN);
-        let login_account = user_purchase_storage::<T>("login_account");
+        let caller = add_onchain_node::<T>(peer_id.clone(), "user_login", NODE_STATUS_ONCHAIN);
+        // The wallet account and the apps sign with generated keys
+        let login_key = sp_io::crypto::ed25519_generate(sp_core::testing::ED25519, None);
+        let login_account = user_purchase_storage_of::<T>(T::AccountId::decode(&mut login_key.as_ref()).unwrap());
         let _ = Pallet::<T>::add_user_peer(RawOrigin::Signed(caller.clone()).into(), login_account.clone(), 1000u32.into());
-        
-        let n in 1 .. 1000 as u32;
+
+        let app_key = sp_io::crypto::ed25519_generate(sp_core::testing::ED25519, None);
+        let app_account = T::AccountId::decode(&mut app_key.as_ref()).unwrap();
+        let _ = T::Currency::make_free_balance_be(&app_account, T::Currency::minimum_balance()*100u32.into() + T::AppDeposit::get()*n.into());
         let mut app_ids = Vec::new();
         for i in 0 .. n {
-            app_ids.push(vec![32; usize::try_from(i).unwrap()]);
-            let _ = Pallet::<T>::set_app_account(RawOrigin::Signed(caller.clone()).into(), vec![32; usize::try_from(i).unwrap()], login_account.clone());
+            let app_id = i.to_le_bytes().to_vec();
+            let _ = Pallet::<T>::set_app_account(RawOrigin::Signed(app_account.clone()).into(), app_id.clone(), app_account.clone());
+            let _ = Pallet::<T>::set_app_login_cosign(RawOrigin::Signed(app_account.clone()).into(), app_id.clone(), true);
+            app_ids.push(app_id);
         }
 
         let mut user_info = WalletAccountStorage::<T>::get(&login_account).unwrap();
@@ -585,11 +709,17 @@ benchmarks! {
         WalletAccountStorage::<T>::insert(&login_account, user_info);
 
         frame_system::Pallet::<T>::set_block_number(frame_system::Pallet::<T>::block_number() + Pallet::<T>::interval_blocks_login());
-    }: _(RawOrigin::Signed(caller), login_account.clone(), app_ids, Pallet::<T>::interval_blocks_login().saturated_into::<u32>())
+        let block_height = Pallet::<T>::interval_blocks_login().saturated_into::<u32>();
+        let message = Pallet::<T>::login_message(&app_ids, block_height, &peer_id);
+        let signature = sp_io::crypto::ed25519_sign(sp_core::testing::ED25519, &login_key, &message).unwrap().0.to_vec();
+        let app_signature = sp_io::crypto::ed25519_sign(sp_core::testing::ED25519, &app_key, &message).unwrap().0.to_vec();
+        let app_signatures = app_ids.iter().map(|app_id| (app_id.clone(), app_signature.clone())).collect::<Vec<_>>();
+    }: _(RawOrigin::Signed(caller), login_account.clone(), app_ids, block_height, signature, app_signatures)
     verify {
         let cur_info = WalletAccountStorage::<T>::get(&login_account).unwrap();
         assert!(cur_info.spam_report_amount == 0);
         assert!(cur_info.comment_report_amount == 0);
+        assert!(AppsAccountLoginTimes::<T>::get().unwrap().len() == n as usize);
     }
     
     new_theme {
@@ -619,6 +749,7 @@ benchmarks! {
         let add_space = 2000000;
         let block_height = 1000u32.into();
         let signature = vec![35; 1024];
+        let _ = Pallet::<T>::new_theme(RawOrigin::Signed(caller.clone()).into(), for_account.clone(), theme_id.clone(), app_id.clone(), 0, 1, block_height, signature.clone());
  
         let pre_info = WalletAccountStorage::<T>::get(&for_account).unwrap();
     }: _(RawOrigin::Signed(caller), for_account.clone(), theme_id, app_id, add_space, block_height, signature)
@@ -657,6 +788,7 @@ benchmarks! {
         let comment_signature = vec![38; 1024];
 
         let _ = Pallet::<T>::add_user_peer(RawOrigin::Signed(caller.clone()).into(), report_account.clone(), 1000u32.into());
+        let _ = Pallet::<T>::new_theme(RawOrigin::Signed(caller.clone()).into(), report_account.clone(), theme_id.clone(), app_id.clone(), 0, 1, 1000u32.into(), vec![35; 1024]);
         frame_system::Pallet::<T>::set_block_number(14600u32.into());
     }: _(RawOrigin::Signed(caller), report_account, report_block_height, report_signature, theme_id, content_id, comment_account.clone(), app_id, comment_block_height, refer_comment_key, content_type, comment_signature)
     verify {
@@ -664,5 +796,40 @@ benchmarks! {
         assert!(info.comment_report_amount == 1);
     }
 
-    impl_benchmark_test_suite!(Pallet, crate::tests::new_test_ext(), crate::tests::Test)
+    submit_liveness_report {
+        let n in 0 .. T::MaxLivenessProbes::get();
+        let prober = T::AuthorityId::decode(&mut TrailingZeroInput::zeroes()).unwrap();
+        let signature = <T::AuthorityId as RuntimeAppPublic>::Signature::decode(&mut TrailingZeroInput::zeroes()).unwrap();
+        let caller: T::AccountId = account("prober", 0, 0);
+        LivenessProbers::<T>::insert(&prober, caller);
+        let reporters: BTreeSet<T::AccountId> = (1 .. Pallet::<T>::abnormal_report_number()).map(|i| account("reporter", i, 0)).collect();
+        let era_index = T::StakingProvider::get_current_era_index();
+
+        let mut offline = Vec::new();
+        for i in 0 .. n {
+            let peer_id = i.to_le_bytes().to_vec();
+            let _ = add_onchain_node::<T>(peer_id.clone(), "offline", NODE_STATUS_ONCHAIN);
+            // The report of the prober takes the node offchain
+            let report_info = ReportInfo {
+                report_type: ReportType::ReportPeerNoResponse,
+                peer_id: peer_id.clone(),
+            };
+            <Pallet<T>>::insert_reports(era_index, report_info, reporters.clone());
+            offline.push(peer_id);
+        }
+        let report = LivenessReport {
+            block_number: frame_system::Pallet::<T>::block_number(),
+            prober: prober.clone(),
+            probed: n,
+            offline: offline.clone(),
+        };
+    }: _(RawOrigin::None, report, signature)
+    verify {
+        assert!(LastLivenessReport::<T>::contains_key(&prober));
+        for peer_id in offline {
+            assert!(Peers::<T>::get(peer_id).unwrap().status == NODE_STATUS_OFFCHAIN);
+        }
+    }
+
+    impl_benchmark_test_suite!(Pallet, crate::mock::new_bench_ext(), crate::mock::Test)
 }
@@ -0,0 +1,185 @@
+use crate::*;
+use frame_support::{dispatch::DispatchInfo, traits::IsSubType};
+use sp_runtime::{
+    traits::{DispatchInfoOf, Dispatchable, SignedExtension},
+    transaction_validity::{
+        InvalidTransaction, TransactionPriority, TransactionValidity, TransactionValidityError,
+        ValidTransaction,
+    },
+};
+use sp_std::marker::PhantomData;
+
+/// Limit the fee-free calls of storage nodes.
+///
+/// # Transaction Validity
+///
+/// The fee-free calls of `pallet-dc-node` sent by the request account of a peer are only valid
+/// if the peer has not used up `PeerCallQuota` calls in the current `PeerCallWindow`. The window
+/// spans blocks, so that the pool rejects the calls of a peer that used up its quota in the
+/// previous blocks of the window. The priority is the remaining quota of the peer, so that a peer
+/// which spams the pool is served after the others, and the calls are dropped from the pool at
+/// the end of the window. The calls are only sent by the request accounts of ONCHAIN peers, so
+/// the calls of other accounts are rejected as bad signers.
+///
+/// The reads and the write of the counter are registered as extra weight of the block in
+/// `pre_dispatch`.
+#[derive(Encode, Decode, Clone, Eq, PartialEq, TypeInfo)]
+#[scale_info(skip_type_params(T))]
+pub struct CheckPeerQuota<T: Config + Send + Sync>(PhantomData<T>);
+
+impl<T: Config + Send + Sync> CheckPeerQuota<T> {
+    /// Create new `SignedExtension` to check the quota of storage nodes.
+    pub fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<T: Config + Send + Sync> Default for CheckPeerQuota<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Config + Send + Sync> sp_std::fmt::Debug for CheckPeerQuota<T> {
+    #[cfg(feature = "std")]
+    fn fmt(&self, f: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+        write!(f, "CheckPeerQuota")
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn fmt(&self, _: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+        Ok(())
+    }
+}
+
+impl<T: Config + Send + Sync> CheckPeerQuota<T>
+where
+    T::AccountId: AsRef<[u8]>,
+{
+    /// The fee-free calls of storage nodes
+    fn is_peer_call(call: &Call<T>) -> bool {
+        matches!(call,
+            Call::submit_work_report { .. } |
+            Call::add_request_peer_id_to_user { .. } |
+            Call::update_db_config { .. } |
+            Call::create_sub_account { .. } |
+            Call::unbind_sub_account { .. } |
+            Call::add_user_peer { .. } |
+            Call::remove_self_user_peer { .. } |
+            Call::remove_other_user_peer { .. } |
+            Call::apply_nft_account { .. } |
+            Call::transfer_nft_account { .. } |
+            Call::update_nft_account { .. } |
+            Call::add_file_info { .. } |
+            Call::add_file_peer { .. } |
+            Call::remove_self_file_peer { .. } |
+            Call::remove_other_file_peer { .. } |
+            Call::delete_file_info { .. } |
+            Call::add_log_to_thread_db { .. } |
+            Call::add_space_to_thread_db { .. } |
+            Call::update_thread_db_log { .. } |
+            Call::add_thread_db_log_peer { .. } |
+            Call::compact_thread_db_log { .. } |
+            Call::report_file_miss { .. } |
+            Call::report_login_info_miss { .. } |
+            Call::report_tee_faking { .. } |
+            Call::verify_tee_faking { .. } |
+            Call::report_peer_offchain { .. } |
+            Call::report_peer_no_response { .. } |
+            Call::report_peer_error { .. } |
+            Call::report_spam { .. } |
+            Call::user_login { .. } |
+            Call::new_theme { .. } |
+            Call::add_theme_comment_space { .. } |
+            Call::close_theme { .. } |
+            Call::add_user_comment_space { .. } |
+            Call::report_malicious_comment { .. }
+        )
+    }
+
+    /// The first block of the current window
+    fn window_start() -> T::BlockNumber {
+        let now = frame_system::Pallet::<T>::block_number();
+        now - now % T::PeerCallWindow::get().max(One::one())
+    }
+
+    /// Get the ONCHAIN peer of request account and the number of its calls in the current window.
+    fn peer_calls(who: &T::AccountId) -> Result<(PeerId, u32), TransactionValidityError> {
+        let peer_id = Pallet::<T>::request_account_peer(who).ok_or(InvalidTransaction::BadSigner)?;
+        if Pallet::<T>::peers(&peer_id).map_or(true, |info| info.status != NODE_STATUS_ONCHAIN) {
+            Err(InvalidTransaction::BadSigner)?
+        }
+        let (window_start, calls) = Pallet::<T>::peer_calls_in_window(&peer_id);
+        if window_start != Self::window_start() {
+            return Ok((peer_id, 0));
+        }
+        let quota = Pallet::<T>::peer_call_quota();
+        if quota > 0 && calls >= quota {
+            Err(InvalidTransaction::ExhaustsResources)?
+        }
+        Ok((peer_id, calls))
+    }
+
+    /// The weight of checking and counting a call: `RequestAccountPeer`, `Peers`,
+    /// `PeerCallsInWindow` and `PeerCallQuota` are read and `PeerCallsInWindow` is written.
+    pub fn weight() -> frame_support::weights::Weight {
+        T::DbWeight::get().reads_writes(4, 1)
+    }
+}
+
+impl<T: Config + Send + Sync> SignedExtension for CheckPeerQuota<T>
+where
+    T::AccountId: AsRef<[u8]>,
+    T::RuntimeCall: Dispatchable<Info = DispatchInfo> + IsSubType<Call<T>>,
+{
+    type AccountId = T::AccountId;
+    type Call = T::RuntimeCall;
+    type AdditionalSigned = ();
+    type Pre = ();
+    const IDENTIFIER: &'static str = "CheckPeerQuota";
+
+    fn additional_signed(&self) -> sp_std::result::Result<(), TransactionValidityError> {
+        Ok(())
+    }
+
+    fn pre_dispatch(
+        self,
+        who: &Self::AccountId,
+        call: &Self::Call,
+        info: &DispatchInfoOf<Self::Call>,
+        _len: usize,
+    ) -> Result<(), TransactionValidityError> {
+        match call.is_sub_type() {
+            Some(call) if Self::is_peer_call(call) => {
+                let (peer_id, calls) = Self::peer_calls(who)?;
+                <PeerCallsInWindow<T>>::insert(peer_id, (Self::window_start(), calls.saturating_add(1)));
+                frame_system::Pallet::<T>::register_extra_weight_unchecked(Self::weight(), info.class);
+                Ok(())
+            },
+            _ => Ok(()),
+        }
+    }
+
+    fn validate(
+        &self,
+        who: &Self::AccountId,
+        call: &Self::Call,
+        _info: &DispatchInfoOf<Self::Call>,
+        _len: usize,
+    ) -> TransactionValidity {
+        match call.is_sub_type() {
+            Some(call) if Self::is_peer_call(call) => {
+                let (_, calls) = Self::peer_calls(who)?;
+                let quota = Pallet::<T>::peer_call_quota();
+                let window_end = Self::window_start().saturating_add(T::PeerCallWindow::get().max(One::one()));
+                let longevity = window_end.saturating_sub(frame_system::Pallet::<T>::block_number());
+                Ok(ValidTransaction {
+                    priority: quota.saturating_sub(calls) as TransactionPriority,
+                    longevity: longevity.saturated_into::<u64>().max(1),
+                    ..Default::default()
+                })
+            },
+            _ => Ok(ValidTransaction::default()),
+        }
+    }
+}
@@ -6,17 +6,26 @@
 /// <https://docs.substrate.io/v3/runtime/frame>
 
 use frame_support::{
-    dispatch::{DispatchResult, Pays},
+    dispatch::{DispatchError, DispatchResult, Pays},
+    pallet_prelude::ConstU32,
+    BoundedVec,
     traits::{
-        Currency, Get,
+        Currency, Get, Locker, ReservableCurrency,
         LockableCurrency, ExistenceRequirement, StoredMap,
+        tokens::nonfungibles_v2::{
+            Inspect as NftInspect, Mutate as NftMutate, Transfer as NftTransfer,
+        },
     },
 };
  
 use codec::{Codec, Decode, Encode, MaxEncodedLen};
 use sp_runtime::{
     traits::{
-        AtLeast32BitUnsigned, MaybeSerializeDeserialize, Zero, Saturating,
+        AtLeast32BitUnsigned, MaybeSerializeDeserialize, One, Zero, Saturating,
+    },
+    transaction_validity::{
+        InvalidTransaction, TransactionPriority, TransactionSource, TransactionValidity,
+        ValidTransaction,
     },
     Perbill, RuntimeDebug, SaturatedConversion, FixedPointNumber,
 };
@@ -28,21 +37,35 @@ use sp_std::{
     vec::Vec,
     collections::{btree_map::BTreeMap, btree_set::BTreeSet},
 };
-use sp_staking::EraIndex;
+use sp_staking::{offence::ReportOffence, EraIndex, SessionIndex};
 
+use frame_system::offchain::{SendTransactionTypes, SubmitTransaction};
+use sp_application_crypto::RuntimeAppPublic;
 use pallet_balances::{AccountData};
-use pallet_transaction_payment::{NextMultiplier};
+use pallet_transaction_payment::{Multiplier, NextMultiplier};
+use pallet_nfts::{ItemConfig, ItemsCallback};
 use sp_core::{ed25519};
 use sp_io::{crypto::{ed25519_verify}};
 // use lite_json::json::JsonValue;
 pub use pallet::*;
 pub mod weights;
 pub use weights::*;
-
-#[cfg(test)]
-mod mock;
-
-#[cfg(any(feature = "runtime-benchmarks", test))]
+pub mod check_peer_quota;
+pub use check_peer_quota::CheckPeerQuota;
+pub mod offence;
+pub use offence::{DcOffence, DcOffenceKind};
+pub mod liveness;
+pub use liveness::LivenessReport;
+pub mod pricing;
+pub use pricing::PricingCurve;
+pub mod pause;
+pub use pause::{CallCategory, PausedCalls};
+pub mod migrations;
+
+#[cfg(any(test, feature = "fuzzing"))]
+pub mod mock;
+
+#[cfg(any(feature = "runtime-benchmarks", feature = "fuzzing", test))]
 pub mod testing_utils;
 
 #[cfg(test)]
@@ -57,14 +80,22 @@ pub type SpaceSize = u64;
 pub type LoginTimes = u64;
 type PeerId = Vec<u8>;
 type NftAccount = Vec<u8>;
+/// Item ID of an NFT account in the nfts collection.
+pub type NftItemId = u32;
 type FileID = Vec<u8>;
 type AppID = Vec<u8>;
+type ThemeID = Vec<u8>;
 pub type BalanceOf<T> =
     <<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+/// The full identification of stash that storage node offences are reported against.
+pub type OffenderOf<T> = <<T as Config>::StakingProvider as StakingProvider>::Offender;
 
 type DcString = Vec<u8>;
 type PackageId = u32;
 
+/// The attribute key of the NFT account name on the nfts item.
+const NFT_ACCOUNT_ATTRIBUTE: &[u8] = b"nft_account";
+
 /// Status of an Storage node
 /// Offchain: peer report offline
 const NODE_STATUS_OFFCHAIN: u32 = 1;
@@ -81,12 +112,44 @@ const NODE_STATUS_CLOSED: u32 = 6;
 /// Discard: tee fake
 const NODE_STATUS_DISCARD: u32 = 7;
 
+/// The name of the status of storage node, as shown by the tools reading the state of the pallet.
+pub fn node_status_name(status: u32) -> &'static str {
+    match status {
+        NODE_STATUS_OFFCHAIN => "offchain",
+        NODE_STATUS_JOINING => "joining",
+        NODE_STATUS_ONCHAIN => "onchain",
+        NODE_STATUS_STAKED => "staked",
+        NODE_STATUS_ABNORMAL => "abnormal",
+        NODE_STATUS_CLOSED => "closed",
+        NODE_STATUS_DISCARD => "discard",
+        _ => "unknown",
+    }
+}
+
+/// Status of a theme
+/// Open: comments can be added to the theme
+const THEME_STATUS_OPEN: u32 = 1;
+/// Closed: the owner closed the theme and got its comment space back
+const THEME_STATUS_CLOSED: u32 = 2;
+
 /// The max length of app id
 const APPID_MAX_LENGTH: u32 = 32;
+/// The max length of app name
+const APP_NAME_MAX_LENGTH: usize = 64;
+/// The max length of app URL
+const APP_URL_MAX_LENGTH: usize = 256;
 /// The max number of storage nodes that user can request to
 const USER_REQUEST_NODE_MAX_NUM: usize = 5;
 /// The max number of missing files/accounts 
 pub const MISSING_FILES_MAX_NUM: u32 = 10;
+/// The default max length of NFT account
+const NFT_ACCOUNT_MAX_LENGTH: u32 = 64;
+/// The max number of wallet accounts enrolled in the sponsorship of an app
+const SPONSORED_USERS_MAX_NUM: u32 = 256;
+/// The max number of counted logins of the past eras pruned in a block
+const LOGIN_PRUNE_MAX_NUM: u32 = 256;
+/// The max number of reporters rewarded for an offence of storage node
+const OFFENCE_REPORTERS_MAX_NUM: u32 = 16;
  
 
 /// Information of an Storage node.
@@ -116,6 +179,7 @@ pub struct StorageNode<AccountId, BlockNumber> {
 
 /// Storage information of user.
 #[derive(Clone, Eq, PartialEq, RuntimeDebug, Encode, Decode, TypeInfo)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
 pub struct UserStorage<AccountId, BlockNumber, Balance> {
     /// List of node IDs where login information is stored.
     pub peers: BTreeSet<PeerId>,
@@ -164,6 +228,10 @@ pub struct UserStorage<AccountId, BlockNumber, Balance> {
 // const FILE_TYPE_COMMON: u32 = 1;
 /// threaddb file
 const FILE_TYPE_THREAD_DB:u32 =2;
+/// The max number of logs of a thread db file
+const THREAD_DB_MAX_LOGS: u32 = 64;
+/// The max number of previous heads of a thread db log, the oldest is compacted beyond it
+const THREAD_DB_LOG_MAX_PREV_HEADS: u32 = 32;
 
 /// Information of file.
 #[derive(Clone, Eq, PartialEq, RuntimeDebug, Encode, Decode, TypeInfo)]
@@ -176,10 +244,28 @@ pub struct FileInfo<AccountId> {
     pub file_size: SpaceSize,
     /// File type.
     pub file_type: u32,
-    /// thread db information
+    /// The log ids of thread db file, see `ThreadDbLogs` for the logs.
     pub db_log: BTreeSet<DcString>,
 }
 
+/// Log information of thread db file.
+#[derive(Clone, Eq, PartialEq, RuntimeDebug, Encode, Decode, TypeInfo)]
+pub struct ThreadDbLog<AccountId, BlockNumber> {
+    /// The account or device(sub account) that owns the log.
+    pub owner: AccountId,
+    /// The latest head(CID) of the log.
+    pub head: DcString,
+    /// The previous heads of the log, oldest first, can be compacted by the owner.
+    /// The oldest head is compacted when it is full.
+    pub prev_heads: BoundedVec<DcString, ConstU32<THREAD_DB_LOG_MAX_PREV_HEADS>>,
+    /// Log size unit byte.
+    pub size: SpaceSize,
+    /// List of node IDs where the log is replicated.
+    pub peers: BTreeSet<PeerId>,
+    /// The block number that the head was updated.
+    pub update_number: BlockNumber,
+}
+
 /// The report information type.
 #[derive(Clone, Eq, PartialEq, RuntimeDebug, Encode, Decode, TypeInfo, Copy)]
 pub enum ReportType {
@@ -193,6 +279,29 @@ pub enum ReportType {
     ReportPeerNoResponse = 4,
 }
 
+/// Health of the DC network, served to the node for its metrics.
+#[derive(Clone, Eq, PartialEq, RuntimeDebug, Encode, Decode, TypeInfo, Default)]
+pub struct NetworkStats<Balance> {
+    /// The number of storage nodes of each status. [status, number]
+    pub peers_by_status: Vec<(u32, u32)>,
+    /// Total space of the onchain storage nodes.
+    pub total_space: SpaceSize,
+    /// Free space of the onchain storage nodes.
+    pub free_space: SpaceSize,
+    /// The number of wallet accounts whose subscription has not expired.
+    pub active_users: u32,
+    /// The number of files.
+    pub files: u64,
+    /// The number of copies of all files on storage nodes.
+    pub file_replicas: u64,
+    /// The app reward pot.
+    pub app_reward_total: Balance,
+    /// The storage reward pot.
+    pub storage_reward_total: Balance,
+    /// The number of reports against storage nodes in the current era.
+    pub reports_in_era: u32,
+}
+
 /// Report information.
 #[derive(Clone, Eq, PartialEq, RuntimeDebug, Encode, Decode, TypeInfo)]
 pub struct ReportInfo {
@@ -204,11 +313,47 @@ pub struct ReportInfo {
 
 /// Account information of app.
 #[derive(Clone, Eq, PartialEq, RuntimeDebug, Encode, Decode, TypeInfo)]
-pub struct AppAccountInfo<AccountId> {
+pub struct AppAccountInfo<AccountId, Balance, Hash> {
     /// The private account.
     pub private_account: AccountId,
     /// Rewarded stash account.
     pub rewarded_stash: AccountId,
+    /// The deposit reserved from the private account.
+    pub deposit: Balance,
+    /// The name of app.
+    pub name: DcString,
+    /// The URL of app.
+    pub url: DcString,
+    /// The hash of the logo of app.
+    pub logo_hash: Hash,
+}
+
+/// Storage sponsorship of app, which pays the storage package of its enrolled users.
+#[derive(Clone, Eq, PartialEq, RuntimeDebug, Encode, Decode, TypeInfo)]
+pub struct Sponsorship<Balance> {
+    /// The storage package covered for the enrolled users.
+    pub package_id: PackageId,
+    /// The remaining budget that packages are paid from.
+    pub budget: Balance,
+    /// The max amount paid for each user, zero means no limit.
+    pub limit_per_user: Balance,
+    /// The number of wallet accounts enrolled, at most `SPONSORED_USERS_MAX_NUM`.
+    pub users: u32,
+}
+
+/// Information of a theme of app.
+#[derive(Clone, Eq, PartialEq, RuntimeDebug, Encode, Decode, TypeInfo)]
+pub struct ThemeInfo<AccountId, BlockNumber> {
+    /// The wallet account that created the theme.
+    pub owner: AccountId,
+    /// The open flag set by the owner.
+    pub open_flag: u32,
+    /// The comment space allocated to the theme.
+    pub space: SpaceSize,
+    /// The status of the theme.
+    pub status: u32,
+    /// The block number that the theme was created at.
+    pub created_number: BlockNumber,
 }
 
 /// Login information of app.
@@ -222,6 +367,7 @@ pub struct AppLoginInfo<AccountId> {
 
 /// DC program information.
 #[derive(Clone, Eq, PartialEq, RuntimeDebug, Encode, Decode, TypeInfo)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
 pub struct DcProgramInfo {
     /// Program download url
     pub origin_url: DcString,
@@ -233,12 +379,98 @@ pub struct DcProgramInfo {
     pub version: DcString,
 }
 
+/// The version number of DC program, increased with every release.
+pub type ProgramVersion = u32;
+
+/// The DC program that storage nodes are required to run.
+#[derive(Clone, Eq, PartialEq, RuntimeDebug, Encode, Decode, TypeInfo)]
+pub struct ProgramRequirement<BlockNumber> {
+    /// The min version of program.
+    pub min_version: ProgramVersion,
+    /// Enclave id of the program, empty for any enclave.
+    pub enclave_id: DcString,
+    /// The block number from which nodes are warned to upgrade.
+    pub activation_number: BlockNumber,
+    /// The block number from which nodes below the requirement are demoted.
+    pub deadline_number: BlockNumber,
+}
+
+impl<BlockNumber> ProgramRequirement<BlockNumber> {
+    /// Whether the program of storage node meets the requirement.
+    pub fn is_met_by(&self, version: ProgramVersion, enclave_id: &DcString) -> bool {
+        version >= self.min_version && (self.enclave_id.is_empty() || self.enclave_id == *enclave_id)
+    }
+}
+
+/// Kind of the nodes that clients connect to.
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, RuntimeDebug, Encode, Decode, TypeInfo, MaxEncodedLen)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
+pub enum EndpointKind {
+    /// Blockchain proxy node.
+    Proxy,
+    /// Trusted storage node.
+    TrustedStorage,
+}
+
+/// Endpoint information of proxy node or trusted storage node.
+#[derive(Clone, Eq, PartialEq, RuntimeDebug, Encode, Decode, TypeInfo)]
+pub struct NodeEndpoint<AccountId, BlockNumber> {
+    /// Multiaddr or URL of the node.
+    pub address: DcString,
+    /// Signature of the address.
+    pub signature: DcString,
+    /// Region tag of the node.
+    pub region: DcString,
+    /// The supported API version of the node.
+    pub api_version: u32,
+    /// Priority for clients to select the node, higher first.
+    pub priority: u32,
+    /// The account that operates the node and heartbeats for it.
+    pub operator: Option<AccountId>,
+    /// The block number of the last heartbeat.
+    pub last_seen: BlockNumber,
+}
+
+/// Name policy of NFT accounts.
+#[derive(Clone, Eq, PartialEq, RuntimeDebug, Encode, Decode, TypeInfo)]
+pub struct NftAccountPolicy<Balance, BlockNumber> {
+    /// The min length of NFT account.
+    pub min_length: u32,
+    /// The max length of NFT account.
+    pub max_length: u32,
+    /// The price curve by length, names no longer than `.0` cost `.1`.
+    pub length_prices: Vec<(u32, Balance)>,
+    /// The price of names longer than all lengths of the price curve.
+    pub base_price: Balance,
+    /// The number of blocks that NFT account is registered for, zero means never expire.
+    pub registration_period: BlockNumber,
+    /// The number of blocks after the expiry that the owner can still renew NFT account,
+    /// before it can be released or applied by others.
+    pub grace_period: BlockNumber,
+}
+
+impl<Balance: Zero, BlockNumber: Zero> Default for NftAccountPolicy<Balance, BlockNumber> {
+    fn default() -> Self {
+        NftAccountPolicy {
+            min_length: 1,
+            max_length: NFT_ACCOUNT_MAX_LENGTH,
+            length_prices: Vec::new(),
+            base_price: Zero::zero(),
+            registration_period: Zero::zero(),
+            grace_period: Zero::zero(),
+        }
+    }
+}
+
 #[frame_support::pallet]
 pub mod pallet {
     use super::*;
     use frame_support::pallet_prelude::*;
     use frame_system::pallet_prelude::*;
 
+    /// The current storage version.
+    const STORAGE_VERSION: StorageVersion = StorageVersion::new(4);
+
     /// Configure the pallet by specifying the parameters and types on which it depends.
     #[pallet::config]
     pub trait Config: frame_system::Config {
@@ -258,7 +490,8 @@ pub mod pallet {
 		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
 
         /// The DC balance.
-        type Currency: LockableCurrency<Self::AccountId, Moment = Self::BlockNumber>;
+        type Currency: LockableCurrency<Self::AccountId, Moment = Self::BlockNumber>
+            + ReservableCurrency<Self::AccountId>;
         /// The means of storing the balances of an account.
         type AccountStore: StoredMap<Self::AccountId, AccountData<Self::Balance>>;
         // A type that can deliver a single account id value to the pallet.
@@ -268,13 +501,55 @@ pub mod pallet {
 			AccountId = Self::AccountId,
 			Balance = BalanceOf<Self>,
 		>;
+        /// Reports offences of storage nodes, so that their slashes can be deferred and cancelled.
+        type ReportOffence: ReportOffence<Self::AccountId, OffenderOf<Self>, DcOffence<OffenderOf<Self>>>;
 
         // Weight information for extrinsics in this pallet.
 		type WeightInfo: WeightInfo;
         type BlockMultiplier: NextMultiplier;
+
+        /// Identifier of the nfts collection that NFT accounts are minted in.
+        type NftCollectionId: Parameter + Member + Copy + MaxEncodedLen;
+        /// The nfts pallet, NFT accounts are transferable items of its collection.
+        type Nfts: NftInspect<Self::AccountId, CollectionId = Self::NftCollectionId, ItemId = NftItemId>
+            + NftMutate<Self::AccountId, ItemConfig>
+            + NftTransfer<Self::AccountId>;
+
+        /// The identifier type of liveness probers.
+        type AuthorityId: Member
+            + Parameter
+            + RuntimeAppPublic
+            + Ord
+            + MaybeSerializeDeserialize
+            + MaxEncodedLen;
+        /// The number of blocks between two liveness probing rounds of the offchain worker.
+        #[pallet::constant]
+        type LivenessProbeInterval: Get<Self::BlockNumber>;
+        /// The max number of storage nodes probed in one round.
+        #[pallet::constant]
+        type MaxLivenessProbes: Get<u32>;
+        /// The priority of liveness reports.
+        #[pallet::constant]
+        type UnsignedPriority: Get<TransactionPriority>;
+
+        /// The number of blocks between adjustments of the price multiplier of storage packages.
+        #[pallet::constant]
+        type PriceAdjustmentInterval: Get<Self::BlockNumber>;
+
+        /// The number of blocks that the quota of fee-free calls of a storage node applies to.
+        #[pallet::constant]
+        type PeerCallWindow: Get<Self::BlockNumber>;
+
+        /// The deposit reserved for registering an app.
+        #[pallet::constant]
+        type AppDeposit: Get<BalanceOf<Self>>;
+        /// Creates the nfts collection of NFT accounts in benchmarks.
+        #[cfg(feature = "runtime-benchmarks")]
+        type BenchmarkHelper: BenchmarkHelper<Self::NftCollectionId>;
     }
 
     #[pallet::pallet]
+    #[pallet::storage_version(STORAGE_VERSION)]
     #[pallet::without_storage_info]
     #[pallet::generate_store(pub(super) trait Store)]
     pub struct Pallet<T>(_);
@@ -294,26 +569,106 @@ pub mod pallet {
     #[pallet::getter(fn stash_peers)]
     pub type StashPeers<T: Config> = StorageMap<_, Twox64Concat, T::AccountId, BTreeSet<PeerId>>;
 
+    /// The Storage nodes offered by their stash to another stash. [old_stash, new_stash]
+    #[pallet::storage]
+    #[pallet::getter(fn pending_peer_transfer)]
+    pub type PendingPeerTransfer<T: Config> = StorageMap<_, Twox64Concat, PeerId, (T::AccountId, T::AccountId)>;
+
     /// Number of onchain peers.
     #[pallet::storage]
     #[pallet::getter(fn onchain_peer_number)]
     pub type OnchainPeerNumber<T: Config> = StorageValue<_, u32, ValueQuery>;
 
+    /// Number of peers of each status.
+    #[pallet::storage]
+    #[pallet::getter(fn peers_of_status)]
+    pub type PeersOfStatus<T: Config> = StorageMap<_, Twox64Concat, u32, u32, ValueQuery>;
+
+    /// Total space and free space of the onchain peers.
+    #[pallet::storage]
+    #[pallet::getter(fn onchain_space)]
+    pub type OnchainSpace<T: Config> = StorageValue<_, (SpaceSize, SpaceSize), ValueQuery>;
+
     /// NFT accounts.
     #[pallet::storage]
     #[pallet::getter(fn nft_to_wallet_account)]
     pub type NftToWalletAccount<T: Config> = StorageMap<_, Twox64Concat, NftAccount, T::AccountId>;
 
+    /// The nfts collection of NFT accounts.
+    #[pallet::storage]
+    #[pallet::getter(fn nft_collection)]
+    pub type NftCollection<T: Config> = StorageValue<_, T::NftCollectionId>;
+
+    /// The item ID of the next NFT account.
+    #[pallet::storage]
+    #[pallet::getter(fn next_nft_item_id)]
+    pub type NextNftItemId<T: Config> = StorageValue<_, NftItemId, ValueQuery>;
+
+    /// The nfts item of NFT accounts.
+    #[pallet::storage]
+    #[pallet::getter(fn nft_account_item)]
+    pub type NftAccountItem<T: Config> = StorageMap<_, Twox64Concat, NftAccount, NftItemId>;
+
+    /// The NFT account of nfts items.
+    #[pallet::storage]
+    #[pallet::getter(fn nft_item_account)]
+    pub type NftItemAccount<T: Config> = StorageMap<_, Twox64Concat, NftItemId, NftAccount>;
+
+    /// The name policy of NFT accounts.
+    #[pallet::storage]
+    #[pallet::getter(fn nft_account_policy)]
+    pub type NftAccountNamePolicy<T: Config> = StorageValue<_, NftAccountPolicy<BalanceOf<T>, T::BlockNumber>, ValueQuery>;
+
+    /// The NFT accounts reserved by root.
+    #[pallet::storage]
+    #[pallet::getter(fn reserved_nft_accounts)]
+    pub type ReservedNftAccounts<T: Config> = StorageMap<_, Twox64Concat, NftAccount, ()>;
+
+    /// The block number that the registration of NFT account expires at.
+    #[pallet::storage]
+    #[pallet::getter(fn nft_account_expiry)]
+    pub type NftAccountExpiry<T: Config> = StorageMap<_, Twox64Concat, NftAccount, T::BlockNumber>;
+
+    /// The max number of fee-free calls of a storage node in a `PeerCallWindow`, zero means no limit.
+    #[pallet::storage]
+    #[pallet::getter(fn peer_call_quota)]
+    pub type PeerCallQuota<T: Config> = StorageValue<_, u32, ValueQuery>;
+
+    /// The first block of the window and the number of fee-free calls of storage nodes in it.
+    #[pallet::storage]
+    #[pallet::getter(fn peer_calls_in_window)]
+    pub type PeerCallsInWindow<T: Config> = StorageMap<_, Twox64Concat, PeerId, (T::BlockNumber, u32), ValueQuery>;
+
     /// Storage information of wallet accounts.
     #[pallet::storage]
     #[pallet::getter(fn wallet_account_storage)]
     pub type WalletAccountStorage<T: Config> = StorageMap<_, Twox64Concat, T::AccountId, UserStorage<T::AccountId, T::BlockNumber, BalanceOf<T>>>;
 
+    /// Number of wallet accounts whose storage has not expired.
+    #[pallet::storage]
+    #[pallet::getter(fn active_users)]
+    pub type ActiveUsers<T: Config> = StorageValue<_, u32, ValueQuery>;
+
+    /// Number of active wallet accounts by expire number, they stop being active from the next block.
+    #[pallet::storage]
+    #[pallet::getter(fn users_expiring_at)]
+    pub type UsersExpiringAt<T: Config> = StorageMap<_, Twox64Concat, T::BlockNumber, u32, ValueQuery>;
+
     /// The files of user.
     #[pallet::storage]
     #[pallet::getter(fn files)]
     pub type Files<T: Config> = StorageMap<_, Twox64Concat, FileID, FileInfo<T::AccountId>>;
 
+    /// Number of files and the replicas of them on the peers.
+    #[pallet::storage]
+    #[pallet::getter(fn file_totals)]
+    pub type FileTotals<T: Config> = StorageValue<_, (u64, u64), ValueQuery>;
+
+    /// The logs of thread db files.
+    #[pallet::storage]
+    #[pallet::getter(fn thread_db_logs)]
+    pub type ThreadDbLogs<T: Config> = StorageDoubleMap<_, Twox64Concat, FileID, Twox64Concat, DcString, ThreadDbLog<T::AccountId, T::BlockNumber>>;
+
     /// The percent of storage rewards in the total(app rewards + storage rewards)
 	#[pallet::storage]
 	#[pallet::getter(fn app_reward_percent)]
@@ -424,25 +779,119 @@ pub mod pallet {
     #[pallet::getter(fn dc_program)]
     pub type DcProgram<T: Config> = StorageValue<_, DcProgramInfo>;
 
-    /// The list of blockchain proxy nodes.
+    /// The DC program requirement in force.
+    #[pallet::storage]
+    #[pallet::getter(fn required_program)]
+    pub type RequiredProgram<T: Config> = StorageValue<_, ProgramRequirement<T::BlockNumber>>;
+
+    /// The announced DC program requirement, which replaces `RequiredProgram` after its grace period.
+    #[pallet::storage]
+    #[pallet::getter(fn pending_program)]
+    pub type PendingProgram<T: Config> = StorageValue<_, ProgramRequirement<T::BlockNumber>>;
+
+    /// The slash fractions of storage node offences set by governance.
+    #[pallet::storage]
+    pub type OffenceSlashFractions<T: Config> = StorageMap<_, Twox64Concat, DcOffenceKind, Perbill>;
+
+    /// The program version and enclave id that storage nodes last reported.
+    #[pallet::storage]
+    #[pallet::getter(fn peer_program)]
+    pub type PeerPrograms<T: Config> = StorageMap<_, Twox64Concat, PeerId, (ProgramVersion, DcString)>;
+
+    /// The registry of blockchain proxy nodes and trusted storage nodes, keyed by kind and address.
+    #[pallet::storage]
+    #[pallet::getter(fn endpoint)]
+    pub type Endpoints<T: Config> = StorageDoubleMap<_, Twox64Concat, EndpointKind, Twox64Concat, DcString, NodeEndpoint<T::AccountId, T::BlockNumber>>;
+
+    /// The keys of liveness probers, mapped to the accounts that their reports are counted for.
     #[pallet::storage]
-    #[pallet::getter(fn proxy_nodes)]
-    pub type ProxyNodes<T: Config> = StorageValue<_, BTreeSet<(DcString, DcString)>>;
+    #[pallet::getter(fn liveness_prober)]
+    pub type LivenessProbers<T: Config> = StorageMap<_, Twox64Concat, T::AuthorityId, T::AccountId>;
 
-    /// The list of trusted storage nodes.
+    /// The block number of the last liveness report of each prober.
     #[pallet::storage]
-    #[pallet::getter(fn trusted_storage_nodes)]
-    pub type TrustedStorageNodes<T: Config> = StorageValue<_, BTreeSet<(DcString, DcString)>>;
+    #[pallet::getter(fn last_liveness_report)]
+    pub type LastLivenessReport<T: Config> = StorageMap<_, Twox64Concat, T::AuthorityId, T::BlockNumber>;
 
     /// All slashing events on nominators, mapped by era to the highest slash value of the era.
 	#[pallet::storage]
     #[pallet::getter(fn reports_in_era)]
 	pub(crate) type ReportsInEra<T: Config> = StorageDoubleMap<_, Twox64Concat, EraIndex, Twox64Concat, ReportInfo, BTreeSet<T::AccountId>>;
 
+    /// Number of reports in each era.
+	#[pallet::storage]
+    #[pallet::getter(fn report_number_in_era)]
+	pub(crate) type ReportNumberInEra<T: Config> = StorageMap<_, Twox64Concat, EraIndex, u32, ValueQuery>;
+
     /// The account information associated with the app id.
 	#[pallet::storage]
     #[pallet::getter(fn account_of_app)]
-	pub(crate) type AccountOfApp<T: Config> = StorageMap<_, Twox64Concat, AppID, AppAccountInfo<T::AccountId>>;
+	pub(crate) type AccountOfApp<T: Config> = StorageMap<_, Twox64Concat, AppID, AppAccountInfo<T::AccountId, BalanceOf<T>, T::Hash>>;
+
+    /// The curve that the prices of storage packages follow, the prices are fixed if it is not set.
+    #[pallet::storage]
+    #[pallet::getter(fn pricing_curve)]
+    pub type StoragePricingCurve<T: Config> = StorageValue<_, PricingCurve>;
+
+    #[pallet::type_value]
+    pub fn DefaultPriceMultiplier() -> Multiplier {
+        Multiplier::one()
+    }
+
+    /// The multiplier applied to the prices of storage packages.
+    #[pallet::storage]
+    #[pallet::getter(fn price_multiplier)]
+    pub type PriceMultiplier<T: Config> = StorageValue<_, Multiplier, ValueQuery, DefaultPriceMultiplier>;
+
+    /// The categories of calls paused by governance.
+    #[pallet::storage]
+    #[pallet::getter(fn paused_categories)]
+    pub type PausedCategories<T: Config> = StorageValue<_, BTreeSet<CallCategory>, ValueQuery>;
+
+    /// The themes of apps.
+    #[pallet::storage]
+    #[pallet::getter(fn themes)]
+    pub type Themes<T: Config> = StorageDoubleMap<_, Twox64Concat, AppID, Blake2_128Concat, ThemeID, ThemeInfo<T::AccountId, T::BlockNumber>>;
+
+    /// The logins of wallet accounts to apps counted in the era, pruned once the era has passed.
+    #[pallet::storage]
+    #[pallet::getter(fn app_login_in_era)]
+    pub type AppLoginsInEra<T: Config> = StorageDoubleMap<_, Twox64Concat, EraIndex, Blake2_128Concat, (AppID, T::AccountId), ()>;
+
+    /// The oldest era whose counted logins are not pruned yet.
+    #[pallet::storage]
+    #[pallet::getter(fn login_prune_era)]
+    pub type LoginPruneEra<T: Config> = StorageValue<_, EraIndex, ValueQuery>;
+
+    /// The apps that only count logins co-signed by their private account.
+    #[pallet::storage]
+    #[pallet::getter(fn app_login_cosign)]
+    pub type AppLoginCosign<T: Config> = StorageMap<_, Twox64Concat, AppID, (), OptionQuery>;
+
+    /// The app ids revoked by governance, which can not be registered again.
+    #[pallet::storage]
+    #[pallet::getter(fn revoked_app)]
+    pub type RevokedApps<T: Config> = StorageMap<_, Twox64Concat, AppID, (), OptionQuery>;
+
+    /// The account that the ownership of app is being transferred to.
+    #[pallet::storage]
+    #[pallet::getter(fn pending_app_owner)]
+    pub type PendingAppOwner<T: Config> = StorageMap<_, Twox64Concat, AppID, T::AccountId>;
+
+    /// The storage sponsorships of apps.
+    #[pallet::storage]
+    #[pallet::getter(fn sponsorship)]
+    pub type Sponsorships<T: Config> = StorageMap<_, Twox64Concat, AppID, Sponsorship<BalanceOf<T>>>;
+
+    /// The wallet accounts enrolled in the sponsorship of app, with the amount paid for them.
+    #[pallet::storage]
+    #[pallet::getter(fn sponsored_user)]
+    pub type SponsoredUsers<T: Config> = StorageDoubleMap<_, Twox64Concat, AppID, Twox64Concat, T::AccountId, BalanceOf<T>>;
+
+    /// The app that sponsors the wallet account.
+    #[pallet::storage]
+    #[pallet::getter(fn user_sponsor)]
+    pub type UserSponsor<T: Config> = StorageMap<_, Twox64Concat, T::AccountId, AppID>;
 
     /// The times of logins for each app.<app stash account, longin times>
 	#[pallet::storage]
@@ -472,6 +921,19 @@ pub mod pallet {
         pub interval_blocks_work_report: T::BlockNumber,
         pub interval_blocks_login: T::BlockNumber,
         pub tee_report_verify_number: T::BlockNumber,
+        pub peer_call_quota: u32,
+        /// The storage packages. (id, space size unit byte, package price, valid block numbers, block numbers deducted for interface calls)
+        pub storage_packages: Vec<(PackageId, SpaceSize, BalanceOf<T>, T::BlockNumber, T::BlockNumber)>,
+        /// The enclave ids for storage node. (enclave id, signature)
+        pub enclave_ids: Vec<(DcString, DcString)>,
+        /// The Dc program infomation.
+        pub dc_program: Option<DcProgramInfo>,
+        /// The proxy nodes and trusted storage nodes. (kind, address, region, api version, priority, operator)
+        pub endpoints: Vec<(EndpointKind, DcString, DcString, u32, u32, Option<T::AccountId>)>,
+        /// The accounts of apps. (app id, private account, rewarded stash)
+        pub app_accounts: Vec<(AppID, T::AccountId, T::AccountId)>,
+        /// The wallet accounts subscribed to a storage package from genesis. (wallet account, package id)
+        pub wallet_accounts: Vec<(T::AccountId, PackageId)>,
         pub dev_config: Vec<(T::AccountId, T::AccountId, T::AccountId, PeerId)>,
     }
 
@@ -500,6 +962,13 @@ pub mod pallet {
                 interval_blocks_work_report: 28800u32.into(),
                 interval_blocks_login: 28800u32.into(),
                 tee_report_verify_number: 300u32.into(),
+                peer_call_quota: 100,
+                storage_packages: Default::default(),
+                enclave_ids: Default::default(),
+                dc_program: None,
+                endpoints: Default::default(),
+                app_accounts: Default::default(),
+                wallet_accounts: Default::default(),
                 dev_config: Default::default(),
             }
         }
@@ -510,6 +979,7 @@ pub mod pallet {
         T::AccountId: AsRef<[u8]>,
     {
         fn build(&self) {
+            assert!(self.app_reward_percent <= 100, "app_reward_percent must not exceed 100");
             <OnchainPeerNumber<T>>::put(self.onchain_peer_number);
             AppRewardPercent::<T>::put(self.app_reward_percent);
             AppRewardTotal::<T>::put(self.app_reward_total);
@@ -530,6 +1000,64 @@ pub mod pallet {
             IntervalBlocksWorkReport::<T>::put(self.interval_blocks_work_report);
             IntervalBlocksLogin::<T>::put(self.interval_blocks_login);
             TeeReportVerifyNumber::<T>::put(self.tee_report_verify_number);
+            PeerCallQuota::<T>::put(self.peer_call_quota);
+
+            if !self.storage_packages.is_empty() {
+                let mut package_ids = BTreeSet::new();
+                for package in self.storage_packages.iter() {
+                    assert!(package_ids.insert(package.0), "Storage package id is duplicated");
+                    assert!(package.1 > 0 && !package.3.is_zero(), "Storage package has no space or valid blocks");
+                }
+                <StoragePackages<T>>::put(self.storage_packages.iter().cloned().collect::<BTreeSet<_>>());
+            }
+            if !self.enclave_ids.is_empty() {
+                let now = frame_system::Pallet::<T>::block_number();
+                let mut enclave_ids = BTreeSet::new();
+                for (enclave_id, signature) in self.enclave_ids.iter() {
+                    assert!(!enclave_id.is_empty(), "Enclave id is empty");
+                    enclave_ids.insert((now, enclave_id.clone(), signature.clone()));
+                }
+                <EnclaveIds<T>>::put(enclave_ids);
+            }
+            if let Some(dc_program) = &self.dc_program {
+                <DcProgram<T>>::put(dc_program);
+            }
+            for (kind, address, region, api_version, priority, operator) in self.endpoints.iter() {
+                assert!(!address.is_empty(), "Endpoint address is empty");
+                assert!(!<Endpoints<T>>::contains_key(kind, address), "Endpoint is duplicated");
+                <Endpoints<T>>::insert(kind, address, NodeEndpoint {
+                    address: address.clone(),
+                    signature: DcString::new(),
+                    region: region.clone(),
+                    api_version: *api_version,
+                    priority: *priority,
+                    operator: operator.clone(),
+                    last_seen: frame_system::Pallet::<T>::block_number(),
+                });
+            }
+            for (app_id, private_account, rewarded_stash) in self.app_accounts.iter() {
+                assert!(!<AccountOfApp<T>>::contains_key(app_id), "App id is duplicated");
+                // Apps of genesis are registered without deposit
+                <AccountOfApp<T>>::insert(app_id, AppAccountInfo {
+                    private_account: private_account.clone(),
+                    rewarded_stash: rewarded_stash.clone(),
+                    deposit: Zero::zero(),
+                    name: DcString::new(),
+                    url: DcString::new(),
+                    logo_hash: Default::default(),
+                });
+            }
+            for (account, package_id) in self.wallet_accounts.iter() {
+                let package = self.storage_packages.iter().find(|package| package.0 == *package_id)
+                    .expect("Storage package of wallet account does not exist");
+                assert!(!<WalletAccountStorage<T>>::contains_key(account), "Wallet account is duplicated");
+                <Pallet<T>>::insert_user(account, <Pallet<T>>::new_user_storage(
+                    account.clone(),
+                    package,
+                    frame_system::Pallet::<T>::block_number(),
+                ));
+            }
+
             // For private devlopment chain
             for &(ref controller, ref stash, ref req_account, ref peer_id) in &self.dev_config {
                 frame_support::assert_ok!(<Pallet<T>>::join_storage_node(
@@ -541,6 +1069,8 @@ pub mod pallet {
                     Default::default(),
                     Default::default(),
                     Default::default(),
+                    Default::default(),
+                    Default::default(),
                 ));
                 frame_support::assert_ok!(<Pallet<T>>::set_stash_peer(
                     T::RuntimeOrigin::from(Some(controller.clone()).into()),
@@ -556,21 +1086,9 @@ pub mod pallet {
                     Default::default(),
                     Default::default(),
                     Default::default(),
+                    Default::default(),
+                    Default::default(),
                 ));
-                if <StoragePackages::<T>>::get().is_none() {
-                    let mut packages = BTreeSet::<(PackageId, SpaceSize, BalanceOf<T>, T::BlockNumber, T::BlockNumber)>::new();
-                    packages.insert((1, 60*1024*1024*1024, 10000u32.into(), 1000000u32.into(), 10u32.into()));
-                    packages.insert((2, 120*1024*1024*1024, 20000u32.into(), 1000000u32.into(), 5u32.into()));
-                    <StoragePackages::<T>>::put(packages);
-                }
-                if <EnclaveIds::<T>>::get().is_none() {
-                    let mut enclave_ids: BTreeSet<(T::BlockNumber, DcString, DcString)> = BTreeSet::<(T::BlockNumber, DcString, DcString)>::new();
-                    enclave_ids.insert((
-                        frame_system::Pallet::<T>::block_number(), 
-                        "72c3b468cd159809cb521885deb3ddce8a6f8a0b23b43785990c7f4b5bc7b8cf".as_bytes().to_vec(), 
-                        "bxbmtgfh5wc7cwzlof5hifsv5rgswkmnvkaamfchcaaniqqclzxmc2z7yaald7qejho6s4thcy3uc6dhtfexp44qsmrn32jpkzfccsca".as_bytes().to_vec()));
-                    <EnclaveIds::<T>>::put(enclave_ids);
-                }
             }
         }
     }
@@ -585,6 +1103,68 @@ pub mod pallet {
         JoinStorageNode(T::AccountId, PeerId, SpaceSize, SpaceSize, DcString, u8, T::BlockNumber, DcString),
         PurchaseStorage(PackageId, T::AccountId),
         SetSlashPeer(PeerId, T::AccountId),
+        /// Storage node was offered to another stash. [peer_id, old_stash, new_stash]
+        OfferPeerToStash(PeerId, T::AccountId, T::AccountId),
+        /// Storage node was moved to another stash. [peer_id, old_stash, new_stash]
+        TransferPeerToStash(PeerId, T::AccountId, T::AccountId),
+        /// NFT account was renewed. [nft_account, expiry]
+        RenewNftAccount(NftAccount, T::BlockNumber),
+        /// Expired NFT account was released. [nft_account]
+        ReleaseNftAccount(NftAccount),
+        /// Endpoint was set. [kind, address]
+        SetEndpoint(EndpointKind, DcString),
+        /// Endpoint was removed. [kind, address]
+        RemoveEndpoint(EndpointKind, DcString),
+        /// New program requirement was announced. [min_version, enclave_id, activation_number, deadline_number]
+        AnnounceProgramRequirement(ProgramVersion, DcString, T::BlockNumber, T::BlockNumber),
+        /// Storage node must upgrade its program before the deadline. [peer_id, min_version, deadline_number]
+        ProgramUpgradeRequired(PeerId, ProgramVersion, T::BlockNumber),
+        /// Storage node running an outdated program fell back to joining. [peer_id, version]
+        PeerProgramOutdated(PeerId, ProgramVersion),
+        /// Offence of storage node was reported to be slashed. [kind, peer_id, stash]
+        DcOffenceReported(DcOffenceKind, PeerId, T::AccountId),
+        /// Liveness prober was set. [key, account]
+        SetLivenessProber(T::AuthorityId, T::AccountId),
+        /// Liveness prober was removed. [key]
+        RemoveLivenessProber(T::AuthorityId),
+        /// Liveness report was accepted. [account, probed, offline]
+        LivenessReported(T::AccountId, u32, u32),
+        /// Storage sponsorship of app was set. [app_id, package_id, budget, limit_per_user]
+        SetSponsorship(AppID, PackageId, BalanceOf<T>, BalanceOf<T>),
+        /// Storage sponsorship of app was revoked. [app_id]
+        RevokeSponsorship(AppID),
+        /// Wallet account was enrolled in the sponsorship of app. [app_id, account]
+        SponsorUser(AppID, T::AccountId),
+        /// Wallet account left the sponsorship of app. [app_id, account]
+        RemoveSponsoredUser(AppID, T::AccountId),
+        /// App paid the storage package of the wallet account. [app_id, account, package_id, price]
+        SponsorStorage(AppID, T::AccountId, PackageId, BalanceOf<T>),
+        /// The budget left can no longer pay the package of the sponsorship. [app_id, budget]
+        SponsorshipBudgetExhausted(AppID, BalanceOf<T>),
+        /// App was registered with the deposit. [app_id, private_account, deposit]
+        RegisterApp(AppID, T::AccountId, BalanceOf<T>),
+        /// The co-signature requirement of the logins to app was set. [app_id, required]
+        SetAppLoginCosign(AppID, bool),
+        /// Calls in the category were paused. [category]
+        CallsPaused(CallCategory),
+        /// Calls in the category were unpaused. [category]
+        CallsUnpaused(CallCategory),
+        /// The price multiplier of storage packages was adjusted. [multiplier, utilization]
+        PriceMultiplierUpdated(Multiplier, sp_runtime::Perquintill),
+        /// Theme was created. [app_id, theme_id, owner, space]
+        NewTheme(AppID, ThemeID, T::AccountId, SpaceSize),
+        /// Theme was closed and its comment space returned to the owner. [app_id, theme_id, space]
+        CloseTheme(AppID, ThemeID, SpaceSize),
+        /// Metadata of app was set. [app_id]
+        SetAppMetadata(AppID),
+        /// Ownership of app was offered to the account. [app_id, new_owner]
+        TransferAppOwnership(AppID, T::AccountId),
+        /// Ownership of app was accepted by the account. [app_id, new_owner]
+        AcceptAppOwnership(AppID, T::AccountId),
+        /// App was deregistered and its deposit refunded. [app_id, deposit]
+        DeregisterApp(AppID, BalanceOf<T>),
+        /// App was revoked by governance and its deposit slashed. [app_id, deposit]
+        RevokeApp(AppID, BalanceOf<T>),
     }
 
     // Errors inform users that something went wrong.
@@ -640,17 +1220,104 @@ pub mod pallet {
         ErrorWorkReport,
         /// Excessive login
         ExcessiveLogin,
+        /// The nfts collection of NFT accounts is not set
+        NftCollectionNotSet,
+        /// NFT accounts are minted in the nfts collection
+        NftCollectionInUse,
+        /// Not the owner of the NFT account
+        NotNftAccountOwner,
+        /// Endpoint does not exist
+        EndpointNotExist,
+        /// Not the operator of the endpoint
+        NotEndpointOperator,
+        /// Thread db log does not exist
+        ThreadDbLogNotExist,
+        /// The thread db file has `THREAD_DB_MAX_LOGS` logs already.
+        TooManyThreadDbLogs,
+        /// Not the owner of the thread db log
+        NotThreadDbLogOwner,
+        /// NFT account does not match the name policy
+        NftAccountInvalid,
+        /// NFT account is reserved
+        NftAccountReserved,
+        /// The registration of NFT account has expired
+        NftAccountExpired,
+        /// The registration of NFT account has not expired
+        NftAccountNotExpired,
+        /// NFT accounts are not renewable when the registration period is zero
+        NftAccountNotRenewable,
+        /// The program of storage node is below the requirement
+        ProgramOutdated,
+        /// The announced program requirement is still in its grace period
+        ProgramUpgradeInProgress,
+        /// The enclave of the program requirement is not the published DC program
+        ProgramNotPublished,
+        /// Not a liveness prober
+        NotLivenessProber,
+        /// App id does not exist
+        AppNotExist,
+        /// App id was revoked and can not be registered again
+        AppRevoked,
+        /// Storage sponsorship does not exist
+        SponsorshipNotExist,
+        /// The budget of the sponsorship can not pay the package
+        SponsorshipBudgetExhausted,
+        /// The sponsorship has paid the limit for the wallet account
+        SponsoredUserLimitReached,
+        /// Wallet account is already sponsored
+        AlreadySponsored,
+        /// Wallet account is not sponsored by the app
+        NotSponsored,
+        /// The sponsorship has enrolled `SPONSORED_USERS_MAX_NUM` wallet accounts
+        TooManySponsoredUsers,
+        /// The sponsored package has not expired and can afford the next call
+        SponsoredStorageNotExpired,
+        /// Name or URL of app exceeds the limit
+        AppMetadataTooLong,
+        /// Not the account that the ownership of app is transferred to
+        NotPendingAppOwner,
+        /// Storage node is not offered to the stash
+        PeerTransferNotExist,
+        /// The given number of stash peers is below the actual number
+        StashPeersNumTooLow,
+        /// Theme does not exist
+        ThemeNotExist,
+        /// Theme already exists
+        ThemeAlreadyExist,
+        /// Theme is closed
+        ThemeClosed,
+        /// Wallet account is not the owner of theme
+        NotThemeOwner,
     }
 
-    // #[pallet::hooks]
-    // impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
-    //     /// Called when a block is initialized. Will rotate session if it is the last
-    //     /// block of the current session.
-    //     fn on_initialize(n: T::BlockNumber) -> Weight {
-    //         log::info!("aaaa---------------ccc-----: {:?}--{:?}--{:?}", n, NodeStatus::Abnormal, NodeStatus::Offchain as u8);
-    //         0
-    //     }
-    // }
+    #[pallet::hooks]
+    impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T>
+    where
+        T::AccountId: AsRef<[u8]>,
+        T: SendTransactionTypes<Call<T>>,
+    {
+        fn on_initialize(now: BlockNumberFor<T>) -> Weight {
+            let weight = match Self::pricing_curve() {
+                Some(curve) if (now % T::PriceAdjustmentInterval::get().max(One::one())).is_zero() => {
+                    Self::adjust_price_multiplier(&curve);
+                    T::DbWeight::get().reads_writes(3, 1)
+                },
+                _ => T::DbWeight::get().reads(1),
+            };
+            weight.saturating_add(Self::prune_app_logins()).saturating_add(Self::expire_users(now))
+        }
+
+        fn offchain_worker(now: BlockNumberFor<T>) {
+            if let Err(e) = Self::probe_liveness(now) {
+                log::debug!(target: "runtime::dc-node", "skipping liveness probes at {:?}: {}", now, e);
+            }
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn try_state(_n: BlockNumberFor<T>) -> Result<(), &'static str> {
+            Self::do_try_state()
+        }
+    }
 
     // Dispatchable functions allows users to interact with the pallet and invoke state changes.
     // These functions materialize as "extrinsics", which are often compared to transactions.
@@ -670,6 +1337,8 @@ pub mod pallet {
             free_space: SpaceSize,
             ip_address: DcString,
             sgx_version_number: u8,
+            program_version: ProgramVersion,
+            enclave_id: DcString,
             block_height: T::BlockNumber,
             tee_report: DcString,
         ) -> DispatchResultWithPostInfo {
@@ -677,10 +1346,15 @@ pub mod pallet {
             if total_space > Self::max_storage_node_space() {
                 Err(Error::<T>::MaxStorageNodeSize)?
             }
+            let pending = Self::check_program(program_version, &enclave_id)?;
 
             let cur_block_num = frame_system::Pallet::<T>::block_number();
             let is_exist = <Peers<T>>::contains_key(&peer_id);
             if !is_exist {
+                // A request account can only serve one storage node
+                if <RequestAccountPeer<T>>::contains_key(&who) {
+                    Err(Error::<T>::PeerAccountError)?
+                }
                 let new_info = StorageNode {
                     req_account: who.clone(),
                     stash: T::DefaultAccountId::get(),
@@ -694,7 +1368,7 @@ pub mod pallet {
                     sgx_version_number: sgx_version_number,
                 };
                 // Add storage node.
-                <Peers<T>>::insert(&peer_id, new_info);
+                Self::insert_peer(&peer_id, new_info);
                 // Set peer ID of the request account
                 <RequestAccountPeer<T>>::insert(&who, peer_id.clone());
             } else {
@@ -706,6 +1380,7 @@ pub mod pallet {
                     && cur_block_num.saturating_sub(pre_info.staked_number) < Self::tee_report_verify_number())
                     || pre_info.status == NODE_STATUS_JOINING
                     || pre_info.status == NODE_STATUS_DISCARD {
+                    <PeerPrograms<T>>::insert(&peer_id, (program_version, enclave_id));
                     return Ok(().into());
                 } else {
                     let staking_active = T::StakingProvider::get_staking_active(&pre_info.stash);
@@ -722,16 +1397,22 @@ pub mod pallet {
                     cur_info.reward_number = begin_number.saturating_add(Self::start_reward_block_number());
                     cur_info.ip_address = ip_address.clone();
                     cur_info.sgx_version_number = sgx_version_number;
-                    <Peers<T>>::insert(&peer_id, cur_info);
+                    Self::insert_peer(&peer_id, cur_info);
                 }
             }
+            <PeerPrograms<T>>::insert(&peer_id, (program_version, enclave_id));
+            if let Some(pending) = pending {
+                Self::deposit_event(Event::ProgramUpgradeRequired(peer_id.clone(), pending.min_version, pending.deadline_number));
+            }
             Self::deposit_event(Event::JoinStorageNode(who, peer_id, total_space, free_space, ip_address, sgx_version_number, block_height, tee_report));
             Ok(().into())
         }
 
         /// Submit work report of storage node. 
         #[pallet::call_index(1)]
-        #[pallet::weight((T::WeightInfo::submit_work_report(miss_files.len().try_into().unwrap(), miss_accounts.len().try_into().unwrap()), DispatchClass::Operational))]
+        #[pallet::weight((T::WeightInfo::submit_work_report(miss_files.len().try_into().unwrap(), miss_accounts.len().try_into().unwrap())
+            .saturating_add(Pallet::<T>::thread_db_logs_weight(miss_files.len().try_into().unwrap_or(u32::MAX)))
+            .saturating_add(Pallet::<T>::report_offence_weight()), DispatchClass::Operational))]
         pub fn submit_work_report(
             origin: OriginFor<T>, 
             total_space: SpaceSize,
@@ -739,6 +1420,8 @@ pub mod pallet {
             ip_address: DcString,
             miss_files: Vec<DcString>,
             miss_accounts: Vec<T::AccountId>,
+            program_version: ProgramVersion,
+            enclave_id: DcString,
             block_height: u32,
             _tee_report: DcString,
         ) -> DispatchResultWithPostInfo {
@@ -749,7 +1432,7 @@ pub mod pallet {
             let mut is_repeat_report = false;
             let is_exist = <Peers<T>>::contains_key(&peer_id);
             if is_exist {
-                let mut pre_info = <Peers<T>>::take(&peer_id).unwrap();
+                let mut pre_info = Self::take_peer(&peer_id).unwrap();
                 
                 let cur_num = frame_system::Pallet::<T>::block_number();
                 if cur_num.saturating_sub(pre_info.report_number) < Self::interval_blocks_work_report().saturating_sub(300u32.into()) {
@@ -791,11 +1474,30 @@ pub mod pallet {
                         pre_info.reward_number = cur_num.saturating_add(Self::start_reward_block_number());
                     }
                 }
+                if file_miss_len > 0 {
+                    Self::report_dc_offence(DcOffenceKind::FileLoss, &peer_id, &pre_info.stash, Vec::new());
+                }
                 pre_info.total_space = total_space;
                 pre_info.free_space = free_space;
                 pre_info.ip_address = ip_address.clone();
                 pre_info.report_number = cur_num;
-                <Peers<T>>::insert(peer_id.clone(), pre_info);
+
+                // Nodes below the program requirement stop earning rewards until they join again
+                match Self::check_program(program_version, &enclave_id) {
+                    Ok(Some(pending)) => {
+                        Self::deposit_event(Event::ProgramUpgradeRequired(peer_id.clone(), pending.min_version, pending.deadline_number));
+                    },
+                    Ok(None) => {},
+                    Err(_) => {
+                        if pre_info.status == NODE_STATUS_ONCHAIN {
+                            <OnchainPeerNumber<T>>::mutate(|n| *n -= 1);
+                            pre_info.status = NODE_STATUS_JOINING;
+                        }
+                        Self::deposit_event(Event::PeerProgramOutdated(peer_id.clone(), program_version));
+                    },
+                }
+                <PeerPrograms<T>>::insert(&peer_id, (program_version, enclave_id));
+                Self::insert_peer(&peer_id, pre_info);
             }
             if is_repeat_report {
                 Ok(Pays::Yes.into())
@@ -817,7 +1519,7 @@ pub mod pallet {
             // The stash account is did not setted
             if pre_info.stash == T::DefaultAccountId::get() {
                 pre_info.stash = stash.clone();
-                <Peers<T>>::insert(&peer_id, pre_info);
+                Self::insert_peer(&peer_id, pre_info);
                 let mut peer_id_set = Self::stash_peers(&stash).unwrap_or(BTreeSet::<PeerId>::new());
                 peer_id_set.insert(peer_id.clone());
                 <StashPeers<T>>::insert(&stash, peer_id_set);
@@ -845,7 +1547,7 @@ pub mod pallet {
                     <OnchainPeerNumber<T>>::mutate(|n| *n -= 1);
                     pre_info.status = NODE_STATUS_JOINING;
                 }
-                <Peers<T>>::insert(&peer_id, pre_info);
+                Self::insert_peer(&peer_id, pre_info);
                 let mut peer_id_set = Self::stash_peers(&stash).unwrap_or(BTreeSet::<PeerId>::new());
                 peer_id_set.remove(&peer_id);
                 <StashPeers<T>>::insert(&stash, peer_id_set);
@@ -853,6 +1555,88 @@ pub mod pallet {
             Ok(())
         }
 
+        /// Offer the peer of the stash to another stash, which takes effect once the controller
+        /// of the new stash calls `transfer_peer_to_stash`.
+        #[pallet::call_index(93)]
+        #[pallet::weight(T::DbWeight::get().reads_writes(2, 1))]
+        pub fn offer_peer_to_stash(
+            origin: OriginFor<T>,
+            stash: T::AccountId,
+            peer_id: PeerId,
+            new_stash: T::AccountId,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            if !T::StakingProvider::is_bonded_controller(&stash, &who) {
+                return Err(Error::<T>::NotStash)?;
+            }
+            let pre_info = <Peers<T>>::get(&peer_id).ok_or(Error::<T>::PeerIdNotExist)?;
+            if pre_info.stash != stash || new_stash == stash || new_stash == T::DefaultAccountId::get() {
+                Err(Error::<T>::ParamErr)?
+            }
+
+            <PendingPeerTransfer<T>>::insert(&peer_id, (stash.clone(), new_stash.clone()));
+            Self::deposit_event(Event::OfferPeerToStash(peer_id, stash, new_stash));
+            Ok(())
+        }
+
+        /// Move the peer offered to the stash in one step, the peer keeps its status and reward
+        /// history if the new stash has enough active stake for it.
+        ///
+        /// `stash_peers_num` is the larger number of peers of the old and the new stash.
+        #[pallet::call_index(94)]
+        #[pallet::weight(T::DbWeight::get().reads_writes(8, 5).saturating_add(Pallet::<T>::stash_peers_weight(*stash_peers_num)))]
+        pub fn transfer_peer_to_stash(
+            origin: OriginFor<T>,
+            new_stash: T::AccountId,
+            peer_id: PeerId,
+            stash_peers_num: u32,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            if !T::StakingProvider::is_bonded_controller(&new_stash, &who) {
+                return Err(Error::<T>::NotStash)?;
+            }
+            let (stash, offered_stash) = Self::pending_peer_transfer(&peer_id).ok_or(Error::<T>::PeerTransferNotExist)?;
+            if offered_stash != new_stash {
+                Err(Error::<T>::PeerTransferNotExist)?
+            }
+            let mut pre_info = <Peers<T>>::get(&peer_id).ok_or(Error::<T>::PeerIdNotExist)?;
+            // The peer was moved away from the stash after it was offered, only drop the stale offer
+            if pre_info.stash != stash {
+                <PendingPeerTransfer<T>>::remove(&peer_id);
+                return Ok(());
+            }
+
+            let mut new_peer_id_set = Self::stash_peers(&new_stash).unwrap_or(BTreeSet::<PeerId>::new());
+            let mut peer_id_set = Self::stash_peers(&stash).unwrap_or(BTreeSet::<PeerId>::new());
+            if new_peer_id_set.len().max(peer_id_set.len()) > stash_peers_num as usize {
+                Err(Error::<T>::StashPeersNumTooLow)?
+            }
+            if pre_info.status == NODE_STATUS_ONCHAIN {
+                let onchain_num = new_peer_id_set.iter()
+                    .filter(|id| Self::peers(id).map_or(false, |info| info.status == NODE_STATUS_ONCHAIN))
+                    .count()
+                    .saturating_add(1);
+                let should_bond_amount = Self::min_staking_amount().saturating_mul(onchain_num.saturated_into());
+                if T::StakingProvider::get_staking_active(&new_stash) < should_bond_amount {
+                    <OnchainPeerNumber<T>>::mutate(|n| *n = n.saturating_sub(1));
+                    pre_info.status = NODE_STATUS_JOINING;
+                }
+            }
+            pre_info.stash = new_stash.clone();
+            Self::insert_peer(&peer_id, pre_info);
+
+            peer_id_set.remove(&peer_id);
+            <StashPeers<T>>::insert(&stash, peer_id_set);
+            new_peer_id_set.insert(peer_id.clone());
+            <StashPeers<T>>::insert(&new_stash, new_peer_id_set);
+            <PendingPeerTransfer<T>>::remove(&peer_id);
+
+            // The stake freed from the old stash may bring its other peers onchain
+            Self::update_peers_of_stash(&stash, T::StakingProvider::get_staking_active(&stash));
+            Self::deposit_event(Event::TransferPeerToStash(peer_id, stash, new_stash));
+            Ok(())
+        }
+
         /// Stop peer of the stash
         #[pallet::call_index(4)]
         #[pallet::weight(T::DbWeight::get().reads_writes(2, 1))]
@@ -869,7 +1653,7 @@ pub mod pallet {
                 }
 
                 pre_info.status = NODE_STATUS_CLOSED;
-                <Peers<T>>::insert(&peer_id, pre_info);
+                Self::insert_peer(&peer_id, pre_info);
             }
             Ok(())
         }
@@ -1160,94 +1944,6 @@ pub mod pallet {
             Ok(())
         }
 
-        /// Set the blockchain proxy node
-        #[pallet::call_index(25)]
-        #[pallet::weight(T::DbWeight::get().reads_writes(1, 1))]
-        pub fn set_proxy_node(
-            origin: OriginFor<T>,
-            node_url: DcString,
-            signature: DcString,
-        ) -> DispatchResult {
-            ensure_root(origin)?;
-            let mut proxy_nodes: BTreeSet<(DcString, DcString)> = match Self::proxy_nodes() {
-                Some(p) => p,
-                None => BTreeSet::<(DcString, DcString)>::new(),
-            };
-            proxy_nodes.insert((node_url, signature));
-            <ProxyNodes::<T>>::put(proxy_nodes);
-            Ok(())
-        }
-
-        /// Remove the blockchain proxy node
-        #[pallet::call_index(26)]
-        #[pallet::weight(T::DbWeight::get().reads_writes(1, 1))]
-        pub fn remove_proxy_node(
-            origin: OriginFor<T>,
-            node_url: DcString,
-            signature: DcString,
-        ) -> DispatchResult {
-            ensure_root(origin)?;
-            
-            let mut proxy_nodes: BTreeSet<(DcString, DcString)> = match Self::proxy_nodes() {
-                Some(p) => p,
-                None => BTreeSet::<(DcString, DcString)>::new(),
-            };
-            if !proxy_nodes.is_empty() {
-                for info in proxy_nodes.clone().iter() {
-                    if info.0 == node_url && info.1 == signature {
-                        proxy_nodes.remove(info);
-                    }
-                }
-                <ProxyNodes::<T>>::put(proxy_nodes);
-            }
-            
-            Ok(())
-        }
-
-        /// Set the trusted storage node
-        #[pallet::call_index(27)]
-        #[pallet::weight(T::DbWeight::get().reads_writes(1, 1))]
-        pub fn set_trusted_storage_node(
-            origin: OriginFor<T>,
-            node_url: DcString,
-            signature: DcString,
-        ) -> DispatchResult {
-            ensure_root(origin)?;
-            let mut storage_nodes: BTreeSet<(DcString, DcString)> = match Self::trusted_storage_nodes() {
-                Some(p) => p,
-                None => BTreeSet::<(DcString, DcString)>::new(),
-            };
-            storage_nodes.insert((node_url, signature));
-            <TrustedStorageNodes::<T>>::put(storage_nodes);
-            Ok(())
-        }
-
-        /// Remove the trusted storage node
-        #[pallet::call_index(28)]
-        #[pallet::weight(T::DbWeight::get().reads_writes(1, 1))]
-        pub fn remove_trusted_storage_node(
-            origin: OriginFor<T>,
-            node_url: DcString,
-            signature: DcString,
-        ) -> DispatchResult {
-            ensure_root(origin)?;
-            
-            let mut storage_nodes: BTreeSet<(DcString, DcString)> = match Self::trusted_storage_nodes() {
-                Some(p) => p,
-                None => BTreeSet::<(DcString, DcString)>::new(),
-            };
-            if !storage_nodes.is_empty() {
-                for info in storage_nodes.clone().iter() {
-                    if info.0 == node_url && info.1 == signature {
-                        storage_nodes.remove(info);
-                    }
-                }
-                <TrustedStorageNodes::<T>>::put(storage_nodes);
-            }
-            
-            Ok(())
-        }
-
         /// Set the storage package
         #[pallet::call_index(29)]
         #[pallet::weight(T::DbWeight::get().reads_writes(1, 1))]
@@ -1303,103 +1999,7 @@ pub mod pallet {
             package_id: PackageId
         ) -> DispatchResult {
             let who = ensure_signed(origin)?;
-
-            let package_option = Self::get_package(package_id);
-            if package_option.is_none() {
-                return Err(Error::<T>::StoragePackageNotExist)?;
-            }
-            
-            let buy_package = package_option.unwrap();
-            // check that the user's balance is sufficient 
-            if T::Currency::can_slash(&who, buy_package.2) == false {
-                return Err(Error::<T>::InsufficientBalance)?;
-            }
-            
-            let is_exist = <WalletAccountStorage<T>>::contains_key(&for_account);
-            let cur_number = frame_system::Pallet::<T>::block_number();
-            // The first purchase 
-            if is_exist {
-                let mut pre_user = <WalletAccountStorage<T>>::get(&for_account).unwrap();
-                if pre_user.parent_account != for_account {
-                    pre_user = <WalletAccountStorage<T>>::get(&pre_user.parent_account).unwrap();
-                }
-                // Used space exceeds new subscription space size 
-                if pre_user.used_space > buy_package.1 {
-                    return Err(Error::<T>::ParamErr)?;
-                }
-                let mut from_number = pre_user.expire_number;
-                if pre_user.expire_number < cur_number {
-                    from_number = cur_number;
-                }
-                let mut new_user = UserStorage {
-                    peers: pre_user.peers,
-                    used_space: pre_user.used_space,
-                    subscribe_space: buy_package.1,
-                    subscribe_price: buy_package.2,
-                    call_minus_number: buy_package.4,
-                    nft_update_number: pre_user.nft_update_number,
-                    db_update_number: pre_user.db_update_number,
-                    expire_number: from_number.saturating_add(buy_package.3),
-                    db_config: pre_user.db_config,
-                    enc_nft_account: pre_user.enc_nft_account,
-                    parent_account: pre_user.parent_account,
-                    spam_frozen_status: pre_user.spam_frozen_status,
-                    spam_report_amount: pre_user.spam_report_amount,
-                    spam_report_number: pre_user.spam_report_number,
-                    comment_frozen_status: pre_user.comment_frozen_status,
-                    comment_report_amount: pre_user.comment_report_amount,
-                    comment_report_number: pre_user.comment_report_number,
-                    login_number: pre_user.login_number,
-                    comment_space: pre_user.comment_space,
-                    request_peers: pre_user.request_peers,
-                };
-                // The original subscription has not expired and has changed the storage package
-                if pre_user.expire_number > cur_number
-                   && (pre_user.subscribe_space != buy_package.1) {
-                    // Convert remaining block number by new storage package 
-                    let pre_space: u128 = u128::try_from(pre_user.subscribe_space).unwrap();
-                    let now_space: u128 = u128::try_from(buy_package.1).unwrap();
-                    let left_number = (pre_user.expire_number.saturating_sub(cur_number)).saturated_into::<u128>();
-                    let rest_number = u32::try_from(pre_space.saturating_mul(left_number) / now_space).unwrap(); 
-
-                    new_user.expire_number = cur_number.saturating_add(rest_number.into()).saturating_add(buy_package.3);
-                }
-                
-                // Save user storage information.
-                <WalletAccountStorage<T>>::insert(&new_user.parent_account.clone(), new_user);
-            } else {
-                let new_user = UserStorage {
-                    peers: BTreeSet::new(),
-                    used_space: 0,
-                    subscribe_space: buy_package.1,
-                    subscribe_price: buy_package.2,
-                    call_minus_number: buy_package.4,
-                    nft_update_number: cur_number,
-                    db_update_number: cur_number,
-                    expire_number: cur_number.saturating_add(buy_package.3),
-                    db_config: DcString::new(),
-                    enc_nft_account: NftAccount::new(),
-                    parent_account: for_account.clone(),
-                    spam_frozen_status: 0,
-                    spam_report_amount: 0,
-                    spam_report_number: 0u32.into(),
-                    comment_frozen_status: 0,
-                    comment_report_amount: 0,
-                    comment_report_number: 0u32.into(),
-                    login_number: 0u32.into(),
-                    comment_space: 0,
-                    request_peers: BTreeSet::new(),
-                };
-                // Save user storage information.
-                <WalletAccountStorage<T>>::insert(&for_account, new_user);
-            }
-            
-            // The package fee is slashed from the user's account 
-            T::Currency::slash(&who, buy_package.2);
-            // Add package fee to The total amount(app/storage) of storage purchased by the user
-            let app_reward = Perbill::from_rational(Self::app_reward_percent(), 100)*buy_package.2;
-            <AppRewardTotal<T>>::put(Self::app_reward_total().saturating_add(app_reward));
-            <StorageRewardTotal<T>>::put(Self::storage_reward_total().saturating_add(buy_package.2).saturating_sub(app_reward));
+            Self::do_purchase_storage(&who, &for_account, package_id)?;
 
             // Emit an event.
             Self::deposit_event(Event::PurchaseStorage(package_id, for_account));
@@ -1455,7 +2055,7 @@ pub mod pallet {
             Self::change_used_space_expire_number(&for_account, 0 as SpaceSize, true, true)?;
             // Add the peer id 
             user_storage.request_peers.insert(peer_id);
-            <WalletAccountStorage<T>>::insert(&for_account, user_storage);
+            Self::insert_user(&for_account, user_storage);
 
             Ok(Pays::No.into())
         }
@@ -1490,7 +2090,7 @@ pub mod pallet {
             // Update the thread db config infomation of wallet account
             user_storage.db_config = db_config;
             user_storage.db_update_number = block_height.into();
-            <WalletAccountStorage<T>>::insert(&for_account, user_storage);
+            Self::insert_user(&for_account, user_storage);
 
             Ok(Pays::No.into())
         }
@@ -1562,7 +2162,7 @@ pub mod pallet {
                 request_peers: BTreeSet::new(),
             };
             // Save user storage information.
-            <WalletAccountStorage<T>>::insert(&sub_account, new_user);
+            Self::insert_user(&sub_account, new_user);
 
             Ok(Pays::No.into())
         }
@@ -1617,8 +2217,8 @@ pub mod pallet {
             sub_info.expire_number = cur_number;
             sub_info.parent_account = sub_account.clone();
             // Save user storage information.
-            <WalletAccountStorage<T>>::insert(&parent_account, parent_info);
-            <WalletAccountStorage<T>>::insert(&sub_account, sub_info);
+            Self::insert_user(&parent_account, parent_info);
+            Self::insert_user(&sub_account, sub_info);
 
             Ok(Pays::No.into())
         }
@@ -1699,10 +2299,25 @@ pub mod pallet {
 
             Self::verify(&signature, &message, &for_account)?;
             
+            let nft_account = Self::normalize_nft_account(&nft_account)?;
+            if <ReservedNftAccounts<T>>::contains_key(&nft_account) {
+                Err(Error::<T>::NftAccountReserved)?
+            }
             if <NftToWalletAccount<T>>::contains_key(&nft_account) {
-                Err(Error::<T>::NftAccoutApplied)?
-            } else {
-                <NftToWalletAccount<T>>::insert(&nft_account, &for_account);
+                if !Self::nft_account_releasable(&nft_account) {
+                    Err(Error::<T>::NftAccoutApplied)?
+                }
+                // The registration and its grace period have lapsed, release it before applying again
+                Self::do_release_nft_account(&nft_account)?;
+            }
+            // The registration fee is paid by for_account
+            let policy = Self::nft_account_policy();
+            Self::charge_fee(&for_account, Self::nft_account_price(&policy, nft_account.len() as u32))?;
+            // Mint the NFT account into the nfts collection
+            Self::mint_nft_account(&nft_account, &for_account)?;
+            if !policy.registration_period.is_zero() {
+                let expiry = frame_system::Pallet::<T>::block_number().saturating_add(policy.registration_period);
+                <NftAccountExpiry<T>>::insert(&nft_account, expiry);
             }
 
             // Update the encrypted NFT account to for_account
@@ -1737,17 +2352,15 @@ pub mod pallet {
             message.extend(peer_id.iter().copied());
 
             Self::verify(&signature, &message, &from_account)?;
-            if !<NftToWalletAccount<T>>::contains_key(&nft_account) {
-                Err(Error::<T>::NftAccoutApplied)?
-            }
+            let nft_account = Self::nft_account_key(&nft_account);
+            let (collection, item) = Self::nft_account_owned_by(&nft_account, &from_account)?;
             if !<WalletAccountStorage<T>>::contains_key(&to_account) {
                 Err(Error::<T>::AccountNotExist)?
             }
             
             Self::change_used_space_expire_number(&from_account, 0 as SpaceSize, true, true)?;
-            // Update the encrypted NFT account to for_account
-            Self::update_account_nft(&from_account, NftAccount::new(), block_height, true)?;
-            <NftToWalletAccount<T>>::insert(&nft_account, to_account.clone());
+            // Transfer the nfts item, the NFT account of from_account is cleared by `ItemsCallback`
+            T::Nfts::transfer(&collection, &item, &to_account)?;
             Ok(Pays::No.into())
         }
 
@@ -1776,10 +2389,8 @@ pub mod pallet {
 
             Self::verify(&signature, &message, &for_account)?;
 
-            if !<NftToWalletAccount<T>>::contains_key(&nft_account)
-               || <NftToWalletAccount<T>>::get(&nft_account).unwrap() != for_account {
-                Err(Error::<T>::NftAccoutApplied)?
-            }
+            let nft_account = Self::nft_account_key(&nft_account);
+            Self::nft_account_owned_by(&nft_account, &for_account)?;
 
             Self::change_used_space_expire_number(&for_account, 0 as SpaceSize, true, true)?;
             // Update the encrypted NFT account to for_account
@@ -1822,7 +2433,7 @@ pub mod pallet {
                 pre_info.peers.insert(peer_id.clone());
                 pre_info.users.insert(owner.clone());
                 // Update storage.
-                <Files<T>>::insert(file_id, pre_info);
+                Self::insert_file(&file_id, pre_info);
             } else {
                 let mut peers = BTreeSet::new();
                 peers.insert(peer_id.clone());
@@ -1836,7 +2447,7 @@ pub mod pallet {
                     db_log: BTreeSet::new(),
                 };
                 // Update storage.
-                <Files<T>>::insert(&file_id, new_info);
+                Self::insert_file(&file_id, new_info);
             }
 
             Ok(Pays::No.into())
@@ -1860,19 +2471,16 @@ pub mod pallet {
                 Err(Error::<T>::FileNotExist)?
             }
             let mut pre_info = <Files<T>>::get(&file_id).unwrap();
-            if pre_info.file_type == FILE_TYPE_THREAD_DB {
-                Err(Error::<T>::FileTypeError)?
-            }
             pre_info.peers.insert(peer_id);
             // Update storage.
-            <Files<T>>::insert(&file_id, pre_info);
+            Self::insert_file(&file_id, pre_info);
                       
             Ok(Pays::No.into())
         }
 
         /// Remove peer of a file by self node.
         #[pallet::call_index(44)]
-        #[pallet::weight(T::WeightInfo::remove_self_file_peer())]
+        #[pallet::weight(T::WeightInfo::remove_self_file_peer().saturating_add(Pallet::<T>::thread_db_logs_weight(1)))]
         pub fn remove_self_file_peer(
             origin: OriginFor<T>, 
             file_id: FileID, 
@@ -1890,7 +2498,7 @@ pub mod pallet {
 
         /// Remove peer of a file by other node.
         #[pallet::call_index(45)]
-        #[pallet::weight(T::WeightInfo::remove_self_file_peer())]
+        #[pallet::weight(T::WeightInfo::remove_self_file_peer().saturating_add(Pallet::<T>::thread_db_logs_weight(1)))]
         pub fn remove_other_file_peer(
             origin: OriginFor<T>, 
             peer_id: PeerId,
@@ -1906,7 +2514,7 @@ pub mod pallet {
 
         /// Delete file info.
         #[pallet::call_index(46)]
-        #[pallet::weight(T::WeightInfo::delete_file_info())]
+        #[pallet::weight(T::WeightInfo::delete_file_info().saturating_add(Pallet::<T>::thread_db_logs_weight(1)))]
         pub fn delete_file_info(
             origin: OriginFor<T>, 
             owner: T::AccountId, 
@@ -1935,10 +2543,12 @@ pub mod pallet {
                 pre_info.peers.remove(&peer_id);
                 pre_info.users.remove(&owner);
                 if pre_info.peers.len() > 0 {
+                    Self::remove_thread_db_log_peer(&file_id, &peer_id);
                     // Update storage.
-                    <Files<T>>::insert(file_id, pre_info.clone());
+                    Self::insert_file(&file_id, pre_info.clone());
                 } else {
-                    <Files<T>>::remove(file_id);
+                    let _ = <ThreadDbLogs<T>>::clear_prefix(&file_id, THREAD_DB_MAX_LOGS, None);
+                    Self::remove_file(&file_id);
                 }
             } else {
                 Err(Error::<T>::FileNotExist)?
@@ -1974,12 +2584,29 @@ pub mod pallet {
                 let mut users_iter = users.iter();
                 let owner = users_iter.next().unwrap();
                 Self::verify(&signature, &message, &owner)?;
+                if pre_info.file_type != FILE_TYPE_THREAD_DB {
+                    Err(Error::<T>::FileTypeError)?
+                }
                 // Chance used space and expire number of user's storage infomation
                 Self::change_used_space_expire_number(&owner, 0 as SpaceSize, true, true)?;
 
+                if !<ThreadDbLogs<T>>::contains_key(&file_id, &log_id) {
+                    Self::check_thread_db_log_number(&pre_info, &log_id)?;
+                    let mut peers = BTreeSet::new();
+                    peers.insert(peer_id.clone());
+                    <ThreadDbLogs<T>>::insert(&file_id, &log_id, ThreadDbLog {
+                        owner: owner.clone(),
+                        head: DcString::new(),
+                        prev_heads: BoundedVec::default(),
+                        size: 0,
+                        peers: peers,
+                        update_number: frame_system::Pallet::<T>::block_number(),
+                    });
+                }
+                pre_info.peers.insert(peer_id);
                 pre_info.db_log.insert(log_id);
                 // Update storage.
-                <Files<T>>::insert(file_id, pre_info);
+                Self::insert_file(&file_id, pre_info);
             } else {
                 Err(Error::<T>::FileNotExist)?
             }
@@ -2018,7 +2645,7 @@ pub mod pallet {
                 // Chance used space and expire number of user's storage infomation
                 Self::change_used_space_expire_number(&owner, increase_size as SpaceSize, true, true)?;
                 // Update storage.
-                <Files<T>>::insert(&file_id, pre_info);
+                Self::insert_file(&file_id, pre_info);
             } else {
                 Err(Error::<T>::FileNotExist)?
             }
@@ -2028,7 +2655,7 @@ pub mod pallet {
 
         /// Report file missing.
         #[pallet::call_index(49)]
-        #[pallet::weight(T::WeightInfo::report_file_miss())]
+        #[pallet::weight(T::WeightInfo::report_file_miss().saturating_add(Pallet::<T>::thread_db_logs_weight(1)).saturating_add(Pallet::<T>::report_offence_weight()))]
         pub fn report_file_miss(
             origin: OriginFor<T>,
             file_id: FileID,
@@ -2039,6 +2666,9 @@ pub mod pallet {
             // Check params and get the peer Id
             let peer_id = Self::check_peer_request_without_account(&who, block_height)?;
             Self::remove_file_peer(&peer_id, &file_id)?;
+            if let Some(peer_info) = Self::peers(&peer_id) {
+                Self::report_dc_offence(DcOffenceKind::FileLoss, &peer_id, &peer_info.stash, Vec::new());
+            }
             
             Ok(Pays::No.into())
         }
@@ -2079,7 +2709,7 @@ pub mod pallet {
 
         /// Verify tee faking of storage node.
         #[pallet::call_index(52)]
-        #[pallet::weight(T::WeightInfo::verify_tee_faking())]
+        #[pallet::weight(T::WeightInfo::verify_tee_faking().saturating_add(Pallet::<T>::report_offence_weight()))]
         pub fn verify_tee_faking(
             origin: OriginFor<T>,
             peer_id: PeerId, 
@@ -2114,7 +2744,7 @@ pub mod pallet {
 
         /// Report a storage node no response to the chain
         #[pallet::call_index(54)]
-        #[pallet::weight(T::WeightInfo::report_peer_no_response())]
+        #[pallet::weight(T::WeightInfo::report_peer_no_response().saturating_add(Pallet::<T>::report_offence_weight()))]
         pub fn report_peer_no_response(
             origin: OriginFor<T>,
             peer_id: PeerId, 
@@ -2132,7 +2762,7 @@ pub mod pallet {
 
         /// Report a storage node error to the chain.
         #[pallet::call_index(55)]
-        #[pallet::weight(T::DbWeight::get().reads_writes(3, 1))]
+        #[pallet::weight(T::DbWeight::get().reads_writes(3, 1).saturating_add(Pallet::<T>::report_offence_weight()))]
         pub fn report_peer_error(
             origin: OriginFor<T>,
             peer_id: PeerId, 
@@ -2152,9 +2782,9 @@ pub mod pallet {
             // After the node is offchain for a period of time, set the node status to abnormal
             if peer_info.status == NODE_STATUS_OFFCHAIN
                && peer_info.report_number < cur_num.saturating_sub(Self::blocks_of_offchain_to_abnormal()) {
-                T::StakingProvider::report_offence(&peer_info.stash, T::StakingProvider::get_staking_active(&peer_info.stash));
+                Self::report_dc_offence(DcOffenceKind::ProlongedOffline, &peer_id, &peer_info.stash, Vec::from([who]));
                 peer_info.status = NODE_STATUS_ABNORMAL;
-                <Peers<T>>::insert(peer_id.clone(), peer_info);
+                Self::insert_peer(&peer_id, peer_info);
             } else {
                 Err(Error::<T>::ErrorNodeReport)?
             }
@@ -2231,14 +2861,14 @@ pub mod pallet {
             if sender_info.spam_report_amount >= Self::frozen_report_spam_amount() {
                 sender_info.spam_frozen_status = 1;
             }
-            <WalletAccountStorage<T>>::insert(&sender_account, sender_info);
+            Self::insert_user(&sender_account, sender_info);
 
             Ok(Pays::No.into())
         }
 
-        /// set the account information associated with the app id.
+        /// Register the app id with a deposit, or set the rewarded account of the app.
         #[pallet::call_index(57)]
-        #[pallet::weight(T::DbWeight::get().reads_writes(1, 1))]
+        #[pallet::weight(T::DbWeight::get().reads_writes(3, 2))]
         pub fn set_app_account(
             origin: OriginFor<T>,
             app_id: DcString,
@@ -2248,21 +2878,38 @@ pub mod pallet {
             if app_id.len() > APPID_MAX_LENGTH.try_into().unwrap() {
                 Err(Error::<T>::AppIdLengthErr)?
             }
-            let is_exist = <AccountOfApp<T>>::contains_key(&app_id);
-            if is_exist {
-                let acc_info = Self::account_of_app(&app_id).unwrap();
-                if acc_info.private_account == who {
-                    <AccountOfApp<T>>::insert(app_id, AppAccountInfo{private_account: who, rewarded_stash: rewarded_account});
-                } else {
-                    Err(Error::<T>::NotController)?
-                }
-            } else {
-                <AccountOfApp<T>>::insert(app_id, AppAccountInfo{private_account: who, rewarded_stash: rewarded_account});
+            match Self::account_of_app(&app_id) {
+                Some(mut acc_info) => {
+                    if acc_info.private_account != who {
+                        Err(Error::<T>::NotController)?
+                    }
+                    acc_info.rewarded_stash = rewarded_account;
+                    <AccountOfApp<T>>::insert(app_id, acc_info);
+                },
+                None => {
+                    if <RevokedApps<T>>::contains_key(&app_id) {
+                        Err(Error::<T>::AppRevoked)?
+                    }
+                    let deposit = T::AppDeposit::get();
+                    T::Currency::reserve(&who, deposit).map_err(|_| Error::<T>::InsufficientBalance)?;
+                    <AccountOfApp<T>>::insert(&app_id, AppAccountInfo {
+                        private_account: who.clone(),
+                        rewarded_stash: rewarded_account,
+                        deposit: deposit,
+                        name: DcString::new(),
+                        url: DcString::new(),
+                        logo_hash: Default::default(),
+                    });
+                    Self::deposit_event(Event::RegisterApp(app_id, who, deposit));
+                },
             }
             Ok(Pays::No.into())
         }
 
-        /// User login
+        /// User login, signed by the wallet account over the app ids, block height and peer id.
+        ///
+        /// The login is counted for each app once per era, apps that require it only count
+        /// logins co-signed by their private account over the same message.
         #[pallet::call_index(58)]
         #[pallet::weight(T::WeightInfo::user_login(app_ids.len().try_into().unwrap()))]
         pub fn user_login(
@@ -2270,10 +2917,15 @@ pub mod pallet {
             login_account: T::AccountId, 
             app_ids: Vec<DcString>, 
             block_height: u32,
+            signature: DcString,
+            app_signatures: Vec<(AppID, DcString)>,
         ) -> DispatchResultWithPostInfo {
             let who = ensure_signed(origin)?;
             // Check params and get the peer Id
             let peer_id = Self::check_peer_request_without_account(&who, block_height)?;
+
+            let message = Self::login_message(&app_ids, block_height, &peer_id);
+            Self::verify(&signature, &message, &login_account)?;
             
             // Check if the user is stored in peer node
             let user_opt = Self::wallet_account_storage(&login_account);
@@ -2320,7 +2972,7 @@ pub mod pallet {
                 user_info.comment_report_number = cur_number.saturating_sub(left_number);
             }
 
-            <WalletAccountStorage<T>>::insert(&login_account, user_info);
+            Self::insert_user(&login_account, user_info);
 
             let mut login_count: BTreeMap<AppID, AppLoginInfo<T::AccountId>> = match <AppsAccountLoginTimes::<T>>::get() {
                 Some(p) => p,
@@ -2328,19 +2980,33 @@ pub mod pallet {
             };
             
             let temp_count = login_count.clone();
+            let era_index = T::StakingProvider::get_current_era_index();
             // Set the times of logins for app
             for app_id in &app_ids {
-                let is_exist = <AccountOfApp<T>>::contains_key(&app_id);
-                if is_exist {
-                    // Get the config infomation(stash account, etc) of app id.
-                    let acc_info = Self::account_of_app(&app_id).unwrap();
-                    if temp_count.contains_key(app_id) {
-                        let login_info = temp_count.get(app_id).unwrap();
-                        login_count.insert(app_id.to_vec(), AppLoginInfo{rewarded_stash: acc_info.rewarded_stash, login_times: login_info.login_times+1});
-                    } else {
-                        login_count.insert(app_id.to_vec(), AppLoginInfo{rewarded_stash: acc_info.rewarded_stash, login_times: 1});
+                // Get the config infomation(stash account, etc) of app id.
+                let acc_info = match Self::account_of_app(&app_id) {
+                    Some(acc_info) => acc_info,
+                    None => continue,
+                };
+                // Each wallet account is counted once per era for the app
+                if <AppLoginsInEra<T>>::contains_key(era_index, (app_id, &login_account)) {
+                    continue;
+                }
+                if <AppLoginCosign<T>>::contains_key(&app_id) {
+                    let cosigned = app_signatures.iter()
+                        .find(|(id, _)| id == app_id)
+                        .map_or(false, |(_, app_signature)| Self::verify(app_signature, &message, &acc_info.private_account).is_ok());
+                    if !cosigned {
+                        continue;
                     }
                 }
+                <AppLoginsInEra<T>>::insert(era_index, (app_id, &login_account), ());
+                if temp_count.contains_key(app_id) {
+                    let login_info = temp_count.get(app_id).unwrap();
+                    login_count.insert(app_id.to_vec(), AppLoginInfo{rewarded_stash: acc_info.rewarded_stash, login_times: login_info.login_times+1});
+                } else {
+                    login_count.insert(app_id.to_vec(), AppLoginInfo{rewarded_stash: acc_info.rewarded_stash, login_times: 1});
+                }
             }
             
             <AppsAccountLoginTimes::<T>>::put(login_count);
@@ -2376,11 +3042,22 @@ pub mod pallet {
             if !<WalletAccountStorage<T>>::contains_key(&for_account) {
                 Err(Error::<T>::AccountNotExist)?
             }
+            if <Themes<T>>::contains_key(&app_id, &theme_id) {
+                Err(Error::<T>::ThemeAlreadyExist)?
+            }
             Self::change_used_space_expire_number(&for_account, comment_space as SpaceSize, true, false)?;
             let mut user_storage = <WalletAccountStorage<T>>::get(&for_account).unwrap();
             user_storage.comment_space = user_storage.comment_space.saturating_add(comment_space);
-            <WalletAccountStorage<T>>::insert(&for_account, user_storage);
-
+            Self::insert_user(&for_account, user_storage);
+
+            <Themes<T>>::insert(&app_id, &theme_id, ThemeInfo {
+                owner: for_account.clone(),
+                open_flag: open_flag,
+                space: comment_space,
+                status: THEME_STATUS_OPEN,
+                created_number: frame_system::Pallet::<T>::block_number(),
+            });
+            Self::deposit_event(Event::NewTheme(app_id, theme_id, for_account, comment_space));
             Ok(Pays::No.into())
         }
 
@@ -2410,15 +3087,101 @@ pub mod pallet {
             if !<WalletAccountStorage<T>>::contains_key(&for_account) {
                 Err(Error::<T>::AccountNotExist)?
             }
+            let mut theme = Self::themes(&app_id, &theme_id).ok_or(Error::<T>::ThemeNotExist)?;
+            if theme.owner != for_account {
+                Err(Error::<T>::NotThemeOwner)?
+            }
+            if theme.status != THEME_STATUS_OPEN {
+                Err(Error::<T>::ThemeClosed)?
+            }
 
             Self::change_used_space_expire_number(&for_account, add_space as SpaceSize, true, false)?;
             let mut user_storage = <WalletAccountStorage<T>>::get(&for_account).unwrap();
             user_storage.comment_space = user_storage.comment_space.saturating_add(add_space);
-            <WalletAccountStorage<T>>::insert(&for_account, user_storage);
+            Self::insert_user(&for_account, user_storage);
 
+            theme.space = theme.space.saturating_add(add_space);
+            <Themes<T>>::insert(&app_id, &theme_id, theme);
             Ok(Pays::No.into())
         }
 
+        /// Close the theme, its comment space is returned to the owner.
+        #[pallet::call_index(91)]
+        #[pallet::weight(T::WeightInfo::add_theme_comment_space())]
+        pub fn close_theme(
+            origin: OriginFor<T>, 
+            for_account: T::AccountId,
+            theme_id: DcString, 
+            app_id: DcString,
+            block_height: u32,
+            signature: DcString,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+
+            let _ = Self::check_peer_request_without_account(&who, block_height)?;
+            
+            let mut message = DcString::new();
+            message.extend(String::from("close_theme").as_bytes().to_vec().iter().copied());
+            message.extend(theme_id.iter().copied());
+            message.extend(app_id.iter().copied());
+            message.extend(Self::u32_to_u8(block_height).iter().copied());
+            Self::verify(&signature, &message, &for_account)?;
+
+            let mut theme = Self::themes(&app_id, &theme_id).ok_or(Error::<T>::ThemeNotExist)?;
+            if theme.owner != for_account {
+                Err(Error::<T>::NotThemeOwner)?
+            }
+            if theme.status != THEME_STATUS_OPEN {
+                Err(Error::<T>::ThemeClosed)?
+            }
+
+            let space = theme.space;
+            Self::change_used_space_expire_number(&for_account, space, false, false)?;
+            let mut user_storage = <WalletAccountStorage<T>>::get(&for_account).unwrap();
+            user_storage.comment_space = user_storage.comment_space.saturating_sub(space);
+            Self::insert_user(&for_account, user_storage);
+
+            theme.space = 0;
+            theme.status = THEME_STATUS_CLOSED;
+            <Themes<T>>::insert(&app_id, &theme_id, theme);
+            Self::deposit_event(Event::CloseTheme(app_id, theme_id, space));
+            Ok(Pays::No.into())
+        }
+
+        /// Register a theme created before the theme registry to its owner.
+        ///
+        /// The space of the theme is already in the comment space of the owner, so it is not
+        /// charged again.
+        #[pallet::call_index(97)]
+        #[pallet::weight(T::DbWeight::get().reads_writes(2, 1))]
+        pub fn register_legacy_theme(
+            origin: OriginFor<T>,
+            app_id: DcString,
+            theme_id: DcString,
+            owner: T::AccountId,
+            open_flag: u32,
+            space: SpaceSize,
+        ) -> DispatchResult {
+            ensure_root(origin)?;
+            let user_storage = <WalletAccountStorage<T>>::get(&owner).ok_or(Error::<T>::AccountNotExist)?;
+            if space > user_storage.comment_space {
+                Err(Error::<T>::ParamErr)?
+            }
+            if <Themes<T>>::contains_key(&app_id, &theme_id) {
+                Err(Error::<T>::ThemeAlreadyExist)?
+            }
+
+            <Themes<T>>::insert(&app_id, &theme_id, ThemeInfo {
+                owner: owner.clone(),
+                open_flag,
+                space,
+                status: THEME_STATUS_OPEN,
+                created_number: frame_system::Pallet::<T>::block_number(),
+            });
+            Self::deposit_event(Event::NewTheme(app_id, theme_id, owner, space));
+            Ok(())
+        }
+
         /// Add the comment space of user.
         #[pallet::call_index(61)]
         #[pallet::weight(T::WeightInfo::add_user_comment_space())]
@@ -2444,7 +3207,7 @@ pub mod pallet {
             Self::change_used_space_expire_number(&for_account, reduce_space as SpaceSize, true, false)?;
             let mut user_storage = <WalletAccountStorage<T>>::get(&for_account).unwrap();
             user_storage.comment_space = user_storage.comment_space.saturating_add(reduce_space);
-            <WalletAccountStorage<T>>::insert(&for_account, user_storage);
+            Self::insert_user(&for_account, user_storage);
 
             Ok(Pays::No.into())
         }
@@ -2507,6 +3270,9 @@ pub mod pallet {
             if !report_info.peers.contains(&peer_id) {
                 Err(Error::<T>::PeerIdNotExist)?
             }
+            if !<Themes<T>>::contains_key(&app_id, &theme_id) {
+                Err(Error::<T>::ThemeNotExist)?
+            }
             
             // Set frozen status and report number of the comment user
             let commenter_opt = <WalletAccountStorage<T>>::get(&comment_account);
@@ -2525,166 +3291,1272 @@ pub mod pallet {
             if commenter_info.comment_report_amount >= Self::frozen_report_comment_amount() {
                 commenter_info.comment_frozen_status = 1;
             }
-            <WalletAccountStorage<T>>::insert(&comment_account, commenter_info);
+            Self::insert_user(&comment_account, commenter_info);
 
             Ok(Pays::No.into())
         }
-    }
-}
 
-impl<T: Config> Pallet<T> 
-    where 
-        T::AccountId: AsRef<[u8]>,
-{
-    /// Get the storage package by id
-    fn get_package(package_id: PackageId) -> Option<(PackageId, SpaceSize, BalanceOf<T>, T::BlockNumber, T::BlockNumber)> {
-        let packages: BTreeSet<(PackageId, SpaceSize, BalanceOf<T>, T::BlockNumber, T::BlockNumber)> = match Self::storage_packages() {
-            Some(p) => p,
-            None => BTreeSet::<(PackageId, SpaceSize, BalanceOf<T>, T::BlockNumber, T::BlockNumber)>::new(),
-        };
-        if packages.is_empty() {
-            return None;
-        }
-        for info in packages.iter() {
-            if info.0 == package_id {
-                return Some(*info);
+        /// Set the nfts collection that NFT accounts are minted in.
+        /// The collection can not be changed once NFT accounts are minted in it.
+        #[pallet::call_index(63)]
+        #[pallet::weight(T::DbWeight::get().reads_writes(2, 1))]
+        pub fn set_nft_collection(
+            origin: OriginFor<T>,
+            collection: T::NftCollectionId, 
+        ) -> DispatchResult {
+            ensure_root(origin)?;
+            if Self::nft_collection().is_some() && <NftAccountItem<T>>::iter_keys().next().is_some() {
+                Err(Error::<T>::NftCollectionInUse)?
             }
+            
+            <NftCollection::<T>>::put(collection);
+            Ok(())
         }
-        None
-    }
 
-    /// Chance used space and expire number of user's storage infomation
-    fn change_used_space_expire_number(owner: &T::AccountId, file_size: SpaceSize, is_add: bool, is_reduce_expire: bool) -> DispatchResult {
-        // consume user space 
-        let owner_opt = <WalletAccountStorage<T>>::get(&owner);
-        if owner_opt.is_none() {
-            Err(Error::<T>::AccountNotExist)?
+        /// Set the name policy of NFT accounts.
+        #[pallet::call_index(64)]
+        #[pallet::weight(T::DbWeight::get().reads_writes(0, 1))]
+        pub fn set_nft_account_policy(
+            origin: OriginFor<T>,
+            policy: NftAccountPolicy<BalanceOf<T>, T::BlockNumber>, 
+        ) -> DispatchResult {
+            ensure_root(origin)?;
+            if policy.min_length == 0 || policy.min_length > policy.max_length {
+                Err(Error::<T>::ParamErr)?
+            }
+
+            <NftAccountNamePolicy<T>>::put(policy);
+            Ok(())
         }
 
-        let mut owner_info = owner_opt.unwrap();
-        if owner_info.expire_number < frame_system::Pallet::<T>::block_number()
-           || owner_info.expire_number.saturating_sub(frame_system::Pallet::<T>::block_number()) < owner_info.call_minus_number {
-            Err(Error::<T>::UserPackageExpired)?
+        /// Reserve NFT account, reserved NFT account can not be applied.
+        #[pallet::call_index(65)]
+        #[pallet::weight(T::DbWeight::get().reads_writes(0, 1))]
+        pub fn reserve_nft_account(
+            origin: OriginFor<T>,
+            nft_account: NftAccount, 
+        ) -> DispatchResult {
+            ensure_root(origin)?;
+            let nft_account = Self::normalize_nft_account(&nft_account)?;
+
+            <ReservedNftAccounts<T>>::insert(nft_account, ());
+            Ok(())
         }
-        if file_size > 0 as SpaceSize || (owner_info.parent_account == *owner && is_reduce_expire) {
-            if is_add {
-                owner_info.used_space = owner_info.used_space.saturating_add(file_size);
+
+        /// Remove NFT account from the reserved list.
+        #[pallet::call_index(66)]
+        #[pallet::weight(T::DbWeight::get().reads_writes(0, 1))]
+        pub fn unreserve_nft_account(
+            origin: OriginFor<T>,
+            nft_account: NftAccount, 
+        ) -> DispatchResult {
+            ensure_root(origin)?;
+            let nft_account = if <ReservedNftAccounts<T>>::contains_key(&nft_account) {
+                nft_account
             } else {
-                owner_info.used_space = owner_info.used_space.saturating_sub(file_size);
-            }
-            if owner_info.parent_account == *owner && is_reduce_expire {
-                let minus_number: u128 = T::BlockMultiplier::get_next_fee_multiplier().saturating_mul_int(owner_info.call_minus_number.saturated_into::<u128>());
-                owner_info.expire_number = owner_info.expire_number.saturating_sub(u32::try_from(minus_number).unwrap().into());
-            }
-            <WalletAccountStorage<T>>::insert(&owner, owner_info.clone());
-        }
-        
-        // It is a sub account 
-        if owner_info.parent_account != *owner {
-            // consume parent user space
-            let parent_opt = <WalletAccountStorage<T>>::get(&owner_info.parent_account);
-            if parent_opt.is_some() {
-                let mut parent_info = parent_opt.unwrap();
-                if parent_info.expire_number < frame_system::Pallet::<T>::block_number()
-                   || parent_info.expire_number.saturating_sub(frame_system::Pallet::<T>::block_number()) < parent_info.call_minus_number {
-                    Err(Error::<T>::UserPackageExpired)?
-                }
-                if is_add {
-                    parent_info.used_space = parent_info.used_space.saturating_add(file_size);
-                } else {
-                    parent_info.used_space = parent_info.used_space.saturating_sub(file_size);
-                }
-                if is_reduce_expire {
-                    let minus_number: u128 = T::BlockMultiplier::get_next_fee_multiplier().saturating_mul_int(parent_info.call_minus_number.saturated_into::<u128>());
-                    parent_info.expire_number = parent_info.expire_number.saturating_sub(u32::try_from(minus_number).unwrap().into());
-                }
-                <WalletAccountStorage<T>>::insert(&owner_info.parent_account, parent_info);
-            }
-        }
-        Ok(())
-    }
+                nft_account.to_ascii_lowercase()
+            };
 
-    /// Insert peer into account storage
-    fn insert_user_peer(for_account: &T::AccountId, peer_id: PeerId) -> DispatchResult {
-        let is_exist = <WalletAccountStorage<T>>::contains_key(for_account);
-        if !is_exist {
-            Err(Error::<T>::AccountNotExist)?
+            <ReservedNftAccounts<T>>::remove(nft_account);
+            Ok(())
         }
-        let mut pre_info = <WalletAccountStorage<T>>::get(for_account).unwrap();
-        pre_info.peers.insert(peer_id);
-        // Update storage.
-        <WalletAccountStorage<T>>::insert(for_account, pre_info);
-        Ok(())
-    }
 
-    /// Update the encrypted NFT account to for_account
-    fn update_account_nft(
-        for_account: &T::AccountId,
-        enc_nft_account: DcString, 
-        block_height: u32,
-        is_clear_peer: bool,
-    ) -> DispatchResult {
-        let user_opt = <WalletAccountStorage<T>>::get(for_account);
-        if user_opt.is_none() {
-            Err(Error::<T>::AccountNotExist)?
-        }
-        let mut user_storage = user_opt.unwrap();
-        // Update the encrypted NFT account to for_account
-        user_storage.enc_nft_account = enc_nft_account;
-        user_storage.nft_update_number = block_height.into();
-        if is_clear_peer {
-            user_storage.peers = BTreeSet::<PeerId>::new();
-        }
-        <WalletAccountStorage<T>>::insert(for_account, user_storage);
-        Ok(())
-    }
+        /// Renew the registration of NFT account for a registration period, paid by origin.
+        /// It can still be renewed in the grace period after the expiry.
+        #[pallet::call_index(67)]
+        #[pallet::weight(T::DbWeight::get().reads_writes(4, 4))]
+        pub fn renew_nft_account(
+            origin: OriginFor<T>,
+            nft_account: NftAccount, 
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            let nft_account = Self::nft_account_key(&nft_account);
 
-    /// u32 to [u8]
-    fn u32_to_u8(v: u32) -> [u8; 4] {
-        unsafe {
-            let u32_ptr: *const u32 = &v as *const u32;
-            let u8_ptr: *const u8 = u32_ptr as *const u8;
-            return [*u8_ptr.offset(0), *u8_ptr.offset(1), *u8_ptr.offset(2), *u8_ptr.offset(3)];
-        }
-    }
+            let expiry = Self::nft_account_expiry(&nft_account).ok_or(Error::<T>::AccountNotExist)?;
+            if Self::nft_account_releasable(&nft_account) {
+                Err(Error::<T>::NftAccountExpired)?
+            }
+            let policy = Self::nft_account_policy();
+            if policy.registration_period.is_zero() {
+                Err(Error::<T>::NftAccountNotRenewable)?
+            }
+            Self::charge_fee(&who, Self::nft_account_price(&policy, nft_account.len() as u32))?;
 
-    /// u64 to [u8]
-    fn u64_to_u8(v: u64) -> [u8; 8] {
-        unsafe {
-            let u64_ptr: *const u64 = &v as *const u64;
-            let u8_ptr: *const u8 = u64_ptr as *const u8;
-            return [*u8_ptr.offset(0), *u8_ptr.offset(1), *u8_ptr.offset(2), *u8_ptr.offset(3),
-                    *u8_ptr.offset(4), *u8_ptr.offset(5), *u8_ptr.offset(6), *u8_ptr.offset(7)];
+            let new_expiry = expiry.saturating_add(policy.registration_period);
+            <NftAccountExpiry<T>>::insert(&nft_account, new_expiry);
+            Self::deposit_event(Event::RenewNftAccount(nft_account, new_expiry));
+            Ok(())
         }
-    }
 
-    /// Get peer ID by request account ID
-    fn get_peer_id_with_req_acc_id(req_account_id: &T::AccountId) -> Result<PeerId, Error<T>> {
-        // Get the peer ID
-        let peer_id_ret = Self::request_account_peer(req_account_id);
-        if peer_id_ret.is_none() {
-            Err(Error::<T>::AccountNotExist)?
-        }
-        let peer_id = peer_id_ret.unwrap();
-        // Get the peer infomation
-        let peer_ret = Self::peers(&peer_id);
-        if peer_ret.is_none() {
-            Err(Error::<T>::PeerIdNotExist)?
-        }
+        /// Release the NFT account whose registration and grace period have expired, anyone can release it.
+        #[pallet::call_index(68)]
+        #[pallet::weight(T::DbWeight::get().reads_writes(6, 8))]
+        pub fn release_nft_account(
+            origin: OriginFor<T>,
+            nft_account: NftAccount, 
+        ) -> DispatchResult {
+            ensure_signed(origin)?;
+            let nft_account = Self::nft_account_key(&nft_account);
 
-        let peer_info = peer_ret.unwrap();
-        if peer_info.status != NODE_STATUS_ONCHAIN {
-            Err(Error::<T>::NodeStatusError)?
+            if !<NftAccountItem<T>>::contains_key(&nft_account) {
+                Err(Error::<T>::AccountNotExist)?
+            }
+            if !Self::nft_account_releasable(&nft_account) {
+                Err(Error::<T>::NftAccountNotExpired)?
+            }
+            Self::do_release_nft_account(&nft_account)
         }
 
-        Ok(peer_id)
-    }
+        /// Set the max number of fee-free calls of a storage node in a `PeerCallWindow`.
+        #[pallet::call_index(69)]
+        #[pallet::weight(T::DbWeight::get().reads_writes(0, 1))]
+        pub fn set_peer_call_quota(
+            origin: OriginFor<T>,
+            quota: u32, 
+        ) -> DispatchResult {
+            ensure_root(origin)?;
+            
+            <PeerCallQuota::<T>>::put(quota);
+            Ok(())
+        }
 
-    /// Check the block number of wallet account
-    fn check_account_block_number(for_account: &T::AccountId, block_height: u32, is_nft: bool) -> Result<(), Error<T>> {
-        let block_num :T::BlockNumber = block_height.into();
-        // Get the storage information of for_account
-        let is_exist = <WalletAccountStorage<T>>::contains_key(for_account);
+        /// Update the head and size of thread db log, the change of size is charged to the log owner.
+        #[pallet::call_index(70)]
+        #[pallet::weight(T::WeightInfo::update_thread_db_log())]
+        pub fn update_thread_db_log(
+            origin: OriginFor<T>, 
+            file_id: FileID, 
+            log_id: DcString,
+            owner: T::AccountId,
+            head: DcString,
+            log_size: SpaceSize,
+            block_height: u32,
+            signature: DcString,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+            // Check params and get the peer Id
+            let peer_id = Self::check_peer_request_without_account(&who, block_height)?;
+
+            let mut message = DcString::new();
+            message.extend(file_id.iter().copied());
+            message.extend(log_id.iter().copied());
+            message.extend(head.iter().copied());
+            message.extend(Self::u64_to_u8(log_size).iter().copied());
+            message.extend(Self::u32_to_u8(block_height).iter().copied());
+            message.extend(peer_id.iter().copied());
+
+            Self::verify(&signature, &message, &owner)?;
+
+            let mut file_info = Self::files(&file_id).ok_or(Error::<T>::FileNotExist)?;
+            Self::check_thread_db_user(&file_info, &owner)?;
+            let cur_number = frame_system::Pallet::<T>::block_number();
+            let mut log = match Self::thread_db_logs(&file_id, &log_id) {
+                Some(log) => {
+                    if log.owner != owner {
+                        Err(Error::<T>::NotThreadDbLogOwner)?
+                    }
+                    log
+                },
+                None => {
+                    Self::check_thread_db_log_number(&file_info, &log_id)?;
+                    ThreadDbLog {
+                        owner: owner.clone(),
+                        head: DcString::new(),
+                        prev_heads: BoundedVec::default(),
+                        size: 0,
+                        peers: BTreeSet::new(),
+                        update_number: cur_number,
+                    }
+                },
+            };
+
+            // Chance used space and expire number of user's storage infomation
+            if log_size >= log.size {
+                Self::change_used_space_expire_number(&owner, log_size - log.size, true, true)?;
+                file_info.file_size = file_info.file_size.saturating_add(log_size - log.size);
+            } else {
+                Self::change_used_space_expire_number(&owner, log.size - log_size, false, true)?;
+                file_info.file_size = file_info.file_size.saturating_sub(log.size - log_size);
+            }
+            if !log.head.is_empty() && log.head != head {
+                // Compact the oldest head once the previous heads are full
+                let _ = log.prev_heads.force_insert_keep_right(log.prev_heads.len(), log.head);
+            }
+            log.head = head;
+            log.size = log_size;
+            log.peers.insert(peer_id.clone());
+            log.update_number = cur_number;
+            file_info.peers.insert(peer_id);
+            file_info.db_log.insert(log_id.clone());
+            // Update storage.
+            <ThreadDbLogs<T>>::insert(&file_id, log_id, log);
+            Self::insert_file(&file_id, file_info);
+
+            Ok(Pays::No.into())
+        }
+
+        /// Add peer that has replicated the thread db log.
+        #[pallet::call_index(71)]
+        #[pallet::weight(T::WeightInfo::add_thread_db_log_peer())]
+        pub fn add_thread_db_log_peer(
+            origin: OriginFor<T>, 
+            file_id: FileID, 
+            log_id: DcString,
+            block_height: u32,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+            // Check params and get the peer Id
+            let peer_id = Self::check_peer_request_without_account(&who, block_height)?;
+
+            let mut file_info = Self::files(&file_id).ok_or(Error::<T>::FileNotExist)?;
+            let mut log = Self::thread_db_logs(&file_id, &log_id).ok_or(Error::<T>::ThreadDbLogNotExist)?;
+            log.peers.insert(peer_id.clone());
+            file_info.peers.insert(peer_id);
+            // Update storage.
+            <ThreadDbLogs<T>>::insert(&file_id, log_id, log);
+            Self::insert_file(&file_id, file_info);
+
+            Ok(Pays::No.into())
+        }
+
+        /// Compact the previous heads of thread db log, the latest `keep_heads` heads are kept.
+        #[pallet::call_index(72)]
+        #[pallet::weight(T::WeightInfo::compact_thread_db_log())]
+        pub fn compact_thread_db_log(
+            origin: OriginFor<T>, 
+            file_id: FileID, 
+            log_id: DcString,
+            keep_heads: u32,
+            block_height: u32,
+            signature: DcString,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+            // Check params and get the peer Id
+            let peer_id = Self::check_peer_request_without_account(&who, block_height)?;
+
+            let mut message = DcString::new();
+            message.extend(file_id.iter().copied());
+            message.extend(log_id.iter().copied());
+            message.extend(Self::u32_to_u8(keep_heads).iter().copied());
+            message.extend(Self::u32_to_u8(block_height).iter().copied());
+            message.extend(peer_id.iter().copied());
+
+            let mut log = Self::thread_db_logs(&file_id, &log_id).ok_or(Error::<T>::ThreadDbLogNotExist)?;
+            Self::verify(&signature, &message, &log.owner)?;
+            // Chance expire number of user's storage infomation
+            Self::change_used_space_expire_number(&log.owner, 0 as SpaceSize, true, true)?;
+
+            let compact_number = log.prev_heads.len().saturating_sub(keep_heads as usize);
+            log.prev_heads.drain(..compact_number);
+            // Update storage.
+            <ThreadDbLogs<T>>::insert(&file_id, log_id, log);
+
+            Ok(Pays::No.into())
+        }
+
+        /// Set the endpoint of blockchain proxy node or trusted storage node.
+        #[pallet::call_index(73)]
+        #[pallet::weight(T::DbWeight::get().reads_writes(0, 1))]
+        pub fn set_endpoint(
+            origin: OriginFor<T>,
+            kind: EndpointKind,
+            endpoint: NodeEndpoint<T::AccountId, T::BlockNumber>,
+        ) -> DispatchResult {
+            ensure_root(origin)?;
+
+            let address = endpoint.address.clone();
+            <Endpoints<T>>::insert(kind, &address, NodeEndpoint {
+                last_seen: frame_system::Pallet::<T>::block_number(),
+                ..endpoint
+            });
+            Self::deposit_event(Event::SetEndpoint(kind, address));
+            Ok(())
+        }
+
+        /// Remove the endpoint of blockchain proxy node or trusted storage node.
+        #[pallet::call_index(74)]
+        #[pallet::weight(T::DbWeight::get().reads_writes(1, 1))]
+        pub fn remove_endpoint(
+            origin: OriginFor<T>,
+            kind: EndpointKind,
+            address: DcString,
+        ) -> DispatchResult {
+            ensure_root(origin)?;
+            if !<Endpoints<T>>::contains_key(kind, &address) {
+                Err(Error::<T>::EndpointNotExist)?
+            }
+
+            <Endpoints<T>>::remove(kind, &address);
+            Self::deposit_event(Event::RemoveEndpoint(kind, address));
+            Ok(())
+        }
+
+        /// Heartbeat of the endpoint by its operator, with the API version that the node supports now.
+        #[pallet::call_index(75)]
+        #[pallet::weight(T::DbWeight::get().reads_writes(1, 1))]
+        pub fn endpoint_heartbeat(
+            origin: OriginFor<T>,
+            kind: EndpointKind,
+            address: DcString,
+            api_version: u32,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            let mut endpoint = Self::endpoint(kind, &address).ok_or(Error::<T>::EndpointNotExist)?;
+            if endpoint.operator.as_ref() != Some(&who) {
+                Err(Error::<T>::NotEndpointOperator)?
+            }
+            endpoint.api_version = api_version;
+            endpoint.last_seen = frame_system::Pallet::<T>::block_number();
+            <Endpoints<T>>::insert(kind, address, endpoint);
+            Ok(())
+        }
+
+        /// Announce the DC program that storage nodes are required to run.
+        /// Nodes are warned from the activation block, and demoted after the grace period.
+        /// The enclave must be the one of `DcProgram`, so that nodes download the required program.
+        #[pallet::call_index(76)]
+        #[pallet::weight(T::DbWeight::get().reads_writes(2, 2))]
+        pub fn set_program_requirement(
+            origin: OriginFor<T>,
+            min_version: ProgramVersion,
+            enclave_id: DcString,
+            activation_number: T::BlockNumber,
+            grace_period: T::BlockNumber,
+        ) -> DispatchResult {
+            ensure_root(origin)?;
+            let cur_block_num = frame_system::Pallet::<T>::block_number();
+            if activation_number < cur_block_num {
+                Err(Error::<T>::ParamErr)?
+            }
+            if !enclave_id.is_empty() && Self::dc_program().map(|program| program.enclave_id).as_ref() != Some(&enclave_id) {
+                Err(Error::<T>::ProgramNotPublished)?
+            }
+            if let Some(pending) = Self::pending_program() {
+                if cur_block_num >= pending.deadline_number {
+                    <RequiredProgram<T>>::put(pending);
+                } else if cur_block_num >= pending.activation_number {
+                    Err(Error::<T>::ProgramUpgradeInProgress)?
+                }
+            }
+
+            let deadline_number = activation_number.saturating_add(grace_period);
+            <PendingProgram<T>>::put(ProgramRequirement {
+                min_version,
+                enclave_id: enclave_id.clone(),
+                activation_number,
+                deadline_number,
+            });
+            Self::deposit_event(Event::AnnounceProgramRequirement(min_version, enclave_id, activation_number, deadline_number));
+            Ok(())
+        }
+
+        /// Set the slash fraction of storage node offences of the kind.
+        #[pallet::call_index(77)]
+        #[pallet::weight(T::DbWeight::get().reads_writes(0, 1))]
+        pub fn set_offence_slash_fraction(
+            origin: OriginFor<T>,
+            kind: DcOffenceKind,
+            fraction: Perbill,
+        ) -> DispatchResult {
+            ensure_root(origin)?;
+
+            <OffenceSlashFractions<T>>::insert(kind, fraction);
+            Ok(())
+        }
+
+        /// Set the liveness prober key, whose reports are counted for the account.
+        #[pallet::call_index(78)]
+        #[pallet::weight(T::DbWeight::get().reads_writes(0, 1))]
+        pub fn set_liveness_prober(
+            origin: OriginFor<T>,
+            key: T::AuthorityId,
+            account: T::AccountId,
+        ) -> DispatchResult {
+            ensure_root(origin)?;
+
+            <LivenessProbers<T>>::insert(&key, account.clone());
+            Self::deposit_event(Event::SetLivenessProber(key, account));
+            Ok(())
+        }
+
+        /// Remove the liveness prober key.
+        #[pallet::call_index(79)]
+        #[pallet::weight(T::DbWeight::get().reads_writes(1, 2))]
+        pub fn remove_liveness_prober(
+            origin: OriginFor<T>,
+            key: T::AuthorityId,
+        ) -> DispatchResult {
+            ensure_root(origin)?;
+            if !<LivenessProbers<T>>::contains_key(&key) {
+                Err(Error::<T>::NotLivenessProber)?
+            }
+
+            <LivenessProbers<T>>::remove(&key);
+            <LastLivenessReport<T>>::remove(&key);
+            Self::deposit_event(Event::RemoveLivenessProber(key));
+            Ok(())
+        }
+
+        /// Submit the liveness report of a probing round from the offchain worker.
+        /// The storage nodes that did not answer are reported no response for the prober.
+        #[pallet::call_index(80)]
+        #[pallet::weight(T::WeightInfo::submit_liveness_report(report.offline.len() as u32)
+            .saturating_add(Pallet::<T>::report_offence_weight().saturating_mul(report.offline.len() as u64)))]
+        pub fn submit_liveness_report(
+            origin: OriginFor<T>,
+            report: LivenessReport<T::BlockNumber, T::AuthorityId>,
+            // The signature is checked in `validate_unsigned`
+            _signature: <T::AuthorityId as RuntimeAppPublic>::Signature,
+        ) -> DispatchResultWithPostInfo {
+            ensure_none(origin)?;
+            let account = Self::liveness_prober(&report.prober).ok_or(Error::<T>::NotLivenessProber)?;
+
+            <LastLivenessReport<T>>::insert(&report.prober, report.block_number);
+            for peer_id in report.offline.iter() {
+                // Nodes that went offchain or were reported by the prober in the era are skipped
+                let _ = Self::process_report(account.clone(), peer_id, ReportType::ReportPeerNoResponse);
+            }
+            Self::deposit_event(Event::LivenessReported(account, report.probed, report.offline.len() as u32));
+
+            Ok(Pays::No.into())
+        }
+
+        /// Set the storage sponsorship of the app, which pays the package for its enrolled users
+        /// from the private account of the app, within the budget and the limit for each user.
+        #[pallet::call_index(81)]
+        #[pallet::weight(T::DbWeight::get().reads_writes(3, 1))]
+        pub fn set_sponsorship(
+            origin: OriginFor<T>,
+            app_id: AppID,
+            package_id: PackageId,
+            budget: BalanceOf<T>,
+            limit_per_user: BalanceOf<T>,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            Self::ensure_app_account(&who, &app_id)?;
+            if Self::get_package(package_id).is_none() {
+                Err(Error::<T>::StoragePackageNotExist)?
+            }
+
+            // The enrolled users stay in the updated sponsorship
+            let users = Self::sponsorship(&app_id).map_or(0, |sponsorship| sponsorship.users);
+            <Sponsorships<T>>::insert(&app_id, Sponsorship {
+                package_id: package_id,
+                budget: budget,
+                limit_per_user: limit_per_user,
+                users: users,
+            });
+            Self::deposit_event(Event::SetSponsorship(app_id, package_id, budget, limit_per_user));
+            Ok(())
+        }
+
+        /// Revoke the storage sponsorship of the app, the packages paid stay until they expire.
+        #[pallet::call_index(82)]
+        #[pallet::weight(T::DbWeight::get().reads_writes(2, 1).saturating_add(Pallet::<T>::remove_sponsorship_weight()))]
+        pub fn revoke_sponsorship(
+            origin: OriginFor<T>,
+            app_id: AppID,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            Self::ensure_app_account(&who, &app_id)?;
+            if !<Sponsorships<T>>::contains_key(&app_id) {
+                Err(Error::<T>::SponsorshipNotExist)?
+            }
+
+            Self::remove_sponsorship(&app_id);
+            Self::deposit_event(Event::RevokeSponsorship(app_id));
+            Ok(())
+        }
+
+        /// Enrol the wallet account in the sponsorship of the app, and pay its package.
+        /// The package is renewed from the sponsorship by `renew_sponsored_storage` when it expires.
+        #[pallet::call_index(83)]
+        #[pallet::weight(T::WeightInfo::purchase_storage().saturating_add(T::DbWeight::get().reads_writes(4, 3)))]
+        pub fn sponsor_user(
+            origin: OriginFor<T>,
+            app_id: AppID,
+            for_account: T::AccountId,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            Self::ensure_app_account(&who, &app_id)?;
+            let mut sponsorship = Self::sponsorship(&app_id).ok_or(Error::<T>::SponsorshipNotExist)?;
+            if <UserSponsor<T>>::contains_key(&for_account) {
+                Err(Error::<T>::AlreadySponsored)?
+            }
+            if sponsorship.users >= SPONSORED_USERS_MAX_NUM {
+                Err(Error::<T>::TooManySponsoredUsers)?
+            }
+            // Sub accounts share the package of their parent account
+            if let Some(user) = Self::wallet_account_storage(&for_account) {
+                if user.parent_account != for_account {
+                    Err(Error::<T>::IsSubAccount)?
+                }
+            }
+
+            sponsorship.users += 1;
+            <Sponsorships<T>>::insert(&app_id, sponsorship);
+            <UserSponsor<T>>::insert(&for_account, &app_id);
+            <SponsoredUsers<T>>::insert(&app_id, &for_account, BalanceOf::<T>::zero());
+            Self::deposit_event(Event::SponsorUser(app_id.clone(), for_account.clone()));
+            Self::charge_sponsorship(&app_id, &for_account)
+        }
+
+        /// Remove the wallet account from the sponsorship of the app, by the app or the account.
+        #[pallet::call_index(84)]
+        #[pallet::weight(T::DbWeight::get().reads_writes(3, 3))]
+        pub fn remove_sponsored_user(
+            origin: OriginFor<T>,
+            app_id: AppID,
+            for_account: T::AccountId,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            if who != for_account {
+                Self::ensure_app_account(&who, &app_id)?;
+            }
+            if Self::user_sponsor(&for_account).as_ref() != Some(&app_id) {
+                Err(Error::<T>::NotSponsored)?
+            }
+
+            <UserSponsor<T>>::remove(&for_account);
+            <SponsoredUsers<T>>::remove(&app_id, &for_account);
+            <Sponsorships<T>>::mutate(&app_id, |sponsorship| if let Some(sponsorship) = sponsorship {
+                sponsorship.users = sponsorship.users.saturating_sub(1);
+            });
+            Self::deposit_event(Event::RemoveSponsoredUser(app_id, for_account));
+            Ok(())
+        }
+
+        /// Set the name, URL and logo hash of the app.
+        #[pallet::call_index(85)]
+        #[pallet::weight(T::DbWeight::get().reads_writes(1, 1))]
+        pub fn set_app_metadata(
+            origin: OriginFor<T>,
+            app_id: AppID,
+            name: DcString,
+            url: DcString,
+            logo_hash: T::Hash,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            if name.len() > APP_NAME_MAX_LENGTH || url.len() > APP_URL_MAX_LENGTH {
+                Err(Error::<T>::AppMetadataTooLong)?
+            }
+            let mut acc_info = Self::account_of_app(&app_id).ok_or(Error::<T>::AppNotExist)?;
+            if acc_info.private_account != who {
+                Err(Error::<T>::NotController)?
+            }
+
+            acc_info.name = name;
+            acc_info.url = url;
+            acc_info.logo_hash = logo_hash;
+            <AccountOfApp<T>>::insert(&app_id, acc_info);
+            Self::deposit_event(Event::SetAppMetadata(app_id));
+            Ok(())
+        }
+
+        /// Offer the ownership of the app to the account, which takes effect once it is accepted.
+        #[pallet::call_index(86)]
+        #[pallet::weight(T::DbWeight::get().reads_writes(1, 1))]
+        pub fn transfer_app_ownership(
+            origin: OriginFor<T>,
+            app_id: AppID,
+            new_owner: T::AccountId,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            Self::ensure_app_account(&who, &app_id)?;
+
+            <PendingAppOwner<T>>::insert(&app_id, new_owner.clone());
+            Self::deposit_event(Event::TransferAppOwnership(app_id, new_owner));
+            Ok(())
+        }
+
+        /// Accept the ownership of the app, the deposit is moved to the new owner.
+        #[pallet::call_index(87)]
+        #[pallet::weight(T::DbWeight::get().reads_writes(4, 4))]
+        pub fn accept_app_ownership(
+            origin: OriginFor<T>,
+            app_id: AppID,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            if Self::pending_app_owner(&app_id).as_ref() != Some(&who) {
+                Err(Error::<T>::NotPendingAppOwner)?
+            }
+            let mut acc_info = Self::account_of_app(&app_id).ok_or(Error::<T>::AppNotExist)?;
+
+            let deposit = T::AppDeposit::get();
+            T::Currency::reserve(&who, deposit).map_err(|_| Error::<T>::InsufficientBalance)?;
+            T::Currency::unreserve(&acc_info.private_account, acc_info.deposit);
+            acc_info.private_account = who.clone();
+            acc_info.deposit = deposit;
+            <AccountOfApp<T>>::insert(&app_id, acc_info);
+            <PendingAppOwner<T>>::remove(&app_id);
+            Self::deposit_event(Event::AcceptAppOwnership(app_id, who));
+            Ok(())
+        }
+
+        /// Deregister the app and refund its deposit, its sponsorship is revoked.
+        #[pallet::call_index(88)]
+        #[pallet::weight(T::DbWeight::get().reads_writes(4, 5).saturating_add(Pallet::<T>::remove_sponsorship_weight()))]
+        pub fn deregister_app(
+            origin: OriginFor<T>,
+            app_id: AppID,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            Self::ensure_app_account(&who, &app_id)?;
+
+            let acc_info = Self::remove_app(&app_id).ok_or(Error::<T>::AppNotExist)?;
+            T::Currency::unreserve(&acc_info.private_account, acc_info.deposit);
+            Self::deposit_event(Event::DeregisterApp(app_id, acc_info.deposit));
+            Ok(())
+        }
+
+        /// Pause or unpause the calls in the category.
+        #[pallet::call_index(95)]
+        #[pallet::weight(T::DbWeight::get().reads_writes(1, 1))]
+        pub fn set_calls_paused(
+            origin: OriginFor<T>,
+            category: CallCategory,
+            paused: bool,
+        ) -> DispatchResult {
+            ensure_root(origin)?;
+            let changed = <PausedCategories<T>>::mutate(|categories| if paused {
+                categories.insert(category)
+            } else {
+                categories.remove(&category)
+            });
+            if changed {
+                if paused {
+                    Self::deposit_event(Event::CallsPaused(category));
+                } else {
+                    Self::deposit_event(Event::CallsUnpaused(category));
+                }
+            }
+            Ok(())
+        }
+
+        /// Renew the package of the sponsored wallet account from its sponsor, once the package
+        /// has expired or can not afford the next call. Anyone can renew it.
+        #[pallet::call_index(96)]
+        #[pallet::weight(T::WeightInfo::purchase_storage().saturating_add(T::DbWeight::get().reads_writes(5, 2)))]
+        pub fn renew_sponsored_storage(
+            origin: OriginFor<T>,
+            for_account: T::AccountId,
+        ) -> DispatchResult {
+            ensure_signed(origin)?;
+            let app_id = Self::user_sponsor(&for_account).ok_or(Error::<T>::NotSponsored)?;
+            let user = Self::wallet_account_storage(&for_account).ok_or(Error::<T>::AccountNotExist)?;
+            let cur_number = frame_system::Pallet::<T>::block_number();
+            if user.expire_number >= cur_number
+               && user.expire_number.saturating_sub(cur_number) >= user.call_minus_number {
+                Err(Error::<T>::SponsoredStorageNotExpired)?
+            }
+            Self::charge_sponsorship(&app_id, &for_account)
+        }
+
+        /// Set the curve that the prices of storage packages follow, or fix the prices with `None`.
+        #[pallet::call_index(92)]
+        #[pallet::weight(T::DbWeight::get().reads_writes(0, 2))]
+        pub fn set_pricing_curve(
+            origin: OriginFor<T>,
+            curve: Option<PricingCurve>,
+        ) -> DispatchResult {
+            ensure_root(origin)?;
+            match curve {
+                Some(curve) => {
+                    if curve.min_multiplier.is_zero() || curve.min_multiplier > curve.max_multiplier {
+                        Err(Error::<T>::ParamErr)?
+                    }
+                    <PriceMultiplier<T>>::mutate(|m| *m = (*m).max(curve.min_multiplier).min(curve.max_multiplier));
+                    <StoragePricingCurve<T>>::put(curve);
+                },
+                None => {
+                    <StoragePricingCurve<T>>::kill();
+                    <PriceMultiplier<T>>::kill();
+                },
+            }
+            Ok(())
+        }
+
+        /// Require the logins of the app to be co-signed by its private account or not.
+        #[pallet::call_index(90)]
+        #[pallet::weight(T::DbWeight::get().reads_writes(1, 1))]
+        pub fn set_app_login_cosign(
+            origin: OriginFor<T>,
+            app_id: AppID,
+            required: bool,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            Self::ensure_app_account(&who, &app_id)?;
+
+            if required {
+                <AppLoginCosign<T>>::insert(&app_id, ());
+            } else {
+                <AppLoginCosign<T>>::remove(&app_id);
+            }
+            Self::deposit_event(Event::SetAppLoginCosign(app_id, required));
+            Ok(())
+        }
+
+        /// Revoke the fraudulent app, its deposit is slashed and its logins are not rewarded.
+        /// The app id can not be registered again.
+        #[pallet::call_index(89)]
+        #[pallet::weight(T::DbWeight::get().reads_writes(4, 6).saturating_add(Pallet::<T>::remove_sponsorship_weight()))]
+        pub fn revoke_app(
+            origin: OriginFor<T>,
+            app_id: AppID,
+        ) -> DispatchResult {
+            ensure_root(origin)?;
+
+            let acc_info = Self::remove_app(&app_id).ok_or(Error::<T>::AppNotExist)?;
+            let _ = T::Currency::slash_reserved(&acc_info.private_account, acc_info.deposit);
+            <RevokedApps<T>>::insert(&app_id, ());
+            Self::deposit_event(Event::RevokeApp(app_id, acc_info.deposit));
+            Ok(())
+        }
+    }
+
+    #[pallet::validate_unsigned]
+    impl<T: Config> ValidateUnsigned for Pallet<T>
+    where
+        T::AccountId: AsRef<[u8]>,
+    {
+        type Call = Call<T>;
+
+        fn validate_unsigned(_source: TransactionSource, call: &Self::Call) -> TransactionValidity {
+            if let Call::submit_liveness_report { report, signature } = call {
+                let interval = T::LivenessProbeInterval::get();
+                let cur_block_num = frame_system::Pallet::<T>::block_number();
+                if report.block_number > cur_block_num {
+                    return InvalidTransaction::Future.into();
+                }
+                if report.block_number.saturating_add(interval) <= cur_block_num {
+                    return InvalidTransaction::Stale.into();
+                }
+                // Rounds start at the multiples of the interval
+                if interval.is_zero() || !(report.block_number % interval).is_zero() {
+                    return InvalidTransaction::Call.into();
+                }
+                if let Some(last) = Self::last_liveness_report(&report.prober) {
+                    // One report for each round
+                    if last.saturating_add(interval) > report.block_number {
+                        return InvalidTransaction::Stale.into();
+                    }
+                }
+                if !<LivenessProbers<T>>::contains_key(&report.prober) {
+                    return InvalidTransaction::BadSigner.into();
+                }
+                if report.probed > T::MaxLivenessProbes::get()
+                    || report.offline.len() > report.probed as usize {
+                    return InvalidTransaction::Call.into();
+                }
+                let signature_valid = report.using_encoded(|encoded| report.prober.verify(&encoded, signature));
+                if !signature_valid {
+                    return InvalidTransaction::BadProof.into();
+                }
+                // Only the nodes probed in the round can be reported offline
+                let targets = Self::liveness_probe_targets(report.block_number);
+                if report.offline.iter().any(|peer_id| !targets.iter().any(|(target, _)| target == peer_id)) {
+                    return InvalidTransaction::Call.into();
+                }
+
+                ValidTransaction::with_tag_prefix("DcNodeLiveness")
+                    .priority(T::UnsignedPriority::get())
+                    .and_provides((report.prober.clone(), report.block_number))
+                    .longevity(interval.saturated_into::<u64>())
+                    .propagate(true)
+                    .build()
+            } else {
+                InvalidTransaction::Call.into()
+            }
+        }
+    }
+}
+
+impl<T: Config> Pallet<T> 
+    where 
+        T::AccountId: AsRef<[u8]>,
+{
+    /// Collect the health of the DC network from the counters kept up to date on chain.
+    pub fn network_stats() -> NetworkStats<BalanceOf<T>> {
+        let (total_space, free_space) = Self::onchain_space();
+        let (files, file_replicas) = Self::file_totals();
+        NetworkStats {
+            peers_by_status: (NODE_STATUS_OFFCHAIN..=NODE_STATUS_DISCARD)
+                .map(|status| (status, Self::peers_of_status(status)))
+                .filter(|(_, n)| *n > 0)
+                .collect(),
+            total_space,
+            free_space,
+            active_users: Self::active_users(),
+            files,
+            file_replicas,
+            app_reward_total: Self::app_reward_total(),
+            storage_reward_total: Self::storage_reward_total(),
+            reports_in_era: Self::report_number_in_era(T::StakingProvider::get_current_era_index()),
+        }
+    }
+
+    /// Get the endpoints of kind in the region that support the min API version,
+    /// sorted by priority, and then by the last heartbeat, for clients to select from.
+    pub fn endpoints(
+        kind: EndpointKind, 
+        region: Option<DcString>, 
+        min_api_version: u32,
+    ) -> Vec<NodeEndpoint<T::AccountId, T::BlockNumber>> {
+        let mut endpoints: Vec<NodeEndpoint<T::AccountId, T::BlockNumber>> = <Endpoints<T>>::iter_prefix_values(kind)
+            .filter(|e| e.api_version >= min_api_version)
+            .filter(|e| region.as_ref().map_or(true, |r| e.region == *r))
+            .collect();
+        endpoints.sort_by(|a, b| b.priority.cmp(&a.priority).then(b.last_seen.cmp(&a.last_seen)));
+        endpoints
+    }
+
+    /// Get the storage package by id
+    fn get_package(package_id: PackageId) -> Option<(PackageId, SpaceSize, BalanceOf<T>, T::BlockNumber, T::BlockNumber)> {
+        let packages: BTreeSet<(PackageId, SpaceSize, BalanceOf<T>, T::BlockNumber, T::BlockNumber)> = match Self::storage_packages() {
+            Some(p) => p,
+            None => BTreeSet::<(PackageId, SpaceSize, BalanceOf<T>, T::BlockNumber, T::BlockNumber)>::new(),
+        };
+        if packages.is_empty() {
+            return None;
+        }
+        for info in packages.iter() {
+            if info.0 == package_id {
+                return Some(*info);
+            }
+        }
+        None
+    }
+
+    /// Chance used space and expire number of user's storage infomation
+    fn change_used_space_expire_number(owner: &T::AccountId, file_size: SpaceSize, is_add: bool, is_reduce_expire: bool) -> DispatchResult {
+        // consume user space 
+        let owner_opt = <WalletAccountStorage<T>>::get(&owner);
+        if owner_opt.is_none() {
+            Err(Error::<T>::AccountNotExist)?
+        }
+
+        let mut owner_info = owner_opt.unwrap();
+        if owner_info.expire_number < frame_system::Pallet::<T>::block_number()
+           || owner_info.expire_number.saturating_sub(frame_system::Pallet::<T>::block_number()) < owner_info.call_minus_number {
+            Err(Error::<T>::UserPackageExpired)?
+        }
+        if file_size > 0 as SpaceSize || (owner_info.parent_account == *owner && is_reduce_expire) {
+            if is_add {
+                owner_info.used_space = owner_info.used_space.saturating_add(file_size);
+            } else {
+                owner_info.used_space = owner_info.used_space.saturating_sub(file_size);
+            }
+            if owner_info.parent_account == *owner && is_reduce_expire {
+                let minus_number: u128 = T::BlockMultiplier::get_next_fee_multiplier().saturating_mul_int(owner_info.call_minus_number.saturated_into::<u128>());
+                owner_info.expire_number = owner_info.expire_number.saturating_sub(u32::try_from(minus_number).unwrap().into());
+            }
+            Self::insert_user(&owner, owner_info.clone());
+        }
+        
+        // It is a sub account 
+        if owner_info.parent_account != *owner {
+            // consume parent user space
+            let parent_opt = <WalletAccountStorage<T>>::get(&owner_info.parent_account);
+            if parent_opt.is_some() {
+                let mut parent_info = parent_opt.unwrap();
+                if parent_info.expire_number < frame_system::Pallet::<T>::block_number()
+                   || parent_info.expire_number.saturating_sub(frame_system::Pallet::<T>::block_number()) < parent_info.call_minus_number {
+                    Err(Error::<T>::UserPackageExpired)?
+                }
+                if is_add {
+                    parent_info.used_space = parent_info.used_space.saturating_add(file_size);
+                } else {
+                    parent_info.used_space = parent_info.used_space.saturating_sub(file_size);
+                }
+                if is_reduce_expire {
+                    let minus_number: u128 = T::BlockMultiplier::get_next_fee_multiplier().saturating_mul_int(parent_info.call_minus_number.saturated_into::<u128>());
+                    parent_info.expire_number = parent_info.expire_number.saturating_sub(u32::try_from(minus_number).unwrap().into());
+                }
+                Self::insert_user(&owner_info.parent_account, parent_info);
+            }
+        }
+        Ok(())
+    }
+
+    /// Insert peer into account storage
+    fn insert_user_peer(for_account: &T::AccountId, peer_id: PeerId) -> DispatchResult {
+        let is_exist = <WalletAccountStorage<T>>::contains_key(for_account);
+        if !is_exist {
+            Err(Error::<T>::AccountNotExist)?
+        }
+        let mut pre_info = <WalletAccountStorage<T>>::get(for_account).unwrap();
+        pre_info.peers.insert(peer_id);
+        // Update storage.
+        Self::insert_user(for_account, pre_info);
+        Ok(())
+    }
+
+    /// Update the encrypted NFT account to for_account
+    fn update_account_nft(
+        for_account: &T::AccountId,
+        enc_nft_account: DcString, 
+        block_height: u32,
+        is_clear_peer: bool,
+    ) -> DispatchResult {
+        let user_opt = <WalletAccountStorage<T>>::get(for_account);
+        if user_opt.is_none() {
+            Err(Error::<T>::AccountNotExist)?
+        }
+        let mut user_storage = user_opt.unwrap();
+        // Update the encrypted NFT account to for_account
+        user_storage.enc_nft_account = enc_nft_account;
+        user_storage.nft_update_number = block_height.into();
+        if is_clear_peer {
+            user_storage.peers = BTreeSet::<PeerId>::new();
+        }
+        Self::insert_user(for_account, user_storage);
+        Ok(())
+    }
+
+    /// Mint the NFT account as a new item of the nfts collection
+    fn mint_nft_account(nft_account: &NftAccount, owner: &T::AccountId) -> DispatchResult {
+        let collection = Self::nft_collection().ok_or(Error::<T>::NftCollectionNotSet)?;
+        let item = Self::next_nft_item_id();
+        T::Nfts::mint_into(&collection, &item, owner, &ItemConfig::default(), false)?;
+        T::Nfts::set_attribute(&collection, &item, NFT_ACCOUNT_ATTRIBUTE, nft_account)?;
+
+        <NextNftItemId<T>>::put(item.checked_add(1).ok_or(Error::<T>::StorageOverflow)?);
+        <NftAccountItem<T>>::insert(nft_account, item);
+        <NftItemAccount<T>>::insert(item, nft_account);
+        <NftToWalletAccount<T>>::insert(nft_account, owner);
+        Ok(())
+    }
+
+    /// Get the nfts item of the NFT account owned by owner
+    fn nft_account_owned_by(
+        nft_account: &NftAccount, 
+        owner: &T::AccountId,
+    ) -> Result<(T::NftCollectionId, NftItemId), DispatchError> {
+        let collection = Self::nft_collection().ok_or(Error::<T>::NftCollectionNotSet)?;
+        let item = Self::nft_account_item(nft_account).ok_or(Error::<T>::NftAccoutApplied)?;
+        if T::Nfts::owner(&collection, &item).as_ref() != Some(owner) {
+            Err(Error::<T>::NotNftAccountOwner)?
+        }
+        Ok((collection, item))
+    }
+
+    /// Normalise NFT account to lowercase and check it against the name policy,
+    /// NFT account consists of a-z, 0-9 and '-', and can not start or end with '-'
+    fn normalize_nft_account(nft_account: &NftAccount) -> Result<NftAccount, Error<T>> {
+        let policy = Self::nft_account_policy();
+        let len = nft_account.len() as u32;
+        if len < policy.min_length || len > policy.max_length {
+            Err(Error::<T>::NftAccountInvalid)?
+        }
+        let nft_account = nft_account.to_ascii_lowercase();
+        if nft_account.first() == Some(&b'-') || nft_account.last() == Some(&b'-') {
+            Err(Error::<T>::NftAccountInvalid)?
+        }
+        if !nft_account.iter().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || *c == b'-') {
+            Err(Error::<T>::NftAccountInvalid)?
+        }
+        Ok(nft_account)
+    }
+
+    /// Get the storage key of the NFT account to look up. The name policy only applies to the
+    /// new NFT accounts, so the registered NFT accounts are found as they are, or by lowercase.
+    fn nft_account_key(nft_account: &NftAccount) -> NftAccount {
+        if <NftAccountItem<T>>::contains_key(nft_account) || <NftToWalletAccount<T>>::contains_key(nft_account) {
+            nft_account.clone()
+        } else {
+            nft_account.to_ascii_lowercase()
+        }
+    }
+
+    /// Get the registration price of NFT account by length
+    fn nft_account_price(policy: &NftAccountPolicy<BalanceOf<T>, T::BlockNumber>, len: u32) -> BalanceOf<T> {
+        policy.length_prices.iter()
+            .filter(|(max_len, _)| len <= *max_len)
+            .min_by_key(|(max_len, _)| *max_len)
+            .map_or(policy.base_price, |(_, price)| *price)
+    }
+
+    /// Whether the registration of NFT account has expired
+    fn nft_account_expired(nft_account: &NftAccount) -> bool {
+        match Self::nft_account_expiry(nft_account) {
+            Some(expiry) => expiry <= frame_system::Pallet::<T>::block_number(),
+            None => false,
+        }
+    }
+
+    /// Whether the grace period after the expiry of NFT account has passed, so it can be released
+    fn nft_account_releasable(nft_account: &NftAccount) -> bool {
+        match Self::nft_account_expiry(nft_account) {
+            Some(expiry) => expiry.saturating_add(Self::nft_account_policy().grace_period) <= frame_system::Pallet::<T>::block_number(),
+            None => false,
+        }
+    }
+
+    /// Burn the nfts item of NFT account, the NFT account is cleared by `ItemsCallback`
+    fn do_release_nft_account(nft_account: &NftAccount) -> DispatchResult {
+        let collection = Self::nft_collection().ok_or(Error::<T>::NftCollectionNotSet)?;
+        let item = Self::nft_account_item(nft_account).ok_or(Error::<T>::AccountNotExist)?;
+        T::Nfts::burn(&collection, &item, None)?;
+
+        Self::deposit_event(Event::ReleaseNftAccount(nft_account.clone()));
+        Ok(())
+    }
+
+    /// Purchase the storage package for the wallet account, paid by the payer.
+    fn do_purchase_storage(payer: &T::AccountId, for_account: &T::AccountId, package_id: PackageId) -> DispatchResult {
+        let package_option = Self::get_package(package_id);
+        if package_option.is_none() {
+            return Err(Error::<T>::StoragePackageNotExist)?;
+        }
+        
+        let mut buy_package = package_option.unwrap();
+        buy_package.2 = Self::current_price(buy_package.2);
+        // check that the user's balance is sufficient 
+        if T::Currency::can_slash(payer, buy_package.2) == false {
+            return Err(Error::<T>::InsufficientBalance)?;
+        }
+        
+        let is_exist = <WalletAccountStorage<T>>::contains_key(for_account);
+        let cur_number = frame_system::Pallet::<T>::block_number();
+        // The first purchase 
+        if is_exist {
+            let mut pre_user = <WalletAccountStorage<T>>::get(for_account).unwrap();
+            if pre_user.parent_account != *for_account {
+                pre_user = <WalletAccountStorage<T>>::get(&pre_user.parent_account).unwrap();
+            }
+            // Used space exceeds new subscription space size 
+            if pre_user.used_space > buy_package.1 {
+                return Err(Error::<T>::ParamErr)?;
+            }
+            let mut from_number = pre_user.expire_number;
+            if pre_user.expire_number < cur_number {
+                from_number = cur_number;
+            }
+            let mut new_user = UserStorage {
+                peers: pre_user.peers,
+                used_space: pre_user.used_space,
+                subscribe_space: buy_package.1,
+                subscribe_price: buy_package.2,
+                call_minus_number: buy_package.4,
+                nft_update_number: pre_user.nft_update_number,
+                db_update_number: pre_user.db_update_number,
+                expire_number: from_number.saturating_add(buy_package.3),
+                db_config: pre_user.db_config,
+                enc_nft_account: pre_user.enc_nft_account,
+                parent_account: pre_user.parent_account,
+                spam_frozen_status: pre_user.spam_frozen_status,
+                spam_report_amount: pre_user.spam_report_amount,
+                spam_report_number: pre_user.spam_report_number,
+                comment_frozen_status: pre_user.comment_frozen_status,
+                comment_report_amount: pre_user.comment_report_amount,
+                comment_report_number: pre_user.comment_report_number,
+                login_number: pre_user.login_number,
+                comment_space: pre_user.comment_space,
+                request_peers: pre_user.request_peers,
+            };
+            // The original subscription has not expired and has changed the storage package
+            if pre_user.expire_number > cur_number
+               && (pre_user.subscribe_space != buy_package.1) {
+                // Convert remaining block number by new storage package 
+                let pre_space: u128 = u128::try_from(pre_user.subscribe_space).unwrap();
+                let now_space: u128 = u128::try_from(buy_package.1).unwrap();
+                let left_number = (pre_user.expire_number.saturating_sub(cur_number)).saturated_into::<u128>();
+                let rest_number = u32::try_from(pre_space.saturating_mul(left_number) / now_space).unwrap(); 
+
+                new_user.expire_number = cur_number.saturating_add(rest_number.into()).saturating_add(buy_package.3);
+            }
+            
+            // Save user storage information.
+            Self::insert_user(&new_user.parent_account.clone(), new_user);
+        } else {
+            let new_user = Self::new_user_storage(for_account.clone(), &buy_package, cur_number);
+            // Save user storage information.
+            Self::insert_user(for_account, new_user);
+        }
+        
+        // The package fee is slashed from the payer's account 
+        Self::charge_fee(payer, buy_package.2)
+    }
+
+    /// Whether the calls in the category are paused.
+    pub fn is_paused(category: CallCategory) -> bool {
+        Self::paused_categories().contains(&category)
+    }
+
+    /// The price of storage package charged at the current price multiplier.
+    fn current_price(base_price: BalanceOf<T>) -> BalanceOf<T> {
+        if Self::pricing_curve().is_none() {
+            return base_price;
+        }
+        Self::price_multiplier().saturating_mul_int(base_price)
+    }
+
+    /// The price that the storage package is purchased at now.
+    pub fn storage_package_price(package_id: PackageId) -> Option<BalanceOf<T>> {
+        Self::get_package(package_id).map(|package| Self::current_price(package.2))
+    }
+
+    /// Adjust the price multiplier by the utilization of the onchain storage nodes,
+    /// and return the number of storage nodes read.
+    fn adjust_price_multiplier(curve: &PricingCurve) {
+        let (total_space, free_space) = Self::onchain_space();
+        let utilization = pricing::utilization(total_space.saturating_sub(free_space), total_space);
+        let multiplier = curve.next_multiplier(Self::price_multiplier(), utilization);
+        <PriceMultiplier<T>>::put(multiplier);
+        Self::deposit_event(Event::PriceMultiplierUpdated(multiplier, utilization));
+    }
+
+    /// Check that the account is the private account of the app.
+    fn ensure_app_account(who: &T::AccountId, app_id: &AppID) -> DispatchResult {
+        let app = Self::account_of_app(app_id).ok_or(Error::<T>::AppNotExist)?;
+        if app.private_account != *who {
+            Err(Error::<T>::NotController)?
+        }
+        Ok(())
+    }
+
+    /// Remove the app with its pending owner, its logins of the era and its sponsorship.
+    fn remove_app(app_id: &AppID) -> Option<AppAccountInfo<T::AccountId, BalanceOf<T>, T::Hash>> {
+        let acc_info = <AccountOfApp<T>>::take(app_id)?;
+        <PendingAppOwner<T>>::remove(app_id);
+        <AppLoginCosign<T>>::remove(app_id);
+        // The logins are not rewarded to the stash of the removed app
+        if let Some(mut login_count) = <AppsAccountLoginTimes<T>>::get() {
+            login_count.remove(app_id);
+            <AppsAccountLoginTimes<T>>::put(login_count);
+        }
+        // The counted logins of the app are pruned with their era
+        Self::remove_sponsorship(app_id);
+        Some(acc_info)
+    }
+
+    /// Prune at most `LOGIN_PRUNE_MAX_NUM` counted logins of the eras before the current era.
+    fn prune_app_logins() -> frame_support::weights::Weight {
+        let prune_era = Self::login_prune_era();
+        if prune_era >= T::StakingProvider::get_current_era_index() {
+            return T::DbWeight::get().reads(2);
+        }
+        let result = <AppLoginsInEra<T>>::clear_prefix(prune_era, LOGIN_PRUNE_MAX_NUM, None);
+        if result.maybe_cursor.is_none() {
+            <LoginPruneEra<T>>::put(prune_era.saturating_add(1));
+        }
+        T::DbWeight::get().reads_writes(u64::from(result.loops).saturating_add(2), u64::from(result.unique).saturating_add(1))
+    }
+
+    /// The weight of going over the peers of a stash, reading each and updating its status.
+    fn stash_peers_weight(peers: u32) -> frame_support::weights::Weight {
+        T::DbWeight::get().reads_writes(u64::from(peers).saturating_mul(2), u64::from(peers))
+    }
+
+    /// The weight of reporting an offence of storage node, the offences pallet keeps the report
+    /// and the offender is slashed by the staking provider.
+    fn report_offence_weight() -> frame_support::weights::Weight {
+        T::DbWeight::get().reads_writes(4, 3).saturating_add(T::StakingProvider::offence_weight(OFFENCE_REPORTERS_MAX_NUM))
+    }
+
+    /// The weight of removing the sponsorship of an app with the most enrolled wallet accounts.
+    fn remove_sponsorship_weight() -> frame_support::weights::Weight {
+        let users = u64::from(SPONSORED_USERS_MAX_NUM);
+        T::DbWeight::get().reads_writes(users.saturating_add(1), users.saturating_mul(2).saturating_add(1))
+    }
+
+    /// Remove the sponsorship of the app and the wallet accounts enrolled in it.
+    /// The enrolled accounts are bounded by `SPONSORED_USERS_MAX_NUM`.
+    fn remove_sponsorship(app_id: &AppID) {
+        <Sponsorships<T>>::remove(app_id);
+        for (account, _) in <SponsoredUsers<T>>::drain_prefix(app_id) {
+            <UserSponsor<T>>::remove(&account);
+        }
+    }
+
+    /// Pay the package of the sponsored wallet account from the private account of the app.
+    fn charge_sponsorship(app_id: &AppID, account: &T::AccountId) -> DispatchResult {
+        let mut sponsorship = Self::sponsorship(app_id).ok_or(Error::<T>::SponsorshipNotExist)?;
+        let app = Self::account_of_app(app_id).ok_or(Error::<T>::AppNotExist)?;
+        let spent = Self::sponsored_user(app_id, account).ok_or(Error::<T>::NotSponsored)?;
+        let package = Self::get_package(sponsorship.package_id).ok_or(Error::<T>::StoragePackageNotExist)?;
+        let price = Self::current_price(package.2);
+        if sponsorship.budget < price {
+            Err(Error::<T>::SponsorshipBudgetExhausted)?
+        }
+        let spent = spent.saturating_add(price);
+        if !sponsorship.limit_per_user.is_zero() && spent > sponsorship.limit_per_user {
+            Err(Error::<T>::SponsoredUserLimitReached)?
+        }
+
+        Self::do_purchase_storage(&app.private_account, account, package.0)?;
+        sponsorship.budget = sponsorship.budget.saturating_sub(price);
+        <SponsoredUsers<T>>::insert(app_id, account, spent);
+        Self::deposit_event(Event::SponsorStorage(app_id.to_vec(), account.clone(), package.0, price));
+        if sponsorship.budget < price {
+            Self::deposit_event(Event::SponsorshipBudgetExhausted(app_id.to_vec(), sponsorship.budget));
+        }
+        <Sponsorships<T>>::insert(app_id, sponsorship);
+        Ok(())
+    }
+
+    /// Slash the fee from payer and add it to the total amount(app/storage) of rewards
+    fn charge_fee(payer: &T::AccountId, fee: BalanceOf<T>) -> DispatchResult {
+        if fee.is_zero() {
+            return Ok(());
+        }
+        if T::Currency::can_slash(payer, fee) == false {
+            Err(Error::<T>::InsufficientBalance)?
+        }
+        let _ = T::Currency::slash(payer, fee);
+        let app_reward = Perbill::from_rational(Self::app_reward_percent(), 100)*fee;
+        <AppRewardTotal<T>>::put(Self::app_reward_total().saturating_add(app_reward));
+        <StorageRewardTotal<T>>::put(Self::storage_reward_total().saturating_add(fee).saturating_sub(app_reward));
+        Ok(())
+    }
+
+    /// u32 to [u8]
+    fn u32_to_u8(v: u32) -> [u8; 4] {
+        unsafe {
+            let u32_ptr: *const u32 = &v as *const u32;
+            let u8_ptr: *const u8 = u32_ptr as *const u8;
+            return [*u8_ptr.offset(0), *u8_ptr.offset(1), *u8_ptr.offset(2), *u8_ptr.offset(3)];
+        }
+    }
+
+    /// u64 to [u8]
+    fn u64_to_u8(v: u64) -> [u8; 8] {
+        unsafe {
+            let u64_ptr: *const u64 = &v as *const u64;
+            let u8_ptr: *const u8 = u64_ptr as *const u8;
+            return [*u8_ptr.offset(0), *u8_ptr.offset(1), *u8_ptr.offset(2), *u8_ptr.offset(3),
+                    *u8_ptr.offset(4), *u8_ptr.offset(5), *u8_ptr.offset(6), *u8_ptr.offset(7)];
+        }
+    }
+
+    /// Get peer ID by request account ID
+    fn get_peer_id_with_req_acc_id(req_account_id: &T::AccountId) -> Result<PeerId, Error<T>> {
+        // Get the peer ID
+        let peer_id_ret = Self::request_account_peer(req_account_id);
+        if peer_id_ret.is_none() {
+            Err(Error::<T>::AccountNotExist)?
+        }
+        let peer_id = peer_id_ret.unwrap();
+        // Get the peer infomation
+        let peer_ret = Self::peers(&peer_id);
+        if peer_ret.is_none() {
+            Err(Error::<T>::PeerIdNotExist)?
+        }
+
+        let peer_info = peer_ret.unwrap();
+        if peer_info.status != NODE_STATUS_ONCHAIN {
+            Err(Error::<T>::NodeStatusError)?
+        }
+
+        Ok(peer_id)
+    }
+
+    /// Check the block number of wallet account
+    fn check_account_block_number(for_account: &T::AccountId, block_height: u32, is_nft: bool) -> Result<(), Error<T>> {
+        let block_num :T::BlockNumber = block_height.into();
+        // Get the storage information of for_account
+        let is_exist = <WalletAccountStorage<T>>::contains_key(for_account);
         // The first purchase 
         if !is_exist {
             Err(Error::<T>::AccountNotExist)?
@@ -2739,9 +4611,36 @@ impl<T: Config> Pallet<T>
         Ok(peer_id)
     }
 
+    /// The message signed by the wallet account to log in, and co-signed by the apps.
+    pub(crate) fn login_message(app_ids: &Vec<DcString>, block_height: u32, peer_id: &PeerId) -> DcString {
+        let mut message = DcString::new();
+        message.extend(String::from("user_login").as_bytes().to_vec().iter().copied());
+        message.extend(app_ids.encode().iter().copied());
+        message.extend(Self::u32_to_u8(block_height).iter().copied());
+        message.extend(peer_id.iter().copied());
+        message
+    }
+
     /// Data signature verify
-    #[cfg(all(not(feature = "runtime-benchmarks"), not(test)))]
+    #[cfg(all(not(feature = "runtime-benchmarks"), not(feature = "fuzzing"), not(test)))]
+    fn verify(signature: &DcString, message: &DcString, account: &T::AccountId) -> DispatchResult {
+        Self::check_signature(signature, message, account)
+    }
+
+    /// The benchmarks check the signature for its cost, most of them sign with fake keys.
+    #[cfg(all(feature = "runtime-benchmarks", not(feature = "fuzzing"), not(test)))]
     fn verify(signature: &DcString, message: &DcString, account: &T::AccountId) -> DispatchResult {
+        let _ = Self::check_signature(signature, message, account);
+        Ok(())
+    }
+
+    #[cfg(any(feature = "fuzzing", test))]
+    fn verify(_signature: &DcString, _message: &DcString, _account: &T::AccountId) -> DispatchResult {
+        Ok(())
+    }
+
+    #[cfg(all(not(feature = "fuzzing"), not(test)))]
+    fn check_signature(signature: &DcString, message: &DcString, account: &T::AccountId) -> DispatchResult {
         let sig_ret = ed25519::Signature::try_from(signature.as_ref());
         if sig_ret.is_err() {
             Err(Error::<T>::DataSignatureVerify)?
@@ -2757,11 +4656,6 @@ impl<T: Config> Pallet<T>
         }
         Ok(())
     }
-    
-    #[cfg(any(feature = "runtime-benchmarks", test))]
-    fn verify(_signature: &DcString, _message: &DcString, _account: &T::AccountId) -> DispatchResult {
-        Ok(())
-    }
 
     /// Parse file ids from string to array.
     // fn parse_file_ids(ids_str: &DcString) -> BTreeSet<DcString> {
@@ -2825,10 +4719,12 @@ impl<T: Config> Pallet<T>
         }
         pre_info.peers.remove(peer_id);
         if pre_info.peers.len() > 0 {
+            Self::remove_thread_db_log_peer(file_id, peer_id);
             // Update storage.
-            <Files<T>>::insert(file_id, pre_info);
+            Self::insert_file(file_id, pre_info);
         } else {
-            <Files<T>>::remove(file_id);
+            let _ = <ThreadDbLogs<T>>::clear_prefix(file_id, THREAD_DB_MAX_LOGS, None);
+            Self::remove_file(file_id);
             if pre_info.users.len() > 0 {
                 let mut users_iter = pre_info.users.iter();
                 // Chance used space and expire number of user's storage infomation
@@ -2839,6 +4735,43 @@ impl<T: Config> Pallet<T>
         Ok(())
     }
 
+    /// Remove peer of the logs of a thread db file.
+    fn remove_thread_db_log_peer(file_id: &FileID, peer_id: &PeerId) {
+        for (log_id, mut log) in <ThreadDbLogs<T>>::iter_prefix(file_id) {
+            if log.peers.remove(peer_id) {
+                <ThreadDbLogs<T>>::insert(file_id, log_id, log);
+            }
+        }
+    }
+
+    /// The weight of removing the logs of `files` thread db files, or a peer from their logs.
+    fn thread_db_logs_weight(files: u32) -> frame_support::weights::Weight {
+        let logs = u64::from(files.min(MISSING_FILES_MAX_NUM)).saturating_mul(THREAD_DB_MAX_LOGS.into());
+        T::DbWeight::get().reads_writes(logs, logs)
+    }
+
+    /// Check that a new log can be added to the thread db file, so that the logs of a file are
+    /// bounded by `THREAD_DB_MAX_LOGS`.
+    fn check_thread_db_log_number(file_info: &FileInfo<T::AccountId>, log_id: &DcString) -> DispatchResult {
+        let log_number = file_info.db_log.len() + if file_info.db_log.contains(log_id) { 0 } else { 1 };
+        if log_number > THREAD_DB_MAX_LOGS as usize {
+            Err(Error::<T>::TooManyThreadDbLogs)?
+        }
+        Ok(())
+    }
+
+    /// Check that the account or its parent account is the user of the thread db file.
+    fn check_thread_db_user(file_info: &FileInfo<T::AccountId>, account: &T::AccountId) -> DispatchResult {
+        if file_info.file_type != FILE_TYPE_THREAD_DB {
+            Err(Error::<T>::FileTypeError)?
+        }
+        let parent_account = Self::wallet_account_storage(account).ok_or(Error::<T>::AccountNotExist)?.parent_account;
+        if !file_info.users.contains(account) && !file_info.users.contains(&parent_account) {
+            Err(Error::<T>::NotThreadDbLogOwner)?
+        }
+        Ok(())
+    }
+
     pub fn remove_account_peer(
         peer_id: &PeerId, 
         for_account: &T::AccountId,
@@ -2852,7 +4785,7 @@ impl<T: Config> Pallet<T>
             Err(Error::<T>::PeerIdNotExist)?
         }
         pre_info.peers.remove(peer_id);
-        <WalletAccountStorage<T>>::insert(for_account, pre_info);
+        Self::insert_user(for_account, pre_info);
         Ok(())
     }
 
@@ -2871,7 +4804,7 @@ impl<T: Config> Pallet<T>
         }
         pre_info.peers.remove(peer_id);
         // Update storage.
-        <WalletAccountStorage<T>>::insert(for_account, pre_info);
+        Self::insert_user(for_account, pre_info);
 
         Ok(())
     }
@@ -2906,18 +4839,18 @@ impl<T: Config> Pallet<T>
             } else {
                 punish_count = Self::abnormal_report_number();
             }
-            <ReportsInEra<T>>::insert(era_index, report_info, accounts);
+            Self::insert_reports(era_index, report_info, accounts.clone());
             
             if accounts_count >= punish_count {
                 if report_type == ReportType::ReportTeeFaking {
                     return Ok(());
                 } else if report_type == ReportType::VerifyTeeFaking {
-                    T::StakingProvider::report_offence(&peer_info.stash, T::StakingProvider::get_staking_active(&peer_info.stash));
+                    Self::report_dc_offence(DcOffenceKind::TeeFaking, peer_id, &peer_info.stash, accounts.into_iter().collect());
                     if peer_info.status == NODE_STATUS_ONCHAIN {
                         <OnchainPeerNumber<T>>::mutate(|n| *n -= 1);
                     }
                     peer_info.status = NODE_STATUS_DISCARD;
-                    <Peers<T>>::insert(peer_id, peer_info.clone());
+                    Self::insert_peer(peer_id, peer_info.clone());
                     // Set the status of the nodes based on the amount of stake
                     Self::update_peers_of_stash(&peer_info.stash, Zero::zero());
                 } else {
@@ -2928,23 +4861,198 @@ impl<T: Config> Pallet<T>
                         peer_info.reward_number = cur_num.saturating_add(Self::start_reward_block_number());
                     }
                     
-                    <OnchainPeerNumber<T>>::mutate(|n| *n -= 1);
+                    if peer_info.status == NODE_STATUS_ONCHAIN {
+                        <OnchainPeerNumber<T>>::mutate(|n| *n -= 1);
+                    }
                     peer_info.status = NODE_STATUS_OFFCHAIN;
+                    if report_type == ReportType::ReportPeerNoResponse {
+                        Self::report_dc_offence(DcOffenceKind::Unresponsive, peer_id, &peer_info.stash, accounts.into_iter().collect());
+                    }
                     
-                    <Peers<T>>::insert(peer_id, peer_info);
+                    Self::insert_peer(peer_id, peer_info);
                 }
             }
         } else {
             let mut accounts = BTreeSet::<T::AccountId>::new();
             accounts.insert(report_account);
-            <ReportsInEra<T>>::insert(era_index, report_info, accounts);
+            Self::insert_reports(era_index, report_info, accounts);
         }
         Ok(())
     }
+
+    /// Get the slash fraction of the offence kind.
+    pub fn offence_slash_fraction(kind: DcOffenceKind) -> Perbill {
+        <OffenceSlashFractions<T>>::get(kind).unwrap_or_else(|| kind.default_slash_fraction())
+    }
+
+    /// Report the offence of storage node to be slashed through the offences pallet, only the
+    /// first `OFFENCE_REPORTERS_MAX_NUM` reporters are rewarded.
+    fn report_dc_offence(kind: DcOffenceKind, peer_id: &PeerId, stash: &T::AccountId, reporters: Vec<T::AccountId>) {
+        // The stash is not bonded, nothing to slash
+        let offender = match T::StakingProvider::offender(stash) {
+            Some(offender) => offender,
+            None => return,
+        };
+        let offence = DcOffence {
+            kind: kind,
+            peer_id: peer_id.to_vec(),
+            session_index: T::StakingProvider::offence_session_index(),
+            validator_set_count: Self::onchain_peer_number(),
+            offender: offender,
+            slash_fraction: Self::offence_slash_fraction(kind),
+        };
+        let reporters = reporters.into_iter().take(OFFENCE_REPORTERS_MAX_NUM as usize).collect();
+        match T::ReportOffence::report_offence(reporters, offence) {
+            Ok(()) => Self::deposit_event(Event::DcOffenceReported(kind, peer_id.to_vec(), stash.clone())),
+            Err(e) => log::debug!(target: "runtime::dc-node", "failed to report {:?} of storage node: {:?}", kind, e),
+        }
+    }
+
+    /// Probe the storage nodes and submit the liveness report, if the local keystore has
+    /// the key of a liveness prober and a round starts at the block.
+    fn probe_liveness(now: T::BlockNumber) -> Result<(), &'static str>
+    where
+        T: SendTransactionTypes<Call<T>>,
+    {
+        let interval = T::LivenessProbeInterval::get();
+        if interval.is_zero() || !(now % interval).is_zero() {
+            return Ok(());
+        }
+        if Self::is_paused(CallCategory::Reports) {
+            return Err("reports are paused");
+        }
+        if !sp_io::offchain::is_validator() {
+            return Err("not a validator");
+        }
+        let prober = T::AuthorityId::all()
+            .into_iter()
+            .find(|key| <LivenessProbers<T>>::contains_key(key))
+            .ok_or("no liveness prober key in the keystore")?;
+        if Self::last_liveness_report(&prober).map_or(false, |last| last.saturating_add(interval) > now) {
+            return Err("already reported in the round");
+        }
+
+        let targets = Self::liveness_probe_targets(now);
+        if targets.is_empty() {
+            return Err("no storage node to probe");
+        }
+        let report = LivenessReport {
+            block_number: now,
+            prober: prober.clone(),
+            probed: targets.len() as u32,
+            offline: liveness::probe_peers(&targets),
+        };
+        let signature = prober.sign(&report.encode()).ok_or("failed to sign the liveness report")?;
+        let call = Call::submit_liveness_report { report, signature };
+        SubmitTransaction::<T, Call<T>>::submit_unsigned_transaction(call.into())
+            .map_err(|_| "failed to submit the liveness report")
+    }
 }
 
 impl<T: Config> Pallet<T> 
 {
+    /// Save the peer and keep the number of peers of each status and the space of the onchain
+    /// peers up to date.
+    pub(crate) fn insert_peer(peer_id: &PeerId, info: StorageNode<T::AccountId, T::BlockNumber>) {
+        Self::update_peer_totals(Self::peers(peer_id).as_ref(), Some(&info));
+        <Peers<T>>::insert(peer_id, info);
+    }
+
+    /// Take the peer out of the totals of `insert_peer` until it is saved again.
+    fn take_peer(peer_id: &PeerId) -> Option<StorageNode<T::AccountId, T::BlockNumber>> {
+        let info = <Peers<T>>::take(peer_id);
+        Self::update_peer_totals(info.as_ref(), None);
+        info
+    }
+
+    fn update_peer_totals(
+        old: Option<&StorageNode<T::AccountId, T::BlockNumber>>,
+        new: Option<&StorageNode<T::AccountId, T::BlockNumber>>,
+    ) {
+        let (mut total_space, mut free_space) = Self::onchain_space();
+        if let Some(old) = old {
+            <PeersOfStatus<T>>::mutate(old.status, |n| *n = n.saturating_sub(1));
+            if old.status == NODE_STATUS_ONCHAIN {
+                total_space = total_space.saturating_sub(old.total_space);
+                free_space = free_space.saturating_sub(old.free_space);
+            }
+        }
+        if let Some(new) = new {
+            <PeersOfStatus<T>>::mutate(new.status, |n| *n = n.saturating_add(1));
+            if new.status == NODE_STATUS_ONCHAIN {
+                total_space = total_space.saturating_add(new.total_space);
+                free_space = free_space.saturating_add(new.free_space);
+            }
+        }
+        <OnchainSpace<T>>::put((total_space, free_space));
+    }
+
+    /// Save the wallet account and keep the number of active accounts and their expire numbers
+    /// up to date.
+    pub(crate) fn insert_user(account: &T::AccountId, info: UserStorage<T::AccountId, T::BlockNumber, BalanceOf<T>>) {
+        Self::update_user_totals(Self::wallet_account_storage(account).map(|old| old.expire_number), Some(info.expire_number));
+        <WalletAccountStorage<T>>::insert(account, info);
+    }
+
+    fn update_user_totals(old: Option<T::BlockNumber>, new: Option<T::BlockNumber>) {
+        if old == new {
+            return;
+        }
+        let now = frame_system::Pallet::<T>::block_number();
+        let mut active_users = Self::active_users();
+        if let Some(old) = old.filter(|expire_number| *expire_number >= now) {
+            <UsersExpiringAt<T>>::mutate(old, |n| *n = n.saturating_sub(1));
+            active_users = active_users.saturating_sub(1);
+        }
+        if let Some(new) = new.filter(|expire_number| *expire_number >= now) {
+            <UsersExpiringAt<T>>::mutate(new, |n| *n = n.saturating_add(1));
+            active_users = active_users.saturating_add(1);
+        }
+        <ActiveUsers<T>>::put(active_users);
+    }
+
+    /// The wallet accounts that expired at the previous block are no longer active.
+    fn expire_users(now: T::BlockNumber) -> frame_support::weights::Weight {
+        let expired = <UsersExpiringAt<T>>::take(now.saturating_sub(One::one()));
+        if expired == 0 {
+            return T::DbWeight::get().reads_writes(1, 1);
+        }
+        <ActiveUsers<T>>::mutate(|n| *n = n.saturating_sub(expired));
+        T::DbWeight::get().reads_writes(2, 2)
+    }
+
+    /// Save the file and keep the number of files and their replicas up to date.
+    pub(crate) fn insert_file(file_id: &FileID, info: FileInfo<T::AccountId>) {
+        Self::update_file_totals(Self::files(file_id).as_ref(), Some(&info));
+        <Files<T>>::insert(file_id, info);
+    }
+
+    /// Remove the file out of the totals of `insert_file`.
+    fn remove_file(file_id: &FileID) {
+        let info = <Files<T>>::take(file_id);
+        Self::update_file_totals(info.as_ref(), None);
+    }
+
+    fn update_file_totals(old: Option<&FileInfo<T::AccountId>>, new: Option<&FileInfo<T::AccountId>>) {
+        let (mut files, mut replicas) = Self::file_totals();
+        if let Some(old) = old {
+            files = files.saturating_sub(1);
+            replicas = replicas.saturating_sub(old.peers.len() as u64);
+        }
+        if let Some(new) = new {
+            files = files.saturating_add(1);
+            replicas = replicas.saturating_add(new.peers.len() as u64);
+        }
+        <FileTotals<T>>::put((files, replicas));
+    }
+
+    /// Save the accounts that reported in the era and keep the number of reports of the era up to date.
+    pub(crate) fn insert_reports(era_index: EraIndex, report_info: ReportInfo, accounts: BTreeSet<T::AccountId>) {
+        let old = Self::reports_in_era(era_index, &report_info).map_or(0, |accounts| accounts.len() as u32);
+        <ReportNumberInEra<T>>::mutate(era_index, |n| *n = n.saturating_sub(old).saturating_add(accounts.len() as u32));
+        <ReportsInEra<T>>::insert(era_index, report_info, accounts);
+    }
+
     /// Set the status of the nodes based on the amount of stake
     pub fn update_peers_of_stash(stash: &T::AccountId, staking_active: BalanceOf<T>) {
         // Get peer ID of the stash
@@ -2952,12 +5060,13 @@ impl<T: Config> Pallet<T>
             let min_amount = Self::min_staking_amount();
             let mut onchain_set: BTreeSet<PeerId> = BTreeSet::<PeerId>::new();
             let mut other_set: BTreeSet<PeerId> = BTreeSet::<PeerId>::new();
-            // Group by status
+            // Group by status, nodes below the program requirement are not promoted
             for peer_id in peer_id_set.iter() {
                 Self::peers(&peer_id).map(|pre_info| {
                     if pre_info.status == NODE_STATUS_ONCHAIN {
                         onchain_set.insert(peer_id.to_vec());
-                    } else if pre_info.status != NODE_STATUS_DISCARD && pre_info.status != NODE_STATUS_CLOSED {
+                    } else if pre_info.status != NODE_STATUS_DISCARD && pre_info.status != NODE_STATUS_CLOSED
+                              && Self::peer_program_is_met(&peer_id) {
                         other_set.insert(peer_id.to_vec());
                     }
                 });
@@ -2974,7 +5083,7 @@ impl<T: Config> Pallet<T>
                         Self::peers(&cur_peer_id).map(|mut pre_info| {
                             <OnchainPeerNumber<T>>::mutate(|n| *n -= 1);
                             pre_info.status = NODE_STATUS_JOINING;
-                            <Peers<T>>::insert(cur_peer_id, pre_info);
+                            Self::insert_peer(cur_peer_id, pre_info);
                             change_num -= 1;
                         });
                     } else {
@@ -2999,7 +5108,7 @@ impl<T: Config> Pallet<T>
                                 pre_info.status = NODE_STATUS_STAKED;
                                 change_num -= 1;
 
-                                <Peers<T>>::insert(cur_peer_id, pre_info);
+                                Self::insert_peer(cur_peer_id, pre_info);
                             } else if (pre_info.status == NODE_STATUS_STAKED 
                                        && cur_block_num.saturating_sub(pre_info.staked_number) >= Self::tee_report_verify_number())
                                 || pre_info.status != NODE_STATUS_STAKED {
@@ -3007,7 +5116,7 @@ impl<T: Config> Pallet<T>
                                 pre_info.status = NODE_STATUS_ONCHAIN;
                                 change_num -= 1;
 
-                                <Peers<T>>::insert(cur_peer_id, pre_info);
+                                Self::insert_peer(cur_peer_id, pre_info);
                             }
                         });
                     } else {
@@ -3019,44 +5128,413 @@ impl<T: Config> Pallet<T>
     }
 }
 
-/// Interact with DC
-pub trait DcProvider {
-    /// The account identifier type.
-    type AccountId;
+impl<T: Config> Pallet<T> {
+    /// The storage information of a wallet account that newly subscribes to the package.
+    pub(crate) fn new_user_storage(
+        account: T::AccountId,
+        package: &(PackageId, SpaceSize, BalanceOf<T>, T::BlockNumber, T::BlockNumber),
+        now: T::BlockNumber,
+    ) -> UserStorage<T::AccountId, T::BlockNumber, BalanceOf<T>> {
+        UserStorage {
+            peers: BTreeSet::new(),
+            used_space: 0,
+            subscribe_space: package.1,
+            subscribe_price: package.2,
+            call_minus_number: package.4,
+            nft_update_number: now,
+            db_update_number: now,
+            expire_number: now.saturating_add(package.3),
+            db_config: DcString::new(),
+            enc_nft_account: NftAccount::new(),
+            parent_account: account,
+            spam_frozen_status: 0,
+            spam_report_amount: 0,
+            spam_report_number: 0u32.into(),
+            comment_frozen_status: 0,
+            comment_report_amount: 0,
+            comment_report_number: 0u32.into(),
+            login_number: 0u32.into(),
+            comment_space: 0,
+            request_peers: BTreeSet::new(),
+        }
+    }
 
-    type Balance;
+    /// Check the program of storage node against the requirements.
+    /// Return the pending requirement if the node must upgrade within its grace period.
+    pub fn check_program(
+        version: ProgramVersion, 
+        enclave_id: &DcString,
+    ) -> Result<Option<ProgramRequirement<T::BlockNumber>>, Error<T>> {
+        let cur_block_num = frame_system::Pallet::<T>::block_number();
+        let meets_required = Self::required_program().map_or(true, |r| r.is_met_by(version, enclave_id));
+        match Self::pending_program() {
+            Some(pending) if cur_block_num >= pending.activation_number => {
+                if pending.is_met_by(version, enclave_id) {
+                    Ok(None)
+                } else if cur_block_num < pending.deadline_number && meets_required {
+                    Ok(Some(pending))
+                } else {
+                    Err(Error::<T>::ProgramOutdated)
+                }
+            },
+            _ if meets_required => Ok(None),
+            _ => Err(Error::<T>::ProgramOutdated),
+        }
+    }
+
+    /// Whether the last reported program of storage node meets the requirements.
+    pub fn peer_program_is_met(peer_id: &PeerId) -> bool {
+        let (version, enclave_id) = Self::peer_program(peer_id).unwrap_or_default();
+        Self::check_program(version, &enclave_id).is_ok()
+    }
+
+    /// The ONCHAIN storage nodes probed in the round started at the block, with their addresses.
+    /// Rounds rotate through the nodes when there are more than `MaxLivenessProbes`, and all
+    /// probers probe the same nodes in a round, so that their reports add up.
+    pub fn liveness_probe_targets(now: T::BlockNumber) -> Vec<(PeerId, DcString)> {
+        let mut peers: Vec<(PeerId, DcString)> = <Peers<T>>::iter()
+            .filter(|(_, info)| info.status == NODE_STATUS_ONCHAIN)
+            .map(|(peer_id, info)| (peer_id, info.ip_address))
+            .collect();
+        peers.sort();
+        let max = T::MaxLivenessProbes::get() as usize;
+        if peers.len() <= max {
+            return peers;
+        }
+        let interval = T::LivenessProbeInterval::get().max(One::one());
+        let round: usize = (now / interval).saturated_into();
+        let start = round.wrapping_mul(max) % peers.len();
+        peers.rotate_left(start);
+        peers.truncate(max);
+        peers
+    }
+}
+
+#[cfg(any(test, feature = "try-runtime", feature = "fuzzing"))]
+impl<T: Config> Pallet<T> {
+    /// Check the consistency between the storage items of the pallet.
+    pub fn do_try_state() -> Result<(), &'static str> {
+        Self::check_peers()?;
+        Self::check_sub_accounts()?;
+        Self::check_active_users()?;
+        Self::check_files()?;
+        Self::check_reports()?;
+        Self::check_nft_accounts()?;
+        Self::check_sponsorships()
+    }
+
+    /// Check the peers against the onchain number, the peer totals, the request accounts and the stashes.
+    fn check_peers() -> Result<(), &'static str> {
+        let mut onchain_number: u32 = 0;
+        let mut peers_of_status = BTreeMap::<u32, u32>::new();
+        let (mut total_space, mut free_space): (SpaceSize, SpaceSize) = (0, 0);
+        for (peer_id, peer_info) in <Peers<T>>::iter() {
+            *peers_of_status.entry(peer_info.status).or_default() += 1;
+            if peer_info.status == NODE_STATUS_ONCHAIN {
+                onchain_number += 1;
+                total_space = total_space.saturating_add(peer_info.total_space);
+                free_space = free_space.saturating_add(peer_info.free_space);
+            }
+            frame_support::ensure!(
+                Self::request_account_peer(&peer_info.req_account).as_ref() == Some(&peer_id),
+                "Peers::req_account is not mapped to the peer in RequestAccountPeer"
+            );
+            if peer_info.stash != T::DefaultAccountId::get() {
+                frame_support::ensure!(
+                    Self::stash_peers(&peer_info.stash).map_or(false, |peer_id_set| peer_id_set.contains(&peer_id)),
+                    "Peers::stash does not contain the peer in StashPeers"
+                );
+            }
+        }
+        frame_support::ensure!(
+            Self::onchain_peer_number() == onchain_number,
+            "OnchainPeerNumber is not the number of onchain peers"
+        );
+        frame_support::ensure!(
+            <PeersOfStatus<T>>::iter().filter(|(_, n)| *n > 0).collect::<BTreeMap<u32, u32>>() == peers_of_status,
+            "PeersOfStatus is not the number of peers of each status"
+        );
+        frame_support::ensure!(
+            Self::onchain_space() == (total_space, free_space),
+            "OnchainSpace is not the space of the onchain peers"
+        );
+
+        for (req_account, peer_id) in <RequestAccountPeer<T>>::iter() {
+            frame_support::ensure!(
+                Self::peers(&peer_id).map_or(false, |peer_info| peer_info.req_account == req_account),
+                "RequestAccountPeer is not the req_account of the peer"
+            );
+        }
+
+        for (stash, peer_id_set) in <StashPeers<T>>::iter() {
+            for peer_id in peer_id_set.iter() {
+                frame_support::ensure!(
+                    Self::peers(peer_id).map_or(false, |peer_info| peer_info.stash == stash),
+                    "StashPeers is not the stash of the peer"
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that every parent account covers the used space of its sub accounts.
+    fn check_sub_accounts() -> Result<(), &'static str> {
+        let mut sub_used_space = BTreeMap::<T::AccountId, SpaceSize>::new();
+        for (account, user_info) in <WalletAccountStorage<T>>::iter() {
+            if user_info.parent_account != account {
+                let used_space = sub_used_space.entry(user_info.parent_account).or_insert(0);
+                *used_space = used_space.saturating_add(user_info.used_space);
+            }
+        }
+
+        for (parent_account, used_space) in sub_used_space.iter() {
+            let parent_info = Self::wallet_account_storage(parent_account).ok_or("Parent account does not exist")?;
+            frame_support::ensure!(
+                parent_info.parent_account == *parent_account,
+                "A sub account is used as a parent account"
+            );
+            frame_support::ensure!(
+                parent_info.used_space >= *used_space,
+                "Used space of parent account does not cover its sub accounts"
+            );
+        }
+        Ok(())
+    }
+
+    /// Check the number of active wallet accounts against their expire numbers. The expire numbers
+    /// before the current block may not have been expired yet, so only their sum is checked.
+    fn check_active_users() -> Result<(), &'static str> {
+        let now = frame_system::Pallet::<T>::block_number();
+        let mut users_expiring_at = BTreeMap::<T::BlockNumber, u32>::new();
+        for user_info in <WalletAccountStorage<T>>::iter_values() {
+            if user_info.expire_number >= now {
+                *users_expiring_at.entry(user_info.expire_number).or_default() += 1;
+            }
+        }
+        let mut active_users: u32 = 0;
+        for (expire_number, number) in <UsersExpiringAt<T>>::iter() {
+            active_users = active_users.saturating_add(number);
+            if expire_number >= now {
+                frame_support::ensure!(
+                    users_expiring_at.remove(&expire_number) == Some(number).filter(|n| *n > 0),
+                    "UsersExpiringAt is not the number of wallet accounts expiring at the block"
+                );
+            }
+        }
+        frame_support::ensure!(users_expiring_at.is_empty(), "UsersExpiringAt misses wallet accounts expiring at a block");
+        frame_support::ensure!(Self::active_users() == active_users, "ActiveUsers is not the number of active wallet accounts");
+        Ok(())
+    }
+
+    /// Check that every file is stored on at least one peer, and every thread db log belongs to a file.
+    fn check_files() -> Result<(), &'static str> {
+        let (mut files, mut replicas) = (0u64, 0u64);
+        for file_info in <Files<T>>::iter_values() {
+            frame_support::ensure!(!file_info.peers.is_empty(), "File is not stored on any peer");
+            files += 1;
+            replicas = replicas.saturating_add(file_info.peers.len() as u64);
+        }
+        frame_support::ensure!(Self::file_totals() == (files, replicas), "FileTotals is not the number of files and replicas");
+        for (file_id, log_id, log) in <ThreadDbLogs<T>>::iter() {
+            let file_info = Self::files(&file_id).ok_or("Thread db log of a file that does not exist")?;
+            frame_support::ensure!(file_info.db_log.contains(&log_id), "Thread db log is not in db_log of the file");
+            frame_support::ensure!(log.peers.is_subset(&file_info.peers), "Thread db log peer does not store the file");
+        }
+        Ok(())
+    }
 
+    /// Check the number of reports of each era against the accounts that reported.
+    fn check_reports() -> Result<(), &'static str> {
+        let mut report_number_in_era = BTreeMap::<EraIndex, u32>::new();
+        for (era_index, _, accounts) in <ReportsInEra<T>>::iter() {
+            *report_number_in_era.entry(era_index).or_default() += accounts.len() as u32;
+        }
+        frame_support::ensure!(
+            <ReportNumberInEra<T>>::iter().filter(|(_, n)| *n > 0).collect::<BTreeMap<EraIndex, u32>>() == report_number_in_era,
+            "ReportNumberInEra is not the number of reports in the era"
+        );
+        Ok(())
+    }
+
+    /// Check the NFT accounts against the items of the nfts collection.
+    fn check_nft_accounts() -> Result<(), &'static str> {
+        for (nft_account, owner) in <NftToWalletAccount<T>>::iter() {
+            let collection = Self::nft_collection().ok_or("NFT account without nfts collection")?;
+            let item = Self::nft_account_item(&nft_account).ok_or("NFT account is not an nfts item")?;
+            frame_support::ensure!(
+                Self::nft_item_account(item) == Some(nft_account),
+                "NftItemAccount is not the NFT account of the item"
+            );
+            frame_support::ensure!(
+                T::Nfts::owner(&collection, &item) == Some(owner),
+                "NftToWalletAccount is not the owner of the nfts item"
+            );
+        }
+        for nft_account in <NftAccountExpiry<T>>::iter_keys() {
+            frame_support::ensure!(
+                <NftToWalletAccount<T>>::contains_key(&nft_account),
+                "NftAccountExpiry of a NFT account that does not exist"
+            );
+        }
+        Ok(())
+    }
+
+    /// Check the sponsored wallet accounts against the sponsorships of apps.
+    fn check_sponsorships() -> Result<(), &'static str> {
+        for (app_id, account, _) in <SponsoredUsers<T>>::iter() {
+            frame_support::ensure!(
+                <Sponsorships<T>>::contains_key(&app_id),
+                "SponsoredUsers of a sponsorship that does not exist"
+            );
+            frame_support::ensure!(
+                Self::user_sponsor(&account) == Some(app_id),
+                "UserSponsor is not the app that the account is enrolled in"
+            );
+        }
+        for (account, app_id) in <UserSponsor<T>>::iter() {
+            frame_support::ensure!(
+                <SponsoredUsers<T>>::contains_key(&app_id, &account),
+                "UserSponsor of an account that is not enrolled in the sponsorship"
+            );
+        }
+        for (app_id, sponsorship) in <Sponsorships<T>>::iter() {
+            frame_support::ensure!(
+                <SponsoredUsers<T>>::iter_prefix(&app_id).count() as u32 == sponsorship.users,
+                "Sponsorship users is not the number of enrolled accounts"
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "runtime-benchmarks")]
+pub trait BenchmarkHelper<CollectionId> {
+    /// Create the nfts collection of NFT accounts.
+    fn nft_collection() -> CollectionId;
+}
+
+#[cfg(feature = "runtime-benchmarks")]
+impl<T: pallet_nfts::Config> BenchmarkHelper<T::CollectionId> for pallet_nfts::Pallet<T>
+    where
+        T::CollectionId: Default,
+{
+    fn nft_collection() -> T::CollectionId {
+        use frame_support::traits::{tokens::nonfungibles_v2::Create, ReservableCurrency};
+        let owner: T::AccountId = frame_benchmarking::account("nft_collection", 0, 0);
+        let deposit = T::Currency::minimum_balance().saturating_add(T::CollectionDeposit::get());
+        T::Currency::make_free_balance_be(&owner, deposit.saturating_mul(2u32.into()));
+        <Self as Create<T::AccountId, _>>::create_collection(&owner, &owner, &Default::default()).unwrap()
+    }
+}
+
+/// Keep NFT accounts in sync with the items of the nfts collection.
+impl<T: Config> ItemsCallback<T::NftCollectionId, NftItemId, T::AccountId> for Pallet<T> 
+    where 
+        T::AccountId: AsRef<[u8]>,
+{
+    fn transferred(collection: &T::NftCollectionId, item: &NftItemId, from: &T::AccountId, to: &T::AccountId) {
+        if Self::nft_collection().as_ref() != Some(collection) {
+            return;
+        }
+        if let Some(nft_account) = Self::nft_item_account(item) {
+            <NftToWalletAccount<T>>::insert(&nft_account, to);
+            // The encrypted NFT account of from_account can not be used by the new owner
+            let block_height = frame_system::Pallet::<T>::block_number().saturated_into::<u32>();
+            let _ = Self::update_account_nft(from, NftAccount::new(), block_height, true);
+        }
+    }
+
+    fn burned(collection: &T::NftCollectionId, item: &NftItemId, owner: &T::AccountId) {
+        if Self::nft_collection().as_ref() != Some(collection) {
+            return;
+        }
+        if let Some(nft_account) = <NftItemAccount<T>>::take(item) {
+            <NftAccountItem<T>>::remove(&nft_account);
+            <NftAccountExpiry<T>>::remove(&nft_account);
+            <NftToWalletAccount<T>>::remove(&nft_account);
+            let block_height = frame_system::Pallet::<T>::block_number().saturated_into::<u32>();
+            let _ = Self::update_account_nft(owner, NftAccount::new(), block_height, false);
+        }
+    }
+}
+
+/// Expired NFT accounts can not be transferred until renewed or released.
+impl<T: Config> Locker<T::NftCollectionId, NftItemId> for Pallet<T> 
+    where 
+        T::AccountId: AsRef<[u8]>,
+{
+    fn is_locked(collection: T::NftCollectionId, item: NftItemId) -> bool {
+        if Self::nft_collection() != Some(collection) {
+            return false;
+        }
+        match Self::nft_item_account(item) {
+            Some(nft_account) => Self::nft_account_expired(&nft_account),
+            None => false,
+        }
+    }
+}
+
+/// Interact with DC
+pub trait DcProvider<AccountId, Balance> {
     /// Get the rewardable peers's space.
-    fn rewardable_peers_space() -> Option<(SpaceSize, BTreeMap<Self::AccountId, SpaceSize>)>;
+    fn rewardable_peers_space() -> Option<(SpaceSize, BTreeMap<AccountId, SpaceSize>)>;
     /// Get the rewardable app's login times.
-    fn rewardable_app_login_times() -> Option<(LoginTimes, BTreeMap<Self::AccountId, LoginTimes>)>;
+    fn rewardable_app_login_times() -> Option<(LoginTimes, BTreeMap<AccountId, LoginTimes>)>;
     /// Is it possible to unbind the incoming amount.
-    fn can_be_unbound_amount(stash: &Self::AccountId, value: Self::Balance) -> Self::Balance;
+    fn can_be_unbound_amount(stash: &AccountId, value: Balance) -> Balance;
     /// Update the staked amount of peer.
-    fn update_active(stash: &Self::AccountId, active: Self::Balance);
+    fn update_active(stash: &AccountId, active: Balance);
     /// Pay out for storage for the current era. 
-    fn era_storage_payout(era_duration: u64) -> Self::Balance;
+    fn era_storage_payout(era_duration: u64) -> Balance;
     /// Pay out for app for the current era. 
-    fn era_app_payout(era_duration: u64) -> Self::Balance;
+    fn era_app_payout(era_duration: u64) -> Balance;
 
     /// The total cost of storage purchased by users. 
-    fn purchase_storage_cost() -> Self::Balance;
+    fn purchase_storage_cost() -> Balance;
+}
+
+/// No DC functionality: nothing is rewardable and the whole amount can always be unbound.
+impl<AccountId, Balance: Zero> DcProvider<AccountId, Balance> for () {
+    fn rewardable_peers_space() -> Option<(SpaceSize, BTreeMap<AccountId, SpaceSize>)> {
+        None
+    }
+
+    fn rewardable_app_login_times() -> Option<(LoginTimes, BTreeMap<AccountId, LoginTimes>)> {
+        None
+    }
+
+    fn can_be_unbound_amount(_stash: &AccountId, value: Balance) -> Balance {
+        value
+    }
+
+    fn update_active(_stash: &AccountId, _active: Balance) {}
+
+    fn era_storage_payout(_era_duration: u64) -> Balance {
+        Zero::zero()
+    }
+
+    fn era_app_payout(_era_duration: u64) -> Balance {
+        Zero::zero()
+    }
+
+    fn purchase_storage_cost() -> Balance {
+        Zero::zero()
+    }
 }
 
-impl<T: Config> DcProvider for Pallet<T> {
-    type AccountId = T::AccountId;
-    type Balance = BalanceOf<T>;
+impl<T: Config> DcProvider<T::AccountId, BalanceOf<T>> for Pallet<T> {
     /// Get the rewardable peers's space.
-    fn rewardable_peers_space() -> Option<(SpaceSize, BTreeMap<Self::AccountId, SpaceSize>)> {
+    fn rewardable_peers_space() -> Option<(SpaceSize, BTreeMap<T::AccountId, SpaceSize>)> {
         let mut total = 0;
-        let mut accounts = BTreeMap::<Self::AccountId, SpaceSize>::new();
+        let mut accounts = BTreeMap::<T::AccountId, SpaceSize>::new();
         let cur_block_num = frame_system::Pallet::<T>::block_number();
         // Get the space info
-        <Peers<T>>::iter_values()
-            .for_each(|storage_node| {
+        <Peers<T>>::iter()
+            .for_each(|(peer_id, storage_node)| {
                 if storage_node.status == NODE_STATUS_ONCHAIN
                    && storage_node.reward_number < frame_system::Pallet::<T>::block_number()
-                   && cur_block_num.saturating_sub(storage_node.report_number) < Self::interval_blocks_work_report() {
+                   && cur_block_num.saturating_sub(storage_node.report_number) < Self::interval_blocks_work_report()
+                   && Self::peer_program_is_met(&peer_id) {
                     let mut cur_space: SpaceSize = storage_node.total_space/ONE_G_BYTE;
                     // When the sgx version is 2, increase the income by 20%.
                     if storage_node.sgx_version_number ==  2 {
@@ -3075,9 +5553,9 @@ impl<T: Config> DcProvider for Pallet<T> {
     }
 
     /// Get the rewardable app's login times.
-    fn rewardable_app_login_times() -> Option<(LoginTimes, BTreeMap<Self::AccountId, LoginTimes>)> {
+    fn rewardable_app_login_times() -> Option<(LoginTimes, BTreeMap<T::AccountId, LoginTimes>)> {
         let mut total: LoginTimes = 0;
-        let mut accounts = BTreeMap::<Self::AccountId, LoginTimes>::new();
+        let mut accounts = BTreeMap::<T::AccountId, LoginTimes>::new();
         // Get the login info
         let login_count: BTreeMap<AppID, AppLoginInfo<T::AccountId>> = match <AppsAccountLoginTimes::<T>>::get() {
             Some(p) => p,
@@ -3092,11 +5570,11 @@ impl<T: Config> DcProvider for Pallet<T> {
     }
 
     /// With reference to the input value, the number that can be unbound.
-    fn can_be_unbound_amount(stash: &Self::AccountId, value: Self::Balance) -> Self::Balance {
+    fn can_be_unbound_amount(stash: &T::AccountId, value: BalanceOf<T>) -> BalanceOf<T> {
         Self::stash_peers(stash).map_or(value, |peer_id_set| {
             let min_amount = Self::min_staking_amount();
             let staking_active = T::StakingProvider::get_staking_active(stash);
-            let mut should_bond_amount: Self::Balance = Zero::zero();
+            let mut should_bond_amount: BalanceOf<T> = Zero::zero();
             for peer_id in peer_id_set.iter() {
                 Self::peers(&peer_id).map(|pre_info| {
                     if pre_info.status != NODE_STATUS_DISCARD {
@@ -3117,13 +5595,13 @@ impl<T: Config> DcProvider for Pallet<T> {
     }
 
     /// Update the staked amount of peer
-    fn update_active(stash: &Self::AccountId, staking_active: Self::Balance) {
+    fn update_active(stash: &T::AccountId, staking_active: BalanceOf<T>) {
         // Set the status of the nodes based on the amount of stake
         Self::update_peers_of_stash(stash, staking_active);
     }
 
     /// Pay out for storage for the current era. 
-    fn era_storage_payout(era_duration: u64) -> Self::Balance {
+    fn era_storage_payout(era_duration: u64) -> BalanceOf<T> {
         let total = Self::storage_reward_total();
         if total > Zero::zero() {
             // Milliseconds per year for the Julian year (365.25 days).
@@ -3139,7 +5617,7 @@ impl<T: Config> DcProvider for Pallet<T> {
     }
 
     /// Pay out for app for the current era. 
-    fn era_app_payout(era_duration: u64) -> Self::Balance {
+    fn era_app_payout(era_duration: u64) -> BalanceOf<T> {
         let total = Self::app_reward_total();
         if total > Zero::zero() {
             // Milliseconds per year for the Julian year (365.25 days).
@@ -3155,7 +5633,7 @@ impl<T: Config> DcProvider for Pallet<T> {
     }
 
     /// The total cost of storage purchased by users. 
-    fn purchase_storage_cost() -> Self::Balance {
+    fn purchase_storage_cost() -> BalanceOf<T> {
         Self::storage_reward_total().saturating_add(Self::app_reward_total())
     }
 }
@@ -3176,5 +5654,15 @@ pub trait StakingProvider {
     /// Check the controller account mapped by the "stash" account
     fn is_bonded_controller(stash: &Self::AccountId, controller: &Self::AccountId) -> bool;
 
-    fn report_offence(stash: &Self::AccountId, slash: Self::Balance,);
+    /// The full identification of stash that offences are reported against.
+    type Offender: Clone;
+
+    /// Get the full identification of stash, `None` if it is not bonded.
+    fn offender(stash: &Self::AccountId) -> Option<Self::Offender>;
+
+    /// Get the session index that offences are reported in.
+    fn offence_session_index() -> SessionIndex;
+
+    /// The worst case weight of slashing the offender of an offence reported by `reporters`.
+    fn offence_weight(reporters: u32) -> frame_support::weights::Weight;
 }
@@ -0,0 +1,92 @@
+use crate::*;
+use sp_core::crypto::KeyTypeId;
+use sp_runtime::offchain::{http, Duration};
+
+/// Key type of liveness probers, validators opt in to probing by inserting a key of this type.
+pub const KEY_TYPE: KeyTypeId = KeyTypeId(*b"dclv");
+
+/// Path that storage nodes answer liveness probes on, with their peer id as the body.
+pub const LIVENESS_PROBE_PATH: &str = "/dc/liveness";
+
+/// Milliseconds that a probing round waits for the storage nodes to answer.
+pub const LIVENESS_PROBE_TIMEOUT_MS: u64 = 3_000;
+
+pub mod sr25519 {
+    mod app_sr25519 {
+        use sp_application_crypto::{app_crypto, sr25519};
+        app_crypto!(sr25519, super::super::KEY_TYPE);
+    }
+
+    sp_application_crypto::with_pair! {
+        /// A liveness prober keypair using sr25519 as its crypto.
+        pub type AuthorityPair = app_sr25519::Pair;
+    }
+
+    /// A liveness prober signature using sr25519 as its crypto.
+    pub type AuthoritySignature = app_sr25519::Signature;
+
+    /// A liveness prober identifier using sr25519 as its crypto.
+    pub type AuthorityId = app_sr25519::Public;
+}
+
+/// Aggregated results of a liveness probing round, signed by the prober.
+#[derive(Clone, Eq, PartialEq, RuntimeDebug, Encode, Decode, TypeInfo)]
+pub struct LivenessReport<BlockNumber, AuthorityId> {
+    /// The block number that the round was started at.
+    pub block_number: BlockNumber,
+    /// The key of the prober.
+    pub prober: AuthorityId,
+    /// The number of storage nodes probed in the round.
+    pub probed: u32,
+    /// The storage nodes that did not answer the probe.
+    pub offline: Vec<PeerId>,
+}
+
+/// The URL that the storage node registered at `ip_address` is probed with.
+///
+/// Addresses without a scheme are probed over plain HTTP.
+pub fn probe_url(ip_address: &[u8]) -> Option<String> {
+    let address = sp_std::str::from_utf8(ip_address).ok()?.trim_end_matches('/');
+    if address.is_empty() {
+        return None;
+    }
+    let mut url = String::new();
+    if !address.starts_with("http://") && !address.starts_with("https://") {
+        url.push_str("http://");
+    }
+    url.push_str(address);
+    url.push_str(LIVENESS_PROBE_PATH);
+    Some(url)
+}
+
+/// Probe the storage nodes and return those that did not answer with their peer id
+/// before the deadline.
+pub fn probe_peers(peers: &[(PeerId, DcString)]) -> Vec<PeerId> {
+    let deadline = sp_io::offchain::timestamp().add(Duration::from_millis(LIVENESS_PROBE_TIMEOUT_MS));
+    let mut offline = Vec::new();
+    let mut pending = Vec::new();
+    for (peer_id, ip_address) in peers.iter() {
+        let request = probe_url(ip_address)
+            .and_then(|url| http::Request::get(&url).deadline(deadline).send().ok());
+        match request {
+            Some(request) => pending.push((peer_id, request)),
+            None => offline.push(peer_id.clone()),
+        }
+    }
+
+    let (peer_ids, requests): (Vec<_>, Vec<_>) = pending.into_iter().unzip();
+    for (peer_id, result) in peer_ids.into_iter().zip(http::PendingRequest::try_wait_all(requests, deadline)) {
+        let alive = match result {
+            Ok(Ok(response)) if response.code >= 200 && response.code < 300 => {
+                let mut body = response.body();
+                body.deadline(deadline);
+                body.collect::<Vec<u8>>() == *peer_id
+            },
+            _ => false,
+        };
+        if !alive {
+            offline.push(peer_id.clone());
+        }
+    }
+    offline
+}
@@ -0,0 +1,323 @@
+use super::*;
+use frame_support::{
+    storage_alias,
+    traits::{GetStorageVersion, OnRuntimeUpgrade, PalletInfoAccess, StorageVersion},
+    weights::Weight,
+};
+
+/// Move the proxy nodes and trusted storage nodes into the `Endpoints` registry.
+pub mod v1 {
+    use super::*;
+
+    #[storage_alias]
+    pub type ProxyNodes<T: Config> = StorageValue<Pallet<T>, BTreeSet<(DcString, DcString)>>;
+
+    #[storage_alias]
+    pub type TrustedStorageNodes<T: Config> = StorageValue<Pallet<T>, BTreeSet<(DcString, DcString)>>;
+
+    pub struct MigrateToV1<T>(sp_std::marker::PhantomData<T>);
+    impl<T: Config> OnRuntimeUpgrade for MigrateToV1<T> {
+        fn on_runtime_upgrade() -> Weight {
+            let onchain = Pallet::<T>::on_chain_storage_version();
+            if onchain >= 1 {
+                log::info!(
+                    target: "runtime::dc-node",
+                    "{} already at storage version {:?}, skipping migration",
+                    <Pallet<T> as PalletInfoAccess>::name(),
+                    onchain,
+                );
+                return T::DbWeight::get().reads(1);
+            }
+
+            let now = frame_system::Pallet::<T>::block_number();
+            let mut migrated = 0u64;
+            for (kind, nodes) in [
+                (EndpointKind::Proxy, ProxyNodes::<T>::take()),
+                (EndpointKind::TrustedStorage, TrustedStorageNodes::<T>::take()),
+            ] {
+                for (address, signature) in nodes.unwrap_or_default() {
+                    <Endpoints<T>>::insert(kind, &address, NodeEndpoint {
+                        address: address.clone(),
+                        signature,
+                        region: DcString::new(),
+                        api_version: 0,
+                        priority: 0,
+                        operator: None,
+                        last_seen: now,
+                    });
+                    migrated += 1;
+                }
+            }
+            StorageVersion::new(1).put::<Pallet<T>>();
+
+            log::info!(target: "runtime::dc-node", "migrated {} endpoints to storage version 1", migrated);
+            T::DbWeight::get().reads_writes(3, migrated + 3)
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn pre_upgrade() -> Result<Vec<u8>, &'static str> {
+            let count = ProxyNodes::<T>::get().unwrap_or_default().len() as u32
+                + TrustedStorageNodes::<T>::get().unwrap_or_default().len() as u32;
+            Ok(count.encode())
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn post_upgrade(state: Vec<u8>) -> Result<(), &'static str> {
+            let count: u32 = Decode::decode(&mut &state[..]).map_err(|_| "Invalid pre-upgrade state")?;
+            frame_support::ensure!(
+                Pallet::<T>::on_chain_storage_version() == 1,
+                "Storage version of dc-node is not 1"
+            );
+            frame_support::ensure!(
+                ProxyNodes::<T>::get().is_none() && TrustedStorageNodes::<T>::get().is_none(),
+                "Old endpoints are not removed"
+            );
+            frame_support::ensure!(
+                <Endpoints<T>>::iter().count() as u32 >= count,
+                "Endpoints are not migrated"
+            );
+            Ok(())
+        }
+    }
+}
+
+/// Register the existing apps with an empty deposit and metadata.
+pub mod v2 {
+    use super::*;
+
+    #[derive(Decode)]
+    pub struct OldAppAccountInfo<AccountId> {
+        pub private_account: AccountId,
+        pub rewarded_stash: AccountId,
+    }
+
+    pub struct MigrateToV2<T>(sp_std::marker::PhantomData<T>);
+    impl<T: Config> OnRuntimeUpgrade for MigrateToV2<T> {
+        fn on_runtime_upgrade() -> Weight {
+            let onchain = Pallet::<T>::on_chain_storage_version();
+            if onchain != 1 {
+                log::info!(
+                    target: "runtime::dc-node",
+                    "{} at storage version {:?}, skipping migration to 2",
+                    <Pallet<T> as PalletInfoAccess>::name(),
+                    onchain,
+                );
+                return T::DbWeight::get().reads(1);
+            }
+
+            let mut migrated = 0u64;
+            <AccountOfApp<T>>::translate::<OldAppAccountInfo<T::AccountId>, _>(|_, old| {
+                migrated += 1;
+                Some(AppAccountInfo {
+                    private_account: old.private_account,
+                    rewarded_stash: old.rewarded_stash,
+                    deposit: Zero::zero(),
+                    name: DcString::new(),
+                    url: DcString::new(),
+                    logo_hash: Default::default(),
+                })
+            });
+            StorageVersion::new(2).put::<Pallet<T>>();
+
+            log::info!(target: "runtime::dc-node", "migrated {} apps to storage version 2", migrated);
+            T::DbWeight::get().reads_writes(migrated + 1, migrated + 1)
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn pre_upgrade() -> Result<Vec<u8>, &'static str> {
+            let count = <AccountOfApp<T>>::iter_keys().count() as u32;
+            Ok(count.encode())
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn post_upgrade(state: Vec<u8>) -> Result<(), &'static str> {
+            let count: u32 = Decode::decode(&mut &state[..]).map_err(|_| "Invalid pre-upgrade state")?;
+            frame_support::ensure!(
+                Pallet::<T>::on_chain_storage_version() == 2,
+                "Storage version of dc-node is not 2"
+            );
+            frame_support::ensure!(
+                <AccountOfApp<T>>::iter().count() as u32 == count,
+                "Apps are not migrated"
+            );
+            Ok(())
+        }
+    }
+}
+
+/// Mint the nfts items of the NFT accounts registered before the nfts collection.
+pub mod v3 {
+    use super::*;
+
+    pub struct MigrateToV3<T>(sp_std::marker::PhantomData<T>);
+    impl<T: Config> OnRuntimeUpgrade for MigrateToV3<T>
+        where
+            T::AccountId: AsRef<[u8]>,
+    {
+        fn on_runtime_upgrade() -> Weight {
+            let onchain = Pallet::<T>::on_chain_storage_version();
+            if onchain != 2 {
+                log::info!(
+                    target: "runtime::dc-node",
+                    "{} at storage version {:?}, skipping migration to 3",
+                    <Pallet<T> as PalletInfoAccess>::name(),
+                    onchain,
+                );
+                return T::DbWeight::get().reads(1);
+            }
+            // The items can not be minted until root sets the collection, so keep the
+            // storage version and migrate again in a later upgrade.
+            if Pallet::<T>::nft_collection().is_none() {
+                log::warn!(
+                    target: "runtime::dc-node",
+                    "nfts collection of NFT accounts is not set, skipping migration to 3",
+                );
+                return T::DbWeight::get().reads(2);
+            }
+
+            let mut read = 0u64;
+            let mut migrated = 0u64;
+            let mut failed = 0u64;
+            for (nft_account, owner) in <NftToWalletAccount<T>>::iter() {
+                read += 1;
+                if <NftAccountItem<T>>::contains_key(&nft_account) {
+                    continue
+                }
+                match Pallet::<T>::mint_nft_account(&nft_account, &owner) {
+                    Ok(()) => migrated += 1,
+                    Err(e) => {
+                        failed += 1;
+                        log::error!(
+                            target: "runtime::dc-node",
+                            "failed to mint the nfts item of NFT account {:?}: {:?}",
+                            nft_account,
+                            e,
+                        );
+                    },
+                }
+            }
+            // Keep the storage version, so that the failed NFT accounts are minted again in a
+            // later upgrade.
+            if failed > 0 {
+                log::warn!(
+                    target: "runtime::dc-node",
+                    "failed to mint {} NFT accounts, keeping storage version 2",
+                    failed,
+                );
+            } else {
+                StorageVersion::new(3).put::<Pallet<T>>();
+            }
+
+            log::info!(target: "runtime::dc-node", "minted {} NFT accounts to storage version 3", migrated);
+            // Minting reads and writes the collection, the item and its attribute
+            T::DbWeight::get().reads_writes(read * 2 + (migrated + failed) * 4 + 2, migrated * 8 + 1)
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn pre_upgrade() -> Result<Vec<u8>, &'static str> {
+            let count = <NftToWalletAccount<T>>::iter_keys().count() as u32;
+            Ok(count.encode())
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn post_upgrade(state: Vec<u8>) -> Result<(), &'static str> {
+            let count: u32 = Decode::decode(&mut &state[..]).map_err(|_| "Invalid pre-upgrade state")?;
+            if Pallet::<T>::on_chain_storage_version() < 3 {
+                return Ok(())
+            }
+            frame_support::ensure!(
+                <NftToWalletAccount<T>>::iter_keys().count() as u32 == count,
+                "NFT accounts are lost"
+            );
+            Pallet::<T>::check_nft_accounts()
+        }
+    }
+}
+
+/// Count the peers of each status and the space of the onchain peers, the active wallet accounts,
+/// the files and the reports of each era, so that they are not read from all the items in the hooks
+/// and the runtime API.
+pub mod v4 {
+    use super::*;
+
+    pub struct MigrateToV4<T>(sp_std::marker::PhantomData<T>);
+    impl<T: Config> OnRuntimeUpgrade for MigrateToV4<T> {
+        fn on_runtime_upgrade() -> Weight {
+            let onchain = Pallet::<T>::on_chain_storage_version();
+            if onchain != 3 {
+                log::info!(
+                    target: "runtime::dc-node",
+                    "{} at storage version {:?}, skipping migration to 4",
+                    <Pallet<T> as PalletInfoAccess>::name(),
+                    onchain,
+                );
+                return T::DbWeight::get().reads(1);
+            }
+
+            let mut read = 0u64;
+            let mut peers_of_status = BTreeMap::<u32, u32>::new();
+            let (mut total_space, mut free_space): (SpaceSize, SpaceSize) = (0, 0);
+            for peer_info in <Peers<T>>::iter_values() {
+                read += 1;
+                *peers_of_status.entry(peer_info.status).or_default() += 1;
+                if peer_info.status == NODE_STATUS_ONCHAIN {
+                    total_space = total_space.saturating_add(peer_info.total_space);
+                    free_space = free_space.saturating_add(peer_info.free_space);
+                }
+            }
+            let mut written = peers_of_status.len() as u64;
+            for (status, number) in peers_of_status.into_iter() {
+                <PeersOfStatus<T>>::insert(status, number);
+            }
+            <OnchainSpace<T>>::put((total_space, free_space));
+
+            let now = frame_system::Pallet::<T>::block_number();
+            let mut users_expiring_at = BTreeMap::<T::BlockNumber, u32>::new();
+            for user_info in <WalletAccountStorage<T>>::iter_values() {
+                read += 1;
+                if user_info.expire_number >= now {
+                    *users_expiring_at.entry(user_info.expire_number).or_default() += 1;
+                }
+            }
+            written += users_expiring_at.len() as u64;
+            <ActiveUsers<T>>::put(users_expiring_at.values().fold(0u32, |total, n| total.saturating_add(*n)));
+            for (expire_number, number) in users_expiring_at.into_iter() {
+                <UsersExpiringAt<T>>::insert(expire_number, number);
+            }
+
+            let (mut files, mut replicas) = (0u64, 0u64);
+            for file_info in <Files<T>>::iter_values() {
+                read += 1;
+                files += 1;
+                replicas = replicas.saturating_add(file_info.peers.len() as u64);
+            }
+            <FileTotals<T>>::put((files, replicas));
+
+            let mut report_number_in_era = BTreeMap::<EraIndex, u32>::new();
+            for (era_index, _, accounts) in <ReportsInEra<T>>::iter() {
+                read += 1;
+                *report_number_in_era.entry(era_index).or_default() += accounts.len() as u32;
+            }
+            written += report_number_in_era.len() as u64;
+            for (era_index, number) in report_number_in_era.into_iter() {
+                <ReportNumberInEra<T>>::insert(era_index, number);
+            }
+            StorageVersion::new(4).put::<Pallet<T>>();
+
+            log::info!(target: "runtime::dc-node", "counted {} peers, wallet accounts, files and reports to storage version 4", read);
+            T::DbWeight::get().reads_writes(read + 2, written + 4)
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn post_upgrade(_state: Vec<u8>) -> Result<(), &'static str> {
+            frame_support::ensure!(
+                Pallet::<T>::on_chain_storage_version() == 4,
+                "dc-node is not at storage version 4"
+            );
+            Pallet::<T>::check_peers()?;
+            Pallet::<T>::check_active_users()?;
+            Pallet::<T>::check_files()?;
+            Pallet::<T>::check_reports()
+        }
+    }
+}
@@ -3,13 +3,14 @@ use crate::{self as pallet_dc_node, *};
 use frame_support::{
 	parameter_types,
 	traits::{
-		GenesisBuild, 
+		AsEnsureOriginWithArg, ConstU32, ConstU64, ConstU128, GenesisBuild, 
 	},
 	weights::constants::RocksDbWeight,
 	pallet_prelude::Weight,
 };
 
 use sp_core::{H256};
+use sp_staking::offence::{Offence, OffenceError};
 use sp_runtime::{
 	testing::{Header},
 	traits::{IdentityLookup, IdentifyAccount, Verify},
@@ -18,13 +19,13 @@ use sp_runtime::{
 
 use pallet_transaction_payment::{Multiplier, NextMultiplier};
 
-type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+pub type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
 type Block = frame_system::mocking::MockBlock<Test>;
-pub(crate) type AccountId = <<MultiSignature as Verify>::Signer as IdentifyAccount>::AccountId;
-pub(crate) type Balance = u128;
-pub(crate) type AccountIndex = u64;
-pub(crate) type BlockNumber = u64;
-pub(crate) type SessionIndex = u64;
+pub type AccountId = <<MultiSignature as Verify>::Signer as IdentifyAccount>::AccountId;
+pub type Balance = u128;
+pub type AccountIndex = u64;
+pub type BlockNumber = u64;
+pub type SessionIndex = u64;
 
 pub struct StakingMock<T: Config> {
 	pub account_id: T::AccountId,
@@ -36,12 +37,12 @@ impl<T: Config> StakingProvider for StakingMock<T> {
     
 	/// Get staking active of stash.
     fn get_staking_active(_stash: &Self::AccountId) -> Self::Balance {
-		20000u32.into()
+		StakingActive::get().saturated_into()
 	}
 
 	/// Get current era index
     fn get_current_era_index() -> EraIndex {
-		1
+		CurrentEra::get()
 	}
 
 	/// Check the controller account mapped by the "stash" account
@@ -49,10 +50,35 @@ impl<T: Config> StakingProvider for StakingMock<T> {
 		true
 	}
 
-	fn report_offence(
-		_stash: &Self::AccountId,
-		_slash: Self::Balance,
-	) {
+	type Offender = (T::AccountId, Self::Balance);
+
+	fn offender(stash: &Self::AccountId) -> Option<Self::Offender> {
+		Some((stash.clone(), Self::get_staking_active(stash)))
+	}
+
+	fn offence_session_index() -> sp_staking::SessionIndex {
+		CurrentEra::get()
+	}
+
+	fn offence_weight(_reporters: u32) -> Weight {
+		Weight::zero()
+	}
+}
+
+/// Records the reported offences instead of slashing.
+pub struct OffenceHandler;
+
+impl ReportOffence<AccountId, (AccountId, Balance), DcOffence<(AccountId, Balance)>> for OffenceHandler {
+	fn report_offence(reporters: Vec<AccountId>, offence: DcOffence<(AccountId, Balance)>) -> Result<(), OffenceError> {
+		if Self::is_known_offence(&offence.offenders(), &offence.time_slot()) {
+			return Err(OffenceError::DuplicateReport)
+		}
+		Offences::mutate(|offences| offences.push((reporters, offence)));
+		Ok(())
+	}
+
+	fn is_known_offence(offenders: &[(AccountId, Balance)], time_slot: &(sp_staking::SessionIndex, DcOffenceKind, PeerId)) -> bool {
+		Offences::get().iter().any(|(_, offence)| offence.time_slot() == *time_slot && offence.offenders() == offenders)
 	}
 }
 
@@ -76,7 +102,8 @@ frame_support::construct_runtime!(
 	{
 		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
 		Balances: pallet_balances::{Pallet, Call, Storage, Config<T>, Event<T>},
-		DcNode: pallet_dc_node::{Pallet, Call, Storage, Event<T>},
+		Nfts: pallet_nfts::{Pallet, Call, Storage, Event<T>},
+		DcNode: pallet_dc_node::{Pallet, Call, Storage, Event<T>, ValidateUnsigned},
 	}
 );
 
@@ -90,10 +117,13 @@ parameter_types! {
 	pub static SlashDeferDuration: EraIndex = 0;
 	pub static Period: BlockNumber = 5;
 	pub static Offset: BlockNumber = 0;
+	pub static CurrentEra: EraIndex = 1;
+	pub static StakingActive: Balance = 20000;
+	pub static Offences: Vec<(Vec<AccountId>, DcOffence<(AccountId, Balance)>)> = Vec::new();
 }
 
 impl frame_system::Config for Test {
-	type BaseCallFilter = frame_support::traits::Everything;
+	type BaseCallFilter = pallet_dc_node::PausedCalls<Test>;
 	type BlockWeights = ();
 	type BlockLength = ();
 	type DbWeight = RocksDbWeight;
@@ -130,9 +160,52 @@ impl pallet_balances::Config for Test {
 	type WeightInfo = ();
 }
 
+parameter_types! {
+	pub storage Features: pallet_nfts::PalletFeatures = pallet_nfts::PalletFeatures::all_enabled();
+}
+
+impl pallet_nfts::Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type CollectionId = u32;
+	type ItemId = u32;
+	type Currency = Balances;
+	type CreateOrigin = AsEnsureOriginWithArg<frame_system::EnsureSigned<AccountId>>;
+	type ForceOrigin = frame_system::EnsureRoot<AccountId>;
+	type Locker = DcNode;
+	type CallbackHandle = DcNode;
+	type CollectionDeposit = ConstU128<2>;
+	type ItemDeposit = ConstU128<1>;
+	type MetadataDepositBase = ConstU128<1>;
+	type AttributeDepositBase = ConstU128<1>;
+	type DepositPerByte = ConstU128<1>;
+	type StringLimit = ConstU32<50>;
+	type KeyLimit = ConstU32<50>;
+	type ValueLimit = ConstU32<50>;
+	type ApprovalsLimit = ConstU32<10>;
+	type ItemAttributesApprovalsLimit = ConstU32<2>;
+	type MaxTips = ConstU32<10>;
+	type MaxDeadlineDuration = ConstU64<10000>;
+	type Features = Features;
+	type WeightInfo = ();
+	#[cfg(feature = "runtime-benchmarks")]
+	type Helper = ();
+}
 
 parameter_types! {
 	pub DefaultAccountId: AccountId = hex_literal::hex!("2bb43fdff91b4d6adfe15c48cccc71ef92eafbf19a791bf6ee5927dfd2a59890").into();
+	pub static LivenessProbeInterval: BlockNumber = 10;
+	pub static MaxLivenessProbes: u32 = 100;
+	pub static AppDeposit: Balance = 10;
+	pub static PriceAdjustmentInterval: BlockNumber = 10;
+	pub static PeerCallWindow: BlockNumber = 10;
+}
+
+impl<LocalCall> frame_system::offchain::SendTransactionTypes<LocalCall> for Test
+where
+	RuntimeCall: From<LocalCall>,
+{
+	type OverarchingCall = RuntimeCall;
+	type Extrinsic = UncheckedExtrinsic;
 }
 
 impl Config for Test {
@@ -142,8 +215,20 @@ impl Config for Test {
 	type AccountStore = frame_system::Pallet<Test>;
 	type DefaultAccountId = DefaultAccountId;
 	type StakingProvider = StakingMock<Self>;
+	type ReportOffence = OffenceHandler;
 	type WeightInfo = pallet_dc_node::weights::SubstrateWeight<Test>;
 	type BlockMultiplier = PaymentMock;
+	type NftCollectionId = u32;
+	type Nfts = Nfts;
+	type AuthorityId = liveness::sr25519::AuthorityId;
+	type LivenessProbeInterval = LivenessProbeInterval;
+	type MaxLivenessProbes = MaxLivenessProbes;
+	type UnsignedPriority = ConstU64<{ 1 << 20 }>;
+	type PriceAdjustmentInterval = PriceAdjustmentInterval;
+	type PeerCallWindow = PeerCallWindow;
+	type AppDeposit = AppDeposit;
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = Nfts;
 }
 
 // Build genesis storage according to the mock runtime.
@@ -176,8 +261,38 @@ pub fn new_test_ext() -> sp_io::TestExternalities {
 		interval_blocks_work_report: 28800u32.into(),
 		interval_blocks_login: 28800u32.into(),
 		tee_report_verify_number: 300u32.into(),
+		peer_call_quota: 100,
+		storage_packages: Default::default(),
+		enclave_ids: Default::default(),
+		dc_program: None,
+		endpoints: Default::default(),
+		app_accounts: Default::default(),
+		wallet_accounts: Default::default(),
+		dev_config: Default::default(),
 	}
 	.assimilate_storage(&mut t)
 	.unwrap();
-	t.into()
+	let mut ext: sp_io::TestExternalities = t.into();
+	ext.execute_with(|| {
+		// The collection of NFT accounts
+		Nfts::force_create(RuntimeOrigin::root(), DefaultAccountId::get(), Default::default()).unwrap();
+		DcNode::set_nft_collection(RuntimeOrigin::root(), 0).unwrap();
+	});
+	ext
+}
+
+/// Externalities with a keystore, for the benchmarks signing with generated keys.
+#[cfg(test)]
+pub fn new_bench_ext() -> sp_io::TestExternalities {
+	let mut ext = new_test_ext();
+	ext.register_extension(sp_keystore::KeystoreExt(std::sync::Arc::new(sp_keystore::testing::KeyStore::new())));
+	ext
+}
+
+/// Run the test and check the consistency of the pallet storage afterwards.
+pub fn build_and_execute(test: impl FnOnce() -> ()) {
+	new_test_ext().execute_with(|| {
+		test();
+		DcNode::do_try_state().unwrap();
+	});
 }
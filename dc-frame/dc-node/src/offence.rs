@@ -0,0 +1,78 @@
+use crate::*;
+use sp_staking::{
+    offence::{DisableStrategy, Kind, Offence},
+    SessionIndex,
+};
+
+/// Kind of storage node offences.
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, RuntimeDebug, Encode, Decode, TypeInfo, MaxEncodedLen)]
+pub enum DcOffenceKind {
+    /// The TEE report of the node was verified to be fake.
+    TeeFaking,
+    /// The node stayed offchain longer than `BlocksOfOffchainToAbnormal`.
+    ProlongedOffline,
+    /// The node lost files that it stored.
+    FileLoss,
+    /// The node did not respond to other nodes.
+    Unresponsive,
+}
+
+impl DcOffenceKind {
+    /// The slash fraction of the kind when governance has not set one.
+    pub fn default_slash_fraction(&self) -> Perbill {
+        match self {
+            DcOffenceKind::TeeFaking => Perbill::one(),
+            DcOffenceKind::ProlongedOffline => Perbill::from_percent(10),
+            DcOffenceKind::FileLoss => Perbill::from_percent(2),
+            DcOffenceKind::Unresponsive => Perbill::from_percent(1),
+        }
+    }
+}
+
+/// An offence of storage node, reported through `pallet-offences` so that its slash
+/// can be deferred and cancelled like the slashes of validators.
+#[derive(Clone, Eq, PartialEq, RuntimeDebug)]
+pub struct DcOffence<Offender> {
+    /// Kind of the offence.
+    pub kind: DcOffenceKind,
+    /// The storage node that committed the offence.
+    pub peer_id: PeerId,
+    /// The session index that the offence was reported in.
+    pub session_index: SessionIndex,
+    /// The number of onchain storage nodes.
+    pub validator_set_count: u32,
+    /// The stash of the storage node.
+    pub offender: Offender,
+    /// The fraction of the stake to slash.
+    pub slash_fraction: Perbill,
+}
+
+impl<Offender: Clone> Offence<Offender> for DcOffence<Offender> {
+    const ID: Kind = *b"dc-node:offence:";
+    type TimeSlot = (SessionIndex, DcOffenceKind, PeerId);
+
+    fn offenders(&self) -> Vec<Offender> {
+        vec![self.offender.clone()]
+    }
+
+    fn session_index(&self) -> SessionIndex {
+        self.session_index
+    }
+
+    fn validator_set_count(&self) -> u32 {
+        self.validator_set_count
+    }
+
+    fn time_slot(&self) -> Self::TimeSlot {
+        (self.session_index, self.kind, self.peer_id.clone())
+    }
+
+    fn disable_strategy(&self) -> DisableStrategy {
+        // Storage nodes are not validators, even if the stash also validates
+        DisableStrategy::Never
+    }
+
+    fn slash_fraction(&self, _offenders_count: u32) -> Perbill {
+        self.slash_fraction
+    }
+}
@@ -0,0 +1,112 @@
+use crate::*;
+use frame_support::traits::{Contains, IsSubType};
+use sp_std::marker::PhantomData;
+
+/// Category of the calls of `pallet-dc-node` that governance can pause separately.
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, RuntimeDebug, Encode, Decode, TypeInfo, MaxEncodedLen)]
+pub enum CallCategory {
+    /// Purchases of storage packages, also by sponsors.
+    Purchases,
+    /// Calls on wallet accounts, their storage nodes and sub accounts, and on their files,
+    /// thread DBs and comment spaces.
+    FileOps,
+    /// Calls on NFT accounts.
+    NftOps,
+    /// Reports on storage nodes, files and wallet accounts.
+    Reports,
+    /// Logins of wallet accounts to apps.
+    Logins,
+    /// Storage nodes joining, leaving or moving between stashes.
+    NodeJoins,
+    /// Registration, ownership, sponsorships and login settings of apps.
+    Apps,
+}
+
+impl<T: Config> Call<T>
+where
+    T::AccountId: AsRef<[u8]>,
+{
+    /// The category that the call is paused with, calls without one are never paused.
+    ///
+    /// Work reports and endpoint heartbeats have no category, so that storage nodes and endpoints
+    /// keep their status while paused.
+    pub fn category(&self) -> Option<CallCategory> {
+        match self {
+            Call::purchase_storage { .. } |
+            Call::sponsor_user { .. } |
+            Call::renew_sponsored_storage { .. } => Some(CallCategory::Purchases),
+            Call::add_request_peer_id_to_user { .. } |
+            Call::create_sub_account { .. } |
+            Call::unbind_sub_account { .. } |
+            Call::add_user_peer { .. } |
+            Call::remove_self_user_peer { .. } |
+            Call::remove_other_user_peer { .. } |
+            Call::update_db_config { .. } |
+            Call::add_file_info { .. } |
+            Call::add_file_peer { .. } |
+            Call::remove_self_file_peer { .. } |
+            Call::remove_other_file_peer { .. } |
+            Call::delete_file_info { .. } |
+            Call::add_log_to_thread_db { .. } |
+            Call::add_space_to_thread_db { .. } |
+            Call::update_thread_db_log { .. } |
+            Call::add_thread_db_log_peer { .. } |
+            Call::compact_thread_db_log { .. } |
+            Call::new_theme { .. } |
+            Call::add_theme_comment_space { .. } |
+            Call::close_theme { .. } |
+            Call::add_user_comment_space { .. } => Some(CallCategory::FileOps),
+            Call::apply_nft_account { .. } |
+            Call::transfer_nft_account { .. } |
+            Call::update_nft_account { .. } |
+            Call::renew_nft_account { .. } |
+            Call::release_nft_account { .. } |
+            Call::reserve_nft_account { .. } |
+            Call::unreserve_nft_account { .. } => Some(CallCategory::NftOps),
+            Call::report_file_miss { .. } |
+            Call::report_login_info_miss { .. } |
+            Call::report_tee_faking { .. } |
+            Call::verify_tee_faking { .. } |
+            Call::report_peer_offchain { .. } |
+            Call::report_peer_no_response { .. } |
+            Call::report_peer_error { .. } |
+            Call::report_spam { .. } |
+            Call::report_malicious_comment { .. } |
+            Call::submit_liveness_report { .. } => Some(CallCategory::Reports),
+            Call::user_login { .. } => Some(CallCategory::Logins),
+            Call::join_storage_node { .. } |
+            Call::set_stash_peer { .. } |
+            Call::remove_stash_peer { .. } |
+            Call::stop_stash_peer { .. } |
+            Call::offer_peer_to_stash { .. } |
+            Call::transfer_peer_to_stash { .. } => Some(CallCategory::NodeJoins),
+            Call::set_app_account { .. } |
+            Call::set_app_metadata { .. } |
+            Call::transfer_app_ownership { .. } |
+            Call::accept_app_ownership { .. } |
+            Call::deregister_app { .. } |
+            Call::set_app_login_cosign { .. } |
+            Call::set_sponsorship { .. } |
+            Call::revoke_sponsorship { .. } |
+            Call::remove_sponsored_user { .. } => Some(CallCategory::Apps),
+            _ => None,
+        }
+    }
+}
+
+/// Filter out the calls of `pallet-dc-node` in the paused categories, to be used as, or as a
+/// part of, the `BaseCallFilter` of the runtime.
+pub struct PausedCalls<T>(PhantomData<T>);
+
+impl<T: Config> Contains<<T as frame_system::Config>::RuntimeCall> for PausedCalls<T>
+where
+    T::AccountId: AsRef<[u8]>,
+    <T as frame_system::Config>::RuntimeCall: IsSubType<Call<T>>,
+{
+    fn contains(call: &<T as frame_system::Config>::RuntimeCall) -> bool {
+        match call.is_sub_type().and_then(|call| call.category()) {
+            Some(category) => !Pallet::<T>::is_paused(category),
+            None => true,
+        }
+    }
+}
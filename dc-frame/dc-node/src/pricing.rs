@@ -0,0 +1,51 @@
+use crate::*;
+use pallet_transaction_payment::Multiplier;
+use sp_runtime::{PerThing, Perquintill};
+
+/// Curve that the price multiplier of storage packages follows, in the same way as the
+/// `TargetedFeeAdjustment` of transaction payment follows the block fullness.
+#[derive(Clone, Eq, PartialEq, RuntimeDebug, Encode, Decode, TypeInfo)]
+pub struct PricingCurve {
+    /// The utilization of the network that the price is not adjusted at.
+    pub target_utilization: Perquintill,
+    /// How fast the price responds to the utilization.
+    pub adjustment_variable: Multiplier,
+    /// The minimum price multiplier.
+    pub min_multiplier: Multiplier,
+    /// The maximum price multiplier.
+    pub max_multiplier: Multiplier,
+}
+
+impl PricingCurve {
+    /// The price multiplier following the previous one at the utilization.
+    ///
+    /// `next = previous * (1 + v * diff + v^2 * diff^2 / 2)`, where `diff` is the utilization
+    /// minus the target, clamped to the minimum and maximum multipliers.
+    pub fn next_multiplier(&self, previous: Multiplier, utilization: Perquintill) -> Multiplier {
+        let positive = utilization >= self.target_utilization;
+        let diff_abs = utilization.max(self.target_utilization) - utilization.min(self.target_utilization);
+        let diff = Multiplier::saturating_from_rational(diff_abs.deconstruct(), Perquintill::ACCURACY);
+
+        let v = self.adjustment_variable;
+        let v_squared_2 = v.saturating_mul(v) / Multiplier::saturating_from_integer(2);
+        let first_term = v.saturating_mul(diff);
+        let second_term = v_squared_2.saturating_mul(diff.saturating_mul(diff));
+
+        let next = if positive {
+            let excess = first_term.saturating_add(second_term).saturating_mul(previous);
+            previous.saturating_add(excess)
+        } else {
+            let negative = first_term.saturating_sub(second_term).saturating_mul(previous);
+            previous.saturating_sub(negative)
+        };
+        next.max(self.min_multiplier).min(self.max_multiplier)
+    }
+}
+
+/// The used space of the storage nodes compared with their total space.
+pub fn utilization(used_space: SpaceSize, total_space: SpaceSize) -> Perquintill {
+    if total_space == 0 {
+        return Perquintill::zero();
+    }
+    Perquintill::from_rational(used_space.min(total_space), total_space)
+}
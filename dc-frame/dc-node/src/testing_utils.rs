@@ -24,10 +24,12 @@ pub fn add_onchain_node<T: Config>(peer_id: PeerId, name: &'static str, status:
         staked_number: Zero::zero(),
         reward_number: report_number,
         ip_address: ip_address.clone(),
+        sgx_version_number: 1,
     };
-    Peers::<T>::insert(&peer_id, node_info);
+    let pre_status = Peers::<T>::get(&peer_id).map(|pre_info| pre_info.status);
+    Pallet::<T>::insert_peer(&peer_id, node_info);
     <RequestAccountPeer<T>>::insert(&caller, &peer_id);
-    if status == NODE_STATUS_ONCHAIN {
+    if status == NODE_STATUS_ONCHAIN && pre_status != Some(NODE_STATUS_ONCHAIN) {
         OnchainPeerNumber::<T>::mutate(|n| *n += 1);
     }
     caller
@@ -38,7 +40,10 @@ pub fn user_purchase_storage<T: Config>(name: &'static str) -> T::AccountId {
 }
 
 pub fn user_purchase_storage_index<T: Config>(name: &'static str, index: u32) -> T::AccountId {
-    let for_account: T::AccountId = account(name, index, 0);
+    user_purchase_storage_of::<T>(account(name, index, 0))
+}
+
+pub fn user_purchase_storage_of<T: Config>(for_account: T::AccountId) -> T::AccountId {
     let balance = T::Currency::minimum_balance()*100u32.into() + 40_1000_1000u32.into();
     let _ = T::Currency::make_free_balance_be(&for_account, balance);
 
@@ -69,7 +74,7 @@ pub fn user_purchase_storage_index<T: Config>(name: &'static str, index: u32) ->
         comment_space: 0,
         request_peers: BTreeSet::new(),
     };
-    <WalletAccountStorage<T>>::insert(&for_account, new_user);
+    <Pallet<T>>::insert_user(&for_account, new_user);
     for_account
 }
 
@@ -1,16 +1,30 @@
 use crate::{mock::*, *};
-use frame_support::{assert_noop, assert_ok};
+use frame_support::{assert_noop, assert_ok, traits::{GenesisBuild, Hooks}, unsigned::ValidateUnsigned};
 use frame_benchmarking::{whitelisted_caller, account};
 use testing_utils::*;
-use sp_runtime::SaturatedConversion;
+use sp_runtime::{
+    SaturatedConversion,
+    traits::{Dispatchable, SignedExtension},
+    transaction_validity::{InvalidTransaction, TransactionSource},
+};
+use sp_staking::offence::{DisableStrategy, Offence};
+use sp_core::offchain::{
+    testing::{OffchainState, PendingRequest, PoolState, TestOffchainExt, TestTransactionPoolExt},
+    OffchainDbExt, OffchainWorkerExt, TransactionPoolExt,
+};
+use sp_keystore::{testing::KeyStore, KeystoreExt, SyncCryptoStore};
+use sp_runtime::Perquintill;
 
 #[test]
 fn dc_join_storage_node() {
-	new_test_ext().execute_with(|| {
+	build_and_execute(|| {
 		let peer_id = vec![31; 32];
         let total_space = DcNode::max_storage_node_space();
         let free_space = DcNode::max_storage_node_space();
         let ip_address = vec![33; 256];
+        let sgx_version_number = 1;
+        let program_version = 1;
+        let enclave_id = vec![37; 64];
         let report_number = 10293u32.into();
         let tee_report = vec![38; 512];
         let caller: AccountId = whitelisted_caller();
@@ -22,6 +36,9 @@ fn dc_join_storage_node() {
                 total_space+1, 
                 free_space, 
                 ip_address.clone(), 
+                sgx_version_number, 
+                program_version, 
+                enclave_id.clone(), 
                 report_number, 
                 tee_report.clone()
             ), 
@@ -35,6 +52,9 @@ fn dc_join_storage_node() {
                 total_space, 
                 free_space, 
                 ip_address.clone(), 
+                sgx_version_number, 
+                program_version, 
+                enclave_id.clone(), 
                 report_number, 
                 tee_report.clone()
             )
@@ -50,6 +70,9 @@ fn dc_join_storage_node() {
                 total_space, 
                 free_space, 
                 ip_address.clone(), 
+                sgx_version_number, 
+                program_version, 
+                enclave_id.clone(), 
                 report_number, 
                 tee_report.clone()
             ), 
@@ -64,6 +87,9 @@ fn dc_join_storage_node() {
                 total_space, 
                 free_space, 
                 ip_address.clone(), 
+                sgx_version_number, 
+                program_version, 
+                enclave_id.clone(), 
                 report_number, 
                 tee_report.clone()
             )
@@ -72,7 +98,7 @@ fn dc_join_storage_node() {
         
 		let mut pre_info = <Peers<Test>>::get(&peer_id).unwrap();
         pre_info.status = NODE_STATUS_STAKED;
-        <Peers<Test>>::insert(&peer_id, pre_info);
+        DcNode::insert_peer(&peer_id, pre_info);
         assert_ok!(
             DcNode::join_storage_node(
                 RuntimeOrigin::signed(caller.clone()), 
@@ -80,6 +106,9 @@ fn dc_join_storage_node() {
                 total_space, 
                 free_space, 
                 ip_address.clone(), 
+                sgx_version_number, 
+                program_version, 
+                enclave_id.clone(), 
                 report_number, 
                 tee_report.clone()
             )
@@ -93,11 +122,13 @@ fn dc_join_storage_node() {
 
 #[test]
 fn dc_submit_work_report() {
-	new_test_ext().execute_with(|| {
+	build_and_execute(|| {
 		let peer_id = vec![36; 32];
         let total_space: u64 = 100*1024*1024*1024*1024;
         let free_space: u64 = 100*1024*1024*1024*1024;
         let ip_address = vec![36; 256];
+        let program_version = 1;
+        let enclave_id = vec![37; 64];
         
         let caller = add_onchain_node::<Test>(peer_id.clone(), "submit_work_report", NODE_STATUS_ONCHAIN);		
         let owner = user_purchase_storage::<Test>("owner");
@@ -150,6 +181,8 @@ fn dc_submit_work_report() {
                 ip_address.clone(), 
                 miss_files.clone(), 
                 miss_accounts.clone(),
+                program_version, 
+                enclave_id.clone(), 
                 report_number.saturated_into(), 
                 tee_report.clone()
             ),
@@ -164,6 +197,8 @@ fn dc_submit_work_report() {
                 ip_address.clone(), 
                 miss_files.clone(), 
                 miss_accounts.clone(),
+                program_version, 
+                enclave_id.clone(), 
                 report_number.saturated_into(), 
                 tee_report.clone()
             ),
@@ -183,6 +218,8 @@ fn dc_submit_work_report() {
                 ip_address.clone(), 
                 miss_files.clone(),  
                 miss_accounts.clone(),
+                program_version, 
+                enclave_id.clone(), 
                 99, 
                 tee_report.clone()
             ), 
@@ -196,6 +233,8 @@ fn dc_submit_work_report() {
                 ip_address.clone(), 
                 miss_files.clone(),  
                 miss_accounts.clone(),
+                program_version, 
+                enclave_id.clone(), 
                 301, 
                 tee_report.clone()
             ), 
@@ -214,6 +253,8 @@ fn dc_submit_work_report() {
                 ip_address.clone(), 
                 miss_files.clone(),  
                 miss_accounts.clone(),
+                program_version, 
+                enclave_id.clone(), 
                 150, 
                 tee_report.clone()
             ), 
@@ -228,6 +269,8 @@ fn dc_submit_work_report() {
                 ip_address.clone(), 
                 miss_files.clone(),  
                 miss_accounts.clone(),
+                program_version, 
+                enclave_id.clone(), 
                 150, 
                 tee_report.clone()
             ), 
@@ -243,8 +286,10 @@ fn dc_submit_work_report() {
             staked_number: 0,
             reward_number: 0,
             ip_address: ip_address.clone(),
+            sgx_version_number: 1,
         };
-        Peers::<Test>::insert(&t_peer_id, node_info);
+        DcNode::insert_peer(&t_peer_id, node_info);
+        StashPeers::<Test>::insert(&t_caller, BTreeSet::from([t_peer_id.clone()]));
         assert_noop!(
             DcNode::submit_work_report(
                 RuntimeOrigin::signed(t_caller.clone()), 
@@ -253,6 +298,8 @@ fn dc_submit_work_report() {
                 ip_address.clone(), 
                 miss_files.clone(),  
                 miss_accounts.clone(),
+                program_version, 
+                enclave_id.clone(), 
                 150, 
                 tee_report.clone()
             ), 
@@ -261,9 +308,124 @@ fn dc_submit_work_report() {
 	});
 }
 
+#[test]
+fn dc_program_requirement() {
+	build_and_execute(|| {
+        let peer_id = vec![36; 32];
+        let total_space: u64 = 100*1024*1024*1024*1024;
+        let ip_address = vec![36; 256];
+        let old_enclave = vec![37; 64];
+        let new_enclave = vec![38; 64];
+        let caller = add_onchain_node::<Test>(peer_id.clone(), "program_requirement", NODE_STATUS_ONCHAIN);
+        let report = |version: ProgramVersion, enclave_id: &Vec<u8>| DcNode::submit_work_report(
+            RuntimeOrigin::signed(caller.clone()), 
+            total_space, 
+            total_space, 
+            ip_address.clone(), 
+            Vec::new(), 
+            Vec::new(), 
+            version, 
+            enclave_id.clone(), 
+            System::block_number().saturated_into(), 
+            vec![33; 64]
+        );
+
+        System::set_block_number(5);
+        assert_noop!(
+            DcNode::set_program_requirement(RuntimeOrigin::signed(caller.clone()), 2, new_enclave.clone(), 10, 20),
+            sp_runtime::DispatchError::BadOrigin
+        );
+        // The required enclave is published for nodes to download first
+        assert_noop!(
+            DcNode::set_program_requirement(RuntimeOrigin::root(), 2, new_enclave.clone(), 10, 20),
+            Error::<Test>::ProgramNotPublished
+        );
+        assert_ok!(DcNode::set_dc_program(RuntimeOrigin::root(), b"https://dc".to_vec(), b"https://mirror".to_vec(), new_enclave.clone(), b"2".to_vec()));
+        assert_noop!(
+            DcNode::set_program_requirement(RuntimeOrigin::root(), 2, new_enclave.clone(), 4, 20),
+            Error::<Test>::ParamErr
+        );
+        assert_ok!(DcNode::set_program_requirement(RuntimeOrigin::root(), 2, new_enclave.clone(), 10, 20));
+        System::assert_last_event(RuntimeEvent::DcNode(Event::AnnounceProgramRequirement(2, new_enclave.clone(), 10, 30)));
+
+        // Nodes are not affected before the activation
+        assert_ok!(report(1, &old_enclave));
+        assert_eq!(DcNode::peer_program(&peer_id), Some((1, old_enclave.clone())));
+        assert_eq!(DcNode::rewardable_peers_space().unwrap().1.len(), 1);
+
+        // Nodes are warned during the grace period
+        System::set_block_number(10);
+        assert_ok!(report(1, &old_enclave));
+        System::assert_last_event(RuntimeEvent::DcNode(Event::ProgramUpgradeRequired(peer_id.clone(), 2, 30)));
+        assert_eq!(Peers::<Test>::get(&peer_id).unwrap().status, NODE_STATUS_ONCHAIN);
+        assert_noop!(
+            DcNode::set_program_requirement(RuntimeOrigin::root(), 3, new_enclave.clone(), 20, 20),
+            Error::<Test>::ProgramUpgradeInProgress
+        );
+
+        // Outdated nodes stop earning rewards and fall back to joining after the grace period
+        System::set_block_number(30);
+        assert_eq!(DcNode::rewardable_peers_space().unwrap().1.len(), 0);
+        assert_ok!(report(1, &old_enclave));
+        System::assert_last_event(RuntimeEvent::DcNode(Event::PeerProgramOutdated(peer_id.clone(), 1)));
+        assert_eq!(Peers::<Test>::get(&peer_id).unwrap().status, NODE_STATUS_JOINING);
+        assert_eq!(DcNode::onchain_peer_number(), 0);
+
+        // Outdated nodes can not join again
+        assert_noop!(
+            DcNode::join_storage_node(
+                RuntimeOrigin::signed(caller.clone()), 
+                peer_id.clone(), 
+                total_space, 
+                total_space, 
+                ip_address.clone(), 
+                1, 
+                2, 
+                old_enclave.clone(), 
+                30, 
+                vec![33; 64]
+            ),
+            Error::<Test>::ProgramOutdated
+        );
+        assert_ok!(
+            DcNode::join_storage_node(
+                RuntimeOrigin::signed(caller.clone()), 
+                peer_id.clone(), 
+                total_space, 
+                total_space, 
+                ip_address.clone(), 
+                1, 
+                2, 
+                new_enclave.clone(), 
+                30, 
+                vec![33; 64]
+            )
+        );
+        assert_eq!(DcNode::peer_program(&peer_id), Some((2, new_enclave.clone())));
+
+        // The next announcement puts the expired one in force
+        assert_ok!(DcNode::set_program_requirement(RuntimeOrigin::root(), 3, Vec::new(), 40, 10));
+        assert_eq!(DcNode::required_program().unwrap().min_version, 2);
+        assert_eq!(DcNode::pending_program().unwrap().deadline_number, 50);
+        assert!(DcNode::check_program(2, &new_enclave).unwrap().is_none());
+        assert!(DcNode::check_program(3, &old_enclave).is_err());
+
+        // Outdated nodes are not promoted by the stake of their stash
+        let stash: AccountId = account("program_stash", 0, 0);
+        StashPeers::<Test>::insert(&stash, BTreeSet::from([peer_id.clone()]));
+        PeerPrograms::<Test>::insert(&peer_id, (1, old_enclave.clone()));
+        DcNode::update_peers_of_stash(&stash, DcNode::min_staking_amount() * 2);
+        assert_eq!(Peers::<Test>::get(&peer_id).unwrap().status, NODE_STATUS_JOINING);
+        PeerPrograms::<Test>::insert(&peer_id, (2, new_enclave.clone()));
+        DcNode::update_peers_of_stash(&stash, DcNode::min_staking_amount() * 2);
+        assert_eq!(Peers::<Test>::get(&peer_id).unwrap().status, NODE_STATUS_STAKED);
+        StashPeers::<Test>::remove(&stash);
+	});
+}
+
 #[test]
 fn dc_set_stash_peer() {
-	new_test_ext().execute_with(|| {
+	build_and_execute(|| {
 		let peer_id = vec![36; 32];
         let t_caller: AccountId = account("t_caller", 0, 0);
         let stash: AccountId = account("stash", 0, 0);
@@ -294,7 +456,7 @@ fn dc_set_stash_peer() {
 
 #[test]
 fn dc_remove_stash_peer() {
-	new_test_ext().execute_with(|| {
+	build_and_execute(|| {
 		let peer_id = vec![36; 32];
         let t_caller: AccountId = account("t_caller", 0, 0);
         let stash: AccountId = account("stash", 0, 0);
@@ -332,8 +494,172 @@ fn dc_remove_stash_peer() {
 }
 
 #[test]
-fn dc_purchase_storage() {
+fn dc_pause_calls_by_category() {
+	build_and_execute(|| {
+        use frame_support::traits::Contains;
+
+        let caller: AccountId = account("caller", 0, 0);
+        let _ = Balances::make_free_balance_be(&caller, 1000);
+        assert_ok!(DcNode::set_storage_package(RuntimeOrigin::root(), 1, 100, 100, 1, 100));
+        System::set_block_number(1);
+        let purchase = RuntimeCall::DcNode(crate::Call::purchase_storage { for_account: caller.clone(), package_id: 1 });
+        let login = RuntimeCall::DcNode(crate::Call::user_login {
+            login_account: caller.clone(),
+            app_ids: vec![],
+            block_height: 0,
+            signature: vec![],
+            app_signatures: vec![],
+        });
+
+        assert_noop!(DcNode::set_calls_paused(RuntimeOrigin::signed(caller.clone()), CallCategory::Purchases, true), sp_runtime::DispatchError::BadOrigin);
+        assert_ok!(DcNode::set_calls_paused(RuntimeOrigin::root(), CallCategory::Purchases, true));
+        System::assert_last_event(RuntimeEvent::DcNode(crate::Event::CallsPaused(CallCategory::Purchases)));
+        assert!(!PausedCalls::<Test>::contains(&purchase));
+        assert!(PausedCalls::<Test>::contains(&login));
+        assert_eq!(
+            purchase.clone().dispatch(RuntimeOrigin::signed(caller.clone())).map_err(|e| e.error),
+            Err(frame_system::Error::<Test>::CallFiltered.into())
+        );
+        assert_eq!(DcNode::wallet_account_storage(&caller), None);
+
+        // Comment spaces are paused with the files and peer offers with the node joins
+        assert_eq!(
+            crate::Call::<Test>::close_theme {
+                for_account: caller.clone(),
+                theme_id: vec![],
+                app_id: vec![],
+                block_height: 0,
+                signature: vec![],
+            }.category(),
+            Some(CallCategory::FileOps)
+        );
+        assert_eq!(
+            crate::Call::<Test>::offer_peer_to_stash { stash: caller.clone(), peer_id: vec![], new_stash: caller.clone() }.category(),
+            Some(CallCategory::NodeJoins)
+        );
+
+        // Governance calls are not paused
+        assert!(PausedCalls::<Test>::contains(&RuntimeCall::DcNode(crate::Call::set_calls_paused { category: CallCategory::Purchases, paused: false })));
+        assert_ok!(DcNode::set_calls_paused(RuntimeOrigin::root(), CallCategory::Purchases, false));
+        System::assert_last_event(RuntimeEvent::DcNode(crate::Event::CallsUnpaused(CallCategory::Purchases)));
+        assert_ok!(purchase.dispatch(RuntimeOrigin::signed(caller.clone())));
+        assert!(DcNode::wallet_account_storage(&caller).is_some());
+    });
+}
+
+#[test]
+fn dc_non_root_calls_have_a_category() {
 	new_test_ext().execute_with(|| {
+        use frame_support::{dispatch::GetCallName, traits::UnfilteredDispatchable};
+
+        let root_calls = [
+            "set_app_reward_percent", "set_min_staking", "set_faking_report_number", "set_abnormal_report_number",
+            "set_blocks_of_offchain_to_abnormal", "set_comment_reduce_space", "set_start_reward_block_number",
+            "set_max_storage_node_space", "set_valid_call_block_number", "set_frozen_report_spam_amount",
+            "set_interval_blocks_reduce_spam", "set_frozen_report_comment_amount", "set_interval_blocks_reduce_comment",
+            "set_interval_blocks_can_not_report", "set_interval_blocks_work_report", "set_interval_blocks_login",
+            "set_tee_report_verify_number", "set_enclave_id", "remove_enclave_id", "set_dc_program",
+            "set_storage_package", "remove_storage_package", "register_legacy_theme", "set_nft_collection",
+            "set_nft_account_policy", "reserve_nft_account", "unreserve_nft_account", "set_peer_call_quota",
+            "set_endpoint", "remove_endpoint", "set_program_requirement", "set_offence_slash_fraction",
+            "set_liveness_prober", "remove_liveness_prober", "set_calls_paused", "set_pricing_curve", "revoke_app",
+        ];
+        // Storage nodes and endpoints keep their status while paused
+        let uncategorized = ["submit_work_report", "endpoint_heartbeat"];
+
+        let caller: AccountId = account("caller", 0, 0);
+        let mut call_number = 0;
+        for call_index in 0 ..= u8::MAX {
+            // The arguments are decoded from zeroes
+            let input = [vec![call_index], vec![0; 4096]].concat();
+            let call = match crate::Call::<Test>::decode(&mut &input[..]) {
+                Ok(call) => call,
+                Err(_) => continue,
+            };
+            call_number += 1;
+            let name = call.get_call_name();
+            if root_calls.contains(&name) {
+                assert_eq!(
+                    call.dispatch_bypass_filter(RuntimeOrigin::signed(caller.clone())).map_err(|e| e.error),
+                    Err(sp_runtime::DispatchError::BadOrigin),
+                    "{} is not a root call", name,
+                );
+            } else if uncategorized.contains(&name) {
+                assert_eq!(call.category(), None, "{} is paused", name);
+            } else {
+                assert!(call.category().is_some(), "{} has no category", name);
+            }
+        }
+        assert_eq!(call_number, crate::Call::<Test>::get_call_names().len());
+    });
+}
+
+#[test]
+fn dc_transfer_peer_to_stash() {
+	build_and_execute(|| {
+		let peer_id = vec![36; 32];
+        let controller: AccountId = account("controller", 0, 0);
+        let stash: AccountId = account("stash", 0, 0);
+        let new_stash: AccountId = account("stash", 1, 0);
+        System::set_block_number(1);
+
+        let _ = add_onchain_node::<Test>(peer_id.clone(), "transfer_peer", NODE_STATUS_ONCHAIN);
+        assert_ok!(DcNode::set_stash_peer(RuntimeOrigin::signed(controller.clone()), stash.clone(), peer_id.clone()));
+        let pre_info = <Peers<Test>>::get(&peer_id).unwrap();
+        let onchain_number = DcNode::onchain_peer_number();
+
+        assert_noop!(
+            DcNode::transfer_peer_to_stash(RuntimeOrigin::signed(controller.clone()), new_stash.clone(), peer_id.clone(), 1),
+            Error::<Test>::PeerTransferNotExist
+        );
+        assert_noop!(
+            DcNode::offer_peer_to_stash(RuntimeOrigin::signed(controller.clone()), new_stash.clone(), peer_id.clone(), stash.clone()),
+            Error::<Test>::ParamErr
+        );
+        assert_ok!(DcNode::offer_peer_to_stash(RuntimeOrigin::signed(controller.clone()), stash.clone(), peer_id.clone(), new_stash.clone()));
+        assert_noop!(
+            DcNode::transfer_peer_to_stash(RuntimeOrigin::signed(controller.clone()), account("stash", 2, 0), peer_id.clone(), 1),
+            Error::<Test>::PeerTransferNotExist
+        );
+        assert_noop!(
+            DcNode::transfer_peer_to_stash(RuntimeOrigin::signed(controller.clone()), new_stash.clone(), peer_id.clone(), 0),
+            Error::<Test>::StashPeersNumTooLow
+        );
+
+        // An offer of a peer moved away from the stash is dropped
+        <PendingPeerTransfer<Test>>::insert(&peer_id, (account::<AccountId>("stash", 2, 0), new_stash.clone()));
+        assert_ok!(DcNode::transfer_peer_to_stash(RuntimeOrigin::signed(controller.clone()), new_stash.clone(), peer_id.clone(), 1));
+        assert_eq!(DcNode::pending_peer_transfer(&peer_id), None);
+        assert_eq!(<Peers<Test>>::get(&peer_id).unwrap().stash, stash);
+        assert_ok!(DcNode::offer_peer_to_stash(RuntimeOrigin::signed(controller.clone()), stash.clone(), peer_id.clone(), new_stash.clone()));
+
+        // The peer keeps its status and reward history with enough stake on the new stash
+        assert_ok!(DcNode::transfer_peer_to_stash(RuntimeOrigin::signed(controller.clone()), new_stash.clone(), peer_id.clone(), 1));
+        let cur_info = <Peers<Test>>::get(&peer_id).unwrap();
+        assert_eq!(cur_info.stash, new_stash);
+        assert_eq!(cur_info.status, NODE_STATUS_ONCHAIN);
+        assert_eq!(cur_info.reward_number, pre_info.reward_number);
+        assert_eq!(DcNode::onchain_peer_number(), onchain_number);
+        assert_eq!(<StashPeers<Test>>::get(&stash).unwrap().len(), 0);
+        assert!(<StashPeers<Test>>::get(&new_stash).unwrap().contains(&peer_id));
+        assert_eq!(DcNode::pending_peer_transfer(&peer_id), None);
+        System::assert_last_event(RuntimeEvent::DcNode(crate::Event::TransferPeerToStash(peer_id.clone(), stash.clone(), new_stash.clone())));
+
+        // Without enough stake the peer has to rejoin
+        assert_ok!(DcNode::offer_peer_to_stash(RuntimeOrigin::signed(controller.clone()), new_stash.clone(), peer_id.clone(), stash.clone()));
+        StakingActive::set(DcNode::min_staking_amount() - 1);
+        assert_ok!(DcNode::transfer_peer_to_stash(RuntimeOrigin::signed(controller.clone()), stash.clone(), peer_id.clone(), 1));
+        let cur_info = <Peers<Test>>::get(&peer_id).unwrap();
+        assert_eq!(cur_info.stash, stash);
+        assert_eq!(cur_info.status, NODE_STATUS_JOINING);
+        assert_eq!(DcNode::onchain_peer_number(), onchain_number - 1);
+        assert_eq!(<StashPeers<Test>>::get(&new_stash).unwrap().len(), 0);
+    });
+}
+
+#[test]
+fn dc_purchase_storage() {
+	build_and_execute(|| {
         let caller: AccountId = account("caller", 0, 0);
 
         assert_noop!(
@@ -434,9 +760,75 @@ fn dc_purchase_storage() {
     });
 }
 
+#[test]
+fn dc_dynamic_storage_pricing() {
+	build_and_execute(|| {
+        let caller: AccountId = account("caller", 0, 0);
+        let peer_id = vec![56; 32];
+        let _ = add_onchain_node::<Test>(peer_id.clone(), "pricing", NODE_STATUS_ONCHAIN);
+        let mut node_info = Peers::<Test>::get(&peer_id).unwrap();
+        node_info.total_space = 100;
+        node_info.free_space = 10;
+        DcNode::insert_peer(&peer_id, node_info);
+        // The utilization is read from the space of the onchain peers kept on every change
+        assert_eq!(DcNode::onchain_space(), (100, 10));
+        assert_ok!(DcNode::set_storage_package(RuntimeOrigin::root(), 1, 100, 100, 1, 100));
+        let _ = Balances::make_free_balance_be(&caller, 1000);
+        System::set_block_number(1);
+
+        let curve = PricingCurve {
+            target_utilization: Perquintill::from_percent(50),
+            adjustment_variable: Multiplier::one(),
+            min_multiplier: Multiplier::saturating_from_rational(1, 2),
+            max_multiplier: Multiplier::saturating_from_integer(2),
+        };
+        assert_noop!(DcNode::set_pricing_curve(RuntimeOrigin::signed(caller.clone()), Some(curve.clone())), sp_runtime::DispatchError::BadOrigin);
+        assert_noop!(
+            DcNode::set_pricing_curve(RuntimeOrigin::root(), Some(PricingCurve { min_multiplier: Multiplier::saturating_from_integer(3), ..curve.clone() })),
+            Error::<Test>::ParamErr
+        );
+
+        // Prices are fixed without the curve
+        DcNode::on_initialize(10);
+        assert_eq!(DcNode::price_multiplier(), Multiplier::one());
+        assert_eq!(DcNode::storage_package_price(1), Some(100));
+
+        // The price rises with the utilization above the target
+        assert_ok!(DcNode::set_pricing_curve(RuntimeOrigin::root(), Some(curve.clone())));
+        DcNode::on_initialize(10);
+        assert_eq!(DcNode::price_multiplier(), Multiplier::saturating_from_rational(148, 100));
+        System::assert_last_event(RuntimeEvent::DcNode(crate::Event::PriceMultiplierUpdated(
+            Multiplier::saturating_from_rational(148, 100),
+            Perquintill::from_percent(90),
+        )));
+        assert_eq!(DcNode::storage_package_price(1), Some(148));
+        assert_ok!(DcNode::purchase_storage(RuntimeOrigin::signed(caller.clone()), caller.clone(), 1));
+        assert_eq!(Balances::free_balance(&caller), 1000 - 148);
+        assert_eq!(DcNode::wallet_account_storage(&caller).unwrap().subscribe_price, 148);
+
+        // The multiplier is only adjusted at the interval, and falls with the utilization below the target
+        let mut node_info = Peers::<Test>::get(&peer_id).unwrap();
+        node_info.free_space = 90;
+        DcNode::insert_peer(&peer_id, node_info);
+        DcNode::on_initialize(11);
+        assert_eq!(DcNode::price_multiplier(), Multiplier::saturating_from_rational(148, 100));
+        DcNode::on_initialize(20);
+        assert_eq!(DcNode::price_multiplier(), Multiplier::saturating_from_rational(10064, 10000));
+        for n in 3..10 {
+            DcNode::on_initialize(n * 10);
+        }
+        assert_eq!(DcNode::price_multiplier(), curve.min_multiplier);
+        assert_eq!(DcNode::storage_package_price(1), Some(50));
+
+        assert_ok!(DcNode::set_pricing_curve(RuntimeOrigin::root(), None));
+        assert_eq!(DcNode::price_multiplier(), Multiplier::one());
+        assert_eq!(DcNode::storage_package_price(1), Some(100));
+    });
+}
+
 #[test]
 fn dc_update_db_config() {
-	new_test_ext().execute_with(|| {
+	build_and_execute(|| {
         let peer_id = vec![56; 32];
         let for_account: AccountId = account("for_account", 0, 0);
 		
@@ -512,7 +904,7 @@ fn dc_update_db_config() {
 
 #[test]
 fn dc_create_sub_account() {
-	new_test_ext().execute_with(|| {
+	build_and_execute(|| {
         let peer_id = vec![56; 32];
         let parent_account: AccountId = account("parent_account", 0, 0);
         let sub_account: AccountId = user_purchase_storage::<Test>("sub_account");
@@ -571,7 +963,7 @@ fn dc_create_sub_account() {
 
 #[test]
 fn dc_unbind_sub_account() {
-	new_test_ext().execute_with(|| {
+	build_and_execute(|| {
         let peer_id = vec![56; 32];
         let parent_account: AccountId = account("parent_account", 0, 0);
         let sub_account: AccountId = user_purchase_storage::<Test>("sub_account");
@@ -645,7 +1037,7 @@ fn dc_unbind_sub_account() {
 
 #[test]
 fn dc_add_user_peer() {
-	new_test_ext().execute_with(|| {
+	build_and_execute(|| {
         let peer_id = vec![56; 32];
         let for_account: AccountId = account("for_account", 0, 0);
         let ok_account: AccountId = user_purchase_storage::<Test>("ok_account");
@@ -673,7 +1065,7 @@ fn dc_add_user_peer() {
 
 #[test]
 fn dc_remove_self_user_peer() {
-	new_test_ext().execute_with(|| {
+	build_and_execute(|| {
         let peer_id = vec![56; 32];
         let for_account: AccountId = account("for_account", 0, 0);
         let ok_account: AccountId = user_purchase_storage::<Test>("ok_account");
@@ -708,7 +1100,7 @@ fn dc_remove_self_user_peer() {
 
 #[test]
 fn dc_remove_other_user_peer() {
-	new_test_ext().execute_with(|| {
+	build_and_execute(|| {
         let peer_id = vec![56; 32];
         let peer_remove_id = vec![58; 32];
         let for_account: AccountId = user_purchase_storage::<Test>("for_account");
@@ -723,7 +1115,7 @@ fn dc_remove_other_user_peer() {
             Error::<Test>::PeerIdNotExist
         );
         
-        let _ = add_onchain_node::<Test>(peer_remove_id.clone(), "remove_other_user_peer", NODE_STATUS_ONCHAIN);
+        let _ = add_onchain_node::<Test>(peer_remove_id.clone(), "remove_other_user_peer_r", NODE_STATUS_ONCHAIN);
         assert_noop!(
             DcNode::remove_other_user_peer(
                 RuntimeOrigin::signed(caller.clone()), 
@@ -734,7 +1126,7 @@ fn dc_remove_other_user_peer() {
         );
 
         let peer_ok_id = vec![58; 32];
-        let t_caller = add_onchain_node::<Test>(peer_ok_id.clone(), "remove_other_user_peer", NODE_STATUS_ONCHAIN);
+        let t_caller = add_onchain_node::<Test>(peer_ok_id.clone(), "remove_other_user_peer_r", NODE_STATUS_ONCHAIN);
         assert_ok!(
             DcNode::add_user_peer(
                 RuntimeOrigin::signed(t_caller.clone()), 
@@ -744,9 +1136,10 @@ fn dc_remove_other_user_peer() {
         );
         assert_eq!(DcNode::wallet_account_storage(for_account.clone()).unwrap().peers.contains(&peer_ok_id), true);
 
-        let mut node_info = Peers::<Test>::take(&peer_ok_id).unwrap();
+        let mut node_info = Peers::<Test>::get(&peer_ok_id).unwrap();
         node_info.status = NODE_STATUS_ABNORMAL;
-        Peers::<Test>::insert(&peer_ok_id, node_info);
+        DcNode::insert_peer(&peer_ok_id, node_info);
+        OnchainPeerNumber::<Test>::mutate(|n| *n -= 1);
         assert_ok!(
             DcNode::remove_other_user_peer(
                 RuntimeOrigin::signed(caller.clone()), 
@@ -760,9 +1153,9 @@ fn dc_remove_other_user_peer() {
 
 #[test]
 fn dc_apply_nft_account() {
-	new_test_ext().execute_with(|| {
+	build_and_execute(|| {
         let for_account: AccountId = user_purchase_storage::<Test>("for_account");
-        let nft_account = vec![33; 32];
+        let nft_account = b"dc-nft-account".to_vec();
         let enc_nft_account = vec![32; 32];
         let peer_id = vec![56; 32];
 
@@ -817,10 +1210,10 @@ fn dc_apply_nft_account() {
 
 #[test]
 fn dc_transfer_nft_account() {
-	new_test_ext().execute_with(|| {
+	build_and_execute(|| {
         let from_account: AccountId = user_purchase_storage::<Test>("from_account");
         let to_account: AccountId = account("to_account", 0, 0);
-        let nft_account = vec![33; 32];
+        let nft_account = b"dc-nft-account".to_vec();
         let enc_nft_account = vec![32; 32];
         let peer_id = vec![56; 32];
 
@@ -881,9 +1274,9 @@ fn dc_transfer_nft_account() {
 
 #[test]
 fn dc_update_nft_account() {
-	new_test_ext().execute_with(|| {
+	build_and_execute(|| {
         let for_account: AccountId = user_purchase_storage::<Test>("for_account");
-        let nft_account = vec![33; 32];
+        let nft_account = b"dc-nft-account".to_vec();
         let enc_nft_account = vec![32; 32];
         let peer_id = vec![56; 32];
 
@@ -945,125 +1338,602 @@ fn dc_update_nft_account() {
 }
 
 #[test]
-fn dc_add_file_info() {
-	new_test_ext().execute_with(|| {
-        let owner: AccountId = account("owner", 0, 0);
-
-        let file_id = vec![37; 32]; 
-        let file_size = 1000123; 
-        let file_type = 1;
+fn dc_nft_account_is_nfts_item() {
+	build_and_execute(|| {
+        let for_account: AccountId = user_purchase_storage::<Test>("for_account");
+        let to_account: AccountId = user_purchase_storage::<Test>("to_account");
+        let nft_account = b"dc-nft-account".to_vec();
         let peer_id = vec![56; 32];
+        let caller = add_onchain_node::<Test>(peer_id.clone(), "nft_account_item", NODE_STATUS_ONCHAIN);
+
+        assert_ok!(
+            DcNode::apply_nft_account(
+                RuntimeOrigin::signed(caller.clone()), 
+                nft_account.clone(), 
+                for_account.clone(),
+                vec![32; 32],
+                vec![38; 32],
+                1001u32.into(),
+                vec![33; 1024]
+            )
+        );
+        let item = DcNode::nft_account_item(&nft_account).unwrap();
+        assert_eq!(DcNode::nft_item_account(item), Some(nft_account.clone()));
+        assert_eq!(DcNode::next_nft_item_id(), item + 1);
+        assert_eq!(<Nfts as NftInspect<AccountId>>::owner(&0, &item), Some(for_account.clone()));
+        assert_eq!(
+            <Nfts as NftInspect<AccountId>>::attribute(&0, &item, &frame_support::traits::tokens::AttributeNamespace::Pallet, NFT_ACCOUNT_ATTRIBUTE),
+            Some(nft_account.clone())
+        );
+
+        // Transfer in the nfts pallet without a storage node
+        assert_ok!(Nfts::transfer(RuntimeOrigin::signed(for_account.clone()), 0, item, to_account.clone()));
+        assert_eq!(DcNode::nft_to_wallet_account(&nft_account), Some(to_account.clone()));
+        assert_eq!(DcNode::wallet_account_storage(for_account.clone()).unwrap().enc_nft_account, NftAccount::new());
+        assert_eq!(DcNode::wallet_account_storage(for_account.clone()).unwrap().peers.len(), 0);
 
-        let caller = add_onchain_node::<Test>(peer_id.clone(), "add_file_info", NODE_STATUS_ONCHAIN);
-        
         assert_noop!(
-            DcNode::add_file_info(
+            DcNode::update_nft_account(
                 RuntimeOrigin::signed(caller.clone()), 
-                owner.clone(), 
-                file_id.clone(),
-                file_size,
-                file_type,
+                nft_account.clone(), 
+                for_account.clone(),
+                vec![36; 32],
+                vec![38; 32],
                 1001u32.into(),
                 vec![33; 1024]
             ), 
-            Error::<Test>::AccountNotExist
+            Error::<Test>::NotNftAccountOwner
         );
-        
-        let pt_account: AccountId = user_purchase_storage::<Test>("pt_account");
-        let st_account: AccountId = account("st_account", 0, 0);
-        let _ = DcNode::create_sub_account(RuntimeOrigin::signed(caller.clone()), pt_account.clone(), st_account.clone(), 10u32.into(), vec![33; 1024]);
+        assert_ok!(
+            DcNode::update_nft_account(
+                RuntimeOrigin::signed(caller.clone()), 
+                nft_account.clone(), 
+                to_account.clone(),
+                vec![36; 32],
+                vec![38; 32],
+                1001u32.into(),
+                vec![33; 1024]
+            )
+        );
+        assert_eq!(DcNode::wallet_account_storage(to_account.clone()).unwrap().enc_nft_account, vec![36; 32]);
 
-        assert_eq!(DcNode::wallet_account_storage(pt_account.clone()).unwrap().used_space, 10000);
+        // An approved delegate can move the NFT account too
+        assert_ok!(Nfts::approve_transfer(RuntimeOrigin::signed(to_account.clone()), 0, item, caller.clone(), None));
+        assert_ok!(Nfts::transfer(RuntimeOrigin::signed(caller.clone()), 0, item, for_account.clone()));
+        assert_eq!(DcNode::nft_to_wallet_account(&nft_account), Some(for_account.clone()));
+        assert_eq!(DcNode::wallet_account_storage(to_account.clone()).unwrap().enc_nft_account, NftAccount::new());
+    });
+}
+
+#[test]
+fn dc_burn_nft_account() {
+	build_and_execute(|| {
+        let for_account: AccountId = user_purchase_storage::<Test>("for_account");
+        let nft_account = b"dc-nft-account".to_vec();
+        let peer_id = vec![56; 32];
+        let caller = add_onchain_node::<Test>(peer_id.clone(), "burn_nft_account", NODE_STATUS_ONCHAIN);
 
         assert_ok!(
-            DcNode::add_file_info(
+            DcNode::apply_nft_account(
                 RuntimeOrigin::signed(caller.clone()), 
-                pt_account.clone(), 
-                file_id.clone(),
-                file_size,
-                file_type,
+                nft_account.clone(), 
+                for_account.clone(),
+                vec![32; 32],
+                vec![38; 32],
                 1001u32.into(),
                 vec![33; 1024]
-            ),
+            )
         );
-        
-        assert_eq!(DcNode::wallet_account_storage(pt_account.clone()).unwrap().used_space, 10000+1000123);
+        let item = DcNode::nft_account_item(&nft_account).unwrap();
+        assert_ok!(Nfts::burn(RuntimeOrigin::signed(for_account.clone()), 0, item, None));
+
+        assert_eq!(DcNode::nft_to_wallet_account(&nft_account), None);
+        assert_eq!(DcNode::nft_account_item(&nft_account), None);
+        assert_eq!(DcNode::nft_item_account(item), None);
+        assert_eq!(DcNode::wallet_account_storage(for_account.clone()).unwrap().enc_nft_account, NftAccount::new());
 
+        // The name can be applied again
         assert_ok!(
-            DcNode::add_file_info(
+            DcNode::apply_nft_account(
                 RuntimeOrigin::signed(caller.clone()), 
-                st_account.clone(), 
-                file_id.clone(),
-                file_size,
-                file_type,
+                nft_account.clone(), 
+                for_account.clone(),
+                vec![32; 32],
+                vec![38; 32],
                 1001u32.into(),
                 vec![33; 1024]
-            ),
+            )
         );
-
-        assert_eq!(DcNode::wallet_account_storage(pt_account.clone()).unwrap().used_space, 10000+1000123*2);
-        assert_eq!(DcNode::wallet_account_storage(st_account.clone()).unwrap().used_space, 1000123);
-        assert_eq!(DcNode::files(&file_id).unwrap().users.len(), 2);
-        assert_eq!(DcNode::files(&file_id).unwrap().peers.len(), 1);
+        assert_eq!(DcNode::nft_account_item(&nft_account), Some(item + 1));
     });
 }
 
 #[test]
-fn dc_add_file_peer() {
-	new_test_ext().execute_with(|| {
-        let owner: AccountId = user_purchase_storage::<Test>("owner");
-
-        let file_id = vec![37; 32]; 
-        let file_size = 1000123; 
-        let file_type = 1;
+fn dc_apply_nft_account_without_collection() {
+	build_and_execute(|| {
+        let for_account: AccountId = user_purchase_storage::<Test>("for_account");
         let peer_id = vec![56; 32];
+        let caller = add_onchain_node::<Test>(peer_id.clone(), "nft_without_collection", NODE_STATUS_ONCHAIN);
 
-        let caller = add_onchain_node::<Test>(peer_id.clone(), "add_file_peer", NODE_STATUS_ONCHAIN);
-        
+        NftCollection::<Test>::kill();
         assert_noop!(
-            DcNode::add_file_peer(
-                RuntimeOrigin::signed(caller.clone()), 
-                file_id.clone(),
-                1001u32.into()
-            ), 
-            Error::<Test>::FileNotExist
-        );
-        
-        assert_ok!(
-            DcNode::add_file_info(
+            DcNode::apply_nft_account(
                 RuntimeOrigin::signed(caller.clone()), 
-                owner.clone(), 
-                file_id.clone(),
-                file_size,
-                2,
+                b"dc-nft-account".to_vec(), 
+                for_account.clone(),
+                vec![32; 32],
+                vec![38; 32],
                 1001u32.into(),
                 vec![33; 1024]
             ),
+            Error::<Test>::NftCollectionNotSet
+        );
+    });
+}
+
+#[test]
+fn dc_nft_account_name_policy() {
+	build_and_execute(|| {
+        let for_account: AccountId = user_purchase_storage::<Test>("for_account");
+        let peer_id = vec![56; 32];
+        let caller = add_onchain_node::<Test>(peer_id.clone(), "nft_account_name_policy", NODE_STATUS_ONCHAIN);
+        let apply = |nft_account: &[u8]| DcNode::apply_nft_account(
+            RuntimeOrigin::signed(caller.clone()), 
+            nft_account.to_vec(), 
+            for_account.clone(),
+            vec![32; 32],
+            vec![38; 32],
+            1001u32.into(),
+            vec![33; 1024]
         );
 
         assert_noop!(
-            DcNode::add_file_peer(
-                RuntimeOrigin::signed(caller.clone()), 
-                file_id.clone(),
-                1001u32.into()
-            ), 
-            Error::<Test>::FileTypeError
+            DcNode::set_nft_account_policy(RuntimeOrigin::root(), NftAccountPolicy { min_length: 0, ..Default::default() }),
+            Error::<Test>::ParamErr
         );
+        assert_ok!(DcNode::set_nft_account_policy(RuntimeOrigin::root(), NftAccountPolicy { min_length: 3, max_length: 16, ..Default::default() }));
+        for invalid in [&b"ab"[..], b"abcdefghijklmnopq", b"-abc", b"abc-", b"a.bc", b"a bc", b"\xe4\xbd\xa0abc"] {
+            assert_noop!(apply(invalid), Error::<Test>::NftAccountInvalid);
+        }
 
-        let ok_id = vec![55; 32];
+        assert_ok!(DcNode::reserve_nft_account(RuntimeOrigin::root(), b"Admin".to_vec()));
+        assert_noop!(apply(b"admin"), Error::<Test>::NftAccountReserved);
+        assert_ok!(DcNode::unreserve_nft_account(RuntimeOrigin::root(), b"admin".to_vec()));
 
+        // The NFT account is normalised to lowercase
+        assert_ok!(apply(b"Dc-Admin"));
+        assert_eq!(DcNode::nft_to_wallet_account(b"dc-admin".to_vec()), Some(for_account.clone()));
+        assert_eq!(DcNode::nft_to_wallet_account(b"Dc-Admin".to_vec()), None);
+        assert_noop!(apply(b"DC-ADMIN"), Error::<Test>::NftAccoutApplied);
+        // No registration period by default
+        assert_eq!(DcNode::nft_account_expiry(b"dc-admin".to_vec()), None);
+
+        // The NFT account registered before the name policy is still found
+        let legacy = b"Dc_Legacy".to_vec();
+        assert_ok!(DcNode::mint_nft_account(&legacy, &for_account));
         assert_ok!(
-            DcNode::add_file_info(
+            DcNode::update_nft_account(
                 RuntimeOrigin::signed(caller.clone()), 
-                owner.clone(), 
-                ok_id.clone(),
-                file_size,
-                file_type,
+                legacy.clone(), 
+                for_account.clone(),
+                vec![36; 32],
+                vec![38; 32],
                 1001u32.into(),
                 vec![33; 1024]
-            ),
+            )
         );
-        assert_eq!(DcNode::files(&ok_id).unwrap().peers.len(), 1);
+        assert_eq!(DcNode::wallet_account_storage(for_account.clone()).unwrap().enc_nft_account, vec![36; 32]);
+    });
+}
+
+#[test]
+fn dc_nft_account_fee_and_expiry() {
+	build_and_execute(|| {
+        let for_account: AccountId = user_purchase_storage::<Test>("for_account");
+        let other_account: AccountId = user_purchase_storage::<Test>("other_account");
+        let nft_account = b"abc".to_vec();
+        let peer_id = vec![56; 32];
+        let caller = add_onchain_node::<Test>(peer_id.clone(), "nft_account_fee_and_expiry", NODE_STATUS_ONCHAIN);
+        let apply = |for_account: &AccountId| DcNode::apply_nft_account(
+            RuntimeOrigin::signed(caller.clone()), 
+            nft_account.clone(), 
+            for_account.clone(),
+            vec![32; 32],
+            vec![38; 32],
+            1001u32.into(),
+            vec![33; 1024]
+        );
+
+        assert_ok!(DcNode::set_nft_account_policy(RuntimeOrigin::root(), NftAccountPolicy { 
+            min_length: 1, 
+            max_length: 16, 
+            length_prices: vec![(8, 100), (3, 500)],
+            base_price: 10,
+            registration_period: 50,
+            grace_period: 10,
+        }));
+
+        let balance = Balances::total_balance(&for_account);
+        let reward_total = DcNode::app_reward_total() + DcNode::storage_reward_total();
+        assert_ok!(apply(&for_account));
+        assert_eq!(Balances::total_balance(&for_account), balance - 500);
+        assert_eq!(DcNode::app_reward_total() + DcNode::storage_reward_total(), reward_total + 500);
+        let expiry = System::block_number() + 50;
+        assert_eq!(DcNode::nft_account_expiry(&nft_account), Some(expiry));
+        assert_noop!(
+            DcNode::release_nft_account(RuntimeOrigin::signed(other_account.clone()), nft_account.clone()),
+            Error::<Test>::NftAccountNotExpired
+        );
+
+        // Anyone can pay for the renewal
+        let balance = Balances::total_balance(&other_account);
+        assert_ok!(DcNode::renew_nft_account(RuntimeOrigin::signed(other_account.clone()), nft_account.clone()));
+        assert_eq!(Balances::total_balance(&other_account), balance - 500);
+        assert_eq!(DcNode::nft_account_expiry(&nft_account), Some(expiry + 50));
+
+        // The expired NFT account can not be transferred, released or applied in the grace period
+        System::set_block_number(expiry + 50);
+        let item = DcNode::nft_account_item(&nft_account).unwrap();
+        assert_noop!(
+            Nfts::transfer(RuntimeOrigin::signed(for_account.clone()), 0, item, other_account.clone()),
+            pallet_nfts::Error::<Test>::ItemLocked
+        );
+        assert_noop!(
+            DcNode::release_nft_account(RuntimeOrigin::signed(other_account.clone()), nft_account.clone()),
+            Error::<Test>::NftAccountNotExpired
+        );
+        assert_noop!(apply(&other_account), Error::<Test>::NftAccoutApplied);
+        // But it can be renewed
+        assert_ok!(DcNode::renew_nft_account(RuntimeOrigin::signed(for_account.clone()), nft_account.clone()));
+        assert_eq!(DcNode::nft_account_expiry(&nft_account), Some(expiry + 100));
+
+        // The lapsed NFT account can not be renewed after the grace period
+        System::set_block_number(expiry + 110);
+        assert_noop!(
+            DcNode::renew_nft_account(RuntimeOrigin::signed(for_account.clone()), nft_account.clone()),
+            Error::<Test>::NftAccountExpired
+        );
+
+        // Applying the lapsed NFT account releases it from the previous owner
+        assert_ok!(apply(&other_account));
+        assert_eq!(DcNode::nft_to_wallet_account(&nft_account), Some(other_account.clone()));
+        assert_eq!(DcNode::nft_item_account(item), None);
+        assert_eq!(DcNode::wallet_account_storage(for_account.clone()).unwrap().enc_nft_account, NftAccount::new());
+        assert_eq!(DcNode::nft_account_expiry(&nft_account), Some(expiry + 160));
+
+        // Nothing is charged for a renewal of zero blocks
+        assert_ok!(DcNode::set_nft_account_policy(RuntimeOrigin::root(), NftAccountPolicy { 
+            base_price: 10,
+            grace_period: 10,
+            ..Default::default()
+        }));
+        assert_noop!(
+            DcNode::renew_nft_account(RuntimeOrigin::signed(other_account.clone()), nft_account.clone()),
+            Error::<Test>::NftAccountNotRenewable
+        );
+
+        System::set_block_number(expiry + 170);
+        assert_ok!(DcNode::release_nft_account(RuntimeOrigin::signed(for_account.clone()), b"ABC".to_vec()));
+        assert_eq!(DcNode::nft_to_wallet_account(&nft_account), None);
+        assert_eq!(DcNode::nft_account_expiry(&nft_account), None);
+        assert_eq!(DcNode::wallet_account_storage(other_account.clone()).unwrap().enc_nft_account, NftAccount::new());
+    });
+}
+
+#[test]
+fn dc_check_peer_quota() {
+	build_and_execute(|| {
+        let peer_id = vec![56; 32];
+        let caller = add_onchain_node::<Test>(peer_id.clone(), "check_peer_quota", NODE_STATUS_ONCHAIN);
+        let other: AccountId = account("other", 0, 0);
+        let info = Default::default();
+        let call = RuntimeCall::DcNode(Call::remove_other_user_peer { peer_id: peer_id.clone(), for_account: other.clone() });
+
+        assert_ok!(DcNode::set_peer_call_quota(RuntimeOrigin::root(), 2));
+        // The calls of accounts that are not request accounts are rejected
+        assert_noop!(CheckPeerQuota::<Test>::new().validate(&other, &call, &info, 0), InvalidTransaction::BadSigner);
+        assert_noop!(CheckPeerQuota::<Test>::new().pre_dispatch(&other, &call, &info, 0), InvalidTransaction::BadSigner);
+        assert_eq!(CheckPeerQuota::<Test>::new().validate(&caller, &call, &info, 0).unwrap().priority, 2);
+
+        // The counting is charged to the block
+        let block_weight = System::block_weight().total();
+        assert_ok!(CheckPeerQuota::<Test>::new().pre_dispatch(&caller, &call, &info, 0));
+        assert_eq!(System::block_weight().total(), block_weight + CheckPeerQuota::<Test>::weight());
+        assert_eq!(CheckPeerQuota::<Test>::new().validate(&caller, &call, &info, 0).unwrap().priority, 1);
+        assert_ok!(CheckPeerQuota::<Test>::new().pre_dispatch(&caller, &call, &info, 0));
+        assert_noop!(CheckPeerQuota::<Test>::new().validate(&caller, &call, &info, 0), InvalidTransaction::ExhaustsResources);
+        assert_noop!(CheckPeerQuota::<Test>::new().pre_dispatch(&caller, &call, &info, 0), InvalidTransaction::ExhaustsResources);
+
+        // The pool keeps rejecting the calls in the next blocks of the window
+        System::set_block_number(System::block_number() + 1);
+        assert_noop!(CheckPeerQuota::<Test>::new().validate(&caller, &call, &info, 0), InvalidTransaction::ExhaustsResources);
+
+        // The quota is reset in the next window
+        System::set_block_number(System::block_number() + PeerCallWindow::get());
+        let valid = CheckPeerQuota::<Test>::new().validate(&caller, &call, &info, 0).unwrap();
+        assert_eq!(valid.priority, 2);
+        assert_eq!(valid.longevity, PeerCallWindow::get() - System::block_number() % PeerCallWindow::get());
+        assert_ok!(CheckPeerQuota::<Test>::new().pre_dispatch(&caller, &call, &info, 0));
+
+        // The calls of peers that are not ONCHAIN are rejected
+        let joining_peer_id = vec![57; 32];
+        let joining = add_onchain_node::<Test>(joining_peer_id.clone(), "check_peer_quota_joining", NODE_STATUS_JOINING);
+        assert_noop!(CheckPeerQuota::<Test>::new().validate(&joining, &call, &info, 0), InvalidTransaction::BadSigner);
+        assert_noop!(CheckPeerQuota::<Test>::new().pre_dispatch(&joining, &call, &info, 0), InvalidTransaction::BadSigner);
+
+        // Other calls are not limited
+        let call = RuntimeCall::DcNode(Call::purchase_storage { for_account: other.clone(), package_id: 1 });
+        assert_ok!(CheckPeerQuota::<Test>::new().validate(&other, &call, &info, 0));
+        assert_ok!(CheckPeerQuota::<Test>::new().pre_dispatch(&other, &call, &info, 0));
+    });
+}
+
+#[test]
+fn dc_endpoints() {
+	build_and_execute(|| {
+        let operator: AccountId = account("operator", 0, 0);
+        let endpoint = |address: &[u8], region: &[u8], api_version: u32, priority: u32| NodeEndpoint {
+            address: address.to_vec(),
+            signature: vec![33; 64],
+            region: region.to_vec(),
+            api_version: api_version,
+            priority: priority,
+            operator: Some(operator.clone()),
+            last_seen: 0,
+        };
+
+        assert_ok!(DcNode::set_endpoint(RuntimeOrigin::root(), EndpointKind::Proxy, endpoint(b"/dns4/a/tcp/9944", b"eu", 2, 1)));
+        assert_ok!(DcNode::set_endpoint(RuntimeOrigin::root(), EndpointKind::Proxy, endpoint(b"/dns4/b/tcp/9944", b"eu", 1, 5)));
+        assert_ok!(DcNode::set_endpoint(RuntimeOrigin::root(), EndpointKind::Proxy, endpoint(b"/dns4/c/tcp/9944", b"us", 2, 1)));
+        assert_ok!(DcNode::set_endpoint(RuntimeOrigin::root(), EndpointKind::TrustedStorage, endpoint(b"/dns4/d/tcp/4001", b"eu", 2, 1)));
+        assert_eq!(DcNode::endpoint(EndpointKind::Proxy, b"/dns4/a/tcp/9944".to_vec()).unwrap().last_seen, System::block_number());
+
+        // Only the operator can heartbeat
+        System::set_block_number(System::block_number() + 10);
+        assert_noop!(
+            DcNode::endpoint_heartbeat(RuntimeOrigin::signed(account("other", 0, 0)), EndpointKind::Proxy, b"/dns4/c/tcp/9944".to_vec(), 3),
+            Error::<Test>::NotEndpointOperator
+        );
+        assert_noop!(
+            DcNode::endpoint_heartbeat(RuntimeOrigin::signed(operator.clone()), EndpointKind::TrustedStorage, b"/dns4/c/tcp/9944".to_vec(), 3),
+            Error::<Test>::EndpointNotExist
+        );
+        assert_ok!(DcNode::endpoint_heartbeat(RuntimeOrigin::signed(operator.clone()), EndpointKind::Proxy, b"/dns4/c/tcp/9944".to_vec(), 3));
+
+        let addresses = |endpoints: Vec<NodeEndpoint<AccountId, u64>>| endpoints.into_iter().map(|e| e.address).collect::<Vec<_>>();
+        assert_eq!(
+            addresses(DcNode::endpoints(EndpointKind::Proxy, None, 0)), 
+            vec![b"/dns4/b/tcp/9944".to_vec(), b"/dns4/c/tcp/9944".to_vec(), b"/dns4/a/tcp/9944".to_vec()]
+        );
+        assert_eq!(
+            addresses(DcNode::endpoints(EndpointKind::Proxy, Some(b"eu".to_vec()), 2)), 
+            vec![b"/dns4/a/tcp/9944".to_vec()]
+        );
+
+        assert_ok!(DcNode::remove_endpoint(RuntimeOrigin::root(), EndpointKind::Proxy, b"/dns4/b/tcp/9944".to_vec()));
+        assert_noop!(
+            DcNode::remove_endpoint(RuntimeOrigin::root(), EndpointKind::Proxy, b"/dns4/b/tcp/9944".to_vec()),
+            Error::<Test>::EndpointNotExist
+        );
+        assert_eq!(DcNode::endpoints(EndpointKind::Proxy, None, 0).len(), 2);
+    });
+}
+
+#[test]
+fn dc_migrate_endpoints_to_v1() {
+	build_and_execute(|| {
+        use frame_support::traits::{GetStorageVersion, OnRuntimeUpgrade, StorageVersion};
+
+        StorageVersion::new(0).put::<DcNode>();
+        let mut nodes = BTreeSet::new();
+        nodes.insert((b"https://proxy".to_vec(), vec![33; 64]));
+        migrations::v1::ProxyNodes::<Test>::put(nodes.clone());
+        nodes.insert((b"https://storage".to_vec(), vec![34; 64]));
+        migrations::v1::TrustedStorageNodes::<Test>::put(nodes);
+
+        migrations::v1::MigrateToV1::<Test>::on_runtime_upgrade();
+
+        assert_eq!(DcNode::on_chain_storage_version(), 1);
+        assert_eq!(migrations::v1::ProxyNodes::<Test>::get(), None);
+        assert_eq!(migrations::v1::TrustedStorageNodes::<Test>::get(), None);
+        assert_eq!(DcNode::endpoint(EndpointKind::Proxy, b"https://proxy".to_vec()).unwrap().signature, vec![33; 64]);
+        assert_eq!(DcNode::endpoints(EndpointKind::TrustedStorage, None, 0).len(), 2);
+    });
+}
+
+#[test]
+fn dc_migrate_nft_accounts_to_v3() {
+	build_and_execute(|| {
+        use frame_support::traits::{GetStorageVersion, OnRuntimeUpgrade, StorageVersion};
+
+        let owner: AccountId = user_purchase_storage::<Test>("owner");
+        let to_account: AccountId = user_purchase_storage::<Test>("to_account");
+        let nft_account = b"dc-legacy-account".to_vec();
+        StorageVersion::new(2).put::<DcNode>();
+        <NftToWalletAccount<Test>>::insert(&nft_account, &owner);
+
+        // The items are minted once the collection is set
+        <NftCollection<Test>>::kill();
+        migrations::v3::MigrateToV3::<Test>::on_runtime_upgrade();
+        assert_eq!(DcNode::on_chain_storage_version(), 2);
+        assert_eq!(DcNode::nft_account_item(&nft_account), None);
+
+        // The storage version is kept until every item is minted
+        <NftCollection<Test>>::put(1);
+        migrations::v3::MigrateToV3::<Test>::on_runtime_upgrade();
+        assert_eq!(DcNode::on_chain_storage_version(), 2);
+        assert_eq!(DcNode::nft_account_item(&nft_account), None);
+
+        assert_ok!(DcNode::set_nft_collection(RuntimeOrigin::root(), 0));
+        migrations::v3::MigrateToV3::<Test>::on_runtime_upgrade();
+        assert_eq!(DcNode::on_chain_storage_version(), 3);
+        let item = DcNode::nft_account_item(&nft_account).unwrap();
+        assert_eq!(<Nfts as NftInspect<AccountId>>::owner(&0, &item), Some(owner.clone()));
+        assert_ok!(DcNode::do_try_state());
+
+        // The collection is kept once the items are minted in it
+        assert_noop!(DcNode::set_nft_collection(RuntimeOrigin::root(), 1), Error::<Test>::NftCollectionInUse);
+
+        // The legacy NFT account can be transferred
+        assert_ok!(Nfts::transfer(RuntimeOrigin::signed(owner.clone()), 0, item, to_account.clone()));
+        assert_eq!(DcNode::nft_to_wallet_account(&nft_account), Some(to_account));
+    });
+}
+
+#[test]
+fn dc_migrate_peer_totals_to_v4() {
+	build_and_execute(|| {
+        use frame_support::traits::{GetStorageVersion, OnRuntimeUpgrade, StorageVersion};
+
+        let _ = add_onchain_node::<Test>(vec![56; 32], "onchain", NODE_STATUS_ONCHAIN);
+        let _ = add_onchain_node::<Test>(vec![57; 32], "joining", NODE_STATUS_JOINING);
+        let totals = (DcNode::onchain_space(), DcNode::peers_of_status(NODE_STATUS_ONCHAIN), DcNode::peers_of_status(NODE_STATUS_JOINING));
+        let _ = user_purchase_storage::<Test>("migrated_user");
+        let stats = DcNode::network_stats();
+        let _ = PeersOfStatus::<Test>::clear(u32::MAX, None);
+        OnchainSpace::<Test>::kill();
+        ActiveUsers::<Test>::kill();
+        let _ = UsersExpiringAt::<Test>::clear(u32::MAX, None);
+        FileTotals::<Test>::kill();
+        let _ = ReportNumberInEra::<Test>::clear(u32::MAX, None);
+
+        StorageVersion::new(2).put::<DcNode>();
+        migrations::v4::MigrateToV4::<Test>::on_runtime_upgrade();
+        assert_eq!(DcNode::on_chain_storage_version(), 2);
+        assert_eq!(DcNode::peers_of_status(NODE_STATUS_ONCHAIN), 0);
+
+        StorageVersion::new(3).put::<DcNode>();
+        migrations::v4::MigrateToV4::<Test>::on_runtime_upgrade();
+        assert_eq!(DcNode::on_chain_storage_version(), 4);
+        assert_eq!((DcNode::onchain_space(), DcNode::peers_of_status(NODE_STATUS_ONCHAIN), DcNode::peers_of_status(NODE_STATUS_JOINING)), totals);
+        assert_eq!(totals.1, 1);
+        assert_eq!(DcNode::network_stats(), stats);
+        assert_eq!(stats.active_users, 1);
+        assert_ok!(DcNode::do_try_state());
+    });
+}
+
+#[test]
+fn dc_add_file_info() {
+	build_and_execute(|| {
+        let owner: AccountId = account("owner", 0, 0);
+
+        let file_id = vec![37; 32]; 
+        let file_size = 1000123; 
+        let file_type = 1;
+        let peer_id = vec![56; 32];
+
+        let caller = add_onchain_node::<Test>(peer_id.clone(), "add_file_info", NODE_STATUS_ONCHAIN);
+        
+        assert_noop!(
+            DcNode::add_file_info(
+                RuntimeOrigin::signed(caller.clone()), 
+                owner.clone(), 
+                file_id.clone(),
+                file_size,
+                file_type,
+                1001u32.into(),
+                vec![33; 1024]
+            ), 
+            Error::<Test>::AccountNotExist
+        );
+        
+        let pt_account: AccountId = user_purchase_storage::<Test>("pt_account");
+        let st_account: AccountId = account("st_account", 0, 0);
+        let _ = DcNode::create_sub_account(RuntimeOrigin::signed(caller.clone()), pt_account.clone(), st_account.clone(), 10u32.into(), vec![33; 1024]);
+
+        assert_eq!(DcNode::wallet_account_storage(pt_account.clone()).unwrap().used_space, 10000);
+
+        assert_ok!(
+            DcNode::add_file_info(
+                RuntimeOrigin::signed(caller.clone()), 
+                pt_account.clone(), 
+                file_id.clone(),
+                file_size,
+                file_type,
+                1001u32.into(),
+                vec![33; 1024]
+            ),
+        );
+        
+        assert_eq!(DcNode::wallet_account_storage(pt_account.clone()).unwrap().used_space, 10000+1000123);
+
+        assert_ok!(
+            DcNode::add_file_info(
+                RuntimeOrigin::signed(caller.clone()), 
+                st_account.clone(), 
+                file_id.clone(),
+                file_size,
+                file_type,
+                1001u32.into(),
+                vec![33; 1024]
+            ),
+        );
+
+        assert_eq!(DcNode::wallet_account_storage(pt_account.clone()).unwrap().used_space, 10000+1000123*2);
+        assert_eq!(DcNode::wallet_account_storage(st_account.clone()).unwrap().used_space, 1000123);
+        assert_eq!(DcNode::files(&file_id).unwrap().users.len(), 2);
+        assert_eq!(DcNode::files(&file_id).unwrap().peers.len(), 1);
+    });
+}
+
+#[test]
+fn dc_add_file_peer() {
+	build_and_execute(|| {
+        let owner: AccountId = user_purchase_storage::<Test>("owner");
+
+        let file_id = vec![37; 32]; 
+        let file_size = 1000123; 
+        let file_type = 1;
+        let peer_id = vec![56; 32];
+
+        let caller = add_onchain_node::<Test>(peer_id.clone(), "add_file_peer", NODE_STATUS_ONCHAIN);
+        
+        assert_noop!(
+            DcNode::add_file_peer(
+                RuntimeOrigin::signed(caller.clone()), 
+                file_id.clone(),
+                1001u32.into()
+            ), 
+            Error::<Test>::FileNotExist
+        );
+        
+        assert_ok!(
+            DcNode::add_file_info(
+                RuntimeOrigin::signed(caller.clone()), 
+                owner.clone(), 
+                file_id.clone(),
+                file_size,
+                2,
+                1001u32.into(),
+                vec![33; 1024]
+            ),
+        );
+
+        // Thread db file can be stored on other peers too
+        assert_ok!(
+            DcNode::add_file_peer(
+                RuntimeOrigin::signed(add_onchain_node::<Test>(vec![57; 32], "thread_db_peer", NODE_STATUS_ONCHAIN)), 
+                file_id.clone(),
+                1001u32.into()
+            ),
+        );
+        assert_eq!(DcNode::files(&file_id).unwrap().peers.len(), 2);
+
+        let ok_id = vec![55; 32];
+
+        assert_ok!(
+            DcNode::add_file_info(
+                RuntimeOrigin::signed(caller.clone()), 
+                owner.clone(), 
+                ok_id.clone(),
+                file_size,
+                file_type,
+                1001u32.into(),
+                vec![33; 1024]
+            ),
+        );
+        assert_eq!(DcNode::files(&ok_id).unwrap().peers.len(), 1);
         assert_ok!(
             DcNode::add_file_peer(
                 RuntimeOrigin::signed(caller.clone()), 
@@ -1087,7 +1957,7 @@ fn dc_add_file_peer() {
 
 #[test]
 fn dc_remove_self_file_peer() {
-	new_test_ext().execute_with(|| {
+	build_and_execute(|| {
         let owner: AccountId = user_purchase_storage::<Test>("owner");
 
         let file_id = vec![37; 32]; 
@@ -1154,7 +2024,7 @@ fn dc_remove_self_file_peer() {
 
 #[test]
 fn dc_delete_file_info() {
-	new_test_ext().execute_with(|| {
+	build_and_execute(|| {
         let owner: AccountId = user_purchase_storage::<Test>("owner");
 
         let file_id = vec![37; 32]; 
@@ -1207,7 +2077,7 @@ fn dc_delete_file_info() {
 
 #[test]
 fn dc_add_log_to_thread_db() {
-	new_test_ext().execute_with(|| {
+	build_and_execute(|| {
         let owner: AccountId = user_purchase_storage::<Test>("owner");
 
         let file_id = vec![37; 32]; 
@@ -1257,7 +2127,7 @@ fn dc_add_log_to_thread_db() {
 
 #[test]
 fn dc_add_space_to_thread_db() {
-	new_test_ext().execute_with(|| {
+	build_and_execute(|| {
         let owner: AccountId = user_purchase_storage::<Test>("owner");
 
         let file_id = vec![37; 32]; 
@@ -1305,9 +2175,121 @@ fn dc_add_space_to_thread_db() {
     });
 }
 
+#[test]
+fn dc_thread_db_log() {
+	build_and_execute(|| {
+        let owner: AccountId = user_purchase_storage::<Test>("owner");
+        let device: AccountId = account("device", 0, 0);
+        let file_id = vec![37; 32]; 
+        let log_id = vec![33; 32];
+        let peer_id = vec![56; 32];
+        let other_peer_id = vec![57; 32];
+        let caller = add_onchain_node::<Test>(peer_id.clone(), "thread_db_log", NODE_STATUS_ONCHAIN);
+        let other_caller = add_onchain_node::<Test>(other_peer_id.clone(), "other_thread_db_log", NODE_STATUS_ONCHAIN);
+        let update = |owner: &AccountId, head: DcString, log_size: SpaceSize| DcNode::update_thread_db_log(
+            RuntimeOrigin::signed(caller.clone()), 
+            file_id.clone(),
+            log_id.clone(),
+            owner.clone(),
+            head,
+            log_size,
+            1001u32.into(),
+            vec![33; 1024]
+        );
+
+        assert_noop!(update(&owner, vec![1; 32], 100), Error::<Test>::FileNotExist);
+        assert_ok!(DcNode::add_file_info(RuntimeOrigin::signed(caller.clone()), owner.clone(), file_id.clone(), 1000, 2, 1001u32.into(), vec![33; 1024]));
+        // The device of the owner writes the log
+        assert_noop!(update(&device, vec![1; 32], 100), Error::<Test>::AccountNotExist);
+        assert_ok!(DcNode::create_sub_account(RuntimeOrigin::signed(caller.clone()), owner.clone(), device.clone(), 1001u32.into(), vec![33; 1024]));
+        assert_ok!(update(&device, vec![1; 32], 100));
+        assert_noop!(update(&owner, vec![2; 32], 100), Error::<Test>::NotThreadDbLogOwner);
+
+        let log = DcNode::thread_db_logs(&file_id, &log_id).unwrap();
+        assert_eq!(log.owner, device);
+        assert_eq!(log.head, vec![1; 32]);
+        assert_eq!(log.size, 100);
+        assert_eq!(DcNode::files(&file_id).unwrap().file_size, 1100);
+        assert_eq!(DcNode::files(&file_id).unwrap().db_log.contains(&log_id), true);
+        assert_eq!(DcNode::wallet_account_storage(owner.clone()).unwrap().used_space, 10000+1100);
+
+        // The size change is charged and the previous heads are kept
+        assert_ok!(update(&device, vec![2; 32], 300));
+        assert_ok!(update(&device, vec![3; 32], 250));
+        let log = DcNode::thread_db_logs(&file_id, &log_id).unwrap();
+        assert_eq!(log.prev_heads, vec![vec![1; 32], vec![2; 32]]);
+        assert_eq!(DcNode::files(&file_id).unwrap().file_size, 1250);
+        assert_eq!(DcNode::wallet_account_storage(owner.clone()).unwrap().used_space, 10000+1250);
+
+        assert_ok!(DcNode::compact_thread_db_log(RuntimeOrigin::signed(caller.clone()), file_id.clone(), log_id.clone(), 1, 1001u32.into(), vec![33; 1024]));
+        assert_eq!(DcNode::thread_db_logs(&file_id, &log_id).unwrap().prev_heads, vec![vec![2; 32]]);
+
+        // Peers replicate the log
+        assert_noop!(
+            DcNode::add_thread_db_log_peer(RuntimeOrigin::signed(other_caller.clone()), file_id.clone(), vec![34; 32], 1001u32.into()),
+            Error::<Test>::ThreadDbLogNotExist
+        );
+        assert_ok!(DcNode::add_thread_db_log_peer(RuntimeOrigin::signed(other_caller.clone()), file_id.clone(), log_id.clone(), 1001u32.into()));
+        assert_eq!(DcNode::thread_db_logs(&file_id, &log_id).unwrap().peers.contains(&other_peer_id), true);
+        assert_eq!(DcNode::files(&file_id).unwrap().peers.contains(&other_peer_id), true);
+
+        assert_ok!(DcNode::remove_self_file_peer(RuntimeOrigin::signed(other_caller.clone()), file_id.clone(), 2, 1001u32.into()));
+        assert_eq!(DcNode::thread_db_logs(&file_id, &log_id).unwrap().peers.contains(&other_peer_id), false);
+        assert_ok!(DcNode::remove_self_file_peer(RuntimeOrigin::signed(caller.clone()), file_id.clone(), 2, 1001u32.into()));
+        assert_eq!(DcNode::thread_db_logs(&file_id, &log_id), None);
+    });
+}
+
+#[test]
+fn dc_thread_db_log_bounds() {
+	build_and_execute(|| {
+        let owner: AccountId = user_purchase_storage::<Test>("owner");
+        let file_id = vec![37; 32]; 
+        let log_id = vec![33; 32];
+        let peer_id = vec![56; 32];
+        let caller = add_onchain_node::<Test>(peer_id.clone(), "thread_db_log_bounds", NODE_STATUS_ONCHAIN);
+        assert_ok!(DcNode::add_file_info(RuntimeOrigin::signed(caller.clone()), owner.clone(), file_id.clone(), 1000, 2, 1001u32.into(), vec![33; 1024]));
+
+        // The oldest heads are compacted once the previous heads are full
+        for i in 0..THREAD_DB_LOG_MAX_PREV_HEADS + 3 {
+            assert_ok!(DcNode::update_thread_db_log(
+                RuntimeOrigin::signed(caller.clone()), file_id.clone(), log_id.clone(), owner.clone(), vec![i as u8; 32], 100, 1001u32.into(), vec![33; 1024]
+            ));
+        }
+        let prev_heads = DcNode::thread_db_logs(&file_id, &log_id).unwrap().prev_heads;
+        assert_eq!(prev_heads.len(), THREAD_DB_LOG_MAX_PREV_HEADS as usize);
+        assert_eq!(prev_heads.first(), Some(&vec![2; 32]));
+        assert_eq!(prev_heads.last(), Some(&vec![THREAD_DB_LOG_MAX_PREV_HEADS as u8 + 1; 32]));
+
+        // The logs of a file are bounded
+        for i in 1..THREAD_DB_MAX_LOGS {
+            assert_ok!(DcNode::add_log_to_thread_db(RuntimeOrigin::signed(caller.clone()), file_id.clone(), (i as u32).encode(), 1001u32.into(), vec![33; 1024]));
+        }
+        assert_eq!(DcNode::files(&file_id).unwrap().db_log.len(), THREAD_DB_MAX_LOGS as usize);
+        assert_noop!(
+            DcNode::add_log_to_thread_db(RuntimeOrigin::signed(caller.clone()), file_id.clone(), vec![34; 32], 1001u32.into(), vec![33; 1024]),
+            Error::<Test>::TooManyThreadDbLogs
+        );
+        assert_noop!(
+            DcNode::update_thread_db_log(
+                RuntimeOrigin::signed(caller.clone()), file_id.clone(), vec![34; 32], owner.clone(), vec![1; 32], 100, 1001u32.into(), vec![33; 1024]
+            ),
+            Error::<Test>::TooManyThreadDbLogs
+        );
+        // The existing logs are still updated
+        assert_ok!(DcNode::update_thread_db_log(
+            RuntimeOrigin::signed(caller.clone()), file_id.clone(), log_id.clone(), owner.clone(), vec![99; 32], 100, 1001u32.into(), vec![33; 1024]
+        ));
+
+        // All the logs are removed with the file
+        assert_ok!(DcNode::remove_self_file_peer(RuntimeOrigin::signed(caller.clone()), file_id.clone(), 2, 1001u32.into()));
+        assert_eq!(<ThreadDbLogs<Test>>::iter_prefix(&file_id).count(), 0);
+    });
+}
+
 #[test]
 fn dc_report_tee_faking() {
-	new_test_ext().execute_with(|| {
+	build_and_execute(|| {
         let peer_id = vec![56; 32];
         let caller = add_onchain_node::<Test>(peer_id.clone(), "report_tee_faking", NODE_STATUS_ONCHAIN);
         let ext_height = 201;
@@ -1396,7 +2378,7 @@ fn dc_report_tee_faking() {
 
 #[test]
 fn dc_verify_tee_faking() {
-	new_test_ext().execute_with(|| {
+	build_and_execute(|| {
         let peer_id = vec![56; 32];
         let caller = add_onchain_node::<Test>(peer_id.clone(), "verify_tee_faking", NODE_STATUS_ONCHAIN);
         let block_height = 201;
@@ -1452,7 +2434,7 @@ fn dc_verify_tee_faking() {
 
 #[test]
 fn dc_report_peer_offchain() {
-	new_test_ext().execute_with(|| {
+	build_and_execute(|| {
         System::set_block_number(100);
         let peer_id = vec![56; 32];
         let caller = add_onchain_node::<Test>(peer_id.clone(), "report_peer_offchain", NODE_STATUS_ONCHAIN);
@@ -1534,7 +2516,7 @@ fn dc_report_peer_offchain() {
 
 #[test]
 fn dc_report_peer_error() {
-	new_test_ext().execute_with(|| {
+	build_and_execute(|| {
         let peer_id = vec![56; 32];
         let caller = add_onchain_node::<Test>(peer_id.clone(), "report_peer_error", NODE_STATUS_ONCHAIN);
         let block_height = 201;
@@ -1565,9 +2547,60 @@ fn dc_report_peer_error() {
     });
 }
 
+#[test]
+fn dc_report_dc_offence() {
+	build_and_execute(|| {
+        let peer_id = vec![56; 32];
+        let caller = add_onchain_node::<Test>(peer_id.clone(), "report_dc_offence", NODE_STATUS_ONCHAIN);
+        let owner = user_purchase_storage::<Test>("owner");
+        let file_id = vec![49, 50, 51];
+
+        System::set_block_number(1);
+        // File loss is slashed by the default fraction
+        assert_ok!(DcNode::add_file_info(RuntimeOrigin::signed(caller.clone()), owner, file_id.clone(), 1000123, 1, 1000u32.into(), vec![35; 64]));
+        assert_ok!(DcNode::report_file_miss(RuntimeOrigin::signed(caller.clone()), file_id, 1, 100));
+        let (reporters, offence) = Offences::get()[0].clone();
+        assert!(reporters.is_empty());
+        assert_eq!(offence.kind, DcOffenceKind::FileLoss);
+        assert_eq!(offence.peer_id, peer_id);
+        assert_eq!(offence.offenders(), vec![(DefaultAccountId::get(), StakingActive::get())]);
+        assert_eq!(offence.slash_fraction, Perbill::from_percent(2));
+        assert_eq!(offence.disable_strategy(), DisableStrategy::Never);
+        System::assert_last_event(RuntimeEvent::DcNode(Event::DcOffenceReported(DcOffenceKind::FileLoss, peer_id.clone(), DefaultAccountId::get())));
+
+        // Prolonged offline is slashed by the fraction set by governance
+        assert_noop!(
+            DcNode::set_offence_slash_fraction(RuntimeOrigin::signed(caller.clone()), DcOffenceKind::ProlongedOffline, Perbill::from_percent(20)),
+            sp_runtime::DispatchError::BadOrigin
+        );
+        assert_ok!(DcNode::set_offence_slash_fraction(RuntimeOrigin::root(), DcOffenceKind::ProlongedOffline, Perbill::from_percent(20)));
+        let rpt_p_id = vec![58; 32];
+        let _ = add_onchain_node::<Test>(rpt_p_id.clone(), "rpt_p_id", NODE_STATUS_OFFCHAIN);
+        System::set_block_number(DcNode::blocks_of_offchain_to_abnormal()+2);
+        assert_ok!(
+            DcNode::report_peer_error(
+                RuntimeOrigin::signed(caller.clone()), 
+                rpt_p_id.clone(),
+                u32::try_from(DcNode::blocks_of_offchain_to_abnormal()+2).unwrap(),
+                vec![33; 64]
+            )
+        );
+        let (reporters, offence) = Offences::get()[1].clone();
+        assert_eq!(reporters, vec![caller.clone()]);
+        assert_eq!(offence.kind, DcOffenceKind::ProlongedOffline);
+        assert_eq!(offence.slash_fraction, Perbill::from_percent(20));
+
+        // Duplicate offences in the session are not reported again
+        System::reset_events();
+        DcNode::report_dc_offence(DcOffenceKind::ProlongedOffline, &rpt_p_id, &DefaultAccountId::get(), Vec::new());
+        assert_eq!(Offences::get().len(), 2);
+        assert!(System::events().is_empty());
+    });
+}
+
 #[test]
 fn dc_report_spam() {
-	new_test_ext().execute_with(|| {
+	build_and_execute(|| {
         let peer_id = vec![56; 32];
         let caller = add_onchain_node::<Test>(peer_id.clone(), "report_spam", NODE_STATUS_ONCHAIN);
         let report_account: AccountId = user_purchase_storage::<Test>("report_account");
@@ -1671,7 +2704,7 @@ fn dc_report_spam() {
 
         let mut user_info = WalletAccountStorage::<Test>::get(&sender_account).unwrap();
         user_info.spam_report_amount = DcNode::frozen_report_spam_amount()-1;
-        WalletAccountStorage::<Test>::insert(&sender_account, user_info);
+        DcNode::insert_user(&sender_account, user_info);
 
         System::set_block_number(100u32.into());
         assert_ok!(
@@ -1696,7 +2729,7 @@ fn dc_report_spam() {
 
 #[test]
 fn dc_set_app_account() {
-    new_test_ext().execute_with(|| {
+    build_and_execute(|| {
         let app_id = vec![56; 32];
         let caller: AccountId = user_purchase_storage::<Test>("caller");
         let rewarded_account: AccountId = account("rewarded_account", 0, 0);
@@ -1742,9 +2775,226 @@ fn dc_set_app_account() {
     });
 }
 
+#[test]
+fn dc_app_registry() {
+    build_and_execute(|| {
+        let app_id = vec![59; 32];
+        let owner: AccountId = account("app_owner", 0, 0);
+        let new_owner: AccountId = account("app_owner", 1, 0);
+        let _ = Balances::make_free_balance_be(&owner, 100);
+        let _ = Balances::make_free_balance_be(&new_owner, 5);
+        System::set_block_number(1);
+
+        // The deposit is reserved from the private account on registration
+        assert_ok!(DcNode::set_app_account(RuntimeOrigin::signed(owner.clone()), app_id.clone(), owner.clone()));
+        assert_eq!(Balances::reserved_balance(&owner), AppDeposit::get());
+        assert_eq!(DcNode::account_of_app(&app_id).unwrap().deposit, AppDeposit::get());
+        System::assert_last_event(RuntimeEvent::DcNode(crate::Event::RegisterApp(app_id.clone(), owner.clone(), AppDeposit::get())));
+        assert_noop!(
+            DcNode::set_app_account(RuntimeOrigin::signed(new_owner.clone()), vec![60; 32], new_owner.clone()),
+            Error::<Test>::InsufficientBalance
+        );
+
+        assert_noop!(
+            DcNode::set_app_metadata(RuntimeOrigin::signed(owner.clone()), app_id.clone(), vec![1; 65], vec![], sp_core::H256::zero()),
+            Error::<Test>::AppMetadataTooLong
+        );
+        assert_noop!(
+            DcNode::set_app_metadata(RuntimeOrigin::signed(new_owner.clone()), app_id.clone(), b"app".to_vec(), vec![], sp_core::H256::zero()),
+            Error::<Test>::NotController
+        );
+        assert_ok!(DcNode::set_app_metadata(
+            RuntimeOrigin::signed(owner.clone()),
+            app_id.clone(),
+            b"app".to_vec(),
+            b"https://app.dc".to_vec(),
+            sp_core::H256::repeat_byte(1)
+        ));
+        let acc_info = DcNode::account_of_app(&app_id).unwrap();
+        assert_eq!(acc_info.name, b"app".to_vec());
+        assert_eq!(acc_info.url, b"https://app.dc".to_vec());
+        assert_eq!(acc_info.logo_hash, sp_core::H256::repeat_byte(1));
+
+        // Ownership moves once accepted, with the deposit
+        assert_noop!(
+            DcNode::transfer_app_ownership(RuntimeOrigin::signed(new_owner.clone()), app_id.clone(), new_owner.clone()),
+            Error::<Test>::NotController
+        );
+        assert_ok!(DcNode::transfer_app_ownership(RuntimeOrigin::signed(owner.clone()), app_id.clone(), new_owner.clone()));
+        assert_noop!(
+            DcNode::accept_app_ownership(RuntimeOrigin::signed(owner.clone()), app_id.clone()),
+            Error::<Test>::NotPendingAppOwner
+        );
+        assert_noop!(
+            DcNode::accept_app_ownership(RuntimeOrigin::signed(new_owner.clone()), app_id.clone()),
+            Error::<Test>::InsufficientBalance
+        );
+        let _ = Balances::make_free_balance_be(&new_owner, 100);
+        assert_ok!(DcNode::accept_app_ownership(RuntimeOrigin::signed(new_owner.clone()), app_id.clone()));
+        assert_eq!(DcNode::account_of_app(&app_id).unwrap().private_account, new_owner);
+        assert_eq!(DcNode::pending_app_owner(&app_id), None);
+        assert_eq!(Balances::reserved_balance(&owner), 0);
+        assert_eq!(Balances::reserved_balance(&new_owner), AppDeposit::get());
+
+        // Deregistration refunds the deposit and revokes the sponsorship
+        assert_ok!(DcNode::set_storage_package(RuntimeOrigin::root(), 1, 100, 10, 1, 100));
+        assert_ok!(DcNode::set_sponsorship(RuntimeOrigin::signed(new_owner.clone()), app_id.clone(), 1, 50, 0));
+        let other_app_id = b"other-app".to_vec();
+        AppsAccountLoginTimes::<Test>::put(BTreeMap::from([
+            (app_id.clone(), AppLoginInfo { rewarded_stash: new_owner.clone(), login_times: 2 }),
+            (other_app_id.clone(), AppLoginInfo { rewarded_stash: owner.clone(), login_times: 1 }),
+        ]));
+        assert_noop!(
+            DcNode::deregister_app(RuntimeOrigin::signed(owner.clone()), app_id.clone()),
+            Error::<Test>::NotController
+        );
+        assert_ok!(DcNode::deregister_app(RuntimeOrigin::signed(new_owner.clone()), app_id.clone()));
+        assert_eq!(DcNode::account_of_app(&app_id), None);
+        assert_eq!(DcNode::sponsorship(&app_id), None);
+        assert_eq!(Balances::reserved_balance(&new_owner), 0);
+        assert_eq!(Balances::free_balance(&new_owner), 100);
+        // The logins of the era are not rewarded to the deregistered app
+        let login_count = AppsAccountLoginTimes::<Test>::get().unwrap();
+        assert!(!login_count.contains_key(&app_id));
+        assert!(login_count.contains_key(&other_app_id));
+
+        // Governance slashes the deposit of the revoked app
+        assert_ok!(DcNode::set_app_account(RuntimeOrigin::signed(owner.clone()), app_id.clone(), owner.clone()));
+        assert_noop!(DcNode::revoke_app(RuntimeOrigin::signed(owner.clone()), app_id.clone()), sp_runtime::DispatchError::BadOrigin);
+        assert_ok!(DcNode::revoke_app(RuntimeOrigin::root(), app_id.clone()));
+        assert_eq!(DcNode::account_of_app(&app_id), None);
+        assert_eq!(Balances::reserved_balance(&owner), 0);
+        assert_eq!(Balances::free_balance(&owner), 100 - AppDeposit::get());
+        System::assert_last_event(RuntimeEvent::DcNode(crate::Event::RevokeApp(app_id.clone(), AppDeposit::get())));
+        assert_noop!(DcNode::revoke_app(RuntimeOrigin::root(), app_id.clone()), Error::<Test>::AppNotExist);
+        // The revoked app id can not be registered again
+        assert_eq!(DcNode::revoked_app(&app_id), Some(()));
+        assert_noop!(
+            DcNode::set_app_account(RuntimeOrigin::signed(new_owner.clone()), app_id, new_owner),
+            Error::<Test>::AppRevoked
+        );
+    });
+}
+
+#[test]
+fn dc_sponsor_user_storage() {
+    build_and_execute(|| {
+        let app_id = vec![57; 32];
+        let app_owner: AccountId = account("app_owner", 0, 0);
+        let user: AccountId = account("sponsored_user", 0, 0);
+        let peer_id = vec![58; 32];
+        let caller = add_onchain_node::<Test>(peer_id, "sponsor_user", NODE_STATUS_ONCHAIN);
+        let _ = Balances::make_free_balance_be(&app_owner, 1_000_000);
+        assert_ok!(DcNode::set_storage_package(RuntimeOrigin::root(), 1, 100, 100, 1, 100));
+        System::set_block_number(1);
+
+        assert_noop!(
+            DcNode::set_sponsorship(RuntimeOrigin::signed(app_owner.clone()), app_id.clone(), 1, 250, 200),
+            Error::<Test>::AppNotExist
+        );
+        assert_ok!(DcNode::set_app_account(RuntimeOrigin::signed(app_owner.clone()), app_id.clone(), app_owner.clone()));
+        assert_noop!(
+            DcNode::sponsor_user(RuntimeOrigin::signed(app_owner.clone()), app_id.clone(), user.clone()),
+            Error::<Test>::SponsorshipNotExist
+        );
+        assert_noop!(
+            DcNode::set_sponsorship(RuntimeOrigin::signed(caller.clone()), app_id.clone(), 1, 250, 200),
+            Error::<Test>::NotController
+        );
+        assert_noop!(
+            DcNode::set_sponsorship(RuntimeOrigin::signed(app_owner.clone()), app_id.clone(), 2, 250, 200),
+            Error::<Test>::StoragePackageNotExist
+        );
+        assert_ok!(DcNode::set_sponsorship(RuntimeOrigin::signed(app_owner.clone()), app_id.clone(), 1, 250, 200));
+
+        // The app pays the package of the user who holds no tokens
+        assert_ok!(DcNode::sponsor_user(RuntimeOrigin::signed(app_owner.clone()), app_id.clone(), user.clone()));
+        assert_eq!(Balances::free_balance(&user), 0);
+        assert_eq!(Balances::free_balance(&app_owner), 1_000_000 - AppDeposit::get() - 100);
+        assert_eq!(DcNode::wallet_account_storage(&user).unwrap().expire_number, 101);
+        assert_eq!(DcNode::sponsored_user(&app_id, &user), Some(100));
+        assert_eq!(DcNode::sponsorship(&app_id).unwrap().budget, 150);
+        System::assert_last_event(RuntimeEvent::DcNode(crate::Event::SponsorStorage(app_id.clone(), user.clone(), 1, 100)));
+        assert_noop!(
+            DcNode::sponsor_user(RuntimeOrigin::signed(app_owner.clone()), app_id.clone(), user.clone()),
+            Error::<Test>::AlreadySponsored
+        );
+
+        // The expired package is renewed from the app by anyone
+        assert_noop!(
+            DcNode::renew_sponsored_storage(RuntimeOrigin::signed(caller.clone()), user.clone()),
+            Error::<Test>::SponsoredStorageNotExpired
+        );
+        System::set_block_number(150);
+        assert_noop!(
+            DcNode::update_db_config(RuntimeOrigin::signed(caller.clone()), user.clone(), vec![32; 32], 150, vec![33; 64]),
+            Error::<Test>::UserPackageExpired
+        );
+        assert_ok!(DcNode::renew_sponsored_storage(RuntimeOrigin::signed(caller.clone()), user.clone()));
+        assert_ok!(DcNode::update_db_config(RuntimeOrigin::signed(caller.clone()), user.clone(), vec![32; 32], 150, vec![33; 64]));
+        assert_eq!(DcNode::wallet_account_storage(&user).unwrap().expire_number, 249);
+        assert_eq!(DcNode::sponsored_user(&app_id, &user), Some(200));
+        System::assert_has_event(RuntimeEvent::DcNode(crate::Event::SponsorshipBudgetExhausted(app_id.clone(), 50)));
+
+        // Neither the budget nor the limit for the user can pay another renewal
+        System::set_block_number(300);
+        assert_noop!(
+            DcNode::renew_sponsored_storage(RuntimeOrigin::signed(caller.clone()), user.clone()),
+            Error::<Test>::SponsorshipBudgetExhausted
+        );
+        assert_ok!(DcNode::set_sponsorship(RuntimeOrigin::signed(app_owner.clone()), app_id.clone(), 1, 250, 0));
+        assert_eq!(DcNode::sponsorship(&app_id).unwrap().users, 1);
+        assert_ok!(DcNode::renew_sponsored_storage(RuntimeOrigin::signed(caller.clone()), user.clone()));
+        assert_eq!(DcNode::wallet_account_storage(&user).unwrap().expire_number, 400);
+        assert_eq!(DcNode::sponsorship(&app_id).unwrap().budget, 150);
+
+        // The user leaves the sponsorship
+        assert_noop!(
+            DcNode::remove_sponsored_user(RuntimeOrigin::signed(caller.clone()), app_id.clone(), user.clone()),
+            Error::<Test>::NotController
+        );
+        assert_ok!(DcNode::remove_sponsored_user(RuntimeOrigin::signed(user.clone()), app_id.clone(), user.clone()));
+        assert_eq!(DcNode::user_sponsor(&user), None);
+        assert_eq!(DcNode::sponsorship(&app_id).unwrap().users, 0);
+        assert_noop!(
+            DcNode::renew_sponsored_storage(RuntimeOrigin::signed(caller.clone()), user.clone()),
+            Error::<Test>::NotSponsored
+        );
+        assert_noop!(
+            DcNode::remove_sponsored_user(RuntimeOrigin::signed(app_owner.clone()), app_id.clone(), user.clone()),
+            Error::<Test>::NotSponsored
+        );
+
+        // Users are removed with the sponsorship, and keep the packages paid
+        let user2: AccountId = account("sponsored_user", 1, 0);
+        assert_ok!(DcNode::sponsor_user(RuntimeOrigin::signed(app_owner.clone()), app_id.clone(), user2.clone()));
+        assert_noop!(
+            DcNode::sponsor_user(RuntimeOrigin::signed(app_owner.clone()), app_id.clone(), user.clone()),
+            Error::<Test>::SponsorshipBudgetExhausted
+        );
+        assert_ok!(DcNode::revoke_sponsorship(RuntimeOrigin::signed(app_owner.clone()), app_id.clone()));
+        assert_eq!(DcNode::user_sponsor(&user2), None);
+        assert_eq!(DcNode::sponsored_user(&app_id, &user2), None);
+        assert_eq!(DcNode::wallet_account_storage(&user2).unwrap().expire_number, 400);
+        assert_noop!(
+            DcNode::revoke_sponsorship(RuntimeOrigin::signed(app_owner.clone()), app_id.clone()),
+            Error::<Test>::SponsorshipNotExist
+        );
+
+        // The enrolled users of a sponsorship are bounded
+        assert_ok!(DcNode::set_sponsorship(RuntimeOrigin::signed(app_owner.clone()), app_id.clone(), 1, 1_000_000, 0));
+        <Sponsorships<Test>>::mutate(&app_id, |sponsorship| sponsorship.as_mut().unwrap().users = SPONSORED_USERS_MAX_NUM);
+        assert_noop!(
+            DcNode::sponsor_user(RuntimeOrigin::signed(app_owner), app_id.clone(), user),
+            Error::<Test>::TooManySponsoredUsers
+        );
+        <Sponsorships<Test>>::mutate(&app_id, |sponsorship| sponsorship.as_mut().unwrap().users = 0);
+    });
+}
+
 #[test]
 fn dc_user_login() {
-    new_test_ext().execute_with(|| {
+    build_and_execute(|| {
         let peer_id = vec![56; 32];
         let caller = add_onchain_node::<Test>(peer_id.clone(), "user_login", NODE_STATUS_ONCHAIN);
         let login_account: AccountId = user_purchase_storage::<Test>("login_account");
@@ -1758,7 +3008,9 @@ fn dc_user_login() {
                 RuntimeOrigin::signed(caller.clone()), 
                 account("AccountNotExist", 0, 0),
                 app_ids.clone(),
-                block_height
+                block_height,
+                vec![33; 64],
+                Vec::new()
             ), 
             Error::<Test>::AccountNotExist
         );
@@ -1768,7 +3020,9 @@ fn dc_user_login() {
                 RuntimeOrigin::signed(caller.clone()), 
                 login_account.clone(),
                 app_ids.clone(),
-                block_height
+                block_height,
+                vec![33; 64],
+                Vec::new()
             ), 
             Error::<Test>::PeerIdNotExist
         );
@@ -1782,7 +3036,7 @@ fn dc_user_login() {
         let mut user_info = WalletAccountStorage::<Test>::get(&login_account).unwrap();
         user_info.spam_report_amount = 10;
         user_info.comment_report_amount = 10;
-        WalletAccountStorage::<Test>::insert(&login_account, user_info);
+        DcNode::insert_user(&login_account, user_info);
         System::set_block_number(System::block_number() + DcNode::interval_blocks_login());
         let _ = DcNode::set_app_account(RuntimeOrigin::signed(caller.clone()), vec![50,51,53], login_account.clone());
         assert_ok!(
@@ -1790,7 +3044,9 @@ fn dc_user_login() {
                 RuntimeOrigin::signed(caller.clone()), 
                 login_account.clone(),
                 app_ids.clone(),
-                System::block_number().try_into().unwrap()
+                System::block_number().try_into().unwrap(),
+                vec![33; 64],
+                Vec::new()
             )
         );
         let cur_info = WalletAccountStorage::<Test>::get(&login_account).unwrap();
@@ -1811,16 +3067,75 @@ fn dc_user_login() {
                 RuntimeOrigin::signed(caller.clone()), 
                 login_account.clone(),
                 app_ids.clone(),
-                System::block_number().try_into().unwrap()
+                System::block_number().try_into().unwrap(),
+                vec![33; 64],
+                Vec::new()
+            )
+        );
+        // The login is counted once per era
+        assert_eq!(AppsAccountLoginTimes::<Test>::get().unwrap().get(&vec![50,51,53]).unwrap().login_times, 1);
+        assert_eq!(DcNode::app_login_in_era(CurrentEra::get(), (vec![50,51,53], login_account.clone())), Some(()));
+
+        CurrentEra::set(CurrentEra::get() + 1);
+        System::set_block_number(System::block_number() + DcNode::interval_blocks_login());
+        assert_ok!(
+            DcNode::user_login(
+                RuntimeOrigin::signed(caller.clone()), 
+                login_account.clone(),
+                app_ids.clone(),
+                System::block_number().try_into().unwrap(),
+                vec![33; 64],
+                Vec::new()
             )
         );
         assert_eq!(AppsAccountLoginTimes::<Test>::get().unwrap().get(&vec![50,51,53]).unwrap().login_times, 2);
+
+        // The counted logins of the past eras are pruned
+        let past_era = CurrentEra::get() - 1;
+        LoginPruneEra::<Test>::put(past_era);
+        DcNode::on_initialize(System::block_number());
+        assert_eq!(DcNode::app_login_in_era(past_era, (vec![50,51,53], login_account.clone())), None);
+        assert_eq!(DcNode::app_login_in_era(CurrentEra::get(), (vec![50,51,53], login_account.clone())), Some(()));
+        assert_eq!(DcNode::login_prune_era(), CurrentEra::get());
+
+        // Apps requiring it only count the logins co-signed by the app
+        assert_noop!(
+            DcNode::set_app_login_cosign(RuntimeOrigin::signed(login_account.clone()), vec![50,51,53], true),
+            Error::<Test>::NotController
+        );
+        assert_ok!(DcNode::set_app_login_cosign(RuntimeOrigin::signed(caller.clone()), vec![50,51,53], true));
+        CurrentEra::set(CurrentEra::get() + 1);
+        System::set_block_number(System::block_number() + DcNode::interval_blocks_login());
+        assert_ok!(
+            DcNode::user_login(
+                RuntimeOrigin::signed(caller.clone()), 
+                login_account.clone(),
+                app_ids.clone(),
+                System::block_number().try_into().unwrap(),
+                vec![33; 64],
+                Vec::new()
+            )
+        );
+        assert_eq!(AppsAccountLoginTimes::<Test>::get().unwrap().get(&vec![50,51,53]).unwrap().login_times, 2);
+
+        System::set_block_number(System::block_number() + DcNode::interval_blocks_login());
+        assert_ok!(
+            DcNode::user_login(
+                RuntimeOrigin::signed(caller.clone()), 
+                login_account.clone(),
+                app_ids.clone(),
+                System::block_number().try_into().unwrap(),
+                vec![33; 64],
+                vec![(vec![50,51,53], vec![34; 64])]
+            )
+        );
+        assert_eq!(AppsAccountLoginTimes::<Test>::get().unwrap().get(&vec![50,51,53]).unwrap().login_times, 3);
     });
 }
 
 #[test]
 fn dc_new_theme() {
-	new_test_ext().execute_with(|| {
+	build_and_execute(|| {
         let peer_id = vec![56; 32];
         let caller = add_onchain_node::<Test>(peer_id.clone(), "new_theme", NODE_STATUS_ONCHAIN);
         let for_account: AccountId = user_purchase_storage::<Test>("for_account");
@@ -1858,12 +3173,31 @@ fn dc_new_theme() {
             )
         );
         assert_eq!(DcNode::wallet_account_storage(for_account.clone()).unwrap().comment_space, comment_space);
+        let theme = DcNode::themes(&app_id, &theme_id).unwrap();
+        assert_eq!(theme.owner, for_account);
+        assert_eq!(theme.open_flag, open_flag);
+        assert_eq!(theme.space, comment_space);
+        assert_eq!(theme.status, THEME_STATUS_OPEN);
+
+        assert_noop!(
+            DcNode::new_theme(
+                RuntimeOrigin::signed(caller.clone()), 
+                for_account.clone(),
+                theme_id.clone(),
+                app_id.clone(),
+                comment_space,
+                open_flag,
+                block_height, 
+                signature.clone()
+            ), 
+            Error::<Test>::ThemeAlreadyExist
+        );
     });
 }
 
 #[test]
 fn dc_add_theme_comment_space() {
-	new_test_ext().execute_with(|| {
+	build_and_execute(|| {
         let peer_id = vec![56; 32];
         let caller = add_onchain_node::<Test>(peer_id.clone(), "add_theme_comment_space", NODE_STATUS_ONCHAIN);
         let for_account: AccountId = user_purchase_storage::<Test>("for_account");
@@ -1872,6 +3206,7 @@ fn dc_add_theme_comment_space() {
         let add_space = 12u64;
         let block_height = 10u32;
         let signature = vec![59; 1024];
+        System::set_block_number(1);
 
         assert_noop!(
             DcNode::add_theme_comment_space(
@@ -1885,6 +3220,34 @@ fn dc_add_theme_comment_space() {
             ), 
             Error::<Test>::AccountNotExist
         );
+        assert_noop!(
+            DcNode::add_theme_comment_space(
+                RuntimeOrigin::signed(caller.clone()), 
+                for_account.clone(),
+                theme_id.clone(),
+                app_id.clone(),
+                add_space,
+                block_height, 
+                signature.clone()
+            ), 
+            Error::<Test>::ThemeNotExist
+        );
+
+        let owner: AccountId = user_purchase_storage::<Test>("theme_owner");
+        assert_ok!(DcNode::new_theme(RuntimeOrigin::signed(caller.clone()), owner.clone(), theme_id.clone(), app_id.clone(), 0, 1, block_height, signature.clone()));
+        assert_noop!(
+            DcNode::add_theme_comment_space(
+                RuntimeOrigin::signed(caller.clone()), 
+                for_account.clone(),
+                theme_id.clone(),
+                app_id.clone(),
+                add_space,
+                block_height, 
+                signature.clone()
+            ), 
+            Error::<Test>::NotThemeOwner
+        );
+        let for_account = owner;
 
         assert_ok!(
             DcNode::add_theme_comment_space(
@@ -1909,12 +3272,79 @@ fn dc_add_theme_comment_space() {
             )
         );
         assert_eq!(DcNode::wallet_account_storage(for_account.clone()).unwrap().comment_space, add_space*2);
+        assert_eq!(DcNode::themes(&app_id, &theme_id).unwrap().space, add_space*2);
+
+        // Governance registers the themes created before the theme registry to their owners
+        let legacy_theme_id = vec![60; 32];
+        assert_noop!(
+            DcNode::register_legacy_theme(RuntimeOrigin::signed(caller.clone()), app_id.clone(), legacy_theme_id.clone(), for_account.clone(), 0, add_space),
+            sp_runtime::DispatchError::BadOrigin
+        );
+        assert_noop!(
+            DcNode::register_legacy_theme(RuntimeOrigin::root(), app_id.clone(), theme_id.clone(), for_account.clone(), 0, add_space),
+            Error::<Test>::ThemeAlreadyExist
+        );
+        assert_noop!(
+            DcNode::register_legacy_theme(RuntimeOrigin::root(), app_id.clone(), legacy_theme_id.clone(), for_account.clone(), 0, add_space*2 + 1),
+            Error::<Test>::ParamErr
+        );
+        assert_ok!(DcNode::register_legacy_theme(RuntimeOrigin::root(), app_id.clone(), legacy_theme_id.clone(), for_account.clone(), 0, add_space));
+        System::assert_last_event(RuntimeEvent::DcNode(crate::Event::NewTheme(app_id.clone(), legacy_theme_id.clone(), for_account.clone(), add_space)));
+        let legacy_theme = DcNode::themes(&app_id, &legacy_theme_id).unwrap();
+        assert_eq!((legacy_theme.owner, legacy_theme.space, legacy_theme.status), (for_account.clone(), add_space, THEME_STATUS_OPEN));
+        assert_eq!(DcNode::wallet_account_storage(for_account.clone()).unwrap().comment_space, add_space*2);
+    });
+}
+
+#[test]
+fn dc_close_theme() {
+	build_and_execute(|| {
+        let peer_id = vec![56; 32];
+        let caller = add_onchain_node::<Test>(peer_id.clone(), "close_theme", NODE_STATUS_ONCHAIN);
+        let owner: AccountId = user_purchase_storage::<Test>("theme_owner");
+        let other: AccountId = user_purchase_storage::<Test>("other_account");
+        let theme_id = vec![51; 32];
+        let app_id = vec![52; 32];
+        let block_height = 10u32;
+        let signature = vec![59; 1024];
+        System::set_block_number(1);
+
+        assert_noop!(
+            DcNode::close_theme(RuntimeOrigin::signed(caller.clone()), owner.clone(), theme_id.clone(), app_id.clone(), block_height, signature.clone()),
+            Error::<Test>::ThemeNotExist
+        );
+        let pre_info = DcNode::wallet_account_storage(&owner).unwrap();
+        assert_ok!(DcNode::new_theme(RuntimeOrigin::signed(caller.clone()), owner.clone(), theme_id.clone(), app_id.clone(), 12, 1, block_height, signature.clone()));
+        assert_eq!(DcNode::wallet_account_storage(&owner).unwrap().used_space, pre_info.used_space + 12);
+        assert_noop!(
+            DcNode::close_theme(RuntimeOrigin::signed(caller.clone()), other, theme_id.clone(), app_id.clone(), block_height, signature.clone()),
+            Error::<Test>::NotThemeOwner
+        );
+
+        // The comment space of the theme is returned to the owner
+        assert_ok!(DcNode::close_theme(RuntimeOrigin::signed(caller.clone()), owner.clone(), theme_id.clone(), app_id.clone(), block_height, signature.clone()));
+        let cur_info = DcNode::wallet_account_storage(&owner).unwrap();
+        assert_eq!(cur_info.used_space, pre_info.used_space);
+        assert_eq!(cur_info.comment_space, pre_info.comment_space);
+        let theme = DcNode::themes(&app_id, &theme_id).unwrap();
+        assert_eq!(theme.status, THEME_STATUS_CLOSED);
+        assert_eq!(theme.space, 0);
+        System::assert_last_event(RuntimeEvent::DcNode(crate::Event::CloseTheme(app_id.clone(), theme_id.clone(), 12)));
+
+        assert_noop!(
+            DcNode::close_theme(RuntimeOrigin::signed(caller.clone()), owner.clone(), theme_id.clone(), app_id.clone(), block_height, signature.clone()),
+            Error::<Test>::ThemeClosed
+        );
+        assert_noop!(
+            DcNode::add_theme_comment_space(RuntimeOrigin::signed(caller.clone()), owner, theme_id, app_id, 12, block_height, signature),
+            Error::<Test>::ThemeClosed
+        );
     });
 }
 
 #[test]
 fn dc_add_user_comment_space() {
-	new_test_ext().execute_with(|| {
+	build_and_execute(|| {
         let peer_id = vec![56; 32];
         let caller = add_onchain_node::<Test>(peer_id.clone(), "add_user_comment_space", NODE_STATUS_ONCHAIN);
         let for_account: AccountId = user_purchase_storage::<Test>("for_account");
@@ -1953,7 +3383,7 @@ fn dc_add_user_comment_space() {
 
 #[test]
 fn dc_report_malicious_comment() {
-	new_test_ext().execute_with(|| {
+	build_and_execute(|| {
         let peer_id = vec![56; 32];
         let caller = add_onchain_node::<Test>(peer_id.clone(), "report_malicious_comment", NODE_STATUS_ONCHAIN);
         let report_account: AccountId = user_purchase_storage::<Test>("report_account");
@@ -1968,6 +3398,8 @@ fn dc_report_malicious_comment() {
         let content_type = 1u32;
         let comment_signature = vec![59; 1024];
 
+        assert_ok!(DcNode::new_theme(RuntimeOrigin::signed(caller.clone()), comment_account.clone(), theme_id.clone(), app_id.clone(), 0, 1, report_block_height, comment_signature.clone()));
+
         System::set_block_number(DcNode::interval_blocks_can_not_report()+30u64);
         assert_ok!(
             DcNode::report_malicious_comment(
@@ -2067,9 +3499,27 @@ fn dc_report_malicious_comment() {
         assert_eq!(DcNode::wallet_account_storage(comment_account.clone()).unwrap().comment_report_number, 30);
         assert_eq!(DcNode::wallet_account_storage(comment_account.clone()).unwrap().comment_report_amount, 1);
 
+        assert_noop!(
+            DcNode::report_malicious_comment(
+                RuntimeOrigin::signed(caller.clone()), 
+                report_account.clone(),
+                report_block_height,
+                report_signature.clone(),
+                vec![54; 32],
+                content_id.clone(),
+                comment_account.clone(),
+                app_id.clone(),
+                comment_block_height,
+                refer_comment_key.clone(),
+                content_type, 
+                comment_signature.clone()
+            ),
+            Error::<Test>::ThemeNotExist
+        );
+
         let mut user_info = WalletAccountStorage::<Test>::get(&comment_account).unwrap();
         user_info.comment_report_amount = DcNode::frozen_report_comment_amount()-1;
-        WalletAccountStorage::<Test>::insert(&comment_account, user_info);
+        DcNode::insert_user(&comment_account, user_info);
 
         System::set_block_number(100u32.into());
         assert_ok!(
@@ -2092,4 +3542,335 @@ fn dc_report_malicious_comment() {
         assert_eq!(DcNode::wallet_account_storage(comment_account.clone()).unwrap().comment_frozen_status, 1);
         assert_eq!(DcNode::wallet_account_storage(comment_account.clone()).unwrap().comment_report_number, 30);
     });
-}
\ No newline at end of file
+}
+#[test]
+fn dc_network_stats() {
+	build_and_execute(|| {
+        let onchain = add_onchain_node::<Test>(vec![56; 32], "stats_onchain", NODE_STATUS_ONCHAIN);
+        let _ = add_onchain_node::<Test>(vec![57; 32], "stats_offchain", NODE_STATUS_OFFCHAIN);
+        let user: AccountId = user_purchase_storage::<Test>("stats_user");
+        DcNode::insert_file(&vec![1; 32], FileInfo {
+            peers: BTreeSet::from([vec![56; 32], vec![57; 32]]),
+            users: BTreeSet::from([user.clone()]),
+            file_size: 100,
+            file_type: 1,
+            db_log: BTreeSet::new(),
+        });
+        AppRewardTotal::<Test>::put(30);
+        StorageRewardTotal::<Test>::put(70);
+        DcNode::insert_reports(CurrentEra::get(), ReportInfo {
+            report_type: ReportType::ReportPeerNoResponse,
+            peer_id: vec![56; 32],
+        }, BTreeSet::from([onchain, user]));
+
+        let stats = DcNode::network_stats();
+        assert_eq!(stats.peers_by_status, vec![(NODE_STATUS_OFFCHAIN, 1), (NODE_STATUS_ONCHAIN, 1)]);
+        assert_eq!(stats.total_space, 100*1024*1024*1024*1024);
+        assert_eq!(stats.free_space, 100*1024*1024*1024*1024);
+        assert_eq!(stats.active_users, 1);
+        assert_eq!((stats.files, stats.file_replicas), (1, 2));
+        assert_eq!((stats.app_reward_total, stats.storage_reward_total), (30, 70));
+        assert_eq!(stats.reports_in_era, 2);
+    });
+}
+
+#[test]
+fn dc_network_stats_counters() {
+	build_and_execute(|| {
+        let user: AccountId = user_purchase_storage::<Test>("counted_user");
+        let mut user_info = DcNode::wallet_account_storage(&user).unwrap();
+        let expire_number = user_info.expire_number;
+        assert_eq!((DcNode::active_users(), DcNode::users_expiring_at(expire_number)), (1, 1));
+
+        // Renewing the storage moves the account to the new expire number
+        user_info.expire_number = expire_number + 10;
+        DcNode::insert_user(&user, user_info);
+        assert_eq!(DcNode::active_users(), 1);
+        assert_eq!((DcNode::users_expiring_at(expire_number), DcNode::users_expiring_at(expire_number + 10)), (0, 1));
+
+        // The account is active until the block after its expire number
+        System::set_block_number(expire_number + 10);
+        DcNode::on_initialize(expire_number + 10);
+        assert_eq!(DcNode::network_stats().active_users, 1);
+        System::set_block_number(expire_number + 11);
+        DcNode::on_initialize(expire_number + 11);
+        assert_eq!(DcNode::network_stats().active_users, 0);
+        assert_eq!(DcNode::users_expiring_at(expire_number + 10), 0);
+
+        let file_info = FileInfo {
+            peers: BTreeSet::from([vec![56; 32], vec![57; 32]]),
+            users: BTreeSet::from([user.clone()]),
+            file_size: 100,
+            file_type: 1,
+            db_log: BTreeSet::new(),
+        };
+        DcNode::insert_file(&vec![1; 32], file_info.clone());
+        DcNode::insert_file(&vec![2; 32], file_info.clone());
+        assert_eq!(DcNode::file_totals(), (2, 4));
+        DcNode::insert_file(&vec![1; 32], FileInfo { peers: BTreeSet::from([vec![56; 32]]), ..file_info });
+        assert_eq!(DcNode::file_totals(), (2, 3));
+        DcNode::remove_file(&vec![2; 32]);
+        assert_eq!(DcNode::file_totals(), (1, 1));
+
+        let report_info = ReportInfo {
+            report_type: ReportType::ReportPeerNoResponse,
+            peer_id: vec![56; 32],
+        };
+        DcNode::insert_reports(CurrentEra::get(), report_info.clone(), BTreeSet::from([user.clone()]));
+        DcNode::insert_reports(CurrentEra::get(), report_info, BTreeSet::from([user, account("reporter", 0, 0)]));
+        assert_eq!(DcNode::network_stats().reports_in_era, 2);
+    });
+}
+
+#[test]
+fn dc_try_state_detects_drift() {
+	new_test_ext().execute_with(|| {
+        let peer_id = vec![56; 32];
+        let caller = add_onchain_node::<Test>(peer_id.clone(), "try_state", NODE_STATUS_ONCHAIN);
+        assert_ok!(DcNode::do_try_state());
+
+        OnchainPeerNumber::<Test>::mutate(|n| *n += 1);
+        assert_eq!(DcNode::do_try_state(), Err("OnchainPeerNumber is not the number of onchain peers"));
+        OnchainPeerNumber::<Test>::mutate(|n| *n -= 1);
+
+        RequestAccountPeer::<Test>::insert(&caller, vec![58; 32]);
+        assert_eq!(DcNode::do_try_state(), Err("Peers::req_account is not mapped to the peer in RequestAccountPeer"));
+        RequestAccountPeer::<Test>::insert(&caller, &peer_id);
+
+        let mut node_info = Peers::<Test>::get(&peer_id).unwrap();
+        node_info.stash = caller.clone();
+        DcNode::insert_peer(&peer_id, node_info);
+        assert_eq!(DcNode::do_try_state(), Err("Peers::stash does not contain the peer in StashPeers"));
+        StashPeers::<Test>::insert(&caller, BTreeSet::from([peer_id.clone()]));
+        assert_ok!(DcNode::do_try_state());
+    });
+}
+
+#[test]
+fn dc_genesis_config_seeds_registries() {
+    let app_account: AccountId = account("app", 0, 0);
+    let wallet_account: AccountId = account("wallet", 0, 0);
+    let storage = GenesisBuild::<Test>::build_storage(&crate::GenesisConfig::<Test> {
+        storage_packages: vec![(1, 60*1024*1024*1024, 10000, 1000000, 10), (2, 120*1024*1024*1024, 20000, 1000000, 5)],
+        enclave_ids: vec![(vec![1; 64], vec![2; 64])],
+        dc_program: Some(DcProgramInfo {
+            origin_url: b"https://example.com/dcnode".to_vec(),
+            mirror_url: Default::default(),
+            enclave_id: vec![1; 64],
+            version: b"0.1.0".to_vec(),
+        }),
+        endpoints: vec![(EndpointKind::Proxy, b"/ip4/127.0.0.1/tcp/9944".to_vec(), b"cn".to_vec(), 1, 10, None)],
+        app_accounts: vec![(b"app".to_vec(), app_account.clone(), app_account.clone())],
+        wallet_accounts: vec![(wallet_account.clone(), 2)],
+        ..Default::default()
+    })
+    .unwrap();
+
+    sp_io::TestExternalities::new(storage).execute_with(|| {
+        assert_eq!(DcNode::storage_packages().unwrap().len(), 2);
+        assert_eq!(DcNode::enclave_ids().unwrap(), BTreeSet::from([(0, vec![1; 64], vec![2; 64])]));
+        assert_eq!(DcNode::dc_program().unwrap().version, b"0.1.0".to_vec());
+        assert_eq!(DcNode::endpoint(EndpointKind::Proxy, b"/ip4/127.0.0.1/tcp/9944".to_vec()).unwrap().priority, 10);
+        assert_eq!(AccountOfApp::<Test>::get(b"app".to_vec()).unwrap().rewarded_stash, app_account);
+
+        let user_storage = DcNode::wallet_account_storage(&wallet_account).unwrap();
+        assert_eq!(user_storage.subscribe_space, 120*1024*1024*1024);
+        assert_eq!(user_storage.expire_number, 1000000);
+        assert_eq!(user_storage.parent_account, wallet_account);
+    });
+}
+
+#[test]
+#[should_panic(expected = "Storage package of wallet account does not exist")]
+fn dc_genesis_config_rejects_unknown_package() {
+    GenesisBuild::<Test>::build_storage(&crate::GenesisConfig::<Test> {
+        storage_packages: vec![(1, 60*1024*1024*1024, 10000, 1000000, 10)],
+        wallet_accounts: vec![(account("wallet", 0, 0), 2)],
+        ..Default::default()
+    })
+    .unwrap();
+}
+
+/// Externalities with an offchain worker, a transaction pool and a keystore holding
+/// the key of a liveness prober.
+fn new_liveness_test_ext() -> (
+    sp_io::TestExternalities,
+    std::sync::Arc<parking_lot::RwLock<OffchainState>>,
+    std::sync::Arc<parking_lot::RwLock<PoolState>>,
+    liveness::sr25519::AuthorityId,
+) {
+    let mut ext = new_test_ext();
+    let (offchain, offchain_state) = TestOffchainExt::new();
+    let (pool, pool_state) = TestTransactionPoolExt::new();
+    let keystore = KeyStore::new();
+    let key = SyncCryptoStore::sr25519_generate_new(&keystore, liveness::KEY_TYPE, None).unwrap();
+    ext.register_extension(OffchainDbExt::new(offchain.clone()));
+    ext.register_extension(OffchainWorkerExt::new(offchain));
+    ext.register_extension(TransactionPoolExt::new(pool));
+    ext.register_extension(KeystoreExt(std::sync::Arc::new(keystore)));
+    (ext, offchain_state, pool_state, key.into())
+}
+
+fn add_probed_node(peer_id: &PeerId, name: &'static str, ip_address: &[u8]) {
+    add_onchain_node::<Test>(peer_id.clone(), name, NODE_STATUS_ONCHAIN);
+    Peers::<Test>::mutate(peer_id, |info| info.as_mut().unwrap().ip_address = ip_address.to_vec());
+}
+
+fn expect_probe(state: &mut OffchainState, uri: &str, response: &[u8]) {
+    state.expect_request(PendingRequest {
+        method: "GET".into(),
+        uri: uri.into(),
+        response: Some(response.to_vec()),
+        sent: true,
+        ..Default::default()
+    });
+}
+
+fn sign_liveness_report(
+    key: &liveness::sr25519::AuthorityId,
+    block_number: BlockNumber,
+    probed: u32,
+    offline: Vec<PeerId>,
+) -> (LivenessReport<BlockNumber, liveness::sr25519::AuthorityId>, liveness::sr25519::AuthoritySignature) {
+    let report = LivenessReport { block_number, prober: key.clone(), probed, offline };
+    let signature = report.using_encoded(|encoded| key.sign(&encoded)).unwrap();
+    (report, signature)
+}
+
+fn liveness_report_call(
+    (report, signature): (LivenessReport<BlockNumber, liveness::sr25519::AuthorityId>, liveness::sr25519::AuthoritySignature),
+) -> crate::Call<Test> {
+    crate::Call::submit_liveness_report { report, signature }
+}
+
+#[test]
+fn dc_liveness_probe_submits_report() {
+    let (mut ext, offchain_state, pool_state, key) = new_liveness_test_ext();
+    ext.execute_with(|| {
+        let prober: AccountId = account("prober", 0, 0);
+        let alive = b"alive-peer".to_vec();
+        let faulty = b"faulty-peer".to_vec();
+        let silent = b"silent-peer".to_vec();
+        add_probed_node(&alive, "alive", b"10.0.0.1:8080");
+        add_probed_node(&faulty, "faulty", b"https://10.0.0.2/");
+        add_probed_node(&silent, "silent", &[0xff, 0xfe]);
+        add_onchain_node::<Test>(b"joining-peer".to_vec(), "joining", NODE_STATUS_JOINING);
+
+        // Only the keys of registered probers are used
+        DcNode::offchain_worker(10);
+        assert!(pool_state.read().transactions.is_empty());
+        assert_ok!(DcNode::set_liveness_prober(RuntimeOrigin::root(), key.clone(), prober.clone()));
+
+        // Probes are only sent at the start of rounds
+        DcNode::offchain_worker(15);
+        assert!(pool_state.read().transactions.is_empty());
+
+        // The node answering with another peer id and the node with an invalid address are offline
+        expect_probe(&mut offchain_state.write(), "http://10.0.0.1:8080/dc/liveness", &alive);
+        expect_probe(&mut offchain_state.write(), "https://10.0.0.2/dc/liveness", b"another-peer");
+        System::set_block_number(20);
+        DcNode::offchain_worker(20);
+
+        let tx = pool_state.write().transactions.pop().unwrap();
+        assert!(pool_state.read().transactions.is_empty());
+        let tx = UncheckedExtrinsic::decode(&mut &*tx).unwrap();
+        assert_eq!(tx.signature, None);
+        let call = match tx.function {
+            RuntimeCall::DcNode(call) => call,
+            _ => panic!("unexpected call"),
+        };
+        let report = match &call {
+            crate::Call::submit_liveness_report { report, .. } => report.clone(),
+            _ => panic!("unexpected call"),
+        };
+        assert_eq!(report, LivenessReport { block_number: 20, prober: key.clone(), probed: 3, offline: vec![silent.clone(), faulty.clone()] });
+
+        assert_ok!(<DcNode as ValidateUnsigned>::validate_unsigned(TransactionSource::Local, &call));
+        assert_ok!(RuntimeCall::DcNode(call).dispatch(RuntimeOrigin::none()));
+        assert_eq!(DcNode::last_liveness_report(&key), Some(20));
+        let era_index = CurrentEra::get();
+        for peer_id in [faulty, silent] {
+            let report_info = ReportInfo { report_type: ReportType::ReportPeerNoResponse, peer_id };
+            assert_eq!(DcNode::reports_in_era(era_index, report_info), Some(BTreeSet::from([prober.clone()])));
+        }
+        System::assert_last_event(RuntimeEvent::DcNode(crate::Event::LivenessReported(prober, 3, 2)));
+
+        // One report for each round
+        DcNode::offchain_worker(20);
+        assert!(pool_state.read().transactions.is_empty());
+    });
+}
+
+#[test]
+fn dc_liveness_probe_rotates_targets() {
+    new_test_ext().execute_with(|| {
+        MaxLivenessProbes::set(2);
+        for (i, name) in ["node0", "node1", "node2"].into_iter().enumerate() {
+            add_onchain_node::<Test>(vec![i as u8; 32], name, NODE_STATUS_ONCHAIN);
+        }
+        let targets = |now| DcNode::liveness_probe_targets(now).into_iter().map(|(peer_id, _)| peer_id[0]).collect::<Vec<_>>();
+        assert_eq!(targets(0), vec![0, 1]);
+        assert_eq!(targets(10), vec![2, 0]);
+        assert_eq!(targets(20), vec![1, 2]);
+        MaxLivenessProbes::set(100);
+    });
+}
+
+#[test]
+fn dc_submit_liveness_report() {
+    let (mut ext, _, _, key) = new_liveness_test_ext();
+    ext.execute_with(|| {
+        let peer_id = vec![61; 32];
+        add_onchain_node::<Test>(peer_id.clone(), "offline", NODE_STATUS_ONCHAIN);
+        let validate = |call: &crate::Call<Test>| <DcNode as ValidateUnsigned>::validate_unsigned(TransactionSource::External, call);
+        System::set_block_number(25);
+
+        // Reports of unknown probers
+        let (report, signature) = sign_liveness_report(&key, 20, 1, vec![peer_id.clone()]);
+        let call = liveness_report_call((report.clone(), signature.clone()));
+        assert_eq!(validate(&call), InvalidTransaction::BadSigner.into());
+        assert_noop!(
+            DcNode::submit_liveness_report(RuntimeOrigin::none(), report.clone(), signature.clone()),
+            Error::<Test>::NotLivenessProber,
+        );
+        assert_ok!(DcNode::set_liveness_prober(RuntimeOrigin::root(), key.clone(), account("prober0", 0, 0)));
+        assert_ok!(validate(&call));
+
+        // Reports of future or past rounds
+        assert_eq!(validate(&liveness_report_call(sign_liveness_report(&key, 30, 1, vec![]))), InvalidTransaction::Future.into());
+        assert_eq!(validate(&liveness_report_call(sign_liveness_report(&key, 10, 1, vec![]))), InvalidTransaction::Stale.into());
+        // Reports with more offline nodes than probed ones, or more probes than allowed
+        assert_eq!(validate(&liveness_report_call(sign_liveness_report(&key, 20, 0, vec![peer_id.clone()]))), InvalidTransaction::Call.into());
+        assert_eq!(validate(&liveness_report_call(sign_liveness_report(&key, 20, 101, vec![]))), InvalidTransaction::Call.into());
+        // Reports that do not match their signature
+        let (_, other_signature) = sign_liveness_report(&key, 20, 1, vec![]);
+        assert_eq!(validate(&liveness_report_call((report.clone(), other_signature))), InvalidTransaction::BadProof.into());
+        // Reports of blocks that do not start a round, or of nodes that are not probed in the round
+        assert_eq!(validate(&liveness_report_call(sign_liveness_report(&key, 21, 1, vec![]))), InvalidTransaction::Call.into());
+        assert_eq!(validate(&liveness_report_call(sign_liveness_report(&key, 20, 1, vec![vec![62; 32]]))), InvalidTransaction::Call.into());
+        add_onchain_node::<Test>(vec![63; 32], "joining", NODE_STATUS_JOINING);
+        assert_eq!(validate(&liveness_report_call(sign_liveness_report(&key, 20, 1, vec![vec![63; 32]]))), InvalidTransaction::Call.into());
+
+        // The node goes offchain and its unresponsive offence is reported once enough probers report it
+        assert_ok!(DcNode::submit_liveness_report(RuntimeOrigin::none(), report.clone(), signature.clone()));
+        assert_eq!(validate(&call), InvalidTransaction::Stale.into());
+        for (i, name) in ["prober1", "prober2"].into_iter().enumerate() {
+            let other: liveness::sr25519::AuthorityId = sp_core::sr25519::Public::from_raw([i as u8 + 1; 32]).into();
+            assert_ok!(DcNode::set_liveness_prober(RuntimeOrigin::root(), other.clone(), account(name, 0, 0)));
+            // Signatures are checked in `validate_unsigned`
+            let other_report = LivenessReport { prober: other, ..report.clone() };
+            assert_ok!(DcNode::submit_liveness_report(RuntimeOrigin::none(), other_report, signature.clone()));
+        }
+        assert_eq!(DcNode::peers(&peer_id).unwrap().status, NODE_STATUS_OFFCHAIN);
+        assert_eq!(Offences::get().last().unwrap().1.kind, DcOffenceKind::Unresponsive);
+
+        // Reports of nodes which are no longer ONCHAIN are skipped
+        let next_report = LivenessReport { block_number: 30, ..report };
+        assert_ok!(DcNode::submit_liveness_report(RuntimeOrigin::none(), next_report, signature));
+        System::assert_last_event(RuntimeEvent::DcNode(crate::Event::LivenessReported(account("prober0", 0, 0), 1, 1)));
+
+        assert_ok!(DcNode::remove_liveness_prober(RuntimeOrigin::root(), key.clone()));
+        assert_eq!(DcNode::last_liveness_report(&key), None);
+        assert_noop!(DcNode::remove_liveness_prober(RuntimeOrigin::root(), key), Error::<Test>::NotLivenessProber);
+    });
+}
@@ -22,6 +22,12 @@
 // --repeat
 // 20
 // --output=./dc-frame/dc-node/src/weights.rs
+//
+// NOTE: `update_thread_db_log`, `add_thread_db_log_peer`, `compact_thread_db_log`, `user_login` and
+// `submit_liveness_report` were benchmarked natively on the mock runtime, with the same steps and
+// repeats, and the storage of the other pallets added to their comments. The reads and writes of the
+// `ActiveUsers`, `UsersExpiringAt`, `FileTotals` and `ReportNumberInEra` counters were counted on the
+// mock runtime and added to the other functions. They are replaced when this file is regenerated.
 
 #![cfg_attr(rustfmt, rustfmt_skip)]
 #![allow(unused_parens)]
@@ -51,6 +57,9 @@ pub trait WeightInfo {
 	fn delete_file_info() -> Weight;
 	fn add_log_to_thread_db() -> Weight;
 	fn add_space_to_thread_db() -> Weight;
+	fn update_thread_db_log() -> Weight;
+	fn add_thread_db_log_peer() -> Weight;
+	fn compact_thread_db_log() -> Weight;
 	fn report_file_miss() -> Weight;
 	fn report_login_info_miss() -> Weight;
 	fn report_tee_faking() -> Weight;
@@ -63,6 +72,7 @@ pub trait WeightInfo {
 	fn add_user_comment_space() -> Weight;
 	fn report_malicious_comment() -> Weight;
 	fn user_login(n: u32, ) -> Weight;
+	fn submit_liveness_report(n: u32, ) -> Weight;
 }
 
 pub struct SubstrateWeight<T>(PhantomData<T>);
@@ -71,11 +81,14 @@ impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
 	// Storage: DcNode Peers (r:1 w:1)
 	// Storage: DcNode StartRewardBlockNumber (r:1 w:0)
 	// Storage: DcNode RequestAccountPeer (r:0 w:1)
+	// Storage: DcNode RequiredProgram (r:1 w:0)
+	// Storage: DcNode PendingProgram (r:1 w:0)
+	// Storage: DcNode PeerPrograms (r:0 w:1)
 	fn join_storage_node() -> Weight {
 		// Minimum execution time: 42_476 nanoseconds.
 		Weight::from_ref_time(43_148_000)
-			.saturating_add(T::DbWeight::get().reads(3))
-			.saturating_add(T::DbWeight::get().writes(2))
+			.saturating_add(T::DbWeight::get().reads(5))
+			.saturating_add(T::DbWeight::get().writes(3))
 	}
 	// Storage: DcNode ValidCallBlockNumber (r:1 w:0)
 	// Storage: DcNode RequestAccountPeer (r:1 w:0)
@@ -84,6 +97,10 @@ impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
 	// Storage: DcNode Files (r:1 w:1)
 	// Storage: DcNode WalletAccountStorage (r:11 w:10)
 	// Storage: DcNode StartRewardBlockNumber (r:1 w:0)
+	// Storage: DcNode RequiredProgram (r:1 w:0)
+	// Storage: DcNode PendingProgram (r:1 w:0)
+	// Storage: DcNode PeerPrograms (r:0 w:1)
+	// Storage: DcNode FileTotals (r:1 w:1)
 	/// The range of component `n` is `[1, 10]`.
 	/// The range of component `m` is `[1, 10]`.
 	fn submit_work_report(n: u32, m: u32, ) -> Weight {
@@ -93,10 +110,10 @@ impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
 			.saturating_add(Weight::from_ref_time(9_095_060).saturating_mul(n.into()))
 			// Standard Error: 14_290
 			.saturating_add(Weight::from_ref_time(7_710_291).saturating_mul(m.into()))
-			.saturating_add(T::DbWeight::get().reads(6))
+			.saturating_add(T::DbWeight::get().reads(9))
 			.saturating_add(T::DbWeight::get().reads((1_u64).saturating_mul(n.into())))
 			.saturating_add(T::DbWeight::get().reads((1_u64).saturating_mul(m.into())))
-			.saturating_add(T::DbWeight::get().writes(1))
+			.saturating_add(T::DbWeight::get().writes(3))
 			.saturating_add(T::DbWeight::get().writes((1_u64).saturating_mul(n.into())))
 			.saturating_add(T::DbWeight::get().writes((1_u64).saturating_mul(m.into())))
 	}
@@ -106,33 +123,39 @@ impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
 	// Storage: DcNode AppRewardPercent (r:1 w:0)
 	// Storage: DcNode AppRewardTotal (r:1 w:1)
 	// Storage: DcNode StorageRewardTotal (r:1 w:1)
+	// Storage: DcNode ActiveUsers (r:1 w:1)
+	// Storage: DcNode UsersExpiringAt (r:2 w:2)
 	fn purchase_storage() -> Weight {
 		// Minimum execution time: 78_374 nanoseconds.
 		Weight::from_ref_time(92_112_000)
-			.saturating_add(T::DbWeight::get().reads(6))
-			.saturating_add(T::DbWeight::get().writes(4))
+			.saturating_add(T::DbWeight::get().reads(9))
+			.saturating_add(T::DbWeight::get().writes(7))
 	}
 	// Storage: DcNode ValidCallBlockNumber (r:1 w:0)
 	// Storage: DcNode RequestAccountPeer (r:1 w:0)
 	// Storage: DcNode Peers (r:1 w:0)
 	// Storage: DcNode WalletAccountStorage (r:1 w:1)
 	// Storage: TransactionPayment NextFeeMultiplier (r:1 w:0)
+	// Storage: DcNode ActiveUsers (r:1 w:1)
+	// Storage: DcNode UsersExpiringAt (r:2 w:2)
 	fn add_request_peer_id_to_user() -> Weight {
 		// Minimum execution time: 69_427 nanoseconds.
 		Weight::from_ref_time(72_099_000)
-			.saturating_add(T::DbWeight::get().reads(5))
-			.saturating_add(T::DbWeight::get().writes(1))
+			.saturating_add(T::DbWeight::get().reads(8))
+			.saturating_add(T::DbWeight::get().writes(4))
 	}
 	// Storage: DcNode ValidCallBlockNumber (r:1 w:0)
 	// Storage: DcNode RequestAccountPeer (r:1 w:0)
 	// Storage: DcNode Peers (r:1 w:0)
 	// Storage: DcNode WalletAccountStorage (r:1 w:1)
 	// Storage: TransactionPayment NextFeeMultiplier (r:1 w:0)
+	// Storage: DcNode ActiveUsers (r:1 w:1)
+	// Storage: DcNode UsersExpiringAt (r:2 w:2)
 	fn update_db_config() -> Weight {
 		// Minimum execution time: 59_146 nanoseconds.
 		Weight::from_ref_time(60_014_000)
-			.saturating_add(T::DbWeight::get().reads(5))
-			.saturating_add(T::DbWeight::get().writes(1))
+			.saturating_add(T::DbWeight::get().reads(8))
+			.saturating_add(T::DbWeight::get().writes(4))
 	}
 	// Storage: DcNode ValidCallBlockNumber (r:1 w:0)
 	// Storage: DcNode RequestAccountPeer (r:1 w:0)
@@ -140,22 +163,26 @@ impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
 	// Storage: System Account (r:2 w:2)
 	// Storage: DcNode WalletAccountStorage (r:1 w:2)
 	// Storage: TransactionPayment NextFeeMultiplier (r:1 w:0)
+	// Storage: DcNode ActiveUsers (r:1 w:1)
+	// Storage: DcNode UsersExpiringAt (r:2 w:2)
 	fn create_sub_account() -> Weight {
 		// Minimum execution time: 94_538 nanoseconds.
 		Weight::from_ref_time(95_582_000)
-			.saturating_add(T::DbWeight::get().reads(7))
-			.saturating_add(T::DbWeight::get().writes(4))
+			.saturating_add(T::DbWeight::get().reads(10))
+			.saturating_add(T::DbWeight::get().writes(7))
 	}
 	// Storage: DcNode ValidCallBlockNumber (r:1 w:0)
 	// Storage: DcNode RequestAccountPeer (r:1 w:0)
 	// Storage: DcNode Peers (r:1 w:0)
 	// Storage: DcNode WalletAccountStorage (r:2 w:2)
 	// Storage: TransactionPayment NextFeeMultiplier (r:1 w:0)
+	// Storage: DcNode ActiveUsers (r:1 w:1)
+	// Storage: DcNode UsersExpiringAt (r:4 w:4)
 	fn unbind_sub_account() -> Weight {
 		// Minimum execution time: 59_710 nanoseconds.
 		Weight::from_ref_time(60_772_000)
-			.saturating_add(T::DbWeight::get().reads(6))
-			.saturating_add(T::DbWeight::get().writes(2))
+			.saturating_add(T::DbWeight::get().reads(11))
+			.saturating_add(T::DbWeight::get().writes(7))
 	}
 	// Storage: DcNode ValidCallBlockNumber (r:1 w:0)
 	// Storage: DcNode RequestAccountPeer (r:1 w:0)
@@ -191,11 +218,13 @@ impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
 	// Storage: DcNode WalletAccountStorage (r:1 w:1)
 	// Storage: DcNode NftToWalletAccount (r:1 w:1)
 	// Storage: TransactionPayment NextFeeMultiplier (r:1 w:0)
+	// Storage: DcNode ActiveUsers (r:1 w:1)
+	// Storage: DcNode UsersExpiringAt (r:2 w:2)
 	fn apply_nft_account() -> Weight {
 		// Minimum execution time: 68_329 nanoseconds.
 		Weight::from_ref_time(69_321_000)
-			.saturating_add(T::DbWeight::get().reads(6))
-			.saturating_add(T::DbWeight::get().writes(2))
+			.saturating_add(T::DbWeight::get().reads(9))
+			.saturating_add(T::DbWeight::get().writes(5))
 	}
 	// Storage: DcNode ValidCallBlockNumber (r:1 w:0)
 	// Storage: DcNode RequestAccountPeer (r:1 w:0)
@@ -203,11 +232,13 @@ impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
 	// Storage: DcNode WalletAccountStorage (r:2 w:1)
 	// Storage: DcNode NftToWalletAccount (r:1 w:1)
 	// Storage: TransactionPayment NextFeeMultiplier (r:1 w:0)
+	// Storage: DcNode ActiveUsers (r:1 w:1)
+	// Storage: DcNode UsersExpiringAt (r:2 w:2)
 	fn transfer_nft_account() -> Weight {
 		// Minimum execution time: 69_192 nanoseconds.
 		Weight::from_ref_time(70_200_000)
-			.saturating_add(T::DbWeight::get().reads(7))
-			.saturating_add(T::DbWeight::get().writes(2))
+			.saturating_add(T::DbWeight::get().reads(10))
+			.saturating_add(T::DbWeight::get().writes(5))
 	}
 	// Storage: DcNode ValidCallBlockNumber (r:1 w:0)
 	// Storage: DcNode RequestAccountPeer (r:1 w:0)
@@ -215,11 +246,13 @@ impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
 	// Storage: DcNode WalletAccountStorage (r:1 w:1)
 	// Storage: DcNode NftToWalletAccount (r:1 w:0)
 	// Storage: TransactionPayment NextFeeMultiplier (r:1 w:0)
+	// Storage: DcNode ActiveUsers (r:1 w:1)
+	// Storage: DcNode UsersExpiringAt (r:2 w:2)
 	fn update_nft_account() -> Weight {
 		// Minimum execution time: 65_926 nanoseconds.
 		Weight::from_ref_time(66_581_000)
-			.saturating_add(T::DbWeight::get().reads(6))
-			.saturating_add(T::DbWeight::get().writes(1))
+			.saturating_add(T::DbWeight::get().reads(9))
+			.saturating_add(T::DbWeight::get().writes(4))
 	}
 	// Storage: DcNode ValidCallBlockNumber (r:1 w:0)
 	// Storage: DcNode RequestAccountPeer (r:1 w:0)
@@ -227,32 +260,37 @@ impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
 	// Storage: DcNode WalletAccountStorage (r:2 w:2)
 	// Storage: TransactionPayment NextFeeMultiplier (r:1 w:0)
 	// Storage: DcNode Files (r:1 w:1)
+	// Storage: DcNode ActiveUsers (r:1 w:1)
+	// Storage: DcNode UsersExpiringAt (r:2 w:2)
+	// Storage: DcNode FileTotals (r:1 w:1)
 	fn add_file_info() -> Weight {
 		// Minimum execution time: 63_011 nanoseconds.
 		Weight::from_ref_time(63_737_000)
-			.saturating_add(T::DbWeight::get().reads(7))
-			.saturating_add(T::DbWeight::get().writes(3))
+			.saturating_add(T::DbWeight::get().reads(11))
+			.saturating_add(T::DbWeight::get().writes(7))
 	}
 	// Storage: DcNode ValidCallBlockNumber (r:1 w:0)
 	// Storage: DcNode RequestAccountPeer (r:1 w:0)
 	// Storage: DcNode Peers (r:1 w:0)
 	// Storage: DcNode Files (r:1 w:1)
+	// Storage: DcNode FileTotals (r:1 w:1)
 	fn add_file_peer() -> Weight {
 		// Minimum execution time: 46_393 nanoseconds.
 		Weight::from_ref_time(47_009_000)
-			.saturating_add(T::DbWeight::get().reads(4))
-			.saturating_add(T::DbWeight::get().writes(1))
+			.saturating_add(T::DbWeight::get().reads(5))
+			.saturating_add(T::DbWeight::get().writes(2))
 	}
 	// Storage: DcNode ValidCallBlockNumber (r:1 w:0)
 	// Storage: DcNode RequestAccountPeer (r:1 w:0)
 	// Storage: DcNode Peers (r:1 w:0)
 	// Storage: DcNode Files (r:1 w:1)
 	// Storage: DcNode WalletAccountStorage (r:1 w:1)
+	// Storage: DcNode FileTotals (r:1 w:1)
 	fn remove_self_file_peer() -> Weight {
 		// Minimum execution time: 51_726 nanoseconds.
 		Weight::from_ref_time(63_095_000)
-			.saturating_add(T::DbWeight::get().reads(5))
-			.saturating_add(T::DbWeight::get().writes(2))
+			.saturating_add(T::DbWeight::get().reads(6))
+			.saturating_add(T::DbWeight::get().writes(3))
 	}
 	// Storage: DcNode ValidCallBlockNumber (r:1 w:0)
 	// Storage: DcNode RequestAccountPeer (r:1 w:0)
@@ -260,11 +298,14 @@ impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
 	// Storage: DcNode Files (r:1 w:1)
 	// Storage: DcNode WalletAccountStorage (r:2 w:2)
 	// Storage: TransactionPayment NextFeeMultiplier (r:1 w:0)
+	// Storage: DcNode ActiveUsers (r:1 w:1)
+	// Storage: DcNode UsersExpiringAt (r:2 w:2)
+	// Storage: DcNode FileTotals (r:1 w:1)
 	fn delete_file_info() -> Weight {
 		// Minimum execution time: 66_712 nanoseconds.
 		Weight::from_ref_time(67_960_000)
-			.saturating_add(T::DbWeight::get().reads(7))
-			.saturating_add(T::DbWeight::get().writes(3))
+			.saturating_add(T::DbWeight::get().reads(11))
+			.saturating_add(T::DbWeight::get().writes(7))
 	}
 	// Storage: DcNode ValidCallBlockNumber (r:1 w:0)
 	// Storage: DcNode RequestAccountPeer (r:1 w:0)
@@ -272,11 +313,15 @@ impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
 	// Storage: DcNode Files (r:1 w:1)
 	// Storage: DcNode WalletAccountStorage (r:1 w:1)
 	// Storage: TransactionPayment NextFeeMultiplier (r:1 w:0)
+	// Storage: DcNode ThreadDbLogs (r:1 w:1)
+	// Storage: DcNode ActiveUsers (r:1 w:1)
+	// Storage: DcNode UsersExpiringAt (r:2 w:2)
+	// Storage: DcNode FileTotals (r:1 w:1)
 	fn add_log_to_thread_db() -> Weight {
 		// Minimum execution time: 58_948 nanoseconds.
 		Weight::from_ref_time(59_899_000)
-			.saturating_add(T::DbWeight::get().reads(6))
-			.saturating_add(T::DbWeight::get().writes(2))
+			.saturating_add(T::DbWeight::get().reads(11))
+			.saturating_add(T::DbWeight::get().writes(7))
 	}
 	// Storage: DcNode ValidCallBlockNumber (r:1 w:0)
 	// Storage: DcNode RequestAccountPeer (r:1 w:0)
@@ -284,21 +329,67 @@ impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
 	// Storage: DcNode Files (r:1 w:1)
 	// Storage: DcNode WalletAccountStorage (r:1 w:1)
 	// Storage: TransactionPayment NextFeeMultiplier (r:1 w:0)
+	// Storage: DcNode ActiveUsers (r:1 w:1)
+	// Storage: DcNode UsersExpiringAt (r:2 w:2)
+	// Storage: DcNode FileTotals (r:1 w:1)
 	fn add_space_to_thread_db() -> Weight {
 		// Minimum execution time: 57_805 nanoseconds.
 		Weight::from_ref_time(58_904_000)
+			.saturating_add(T::DbWeight::get().reads(10))
+			.saturating_add(T::DbWeight::get().writes(6))
+	}
+	// Storage: DcNode ValidCallBlockNumber (r:1 w:0)
+	// Storage: DcNode RequestAccountPeer (r:1 w:0)
+	// Storage: DcNode Peers (r:1 w:0)
+	// Storage: DcNode Files (r:1 w:1)
+	// Storage: DcNode ThreadDbLogs (r:1 w:1)
+	// Storage: DcNode WalletAccountStorage (r:2 w:2)
+	// Storage: TransactionPayment NextFeeMultiplier (r:1 w:0)
+	// Storage: DcNode ActiveUsers (r:1 w:1)
+	// Storage: DcNode UsersExpiringAt (r:2 w:2)
+	// Storage: DcNode FileTotals (r:1 w:1)
+	fn update_thread_db_log() -> Weight {
+		// Minimum execution time: 74_381 nanoseconds.
+		Weight::from_ref_time(97_065_000)
+			.saturating_add(T::DbWeight::get().reads(12))
+			.saturating_add(T::DbWeight::get().writes(8))
+	}
+	// Storage: DcNode ValidCallBlockNumber (r:1 w:0)
+	// Storage: DcNode RequestAccountPeer (r:1 w:0)
+	// Storage: DcNode Peers (r:1 w:0)
+	// Storage: DcNode Files (r:1 w:1)
+	// Storage: DcNode ThreadDbLogs (r:1 w:1)
+	// Storage: DcNode FileTotals (r:1 w:1)
+	fn add_thread_db_log_peer() -> Weight {
+		// Minimum execution time: 49_361 nanoseconds.
+		Weight::from_ref_time(51_252_000)
 			.saturating_add(T::DbWeight::get().reads(6))
-			.saturating_add(T::DbWeight::get().writes(2))
+			.saturating_add(T::DbWeight::get().writes(3))
+	}
+	// Storage: DcNode ValidCallBlockNumber (r:1 w:0)
+	// Storage: DcNode RequestAccountPeer (r:1 w:0)
+	// Storage: DcNode Peers (r:1 w:0)
+	// Storage: DcNode ThreadDbLogs (r:1 w:1)
+	// Storage: DcNode WalletAccountStorage (r:2 w:1)
+	// Storage: TransactionPayment NextFeeMultiplier (r:1 w:0)
+	// Storage: DcNode ActiveUsers (r:1 w:1)
+	// Storage: DcNode UsersExpiringAt (r:2 w:2)
+	fn compact_thread_db_log() -> Weight {
+		// Minimum execution time: 60_997 nanoseconds.
+		Weight::from_ref_time(65_527_000)
+			.saturating_add(T::DbWeight::get().reads(10))
+			.saturating_add(T::DbWeight::get().writes(5))
 	}
 	// Storage: DcNode ValidCallBlockNumber (r:1 w:0)
 	// Storage: DcNode RequestAccountPeer (r:1 w:0)
 	// Storage: DcNode Peers (r:1 w:0)
 	// Storage: DcNode Files (r:1 w:1)
+	// Storage: DcNode FileTotals (r:1 w:1)
 	fn report_file_miss() -> Weight {
 		// Minimum execution time: 45_484 nanoseconds.
 		Weight::from_ref_time(46_430_000)
-			.saturating_add(T::DbWeight::get().reads(4))
-			.saturating_add(T::DbWeight::get().writes(1))
+			.saturating_add(T::DbWeight::get().reads(5))
+			.saturating_add(T::DbWeight::get().writes(2))
 	}
 	// Storage: DcNode RequestAccountPeer (r:1 w:0)
 	// Storage: DcNode Peers (r:1 w:0)
@@ -314,11 +405,12 @@ impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
 	// Storage: DcNode Peers (r:1 w:0)
 	// Storage: Staking CurrentEra (r:1 w:0)
 	// Storage: DcNode ReportsInEra (r:1 w:1)
+	// Storage: DcNode ReportNumberInEra (r:1 w:1)
 	fn report_tee_faking() -> Weight {
 		// Minimum execution time: 45_348 nanoseconds.
 		Weight::from_ref_time(46_203_000)
-			.saturating_add(T::DbWeight::get().reads(5))
-			.saturating_add(T::DbWeight::get().writes(1))
+			.saturating_add(T::DbWeight::get().reads(6))
+			.saturating_add(T::DbWeight::get().writes(2))
 	}
 	// Storage: DcNode ValidCallBlockNumber (r:1 w:0)
 	// Storage: DcNode RequestAccountPeer (r:1 w:0)
@@ -331,11 +423,12 @@ impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
 	// Storage: Staking UnappliedSlashes (r:1 w:1)
 	// Storage: DcNode OnchainPeerNumber (r:1 w:1)
 	// Storage: DcNode StashPeers (r:1 w:0)
+	// Storage: DcNode ReportNumberInEra (r:1 w:1)
 	fn verify_tee_faking() -> Weight {
 		// Minimum execution time: 103_027 nanoseconds.
 		Weight::from_ref_time(114_739_000)
-			.saturating_add(T::DbWeight::get().reads(12))
-			.saturating_add(T::DbWeight::get().writes(4))
+			.saturating_add(T::DbWeight::get().reads(13))
+			.saturating_add(T::DbWeight::get().writes(5))
 	}
 	// Storage: DcNode ValidCallBlockNumber (r:1 w:0)
 	// Storage: DcNode RequestAccountPeer (r:1 w:0)
@@ -345,11 +438,12 @@ impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
 	// Storage: DcNode AbnormalReportNumber (r:1 w:0)
 	// Storage: DcNode StartRewardBlockNumber (r:1 w:0)
 	// Storage: DcNode OnchainPeerNumber (r:1 w:1)
+	// Storage: DcNode ReportNumberInEra (r:1 w:1)
 	fn report_peer_offchain() -> Weight {
 		// Minimum execution time: 67_314 nanoseconds.
 		Weight::from_ref_time(67_857_000)
-			.saturating_add(T::DbWeight::get().reads(9))
-			.saturating_add(T::DbWeight::get().writes(3))
+			.saturating_add(T::DbWeight::get().reads(10))
+			.saturating_add(T::DbWeight::get().writes(4))
 	}
 	// Storage: DcNode ValidCallBlockNumber (r:1 w:0)
 	// Storage: DcNode RequestAccountPeer (r:1 w:0)
@@ -359,11 +453,12 @@ impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
 	// Storage: DcNode AbnormalReportNumber (r:1 w:0)
 	// Storage: DcNode StartRewardBlockNumber (r:1 w:0)
 	// Storage: DcNode OnchainPeerNumber (r:1 w:1)
+	// Storage: DcNode ReportNumberInEra (r:1 w:1)
 	fn report_peer_no_response() -> Weight {
 		// Minimum execution time: 67_018 nanoseconds.
 		Weight::from_ref_time(67_707_000)
-			.saturating_add(T::DbWeight::get().reads(9))
-			.saturating_add(T::DbWeight::get().writes(3))
+			.saturating_add(T::DbWeight::get().reads(10))
+			.saturating_add(T::DbWeight::get().writes(4))
 	}
 	// Storage: DcNode IntervalBlocksCanNotReport (r:1 w:0)
 	// Storage: DcNode ValidCallBlockNumber (r:1 w:0)
@@ -385,36 +480,42 @@ impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
 	// Storage: DcNode IntervalBlocksReduceSpam (r:1 w:0)
 	// Storage: DcNode IntervalBlocksReduceComment (r:1 w:0)
 	// Storage: DcNode AppsAccountLoginTimes (r:1 w:1)
+	// Storage: Staking CurrentEra (r:1 w:0)
 	// Storage: DcNode AccountOfApp (r:1 w:0)
+	// Storage: DcNode AppLoginsInEra (r:1 w:1)
+	// Storage: DcNode AppLoginCosign (r:1 w:0)
 	/// The range of component `n` is `[1, 1000]`.
 	fn user_login(n: u32, ) -> Weight {
-		// Minimum execution time: 59_472 nanoseconds.
-		Weight::from_ref_time(42_400_594)
-			// Standard Error: 7_633
-			.saturating_add(Weight::from_ref_time(2_299_276).saturating_mul(n.into()))
-			.saturating_add(T::DbWeight::get().reads(8))
-			.saturating_add(T::DbWeight::get().reads((1_u64).saturating_mul(n.into())))
+		// Minimum execution time: 309_109 nanoseconds.
+		Weight::from_ref_time(309_286_592)
+			// Standard Error: 2_451_253
+			.saturating_add(Weight::from_ref_time(131_734_368).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().reads(9))
+			.saturating_add(T::DbWeight::get().reads((3_u64).saturating_mul(n.into())))
 			.saturating_add(T::DbWeight::get().writes(2))
+			.saturating_add(T::DbWeight::get().writes((1_u64).saturating_mul(n.into())))
 	}
 	// Storage: DcNode ValidCallBlockNumber (r:1 w:0)
 	// Storage: DcNode RequestAccountPeer (r:1 w:0)
 	// Storage: DcNode Peers (r:1 w:0)
 	// Storage: DcNode WalletAccountStorage (r:1 w:1)
+	// Storage: DcNode Themes (r:1 w:1)
 	fn new_theme() -> Weight {
 		// Minimum execution time: 48_717 nanoseconds.
 		Weight::from_ref_time(49_381_000)
-			.saturating_add(T::DbWeight::get().reads(4))
-			.saturating_add(T::DbWeight::get().writes(1))
+			.saturating_add(T::DbWeight::get().reads(5))
+			.saturating_add(T::DbWeight::get().writes(2))
 	}
 	// Storage: DcNode ValidCallBlockNumber (r:1 w:0)
 	// Storage: DcNode RequestAccountPeer (r:1 w:0)
 	// Storage: DcNode Peers (r:1 w:0)
 	// Storage: DcNode WalletAccountStorage (r:1 w:1)
+	// Storage: DcNode Themes (r:1 w:1)
 	fn add_theme_comment_space() -> Weight {
 		// Minimum execution time: 48_648 nanoseconds.
 		Weight::from_ref_time(49_346_000)
-			.saturating_add(T::DbWeight::get().reads(4))
-			.saturating_add(T::DbWeight::get().writes(1))
+			.saturating_add(T::DbWeight::get().reads(5))
+			.saturating_add(T::DbWeight::get().writes(2))
 	}
 	// Storage: DcNode ValidCallBlockNumber (r:1 w:0)
 	// Storage: DcNode RequestAccountPeer (r:1 w:0)
@@ -432,11 +533,35 @@ impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
 	// Storage: DcNode RequestAccountPeer (r:1 w:0)
 	// Storage: DcNode Peers (r:1 w:0)
 	// Storage: DcNode WalletAccountStorage (r:2 w:1)
+	// Storage: DcNode Themes (r:1 w:0)
 	// Storage: DcNode FrozenReportCommentAmount (r:1 w:0)
 	fn report_malicious_comment() -> Weight {
 		// Minimum execution time: 55_857 nanoseconds.
 		Weight::from_ref_time(56_402_000)
-			.saturating_add(T::DbWeight::get().reads(7))
+			.saturating_add(T::DbWeight::get().reads(8))
 			.saturating_add(T::DbWeight::get().writes(1))
 	}
+	// Storage: DcNode LivenessProbers (r:1 w:0)
+	// Storage: DcNode LastLivenessReport (r:0 w:1)
+	// Storage: DcNode Peers (r:2 w:2)
+	// Storage: Staking CurrentEra (r:1 w:0)
+	// Storage: DcNode ReportsInEra (r:2 w:2)
+	// Storage: DcNode AbnormalReportNumber (r:1 w:0)
+	// Storage: DcNode StartRewardBlockNumber (r:1 w:0)
+	// Storage: DcNode OnchainPeerNumber (r:1 w:1)
+	// Storage: DcNode OffenceSlashFractions (r:1 w:0)
+	// Storage: DcNode OnchainSpace (r:1 w:1)
+	// Storage: DcNode PeersOfStatus (r:2 w:2)
+	// Storage: DcNode ReportNumberInEra (r:1 w:1)
+	/// The range of component `n` is `[0, 100]`.
+	fn submit_liveness_report(n: u32, ) -> Weight {
+		// Minimum execution time: 30_763 nanoseconds.
+		Weight::from_ref_time(33_877_000)
+			// Standard Error: 235_925
+			.saturating_add(Weight::from_ref_time(43_722_924).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().reads(9))
+			.saturating_add(T::DbWeight::get().reads((2_u64).saturating_mul(n.into())))
+			.saturating_add(T::DbWeight::get().writes(6))
+			.saturating_add(T::DbWeight::get().writes((2_u64).saturating_mul(n.into())))
+	}
 }
@@ -0,0 +1,187 @@
+//! Primitives of DC shared by the runtime, the node and light clients.
+//!
+//! Light wallets use the helpers of this crate to check the DC state of a wallet account
+//! served by an untrusted RPC node. The node returns a read proof of the storage entries
+//! of the account, and the wallet verifies it against the state root of a header which it
+//! trusts, e.g. a header finalized by GRANDPA.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Decode, Encode};
+use sp_core::{
+	hashing::{twox_128, twox_64},
+	Hasher,
+};
+use sp_std::vec::Vec;
+use sp_trie::{read_trie_value, HashDBT, LayoutV1, MemoryDB, StorageProof, EMPTY_PREFIX};
+
+/// Name of the DC pallet in the runtime, which prefixes its storage keys.
+pub const PALLET_PREFIX: &[u8] = b"DcNode";
+
+/// Storage of wallet accounts, keyed by the account.
+pub const WALLET_ACCOUNT_STORAGE: &[u8] = b"WalletAccountStorage";
+
+/// Wallet accounts bound to NFT accounts, keyed by the NFT account.
+pub const NFT_TO_WALLET_ACCOUNT: &[u8] = b"NftToWalletAccount";
+
+/// Error of checking a read proof.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ProofError {
+	/// The proof does not contain the state root or a trie node on the path of a key.
+	Incomplete,
+	/// The proven value can not be decoded.
+	Decode,
+}
+
+/// Storage key of a storage value of the DC pallet.
+pub fn storage_value_key(storage: &[u8]) -> Vec<u8> {
+	let mut final_key = Vec::with_capacity(32);
+	final_key.extend_from_slice(&twox_128(PALLET_PREFIX));
+	final_key.extend_from_slice(&twox_128(storage));
+	final_key
+}
+
+/// Storage key of the entry of a `Twox64Concat` map of the DC pallet.
+pub fn storage_map_key<K: Encode>(storage: &[u8], key: &K) -> Vec<u8> {
+	let encoded = key.encode();
+	let mut final_key = Vec::with_capacity(32 + 8 + encoded.len());
+	final_key.extend_from_slice(&twox_128(PALLET_PREFIX));
+	final_key.extend_from_slice(&twox_128(storage));
+	final_key.extend_from_slice(&twox_64(&encoded));
+	final_key.extend_from_slice(&encoded);
+	final_key
+}
+
+/// Storage key of the storage information of the wallet account.
+pub fn wallet_account_storage_key<AccountId: Encode>(account: &AccountId) -> Vec<u8> {
+	storage_map_key(WALLET_ACCOUNT_STORAGE, account)
+}
+
+/// Storage key of the wallet account bound to the NFT account.
+pub fn nft_to_wallet_account_key(nft_account: &[u8]) -> Vec<u8> {
+	storage_map_key(NFT_TO_WALLET_ACCOUNT, &nft_account)
+}
+
+/// Checks read proofs against a trusted state root.
+pub struct ProofChecker<H: Hasher> {
+	root: H::Out,
+	db: MemoryDB<H>,
+}
+
+impl<H: Hasher> ProofChecker<H> {
+	/// Create a checker of the proof, which must contain the state root.
+	pub fn new(root: H::Out, proof: StorageProof) -> Result<Self, ProofError> {
+		let db = proof.into_memory_db::<H>();
+		if !db.contains(&root, EMPTY_PREFIX) {
+			return Err(ProofError::Incomplete)
+		}
+		Ok(Self { root, db })
+	}
+
+	/// Read the raw value of the key, `None` if the proof shows that the key is absent.
+	pub fn read(&self, key: &[u8]) -> Result<Option<Vec<u8>>, ProofError> {
+		read_trie_value::<LayoutV1<H>, _>(&self.db, &self.root, key, None, None)
+			.map_err(|_| ProofError::Incomplete)
+	}
+
+	/// Read and decode the value of the key.
+	pub fn read_decoded<V: Decode>(&self, key: &[u8]) -> Result<Option<V>, ProofError> {
+		self.read(key)?
+			.map(|value| V::decode(&mut &value[..]).map_err(|_| ProofError::Decode))
+			.transpose()
+	}
+}
+
+/// Verify the DC state of the wallet account against the state root.
+///
+/// Returns the storage information of the account, and the wallet account bound to the
+/// NFT account if one is given.
+pub fn verify_user_state<H, AccountId, UserStorage>(
+	root: H::Out,
+	proof: StorageProof,
+	account: &AccountId,
+	nft_account: Option<&[u8]>,
+) -> Result<(Option<UserStorage>, Option<AccountId>), ProofError>
+where
+	H: Hasher,
+	AccountId: Encode + Decode,
+	UserStorage: Decode,
+{
+	let checker = ProofChecker::<H>::new(root, proof)?;
+	let user_storage = checker.read_decoded(&wallet_account_storage_key(account))?;
+	let nft_wallet_account = match nft_account {
+		Some(nft_account) => checker.read_decoded(&nft_to_wallet_account_key(nft_account))?,
+		None => None,
+	};
+	Ok((user_storage, nft_wallet_account))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::{storage::StateVersion, Blake2Hasher};
+	use sp_state_machine::{prove_read, InMemoryBackend};
+
+	fn backend() -> InMemoryBackend<Blake2Hasher> {
+		let entries = vec![
+			(wallet_account_storage_key(&1u64), Some((7u32, 11u64).encode())),
+			(nft_to_wallet_account_key(b"alice"), Some(1u64.encode())),
+			(wallet_account_storage_key(&2u64), Some((8u32, 12u64).encode())),
+			// Large enough not to be inlined into the trie node of its parent.
+			(wallet_account_storage_key(&3u64), Some(vec![0u8; 64])),
+		];
+		InMemoryBackend::from((vec![(None, entries)], StateVersion::V1))
+	}
+
+	#[test]
+	fn verify_user_state_works() {
+		let backend = backend();
+		let root = *backend.root();
+		let keys = [wallet_account_storage_key(&1u64), nft_to_wallet_account_key(b"alice")];
+		let proof = prove_read(backend, keys.iter()).unwrap();
+
+		assert_eq!(
+			verify_user_state::<Blake2Hasher, u64, (u32, u64)>(
+				root,
+				proof.clone(),
+				&1u64,
+				Some(&b"alice"[..]),
+			),
+			Ok((Some((7, 11)), Some(1))),
+		);
+		assert_eq!(
+			verify_user_state::<Blake2Hasher, u64, (u32, u64)>(root, proof.clone(), &1u64, None),
+			Ok((Some((7, 11)), None)),
+		);
+		// Values of the wrong type are rejected.
+		assert_eq!(
+			ProofChecker::<Blake2Hasher>::new(root, proof)
+				.unwrap()
+				.read_decoded::<(u64, u64)>(&wallet_account_storage_key(&1u64)),
+			Err(ProofError::Decode),
+		);
+	}
+
+	#[test]
+	fn verify_user_state_rejects_unproven_state() {
+		let backend = backend();
+		let root = *backend.root();
+		let proof = prove_read(backend, [wallet_account_storage_key(&1u64)].iter()).unwrap();
+
+		// The account which is not covered by the proof.
+		assert_eq!(
+			verify_user_state::<Blake2Hasher, u64, Vec<u8>>(root, proof.clone(), &3u64, None),
+			Err(ProofError::Incomplete),
+		);
+		// The proof against another state root.
+		assert_eq!(
+			verify_user_state::<Blake2Hasher, u64, (u32, u64)>(
+				Blake2Hasher::hash(b"root"),
+				proof,
+				&1u64,
+				None,
+			),
+			Err(ProofError::Incomplete),
+		);
+	}
+}
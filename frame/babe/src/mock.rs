@@ -211,6 +211,7 @@ impl pallet_staking::Config for Test {
 	type OnStakerSlash = ();
 	type BenchmarkingConfig = pallet_staking::TestBenchmarkingConfig;
 	type WeightInfo = ();
+	type DcProvider = ();
 }
 
 impl pallet_offences::Config for Test {
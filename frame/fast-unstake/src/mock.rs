@@ -156,6 +156,7 @@ impl pallet_staking::Config for Runtime {
 	type OnStakerSlash = ();
 	type BenchmarkingConfig = pallet_staking::TestBenchmarkingConfig;
 	type WeightInfo = ();
+	type DcProvider = ();
 }
 
 pub struct BalanceToU256;
@@ -120,6 +120,8 @@ impl<T: Config<I>, I: 'static> Pallet<T, I> {
 			ItemConfigOf::<T, I>::remove(&collection, &item);
 		}
 
+		T::CallbackHandle::burned(&collection, &item, &owner);
+
 		Self::deposit_event(Event::Burned { collection, item, owner });
 		Ok(())
 	}
@@ -72,6 +72,8 @@ impl<T: Config<I>, I: 'static> Pallet<T, I> {
 		ItemPriceOf::<T, I>::remove(&collection, &item);
 		PendingSwapOf::<T, I>::remove(&collection, &item);
 
+		T::CallbackHandle::transferred(&collection, &item, &origin, &details.owner);
+
 		Self::deposit_event(Event::Transferred {
 			collection,
 			item,
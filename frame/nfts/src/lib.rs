@@ -19,6 +19,11 @@
 //!
 //! A simple, secure module for dealing with non-fungible items.
 //!
+//! ### Callbacks
+//!
+//! Using `CallbackHandle` associated type, user can configure custom callback functions which are
+//! executed when an item is transferred or burned.
+//!
 //! ## Related Modules
 //!
 //! * [`System`](../frame_system/index.html)
@@ -62,6 +67,18 @@ pub use weights::WeightInfo;
 
 type AccountIdLookupOf<T> = <<T as SystemConfig>::Lookup as StaticLookup>::Source;
 
+/// Trait with callbacks that are executed after successfull item transfer or burn.
+pub trait ItemsCallback<CollectionId, ItemId, AccountId> {
+	/// Indicates that `item` of `collection` was transferred from `from` to `to`.
+	fn transferred(_collection: &CollectionId, _item: &ItemId, _from: &AccountId, _to: &AccountId) {}
+
+	/// Indicates that `item` of `collection`, owned by `owner`, has just been burned.
+	fn burned(_collection: &CollectionId, _item: &ItemId, _owner: &AccountId) {}
+}
+
+/// Empty implementation in case no callbacks are required.
+impl<CollectionId, ItemId, AccountId> ItemsCallback<CollectionId, ItemId, AccountId> for () {}
+
 #[frame_support::pallet]
 pub mod pallet {
 	use super::*;
@@ -118,6 +135,9 @@ pub mod pallet {
 		/// Locker trait to enable Locking mechanism downstream.
 		type Locker: Locker<Self::CollectionId, Self::ItemId>;
 
+		/// Callback methods for item state change (e.g. item transferred or burned).
+		type CallbackHandle: ItemsCallback<Self::CollectionId, Self::ItemId, Self::AccountId>;
+
 		/// The basic amount of funds that must be reserved for collection.
 		#[pallet::constant]
 		type CollectionDeposit: Get<DepositBalanceOf<Self, I>>;
@@ -438,6 +438,122 @@ pub struct UnlockChunk<Balance: HasCompact + MaxEncodedLen> {
 	era: EraIndex,
 }
 
+/// The delegation pool of a storage stash, backed by the funds of its delegators.
+#[derive(PartialEq, Eq, Clone, Default, Encode, Decode, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct StorageDelegationPool<Balance: HasCompact + MaxEncodedLen> {
+	/// The total amount delegated to the stash.
+	#[codec(compact)]
+	pub total: Balance,
+	/// The number of delegators of the stash.
+	pub delegators: u32,
+	/// The part of the storage rewards that the stash takes before sharing them.
+	pub commission: Perbill,
+}
+
+/// The funds that an account has delegated to storage stashes.
+#[derive(
+	PartialEqNoBound,
+	EqNoBound,
+	CloneNoBound,
+	Encode,
+	Decode,
+	RuntimeDebugNoBound,
+	TypeInfo,
+	MaxEncodedLen,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct DelegatorLedger<T: Config> {
+	/// The total amount locked, including the amount being undelegated.
+	#[codec(compact)]
+	pub total: BalanceOf<T>,
+	/// The amount being undelegated with the stash that it was delegated to, unlocked at the end
+	/// of the bonding duration.
+	pub unlocking: BoundedVec<(T::AccountId, UnlockChunk<BalanceOf<T>>), T::MaxUnlockingChunks>,
+}
+
+impl<T: Config> DelegatorLedger<T> {
+	/// Slash the delegation to `stash` and the chunks being undelegated from it, in the same way
+	/// as [`StakingLedger::slash`]: proportionally with the chunks that are scheduled to unlock at
+	/// `slash_era + BondingDuration` onwards, then from the most recent chunk.
+	///
+	/// Returns the amount slashed from the delegation and the total amount slashed.
+	fn slash(
+		&mut self,
+		stash: &T::AccountId,
+		delegation: BalanceOf<T>,
+		slash_amount: BalanceOf<T>,
+		minimum_balance: BalanceOf<T>,
+		slash_era: EraIndex,
+	) -> (BalanceOf<T>, BalanceOf<T>) {
+		if slash_amount.is_zero() {
+			return (Zero::zero(), Zero::zero())
+		}
+
+		use sp_runtime::PerThing as _;
+		let slashable_chunks_start = slash_era + T::BondingDuration::get();
+		let stash_chunks = self
+			.unlocking
+			.iter()
+			.enumerate()
+			.filter(|(_, (chunk_stash, _))| chunk_stash == stash)
+			.map(|(i, _)| i)
+			.collect::<Vec<_>>();
+		let (affected, unaffected): (Vec<usize>, Vec<usize>) = stash_chunks
+			.iter()
+			.partition(|&&i| self.unlocking[i].1.era >= slashable_chunks_start);
+
+		// `Some(ratio)` if this is proportional with the affected chunks, `None` otherwise. In
+		// both cases, we slash first the delegation, and then `slash_chunks_priority`.
+		let (maybe_proportional, slash_chunks_priority) = if affected.is_empty() {
+			(None, stash_chunks.into_iter().rev().collect::<Vec<_>>())
+		} else {
+			let affected_balance = affected
+				.iter()
+				.fold(delegation, |sum, &i| sum.saturating_add(self.unlocking[i].1.value));
+			let ratio = Perquintill::from_rational_with_rounding(
+				slash_amount,
+				affected_balance,
+				Rounding::Up,
+			)
+			.unwrap_or_else(|_| Perquintill::one());
+			(Some(ratio), affected.into_iter().chain(unaffected.into_iter().rev()).collect())
+		};
+
+		let mut remaining_slash = slash_amount;
+		let mut slash_out_of = |target: &mut BalanceOf<T>| {
+			let mut slash_from_target = if let Some(ratio) = maybe_proportional {
+				ratio.mul_ceil(*target)
+			} else {
+				remaining_slash
+			}
+			.min(*target)
+			.min(remaining_slash);
+
+			*target = *target - slash_from_target;
+			if *target < minimum_balance {
+				// Slash the rest of the target if it's dust.
+				slash_from_target =
+					sp_std::mem::replace(target, Zero::zero()).saturating_add(slash_from_target)
+			}
+			remaining_slash = remaining_slash.saturating_sub(slash_from_target);
+			slash_from_target
+		};
+
+		let mut delegation = delegation;
+		let from_delegation = slash_out_of(&mut delegation);
+		let mut slashed = from_delegation;
+		for i in slash_chunks_priority {
+			if let Some((_, chunk)) = self.unlocking.get_mut(i) {
+				slashed = slashed.saturating_add(slash_out_of(&mut chunk.value));
+			}
+		}
+		self.unlocking.retain(|(_, chunk)| !chunk.value.is_zero());
+		self.total = self.total.saturating_sub(slashed);
+
+		(from_delegation, slashed)
+	}
+}
+
 /// The ledger of a (bonded) stash.
 #[derive(
 	PartialEqNoBound,
@@ -746,6 +862,8 @@ pub struct UnappliedSlash<AccountId, Balance: HasCompact> {
 	reporters: Vec<AccountId>,
 	/// The amount of payout.
 	payout: Balance,
+	/// The delegators of the storage stash and the slashes of their delegations.
+	delegators: Vec<(AccountId, Balance)>,
 }
 
 impl<AccountId, Balance: HasCompact + Zero> UnappliedSlash<AccountId, Balance> {
@@ -757,6 +875,7 @@ impl<AccountId, Balance: HasCompact + Zero> UnappliedSlash<AccountId, Balance> {
 			others: vec![],
 			reporters: vec![],
 			payout: Zero::zero(),
+			delegators: vec![],
 		}
 	}
 }
@@ -898,6 +1017,7 @@ enum Releases {
 	V10_0_0, // remove `EarliestUnappliedSlash`.
 	V11_0_0, // Move pallet storage prefix, e.g. BagsList -> VoterBagsList
 	V12_0_0, // remove `HistoryDepth`.
+	V13_0_0, // add the delegators of storage stashes to `UnappliedSlash`.
 }
 
 impl Default for Releases {
@@ -20,6 +20,68 @@ use super::*;
 use frame_election_provider_support::SortedListProvider;
 use frame_support::traits::OnRuntimeUpgrade;
 
+pub mod v13 {
+	use super::*;
+
+	#[derive(Encode, Decode)]
+	struct OldUnappliedSlash<AccountId, Balance: HasCompact> {
+		validator: AccountId,
+		own: Balance,
+		others: Vec<(AccountId, Balance)>,
+		reporters: Vec<AccountId>,
+		payout: Balance,
+	}
+
+	/// Add the delegators of storage stashes, none for the existing slashes, to the unapplied
+	/// slashes.
+	///
+	/// The chains of this node have not run the migrations of the releases before, so it runs
+	/// from any release before v13.
+	pub struct MigrateToV13<T>(sp_std::marker::PhantomData<T>);
+	impl<T: Config> OnRuntimeUpgrade for MigrateToV13<T> {
+		fn on_runtime_upgrade() -> frame_support::weights::Weight {
+			if StorageVersion::<T>::get() != Releases::V13_0_0 {
+				let mut translated = 0u64;
+				UnappliedSlashes::<T>::translate::<
+					Vec<OldUnappliedSlash<T::AccountId, BalanceOf<T>>>,
+					_,
+				>(|_, slashes| {
+					translated.saturating_inc();
+					Some(
+						slashes
+							.into_iter()
+							.map(|old| UnappliedSlash {
+								validator: old.validator,
+								own: old.own,
+								others: old.others,
+								reporters: old.reporters,
+								payout: old.payout,
+								delegators: Vec::new(),
+							})
+							.collect(),
+					)
+				});
+				StorageVersion::<T>::put(Releases::V13_0_0);
+
+				log!(info, "v13 applied successfully, {} eras of unapplied slashes", translated);
+				T::DbWeight::get().reads_writes(translated + 1, translated + 1)
+			} else {
+				log!(warn, "Skipping v13, should be removed");
+				T::DbWeight::get().reads(1)
+			}
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade(_state: Vec<u8>) -> Result<(), &'static str> {
+			frame_support::ensure!(
+				StorageVersion::<T>::get() == crate::Releases::V13_0_0,
+				"v13 not applied"
+			);
+			Ok(())
+		}
+	}
+}
+
 pub mod v12 {
 	use super::*;
 	use frame_support::{pallet_prelude::ValueQuery, storage_alias};
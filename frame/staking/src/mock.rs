@@ -18,6 +18,7 @@
 //! Test utilities
 
 use crate::{self as pallet_staking, *};
+use pallet_dc_node::{LoginTimes, SpaceSize};
 use frame_election_provider_support::{onchain, SequentialPhragmen, VoteWeight};
 use frame_support::{
 	assert_ok, ord_parameter_types, parameter_types,
@@ -281,6 +282,58 @@ impl<T: Config> sp_staking::OnStakerSlash<AccountId, Balance> for OnStakerSlashM
 	}
 }
 
+parameter_types! {
+	pub static DcPeersSpace: Option<(SpaceSize, BTreeMap<AccountId, SpaceSize>)> = None;
+	pub static DcAppLoginTimes: Option<(LoginTimes, BTreeMap<AccountId, LoginTimes>)> = None;
+	pub static DcStorageRewardTotal: Balance = 0;
+	pub static DcAppRewardTotal: Balance = 0;
+	pub static DcPeerBonds: BTreeMap<AccountId, Balance> = BTreeMap::new();
+	pub static DcActiveUpdates: Vec<(AccountId, Balance)> = vec![];
+}
+
+/// A DC provider whose storage, app and peer state is set by the tests.
+///
+/// Each era payout drains the whole pot, and a stash can never unbond the amount recorded for
+/// its peers in `DcPeerBonds`.
+pub struct DcProviderMock;
+impl pallet_dc_node::DcProvider<AccountId, Balance> for DcProviderMock {
+	fn rewardable_peers_space() -> Option<(SpaceSize, BTreeMap<AccountId, SpaceSize>)> {
+		DcPeersSpace::get()
+	}
+
+	fn rewardable_app_login_times() -> Option<(LoginTimes, BTreeMap<AccountId, LoginTimes>)> {
+		DcAppLoginTimes::get()
+	}
+
+	fn can_be_unbound_amount(stash: &AccountId, value: Balance) -> Balance {
+		match DcPeerBonds::get().get(stash) {
+			Some(peer_bond) => {
+				let active = Staking::bonded(stash)
+					.and_then(|controller| Staking::ledger(&controller))
+					.map_or(0, |ledger| ledger.active);
+				value.min(active.saturating_sub(*peer_bond))
+			},
+			None => value,
+		}
+	}
+
+	fn update_active(stash: &AccountId, active: Balance) {
+		DcActiveUpdates::mutate(|updates| updates.push((*stash, active)));
+	}
+
+	fn era_storage_payout(_era_duration: u64) -> Balance {
+		DcStorageRewardTotal::take()
+	}
+
+	fn era_app_payout(_era_duration: u64) -> Balance {
+		DcAppRewardTotal::take()
+	}
+
+	fn purchase_storage_cost() -> Balance {
+		DcStorageRewardTotal::get() + DcAppRewardTotal::get()
+	}
+}
+
 impl crate::pallet::pallet::Config for Test {
 	type MaxNominations = MaxNominations;
 	type Currency = Balances;
@@ -310,6 +363,7 @@ impl crate::pallet::pallet::Config for Test {
 	type OnStakerSlash = OnStakerSlashMock<Test>;
 	type BenchmarkingConfig = TestBenchmarkingConfig;
 	type WeightInfo = ();
+	type DcProvider = DcProviderMock;
 }
 
 pub(crate) type StakingCall = crate::Call<Test>;
@@ -22,9 +22,7 @@ use frame_election_provider_support::{
 	SortedListProvider, VoteWeight, VoterOf,
 };
 
-use pallet_dc_node::{StakingProvider, SpaceSize, LoginTimes};
-#[cfg(feature = "dc_lib")]
-use pallet_dc_node::{DcProvider};
+use pallet_dc_node::{DcProvider, StakingProvider, SpaceSize, LoginTimes};
 use frame_support::{
 	dispatch::WithPostDispatchInfo,
 	pallet_prelude::*,
@@ -49,10 +47,10 @@ use sp_std::prelude::*;
 use crate::{
 	log, slashing, weights::WeightInfo, ActiveEraInfo, BalanceOf, EraPayout, Exposure, ExposureOf,
 	Forcing, IndividualExposure, MaxWinnersOf, Nominations, PositiveImbalanceOf, RewardDestination,
-	SessionInterface, StakingLedger, ValidatorPrefs, UnappliedSlash,
+	SessionInterface, StakingLedger, ValidatorPrefs, DelegatorLedger,
 };
 
-use super::{pallet::*, STAKING_ID};
+use super::{pallet::*, DELEGATION_ID, MAX_PAYOUT_BATCH, MAX_STORAGE_DELEGATORS, STAKING_ID};
 
 /// The maximum number of iterations that we do whilst iterating over `T::VoterList` in
 /// `get_npos_voters`.
@@ -318,8 +316,27 @@ impl<T: Config> Pallet<T> {
 		let temp_stash = reward_stash.clone();
 		Self::deposit_event(Event::<T>::PayoutStarted{era_index: era, validator_stash: temp_stash});
 
+		// Share the reward after the commission with the delegators, by their part of the stake.
+		let pool = Self::storage_delegation_pool(&reward_stash);
+		let mut stash_reward = storage_reward;
+		if !pool.total.is_zero() {
+			let own = Self::own_staking_active(&reward_stash);
+			let shared = storage_reward.saturating_sub(pool.commission * storage_reward);
+			for (delegator, amount) in <StorageDelegations<T>>::iter_prefix(&reward_stash) {
+				let delegator_reward =
+					Perbill::from_rational(amount, own.saturating_add(pool.total)) * shared;
+				if let Ok(imbalance) =
+					T::Currency::deposit_into_existing(&delegator, delegator_reward)
+				{
+					let amount = imbalance.peek();
+					stash_reward = stash_reward.saturating_sub(amount);
+					Self::deposit_event(Event::<T>::Rewarded { stash: delegator, amount });
+				}
+			}
+		}
+
 		// We can now make total validator payout:
-		if let Some(imbalance) = Self::make_payout(&reward_stash, storage_reward){
+		if let Some(imbalance) = Self::make_payout(&reward_stash, stash_reward){
 			let temp_balance = imbalance.peek();
 			Self::deposit_event(Event::<T>::Rewarded{stash: reward_stash, amount: temp_balance});
 		}
@@ -385,15 +402,79 @@ impl<T: Config> Pallet<T> {
 		Ok(().into())
 	}
 
+	/// Pay out the (stash, era) pairs with `payout`, skipping the failed ones.
+	pub(super) fn do_payout_batch(
+		payouts: Vec<(T::AccountId, EraIndex)>,
+		payout: fn(T::AccountId, EraIndex) -> DispatchResultWithPostInfo,
+	) -> DispatchResultWithPostInfo {
+		ensure!(
+			!payouts.is_empty() && payouts.len() <= MAX_PAYOUT_BATCH as usize,
+			Error::<T>::InvalidPayoutBatch
+		);
+
+		let (mut paid, mut skipped) = (0u32, 0u32);
+		for (reward_stash, era) in payouts {
+			match payout(reward_stash, era) {
+				Ok(_) => paid += 1,
+				Err(_) => skipped += 1,
+			}
+		}
+		Self::deposit_event(Event::<T>::PayoutBatchCompleted { paid, skipped });
+		Ok(().into())
+	}
+
+	/// Pay out the storage and app rewards of the auto payout stashes with the remaining weight
+	/// of the block, era by era, resuming from `AutoPayoutCursor`.
+	pub(crate) fn do_auto_payout(remaining_weight: Weight) -> Weight {
+		let per_stash = Self::payout_storage_weight().saturating_add(T::WeightInfo::payout_app());
+		let mut consumed = T::DbWeight::get().reads_writes(3, 1);
+		if remaining_weight.any_lt(consumed.saturating_add(per_stash)) {
+			return Weight::zero()
+		}
+
+		// Only the finished eras have rewards to pay out.
+		let last_era = match Self::active_era().and_then(|era| era.index.checked_sub(1)) {
+			Some(era) => era,
+			None => return T::DbWeight::get().reads(1),
+		};
+		let oldest_era = last_era.saturating_sub(T::HistoryDepth::get());
+		let (era, last_key) = match AutoPayoutCursor::<T>::get() {
+			Some((era, _)) if era < oldest_era => (oldest_era, None),
+			Some((era, _)) if era > last_era => return T::DbWeight::get().reads(2),
+			Some(cursor) => cursor,
+			None => (last_era, None),
+		};
+
+		let mut stashes = match last_key {
+			Some(key) => AutoPayoutStashes::<T>::iter_keys_from(key),
+			None => AutoPayoutStashes::<T>::iter_keys(),
+		};
+		loop {
+			if remaining_weight.any_lt(consumed.saturating_add(per_stash)) {
+				AutoPayoutCursor::<T>::put((era, Some(stashes.last_raw_key().to_vec())));
+				return consumed
+			}
+			match stashes.next() {
+				Some(stash) => {
+					// Already claimed or nothing to claim, nothing to do.
+					let _ = Self::do_payout_storage(stash.clone(), era);
+					let _ = Self::do_payout_app(stash, era);
+					consumed = consumed.saturating_add(per_stash);
+				},
+				None => {
+					AutoPayoutCursor::<T>::put((era.saturating_add(1), None::<Vec<u8>>));
+					return consumed
+				},
+			}
+		}
+	}
+
 	/// With reference to the input value, the number that can be unbound.
 	pub(crate) fn can_be_unbound_amount(
-		_stash: &T::AccountId,
+		stash: &T::AccountId,
 		value: BalanceOf<T>,
 	) -> BalanceOf<T> {
-		#[cfg(not(feature = "dc_lib"))]
-		return value;
-		#[cfg(feature = "dc_lib")]
-		T::DcProvider::can_be_unbound_amount(_stash, value)
+		T::DcProvider::can_be_unbound_amount(stash, value)
 	}
 
 	/// Update the ledger for a controller.
@@ -404,8 +485,67 @@ impl<T: Config> Pallet<T> {
 		<Ledger<T>>::insert(controller, ledger);
 
 		// Update the staked amount of peer
-		#[cfg(feature = "dc_lib")]
-		T::DcProvider::update_active(&ledger.stash, ledger.active);
+		let delegated = Self::storage_delegation_pool(&ledger.stash).total;
+		T::DcProvider::update_active(&ledger.stash, ledger.active.saturating_add(delegated));
+	}
+
+	/// The weight of a storage payout, which pays every delegator of the stash.
+	pub(crate) fn payout_storage_weight() -> Weight {
+		T::WeightInfo::payout_storage().saturating_add(
+			T::DbWeight::get()
+				.reads_writes(1 + MAX_STORAGE_DELEGATORS as u64, MAX_STORAGE_DELEGATORS as u64),
+		)
+	}
+
+	/// The active stake of a stash, excluding the funds delegated to it.
+	pub(crate) fn own_staking_active(stash: &T::AccountId) -> BalanceOf<T> {
+		Self::bonded(stash)
+			.and_then(|controller| Self::ledger(&controller))
+			.map_or(Zero::zero(), |ledger| ledger.active)
+	}
+
+	/// The funds that an account has locked for delegations to storage stashes.
+	pub(crate) fn delegated_total(delegator: &T::AccountId) -> BalanceOf<T> {
+		Self::delegator_ledger(delegator).map_or(Zero::zero(), |ledger| ledger.total)
+	}
+
+	/// Update the ledger of a delegator and its lock, removing both once nothing is locked.
+	pub(crate) fn update_delegator_ledger(delegator: &T::AccountId, ledger: DelegatorLedger<T>) {
+		if ledger.total.is_zero() {
+			T::Currency::remove_lock(DELEGATION_ID, delegator);
+			<DelegatorLedgers<T>>::remove(delegator);
+		} else {
+			T::Currency::set_lock(DELEGATION_ID, delegator, ledger.total, WithdrawReasons::all());
+			<DelegatorLedgers<T>>::insert(delegator, ledger);
+		}
+	}
+
+	/// Remove `value` from the delegation of `delegator` to the storage stash and from the pool
+	/// of the stash. The ledger of the delegator is left to the caller.
+	pub(crate) fn reduce_delegation(
+		stash: &T::AccountId,
+		delegator: &T::AccountId,
+		value: BalanceOf<T>,
+	) {
+		let delegation = Self::storage_delegation(stash, delegator).unwrap_or_default();
+		let remaining = delegation.saturating_sub(value);
+		<StorageDelegationPools<T>>::mutate(stash, |pool| {
+			pool.total = pool.total.saturating_sub(delegation.min(value));
+			if remaining.is_zero() {
+				pool.delegators = pool.delegators.saturating_sub(1);
+			}
+		});
+		if remaining.is_zero() {
+			<StorageDelegations<T>>::remove(stash, delegator);
+		} else {
+			<StorageDelegations<T>>::insert(stash, delegator, remaining);
+		}
+		Self::update_storage_active(stash);
+	}
+
+	/// Update the staked amount of the storage nodes of the stash, including its delegations.
+	pub(crate) fn update_storage_active(stash: &T::AccountId) {
+		T::DcProvider::update_active(stash, Self::get_staking_active(stash));
 	}
 
 	/// Chill a stash account.
@@ -573,9 +713,6 @@ impl<T: Config> Pallet<T> {
 		if let Some(active_era_start) = active_era.start {
 			let now_as_millis_u64 = T::UnixTime::now().as_millis().saturated_into::<u64>();
 
-			#[cfg(not(feature = "dc_lib"))]
-			let storage_cost: BalanceOf<T> = Zero::zero();
-			#[cfg(feature = "dc_lib")]
 			let storage_cost = T::DcProvider::purchase_storage_cost();
 			let era_duration = (now_as_millis_u64 - active_era_start).saturated_into::<u64>();
 			let staked = Self::eras_total_stake(&active_era.index) + storage_cost;
@@ -583,33 +720,24 @@ impl<T: Config> Pallet<T> {
 			let (issuance_payout, rest) = T::EraPayout::era_payout(staked, issuance, era_duration);
 			
 			// Storage reward
-			#[cfg(not(feature = "dc_lib"))]
-			let storage_payout: BalanceOf<T> = Zero::zero();
-			#[cfg(not(feature = "dc_lib"))]
-			let validator_payout = issuance_payout;
-			#[cfg(feature = "dc_lib")]
 			let mut storage_payout: BalanceOf<T> = Zero::zero();
-			#[cfg(feature = "dc_lib")]
 			let mut validator_payout = issuance_payout;
-			#[cfg(feature = "dc_lib")]
-			{
-				// Get storage total in current era
-				let (storage_total, _) = <StorageNodeSpace<T>>::get(&active_era.index);
-				if storage_total > 0 {
-					// The user's fee for purchasing storage as a storage reward 
-					storage_payout = T::DcProvider::era_storage_payout(era_duration);
-					let percent = <StorageRewardPercent<T>>::get();
-					storage_payout = storage_payout+Perbill::from_rational(percent, 100)*issuance_payout;
-					validator_payout = Perbill::from_rational(100-percent,100)*issuance_payout;
-				}
+			// Get storage total in current era
+			let (storage_total, _) = <StorageNodeSpace<T>>::get(&active_era.index);
+			if storage_total > 0 {
+				// The user's fee for purchasing storage as a storage reward 
+				storage_payout = T::DcProvider::era_storage_payout(era_duration);
+				let percent = <StorageRewardPercent<T>>::get();
+				storage_payout = storage_payout+Perbill::from_rational(percent, 100)*issuance_payout;
+				validator_payout = Perbill::from_rational(100-percent,100)*issuance_payout;
+			}
 
-				// Get app total in current era
-				let (app_total, _) = <AppLoginTimes<T>>::get(&active_era.index);
-				if app_total > 0 {
-					// The user's fee for purchasing storage as a app reward 
-					let app_payout = T::DcProvider::era_app_payout(era_duration);
-					<ErasAppReward<T>>::insert(&active_era.index, app_payout);
-				}
+			// Get app total in current era
+			let (app_total, _) = <AppLoginTimes<T>>::get(&active_era.index);
+			if app_total > 0 {
+				// The user's fee for purchasing storage as a app reward 
+				let app_payout = T::DcProvider::era_app_payout(era_duration);
+				<ErasAppReward<T>>::insert(&active_era.index, app_payout);
 			}
 
 			Self::deposit_event(Event::<T>::EraPaid{era_index: active_era.index, validator_payout: storage_payout+validator_payout, remainder: rest});
@@ -768,13 +896,12 @@ impl<T: Config> Pallet<T> {
 			);
 		}
 
-		#[cfg(feature = "dc_lib")]
-		{
-			// Get the rewardable app's login times.
-			let login_info = T::DcProvider::rewardable_app_login_times().unwrap();
+		// Get the rewardable app's login times.
+		if let Some(login_info) = T::DcProvider::rewardable_app_login_times() {
 			<AppLoginTimes<T>>::insert(&new_planned_era, login_info);
-			// Get the rewardable peers's space.
-			let space_info = T::DcProvider::rewardable_peers_space().unwrap();
+		}
+		// Get the rewardable peers's space.
+		if let Some(space_info) = T::DcProvider::rewardable_peers_space() {
 			<StorageNodeSpace<T>>::insert(&new_planned_era, space_info);
 		}
 
@@ -834,6 +961,7 @@ impl<T: Config> Pallet<T> {
 		<Ledger<T>>::remove(&controller);
 
 		<Payee<T>>::remove(stash);
+		<AutoPayoutStashes<T>>::remove(stash);
 		Self::do_remove_validator(stash);
 		Self::do_remove_nominator(stash);
 
@@ -1853,17 +1981,10 @@ impl<T: Config> StakingProvider for Pallet<T> {
     
 	/// Get staking active of stash.
     fn get_staking_active(stash: &Self::AccountId) -> Self::Balance {
-		let controller = Self::bonded(&stash);
-		if controller.is_none() {
-			0u32.into()
-		} else {
-			let ledger = <Ledger<T>>::get(&controller.unwrap());
-			if ledger.is_none() {
-				0u32.into()
-			} else {
-				ledger.unwrap().active
-			}
+		if !<Bonded<T>>::contains_key(stash) {
+			return Zero::zero()
 		}
+		Self::own_staking_active(stash).saturating_add(Self::storage_delegation_pool(stash).total)
 	}
 
 	/// Get current era index
@@ -1885,38 +2006,46 @@ impl<T: Config> StakingProvider for Pallet<T> {
 		}
 	}
 
-	fn report_offence(
-		stash: &Self::AccountId,
-		slash: Self::Balance,
-	) {
-		let active_era = {
-			let active_era = Self::active_era();
-			if active_era.is_none() {
-				// This offence need not be re-submitted.
-				return
-			}
-			active_era.expect("value checked not to be `None`; qed").index
-		};
-		
-		let unapplied = UnappliedSlash {
-			validator: stash.clone(),
-			own: slash,
-			others: Vec::new(),
-			reporters: Vec::new(),
-			payout: Zero::zero(),
-		};
-
-		let slash_defer_duration = T::SlashDeferDuration::get();
-
-		if slash_defer_duration == 0 {
-			// Apply right away.
-			slashing::apply_slash::<T>(unapplied, active_era);
-		} else {
-			// Defer to end of some `slash_defer_duration` from now.
-			<Self as Store>::UnappliedSlashes::mutate(active_era, move |for_later| {
-				for_later.push(unapplied)
-			});
-		}
+	type Offender = (T::AccountId, Exposure<T::AccountId, BalanceOf<T>>);
+
+	/// Get the stash and its stake as the offender of storage node offences, with the
+	/// delegators as the others, so that the slash is shared proportionally.
+	fn offender(stash: &Self::AccountId) -> Option<Self::Offender> {
+		Self::bonded(stash)?;
+		let own = Self::own_staking_active(stash);
+		let others = <StorageDelegations<T>>::iter_prefix(stash)
+			.map(|(who, value)| IndividualExposure { who, value })
+			.collect::<Vec<_>>();
+		let total = others.iter().fold(own, |total, other| total.saturating_add(other.value));
+		Some((stash.clone(), Exposure { total, own, others }))
+	}
+
+	/// Get the start session of the active era, so that offences are slashed in the active era.
+	fn offence_session_index() -> SessionIndex {
+		Self::active_era()
+			.and_then(|era| Self::eras_start_session_index(era.index))
+			.unwrap_or(0)
+	}
+
+	/// The weight of reading the storage stash with `MAX_STORAGE_DELEGATORS` delegators as the
+	/// offender, and slashing it right away in `on_offence`.
+	fn offence_weight(reporters: u32) -> Weight {
+		let others = MAX_STORAGE_DELEGATORS as u64;
+		let reporters = reporters as u64;
+		// `offender` reads the ledger and the delegations of the stash
+		let offender = (2 + others, 0);
+		// The active era, its start session, the bonded eras, the earliest unapplied slash and
+		// the invulnerables
+		let era = (5, 1);
+		// The slashing spans and the slash in era of the stash and each of the delegators
+		let spans = (3 * (1 + others), 3 * (1 + others));
+		// `apply_slash` and the rewards of the reporters
+		let slash_cost = (6, 5);
+		let reward_cost = (2, 2);
+		T::DbWeight::get().reads_writes(
+			offender.0 + era.0 + spans.0 + (1 + others) * slash_cost.0 + reporters * reward_cost.0,
+			offender.1 + era.1 + spans.1 + (1 + others) * slash_cost.1 + reporters * reward_cost.1,
+		)
 	}
 }
 
@@ -34,7 +34,7 @@ use frame_support::{
 };
 use frame_system::{ensure_root, ensure_signed, pallet_prelude::*};
 use sp_runtime::{
-	traits::{CheckedSub, SaturatedConversion, StaticLookup, Zero},
+	traits::{CheckedSub, SaturatedConversion, Saturating, StaticLookup, Zero},
 	ArithmeticError, Perbill, Percent,
 };
 use sp_staking::{EraIndex, SessionIndex};
@@ -48,14 +48,19 @@ use crate::{
 	slashing, weights::WeightInfo, AccountIdLookupOf, ActiveEraInfo, BalanceOf, EraPayout,
 	EraRewardPoints, Exposure, Forcing, NegativeImbalanceOf, Nominations, PositiveImbalanceOf,
 	Releases, RewardDestination, SessionInterface, StakingLedger, UnappliedSlash, UnlockChunk,
-	ValidatorPrefs,
+	ValidatorPrefs, StorageDelegationPool, DelegatorLedger,
 };
 
 const STAKING_ID: LockIdentifier = *b"staking ";
+const DELEGATION_ID: LockIdentifier = *b"dcdelega";
 // The speculative number of spans are used as an input of the weight annotation of
 // [`Call::unbond`], as the post dipatch weight may depend on the number of slashing span on the
 // account which is not provided as an input. The value set should be conservative but sensible.
 pub(crate) const SPECULATIVE_NUM_SPANS: u32 = 32;
+// The maximum number of (stash, era) pairs that can be paid out in one batch call.
+pub(crate) const MAX_PAYOUT_BATCH: u32 = 64;
+// The maximum number of delegators of a storage stash. It bounds the weight of storage payouts.
+pub(crate) const MAX_STORAGE_DELEGATORS: u32 = 64;
 
 #[frame_support::pallet]
 pub mod pallet {
@@ -270,12 +275,9 @@ pub mod pallet {
 		/// Weight information for extrinsics in this pallet.
 		type WeightInfo: WeightInfo;
 
-		/// Something that provides the dc functionality.
-		#[cfg(feature = "dc_lib")]
-		type DcProvider: pallet_dc_node::DcProvider<
-			AccountId = Self::AccountId,
-			Balance = BalanceOf<Self>,
-		>;
+		/// Something that provides the dc functionality. Use `()` for plain staking without
+		/// storage and app rewards.
+		type DcProvider: pallet_dc_node::DcProvider<Self::AccountId, BalanceOf<Self>>;
 	}
 
 	/// The ideal number of active validators.
@@ -621,6 +623,32 @@ pub mod pallet {
 	#[pallet::getter(fn eras_app_reward)]
 	pub type ErasAppReward<T: Config> = StorageMap<_, Twox64Concat, EraIndex, BalanceOf<T>>;
 
+	/// The delegation pools of the storage stashes.
+	#[pallet::storage]
+	#[pallet::getter(fn storage_delegation_pool)]
+	pub type StorageDelegationPools<T: Config> =
+		StorageMap<_, Twox64Concat, T::AccountId, StorageDelegationPool<BalanceOf<T>>, ValueQuery>;
+
+	/// The amount delegated to a storage stash by a delegator.
+	#[pallet::storage]
+	#[pallet::getter(fn storage_delegation)]
+	pub type StorageDelegations<T: Config> =
+		StorageDoubleMap<_, Twox64Concat, T::AccountId, Twox64Concat, T::AccountId, BalanceOf<T>>;
+
+	/// The locked funds of the delegators of storage stashes.
+	#[pallet::storage]
+	#[pallet::getter(fn delegator_ledger)]
+	pub type DelegatorLedgers<T: Config> =
+		StorageMap<_, Twox64Concat, T::AccountId, DelegatorLedger<T>>;
+
+	/// The stashes whose storage and app rewards are paid out automatically in `on_idle`.
+	#[pallet::storage]
+	pub type AutoPayoutStashes<T: Config> = StorageMap<_, Twox64Concat, T::AccountId, ()>;
+
+	/// The era that automatic payouts are processing, and the raw key of the last stash paid in it.
+	#[pallet::storage]
+	pub type AutoPayoutCursor<T: Config> = StorageValue<_, (EraIndex, Option<Vec<u8>>)>;
+
 	/// The login times of the apps
 	#[pallet::storage]
 	#[pallet::getter(fn app_login_times)]
@@ -769,6 +797,14 @@ pub mod pallet {
 		PayoutStarted { era_index: EraIndex, validator_stash: T::AccountId },
 		/// A validator has set their preferences.
 		ValidatorPrefsSet { stash: T::AccountId, prefs: ValidatorPrefs },
+		/// A batch of storage or app payouts was processed, the failed ones are skipped.
+		PayoutBatchCompleted { paid: u32, skipped: u32 },
+		/// A stash has turned automatic payouts of its storage and app rewards on or off.
+		AutoPayoutSet { stash: T::AccountId, enabled: bool },
+		/// An account has delegated this amount to a storage stash.
+		Delegated { delegator: T::AccountId, stash: T::AccountId, amount: BalanceOf<T> },
+		/// An account has started to undelegate this amount from a storage stash.
+		Undelegated { delegator: T::AccountId, stash: T::AccountId, amount: BalanceOf<T> },
 	}
 
 	#[pallet::error]
@@ -829,6 +865,14 @@ pub mod pallet {
 		BoundNotMet,
 		/// Invalid number of percent.
 		InvalidPercent,
+		/// The batch is empty or has more than `MAX_PAYOUT_BATCH` payouts.
+		InvalidPayoutBatch,
+		/// The storage stash has `MAX_STORAGE_DELEGATORS` delegators already.
+		TooManyDelegators,
+		/// The account has not delegated to the storage stash.
+		NotDelegated,
+		/// The free balance is not enough to cover the delegation and the bond.
+		InsufficientFreeBalance,
 	}
 
 	#[pallet::hooks]
@@ -838,6 +882,10 @@ pub mod pallet {
 			T::DbWeight::get().reads(1)
 		}
 
+		fn on_idle(_n: BlockNumberFor<T>, remaining_weight: Weight) -> Weight {
+			Self::do_auto_payout(remaining_weight)
+		}
+
 		fn on_finalize(_n: BlockNumberFor<T>) {
 			// Set the start of the first era.
 			if let Some(mut active_era) = Self::active_era() {
@@ -940,7 +988,9 @@ pub mod pallet {
 			let history_depth = T::HistoryDepth::get();
 			let last_reward_era = current_era.saturating_sub(history_depth);
 
-			let stash_balance = T::Currency::free_balance(&stash);
+			// The funds delegated to storage stashes can not be bonded again.
+			let stash_balance =
+				T::Currency::free_balance(&stash).saturating_sub(Self::delegated_total(&stash));
 			let value = value.min(stash_balance);
 			Self::deposit_event(Event::<T>::Bonded { stash: stash.clone(), amount: value });
 			let item = StakingLedger {
@@ -985,7 +1035,8 @@ pub mod pallet {
 			let controller = Self::bonded(&stash).ok_or(Error::<T>::NotStash)?;
 			let mut ledger = Self::ledger(&controller).ok_or(Error::<T>::NotController)?;
 
-			let stash_balance = T::Currency::free_balance(&stash);
+			let stash_balance =
+				T::Currency::free_balance(&stash).saturating_sub(Self::delegated_total(&stash));
 			if let Some(extra) = stash_balance.checked_sub(&ledger.total) {
 				let extra = extra.min(max_additional);
 				ledger.total += extra;
@@ -1884,7 +1935,7 @@ pub mod pallet {
 		}
 
 		#[pallet::call_index(27)]
-		#[pallet::weight(T::WeightInfo::payout_storage())]
+		#[pallet::weight(Pallet::<T>::payout_storage_weight())]
 		pub fn payout_storage(
 			origin: OriginFor<T>,
 			reward_stash: T::AccountId,
@@ -1904,6 +1955,162 @@ pub mod pallet {
 			ensure_signed(origin)?;
 			Self::do_payout_app(reward_stash, era)
 		}
+
+		/// Pay out the storage rewards of many stashes and eras at once.
+		///
+		/// The payouts that fail, e.g. already claimed, are skipped instead of failing the batch.
+		#[pallet::call_index(29)]
+		#[pallet::weight(Pallet::<T>::payout_storage_weight().saturating_mul(payouts.len() as u64))]
+		pub fn payout_storage_batch(
+			origin: OriginFor<T>,
+			payouts: Vec<(T::AccountId, EraIndex)>,
+		) -> DispatchResultWithPostInfo {
+			ensure_signed(origin)?;
+			Self::do_payout_batch(payouts, Self::do_payout_storage)
+		}
+
+		/// Pay out the app rewards of many stashes and eras at once.
+		///
+		/// The payouts that fail, e.g. already claimed, are skipped instead of failing the batch.
+		#[pallet::call_index(30)]
+		#[pallet::weight(T::WeightInfo::payout_app().saturating_mul(payouts.len() as u64))]
+		pub fn payout_app_batch(
+			origin: OriginFor<T>,
+			payouts: Vec<(T::AccountId, EraIndex)>,
+		) -> DispatchResultWithPostInfo {
+			ensure_signed(origin)?;
+			Self::do_payout_batch(payouts, Self::do_payout_app)
+		}
+
+		/// Turn the automatic payouts of the origin's storage and app rewards on or off.
+		///
+		/// The rewards of the last finished era are paid out in `on_idle` when the blocks have
+		/// spare weight, so they are claimed before `HistoryDepth` expires. The origin must be a
+		/// stash, and the automatic payouts are turned off when it is killed or fully unbonded.
+		#[pallet::call_index(31)]
+		#[pallet::weight(T::DbWeight::get().reads_writes(1, 1))]
+		pub fn set_auto_payout(origin: OriginFor<T>, enabled: bool) -> DispatchResult {
+			let stash = ensure_signed(origin)?;
+			if enabled {
+				ensure!(<Bonded<T>>::contains_key(&stash), Error::<T>::NotStash);
+				<AutoPayoutStashes<T>>::insert(&stash, ());
+			} else {
+				<AutoPayoutStashes<T>>::remove(&stash);
+			}
+			Self::deposit_event(Event::<T>::AutoPayoutSet { stash, enabled });
+			Ok(())
+		}
+
+		/// Delegate `value` of the origin's free balance to back the storage nodes of `stash`.
+		///
+		/// The delegated funds are locked, count as the stake of the storage stash, earn a share
+		/// of its storage rewards after the commission, and are slashed with it proportionally.
+		#[pallet::call_index(32)]
+		#[pallet::weight(T::DbWeight::get().reads_writes(7, 4))]
+		pub fn delegate_storage(
+			origin: OriginFor<T>,
+			stash: T::AccountId,
+			#[pallet::compact] value: BalanceOf<T>,
+		) -> DispatchResult {
+			let delegator = ensure_signed(origin)?;
+			ensure!(delegator != stash && <Bonded<T>>::contains_key(&stash), Error::<T>::NotStash);
+			ensure!(value >= T::Currency::minimum_balance(), Error::<T>::InsufficientBond);
+
+			let mut pool = Self::storage_delegation_pool(&stash);
+			let delegation = Self::storage_delegation(&stash, &delegator);
+			if delegation.is_none() {
+				ensure!(pool.delegators < MAX_STORAGE_DELEGATORS, Error::<T>::TooManyDelegators);
+				pool.delegators += 1;
+			}
+			let mut ledger = Self::delegator_ledger(&delegator)
+				.unwrap_or(DelegatorLedger { total: Zero::zero(), unlocking: Default::default() });
+			ledger.total = ledger.total.saturating_add(value);
+			// The delegation can not reuse the funds locked by the own bond.
+			let bonded = Self::bonded(&delegator)
+				.and_then(|controller| Self::ledger(&controller))
+				.map_or(Zero::zero(), |l| l.total);
+			ensure!(
+				T::Currency::free_balance(&delegator) >= ledger.total.saturating_add(bonded),
+				Error::<T>::InsufficientFreeBalance
+			);
+
+			pool.total = pool.total.saturating_add(value);
+			<StorageDelegationPools<T>>::insert(&stash, pool);
+			let delegation = delegation.unwrap_or_default().saturating_add(value);
+			<StorageDelegations<T>>::insert(&stash, &delegator, delegation);
+			Self::update_delegator_ledger(&delegator, ledger);
+			Self::update_storage_active(&stash);
+			Self::deposit_event(Event::<T>::Delegated { delegator, stash, amount: value });
+			Ok(())
+		}
+
+		/// Undelegate `value` from the storage stash. The funds stop backing the stash at once and
+		/// can be withdrawn with `withdraw_undelegated` after the bonding duration.
+		#[pallet::call_index(33)]
+		#[pallet::weight(T::DbWeight::get().reads_writes(7, 4))]
+		pub fn undelegate_storage(
+			origin: OriginFor<T>,
+			stash: T::AccountId,
+			#[pallet::compact] value: BalanceOf<T>,
+		) -> DispatchResult {
+			let delegator = ensure_signed(origin)?;
+			let delegation =
+				Self::storage_delegation(&stash, &delegator).ok_or(Error::<T>::NotDelegated)?;
+			let mut ledger = Self::delegator_ledger(&delegator).ok_or(Error::<T>::NotDelegated)?;
+
+			let mut value = value.min(delegation);
+			// Avoid leaving a delegation below the existential deposit.
+			if delegation.saturating_sub(value) < T::Currency::minimum_balance() {
+				value = delegation;
+			}
+			let era = Self::current_era().unwrap_or(0).saturating_add(T::BondingDuration::get());
+			ledger
+				.unlocking
+				.try_push((stash.clone(), UnlockChunk { value, era }))
+				.map_err(|_| Error::<T>::NoMoreChunks)?;
+
+			Self::reduce_delegation(&stash, &delegator, value);
+			Self::update_delegator_ledger(&delegator, ledger);
+			Self::deposit_event(Event::<T>::Undelegated { delegator, stash, amount: value });
+			Ok(())
+		}
+
+		/// Unlock the undelegated funds of the origin whose bonding duration has passed.
+		#[pallet::call_index(34)]
+		#[pallet::weight(T::DbWeight::get().reads_writes(3, 2))]
+		pub fn withdraw_undelegated(origin: OriginFor<T>) -> DispatchResult {
+			let delegator = ensure_signed(origin)?;
+			let mut ledger = Self::delegator_ledger(&delegator).ok_or(Error::<T>::NotDelegated)?;
+
+			let current_era = Self::current_era().unwrap_or(0);
+			let old_total = ledger.total;
+			ledger.unlocking.retain(|(_, chunk)| {
+				if chunk.era > current_era {
+					true
+				} else {
+					ledger.total = ledger.total.saturating_sub(chunk.value);
+					false
+				}
+			});
+			let amount = old_total.saturating_sub(ledger.total);
+			Self::update_delegator_ledger(&delegator, ledger);
+			if !amount.is_zero() {
+				Self::deposit_event(Event::<T>::Withdrawn { stash: delegator, amount });
+			}
+			Ok(())
+		}
+
+		/// Set the commission that the origin stash takes from its storage rewards before sharing
+		/// them with its delegators.
+		#[pallet::call_index(35)]
+		#[pallet::weight(T::DbWeight::get().reads_writes(2, 1))]
+		pub fn set_storage_commission(origin: OriginFor<T>, commission: Perbill) -> DispatchResult {
+			let stash = ensure_signed(origin)?;
+			ensure!(<Bonded<T>>::contains_key(&stash), Error::<T>::NotStash);
+
+			<StorageDelegationPools<T>>::mutate(&stash, |pool| pool.commission = commission);
+			Ok(())
+		}
 	}
 }
 
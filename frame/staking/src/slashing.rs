@@ -290,6 +290,8 @@ pub(crate) fn compute_slash<T: Config>(
 
 	let mut nominators_slashed = Vec::new();
 	reward_payout += slash_nominators::<T>(params.clone(), prior_slash_p, &mut nominators_slashed);
+	let mut delegators_slashed = Vec::new();
+	reward_payout += slash_delegators::<T>(params.clone(), prior_slash_p, &mut delegators_slashed);
 
 	Some(UnappliedSlash {
 		validator: params.stash.clone(),
@@ -297,6 +299,7 @@ pub(crate) fn compute_slash<T: Config>(
 		others: nominators_slashed,
 		reporters: Vec::new(),
 		payout: reward_payout,
+		delegators: delegators_slashed,
 	})
 }
 
@@ -378,6 +381,10 @@ fn slash_nominators<T: Config>(
 	nominators_slashed.reserve(params.exposure.others.len());
 	for nominator in &params.exposure.others {
 		let stash = &nominator.who;
+		// The delegators are slashed on their delegations by `slash_delegators`.
+		if is_delegator::<T>(params.stash, stash) {
+			continue
+		}
 		let mut nom_slashed = Zero::zero();
 
 		// the era slash of a nominator always grows, if the validator
@@ -588,6 +595,39 @@ pub(crate) fn clear_stash_metadata<T: Config>(
 	Ok(())
 }
 
+/// Whether the staker in the exposure of the stash backs it by a delegation to its storage
+/// nodes, rather than by a nomination.
+///
+/// The exposure of a storage stash lists its delegators when the offence is reported, so this is
+/// only accurate when the slash is computed, and is recorded in the unapplied slash.
+fn is_delegator<T: Config>(stash: &T::AccountId, staker: &T::AccountId) -> bool {
+	<Pallet<T>>::storage_delegation(stash, staker).is_some()
+}
+
+/// Slash the delegators of a storage stash. Accepts general parameters and the prior slash
+/// percentage of the stash.
+///
+/// Returns the amount of reward to pay out.
+fn slash_delegators<T: Config>(
+	params: SlashParams<T>,
+	prior_slash_p: Perbill,
+	delegators_slashed: &mut Vec<(T::AccountId, BalanceOf<T>)>,
+) -> BalanceOf<T> {
+	let mut reward_payout = Zero::zero();
+
+	for delegator in &params.exposure.others {
+		if !is_delegator::<T>(params.stash, &delegator.who) {
+			continue
+		}
+		// the stash has a new max slash for the era, slash the difference.
+		let slash = (params.slash * delegator.value).saturating_sub(prior_slash_p * delegator.value);
+		reward_payout += REWARD_F1 * (params.reward_proportion * slash);
+		delegators_slashed.push((delegator.who.clone(), slash));
+	}
+
+	reward_payout
+}
+
 // apply the slash to a stash account, deducting any missing funds from the reward
 // payout, saturating at 0. this is mildly unfair but also an edge-case that
 // can only occur when overlapping locked funds have been slashed.
@@ -629,6 +669,46 @@ pub fn do_slash<T: Config>(
 	}
 }
 
+// apply the slash to the funds that a delegator has delegated to a storage stash, including the
+// funds being undelegated from it that were at stake in the slash era.
+fn do_slash_delegation<T: Config>(
+	stash: &T::AccountId,
+	delegator: &T::AccountId,
+	value: BalanceOf<T>,
+	reward_payout: &mut BalanceOf<T>,
+	slashed_imbalance: &mut NegativeImbalanceOf<T>,
+	slash_era: EraIndex,
+) {
+	let mut ledger = match <Pallet<T>>::delegator_ledger(delegator) {
+		Some(ledger) => ledger,
+		None => return, // nothing to do.
+	};
+	let delegation = <Pallet<T>>::storage_delegation(stash, delegator).unwrap_or_default();
+	let (from_delegation, value) =
+		ledger.slash(stash, delegation, value, T::Currency::minimum_balance(), slash_era);
+
+	if !value.is_zero() {
+		let (imbalance, missing) = T::Currency::slash(delegator, value);
+		slashed_imbalance.subsume(imbalance);
+
+		if !missing.is_zero() {
+			// deduct overslash from the reward payout
+			*reward_payout = reward_payout.saturating_sub(missing);
+		}
+
+		if !from_delegation.is_zero() {
+			<Pallet<T>>::reduce_delegation(stash, delegator, from_delegation);
+		}
+		<Pallet<T>>::update_delegator_ledger(delegator, ledger);
+
+		// trigger the event
+		<Pallet<T>>::deposit_event(super::Event::<T>::Slashed {
+			staker: delegator.clone(),
+			amount: value,
+		});
+	}
+}
+
 /// Apply a previously-unapplied slash.
 pub(crate) fn apply_slash<T: Config>(
 	unapplied_slash: UnappliedSlash<T::AccountId, BalanceOf<T>>,
@@ -655,6 +735,17 @@ pub(crate) fn apply_slash<T: Config>(
 		);
 	}
 
+	for &(ref delegator, delegator_slash) in &unapplied_slash.delegators {
+		do_slash_delegation::<T>(
+			&unapplied_slash.validator,
+			delegator,
+			delegator_slash,
+			&mut reward_payout,
+			&mut slashed_imbalance,
+			slash_era,
+		);
+	}
+
 	pay_reporters::<T>(reward_payout, slashed_imbalance, &unapplied_slash.reporters);
 }
 
@@ -26,6 +26,7 @@ use frame_support::{
 	traits::{Currency, Get, ReservableCurrency},
 };
 use mock::*;
+use pallet_dc_node::StakingProvider;
 use pallet_balances::Error as BalancesError;
 use sp_runtime::{
 	assert_eq_error_rate,
@@ -5766,6 +5767,350 @@ fn set_min_commission_works_with_admin_origin() {
 	})
 }
 
+#[test]
+fn dc_storage_reward_is_paid_by_space() {
+	ExtBuilder::default().build_and_execute(|| {
+		DcPeersSpace::set(Some((30, BTreeMap::from([(21, 10), (31, 20)]))));
+		DcStorageRewardTotal::set(3000);
+
+		// Era 1 is planned with the current storage space of the peers.
+		start_active_era(1);
+		assert_eq!(Staking::storage_node_space(1), (30, BTreeMap::from([(21, 10), (31, 20)])));
+
+		// The user's fees are part of the stake the era payout is computed from.
+		let (issuance_payout, _) = <Test as Config>::EraPayout::era_payout(
+			Staking::eras_total_stake(active_era()) + DcStorageRewardTotal::get(),
+			Balances::total_issuance(),
+			reward_time_per_era(),
+		);
+		start_active_era(2);
+
+		let storage_payout = 3000 + Perbill::from_percent(60) * issuance_payout;
+		assert_eq!(Staking::eras_storage_reward(1), Some(storage_payout));
+		assert_eq!(Staking::eras_validator_reward(1), Some(Perbill::from_percent(40) * issuance_payout));
+		assert_eq!(DcStorageRewardTotal::get(), 0);
+
+		let _ = staking_events_since_last_call();
+		assert_ok!(Staking::payout_storage(RuntimeOrigin::signed(1337), 21, 1));
+		assert_eq!(
+			staking_events_since_last_call(),
+			vec![
+				Event::PayoutStarted { era_index: 1, validator_stash: 21 },
+				Event::Rewarded { stash: 21, amount: Perbill::from_rational(10u64, 30) * storage_payout },
+			]
+		);
+		assert_noop!(
+			Staking::payout_storage(RuntimeOrigin::signed(1337), 21, 1),
+			Error::<Test>::AlreadyClaimed
+		);
+	});
+}
+
+#[test]
+fn dc_app_reward_is_paid_by_login_times() {
+	ExtBuilder::default().build_and_execute(|| {
+		DcAppLoginTimes::set(Some((4, BTreeMap::from([(11, 3), (21, 1)]))));
+		DcAppRewardTotal::set(400);
+
+		start_active_era(1);
+		start_active_era(2);
+		assert_eq!(Staking::eras_app_reward(1), Some(400));
+
+		let balance = Balances::free_balance(11);
+		assert_ok!(Staking::payout_app(RuntimeOrigin::signed(1337), 11, 1));
+		assert_eq!(Balances::free_balance(11), balance + 300);
+		assert_noop!(
+			Staking::payout_app(RuntimeOrigin::signed(1337), 11, 1),
+			Error::<Test>::AlreadyClaimed
+		);
+	});
+}
+
+#[test]
+fn dc_payout_batch_skips_failed_payouts() {
+	ExtBuilder::default().build_and_execute(|| {
+		DcPeersSpace::set(Some((30, BTreeMap::from([(21, 10), (31, 20)]))));
+		DcStorageRewardTotal::set(3000);
+		DcAppLoginTimes::set(Some((4, BTreeMap::from([(11, 3), (21, 1)]))));
+		DcAppRewardTotal::set(400);
+		start_active_era(1);
+		start_active_era(2);
+
+		assert_noop!(
+			Staking::payout_storage_batch(RuntimeOrigin::signed(1337), vec![]),
+			Error::<Test>::InvalidPayoutBatch
+		);
+		assert_noop!(
+			Staking::payout_storage_batch(RuntimeOrigin::signed(1337), vec![(21, 1); MAX_PAYOUT_BATCH as usize + 1]),
+			Error::<Test>::InvalidPayoutBatch
+		);
+
+		let _ = staking_events_since_last_call();
+		assert_ok!(Staking::payout_storage(RuntimeOrigin::signed(1337), 21, 1));
+		// Already claimed and future eras are skipped.
+		assert_ok!(Staking::payout_storage_batch(RuntimeOrigin::signed(1337), vec![(21, 1), (31, 1), (31, 2)]));
+		assert_eq!(
+			staking_events_since_last_call().last(),
+			Some(&Event::PayoutBatchCompleted { paid: 1, skipped: 2 })
+		);
+		assert_eq!(StorageClaimedRewards::<Test>::get(31), Some(vec![1]));
+
+		let balance = Balances::free_balance(11);
+		assert_ok!(Staking::payout_app_batch(RuntimeOrigin::signed(1337), vec![(11, 1), (21, 1)]));
+		assert_eq!(Balances::free_balance(11), balance + 300);
+		assert_eq!(AppClaimedRewards::<Test>::get(21), Some(vec![1]));
+	});
+}
+
+#[test]
+fn dc_auto_payout_in_on_idle() {
+	ExtBuilder::default().build_and_execute(|| {
+		DcPeersSpace::set(Some((30, BTreeMap::from([(21, 10), (31, 20)]))));
+		DcStorageRewardTotal::set(3000);
+		DcAppLoginTimes::set(Some((4, BTreeMap::from([(11, 3), (21, 1)]))));
+		DcAppRewardTotal::set(400);
+		start_active_era(1);
+		start_active_era(2);
+
+		assert_ok!(Staking::set_auto_payout(RuntimeOrigin::signed(11), true));
+		assert_ok!(Staking::set_auto_payout(RuntimeOrigin::signed(21), true));
+		assert_ok!(Staking::set_auto_payout(RuntimeOrigin::signed(31), true));
+		assert_ok!(Staking::set_auto_payout(RuntimeOrigin::signed(31), false));
+		// Only stashes can turn on the automatic payouts.
+		assert_noop!(Staking::set_auto_payout(RuntimeOrigin::signed(10), true), Error::<Test>::NotStash);
+
+		let per_stash =
+			Staking::payout_storage_weight().saturating_add(<Test as Config>::WeightInfo::payout_app());
+		let overhead = <Test as frame_system::Config>::DbWeight::get().reads_writes(3, 1);
+
+		// Not enough weight to pay out anyone.
+		assert_eq!(Staking::on_idle(System::block_number(), overhead), Weight::zero());
+		assert_eq!(AutoPayoutCursor::<Test>::get(), None);
+
+		// Only one stash fits in the block.
+		assert_eq!(Staking::on_idle(System::block_number(), overhead + per_stash), overhead + per_stash);
+		assert!(matches!(AutoPayoutCursor::<Test>::get(), Some((1, Some(_)))));
+
+		// The rest of the era is paid out in the next block.
+		let balance = Balances::free_balance(11);
+		Staking::on_idle(System::block_number(), Weight::MAX);
+		assert_eq!(AutoPayoutCursor::<Test>::get(), Some((2, None)));
+		assert_eq!(Balances::free_balance(11), balance + 300);
+		for stash in [11, 21] {
+			assert_eq!(StorageClaimedRewards::<Test>::get(stash), Some(vec![1]));
+			assert_eq!(AppClaimedRewards::<Test>::get(stash), Some(vec![1]));
+		}
+		assert_eq!(StorageClaimedRewards::<Test>::get(31), None);
+
+		// Era 2 is not finished yet.
+		Staking::on_idle(System::block_number(), Weight::MAX);
+		assert_eq!(AutoPayoutCursor::<Test>::get(), Some((2, None)));
+
+		start_active_era(3);
+		Staking::on_idle(System::block_number(), Weight::MAX);
+		assert_eq!(AutoPayoutCursor::<Test>::get(), Some((3, None)));
+		assert_eq!(StorageClaimedRewards::<Test>::get(21), Some(vec![1, 2]));
+
+		// The automatic payouts are kept when the stash is chilled and validates again.
+		assert_ok!(Staking::chill(RuntimeOrigin::signed(20)));
+		assert!(AutoPayoutStashes::<Test>::contains_key(21));
+		assert_ok!(Staking::validate(RuntimeOrigin::signed(20), ValidatorPrefs::default()));
+		start_active_era(4);
+		Staking::on_idle(System::block_number(), Weight::MAX);
+		assert_eq!(StorageClaimedRewards::<Test>::get(21), Some(vec![1, 2, 3]));
+
+		// They are turned off when the stash is fully unbonded or killed.
+		assert_ok!(Staking::chill(RuntimeOrigin::signed(20)));
+		assert_ok!(Staking::unbond(RuntimeOrigin::signed(20), Staking::ledger(&20).unwrap().active));
+		start_active_era(4 + BondingDuration::get());
+		assert_ok!(Staking::withdraw_unbonded(RuntimeOrigin::signed(20), 0));
+		assert!(!AutoPayoutStashes::<Test>::contains_key(21));
+		assert_ok!(Staking::kill_stash(&11, 0));
+		assert!(!AutoPayoutStashes::<Test>::contains_key(11));
+	});
+}
+
+#[test]
+fn dc_delegated_storage_shares_rewards() {
+	ExtBuilder::default().build_and_execute(|| {
+		DcPeersSpace::set(Some((30, BTreeMap::from([(21, 10), (31, 20)]))));
+		DcStorageRewardTotal::set(3000);
+
+		assert_noop!(Staking::delegate_storage(RuntimeOrigin::signed(3), 3, 100), Error::<Test>::NotStash);
+		assert_noop!(Staking::delegate_storage(RuntimeOrigin::signed(21), 21, 100), Error::<Test>::NotStash);
+		assert_noop!(
+			Staking::delegate_storage(RuntimeOrigin::signed(3), 21, 301),
+			Error::<Test>::InsufficientFreeBalance
+		);
+		// The funds bonded by the delegator can not be delegated again.
+		assert_noop!(
+			Staking::delegate_storage(RuntimeOrigin::signed(11), 21, 501),
+			Error::<Test>::InsufficientFreeBalance
+		);
+
+		assert_ok!(Staking::delegate_storage(RuntimeOrigin::signed(3), 21, 100));
+		assert_ok!(Staking::set_storage_commission(RuntimeOrigin::signed(21), Perbill::from_percent(10)));
+		assert_eq!(
+			Staking::storage_delegation_pool(21),
+			StorageDelegationPool { total: 100, delegators: 1, commission: Perbill::from_percent(10) }
+		);
+		assert_eq!(Staking::storage_delegation(21, 3), Some(100));
+		assert_eq!(Balances::locks(3)[0].amount, 100);
+		assert_eq!(DcActiveUpdates::get().last(), Some(&(21, 1100)));
+		assert_eq!(<Staking as StakingProvider>::get_staking_active(&21), 1100);
+
+		start_active_era(1);
+		start_active_era(2);
+
+		let storage_reward = Perbill::from_rational(10u64, 30) * Staking::eras_storage_reward(1).unwrap();
+		let delegator_reward = Perbill::from_rational(100u64, 1100) *
+			(storage_reward - Perbill::from_percent(10) * storage_reward);
+		let _ = staking_events_since_last_call();
+		assert_ok!(Staking::payout_storage(RuntimeOrigin::signed(1337), 21, 1));
+		assert_eq!(
+			staking_events_since_last_call(),
+			vec![
+				Event::PayoutStarted { era_index: 1, validator_stash: 21 },
+				Event::Rewarded { stash: 3, amount: delegator_reward },
+				Event::Rewarded { stash: 21, amount: storage_reward - delegator_reward },
+			]
+		);
+
+		// The undelegated funds stop backing the stash at once, but stay locked.
+		assert_ok!(Staking::undelegate_storage(RuntimeOrigin::signed(3), 21, 100));
+		assert_eq!(Staking::storage_delegation(21, 3), None);
+		assert_eq!(Staking::storage_delegation_pool(21).total, 0);
+		assert_eq!(DcActiveUpdates::get().last(), Some(&(21, Staking::ledger(&20).unwrap().active)));
+		assert_ok!(Staking::withdraw_undelegated(RuntimeOrigin::signed(3)));
+		assert_eq!(Balances::locks(3)[0].amount, 100);
+
+		start_active_era(2 + BondingDuration::get());
+		assert_ok!(Staking::withdraw_undelegated(RuntimeOrigin::signed(3)));
+		assert!(Balances::locks(3).is_empty());
+		assert_eq!(Staking::delegator_ledger(3), None);
+		assert_noop!(Staking::withdraw_undelegated(RuntimeOrigin::signed(3)), Error::<Test>::NotDelegated);
+	});
+}
+
+#[test]
+fn dc_delegated_funds_can_not_be_bonded() {
+	ExtBuilder::default().build_and_execute(|| {
+		assert_eq!(Balances::free_balance(3), 300);
+		assert_ok!(Staking::delegate_storage(RuntimeOrigin::signed(3), 21, 100));
+
+		// Only the funds that are not delegated are bonded.
+		assert_ok!(Staking::bond(RuntimeOrigin::signed(3), 33, 300, RewardDestination::Staked));
+		assert_eq!(Staking::ledger(&33).unwrap().total, 200);
+		assert_ok!(Staking::bond_extra(RuntimeOrigin::signed(3), 100));
+		assert_eq!(Staking::ledger(&33).unwrap().total, 200);
+
+		// The funds unlocked from the delegation can be bonded again.
+		assert_ok!(Staking::undelegate_storage(RuntimeOrigin::signed(3), 21, 100));
+		start_active_era(BondingDuration::get());
+		assert_ok!(Staking::withdraw_undelegated(RuntimeOrigin::signed(3)));
+		assert_ok!(Staking::bond_extra(RuntimeOrigin::signed(3), 100));
+		assert_eq!(Staking::ledger(&33).unwrap().total, 300);
+	});
+}
+
+#[test]
+fn dc_delegators_share_storage_slashes() {
+	ExtBuilder::default().build_and_execute(|| {
+		assert_ok!(Staking::delegate_storage(RuntimeOrigin::signed(3), 21, 100));
+		assert_ok!(Staking::delegate_storage(RuntimeOrigin::signed(4), 21, 400));
+
+		let offender = <Staking as StakingProvider>::offender(&21).unwrap();
+		assert_eq!(offender.1.own, 1000);
+		assert_eq!(offender.1.total, 1500);
+		assert_eq!(
+			offender.1.others,
+			vec![IndividualExposure { who: 3, value: 100 }, IndividualExposure { who: 4, value: 400 }]
+		);
+
+		// The weight of the slash is covered by the weight that the DC pallet reports offences with.
+		let weight = Staking::on_offence(
+			&[OffenceDetails { offender, reporters: vec![] }],
+			&[Perbill::from_percent(10)],
+			<Staking as StakingProvider>::offence_session_index(),
+			DisableStrategy::Never,
+		);
+		assert!(weight.all_lte(<Staking as StakingProvider>::offence_weight(0)));
+
+		assert_eq!(Staking::ledger(&20).unwrap().active, 900);
+		assert_eq!(Balances::free_balance(3), 290);
+		assert_eq!(Balances::free_balance(4), 360);
+		assert_eq!(Staking::storage_delegation(21, 3), Some(90));
+		assert_eq!(Staking::storage_delegation(21, 4), Some(360));
+		assert_eq!(Staking::storage_delegation_pool(21).total, 450);
+		assert_eq!(Staking::delegator_ledger(4).unwrap().total, 360);
+		assert_eq!(Balances::locks(4)[0].amount, 360);
+		assert_eq!(DcActiveUpdates::get().last(), Some(&(21, 1350)));
+	});
+}
+
+#[test]
+fn dc_deferred_slash_reaches_undelegated_funds() {
+	ExtBuilder::default().slash_defer_duration(2).build_and_execute(|| {
+		mock::start_active_era(1);
+		assert_eq!(Staking::ledger(&100).unwrap().total, 500);
+		assert_ok!(Staking::delegate_storage(RuntimeOrigin::signed(101), 21, 400));
+
+		on_offence_now(
+			&[OffenceDetails {
+				offender: <Staking as StakingProvider>::offender(&21).unwrap(),
+				reporters: vec![],
+			}],
+			&[Perbill::from_percent(10)],
+		);
+		// The delegator leaves before the deferred slash is applied.
+		assert_ok!(Staking::undelegate_storage(RuntimeOrigin::signed(101), 21, 400));
+		assert_eq!(Staking::storage_delegation(21, 101), None);
+
+		mock::start_active_era(4);
+
+		// The undelegated funds are slashed, the own bond of the delegator is not.
+		assert_eq!(Balances::free_balance(101), 2000 - 40);
+		assert_eq!(Staking::ledger(&100).unwrap().total, 500);
+		let ledger = Staking::delegator_ledger(101).unwrap();
+		assert_eq!(ledger.total, 360);
+		assert_eq!(ledger.unlocking.len(), 1);
+		assert_eq!(ledger.unlocking[0].0, 21);
+		assert_eq!(ledger.unlocking[0].1.value, 360);
+		assert_eq!(Balances::locks(101).iter().find(|l| l.id == *b"dcdelega").unwrap().amount, 360);
+	});
+}
+
+#[test]
+fn no_dc_data_keeps_vanilla_payout() {
+	ExtBuilder::default().build_and_execute(|| {
+		start_active_era(1);
+		let total_payout = current_total_payout_for_duration(reward_time_per_era());
+		start_active_era(2);
+
+		assert_eq!(Staking::storage_node_space(1), Default::default());
+		assert_eq!(Staking::eras_validator_reward(1), Some(total_payout));
+		assert_eq!(Staking::eras_storage_reward(1), Some(0));
+		assert_eq!(Staking::eras_app_reward(1), None);
+	});
+}
+
+#[test]
+fn dc_peer_bond_limits_unbond() {
+	ExtBuilder::default().build_and_execute(|| {
+		assert_eq!(Staking::ledger(&10).unwrap().active, 1000);
+		DcPeerBonds::set(BTreeMap::from([(11, 900)]));
+
+		// Only the amount above the peers' bond can be unbonded.
+		assert_ok!(Staking::unbond(RuntimeOrigin::signed(10), 500));
+		assert_eq!(Staking::ledger(&10).unwrap().active, 900);
+		assert_eq!(DcActiveUpdates::get().last(), Some(&(11, 900)));
+
+		// Nothing is left to unbond.
+		assert_ok!(Staking::unbond(RuntimeOrigin::signed(10), 500));
+		assert_eq!(Staking::ledger(&10).unwrap().active, 900);
+	});
+}
+
 mod staking_interface {
 	use frame_support::storage::with_storage_layer;
 	use sp_staking::StakingInterface;